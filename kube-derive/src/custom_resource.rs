@@ -1,6 +1,6 @@
-use darling::FromDeriveInput;
-use proc_macro2::{Ident, Span};
-use syn::{Data, DeriveInput, Path};
+use darling::{FromDeriveInput, FromField};
+use proc_macro2::{Ident, Span, TokenStream};
+use syn::{Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta, Path};
 
 /// Values we can parse from #[kube(attrs)]
 #[derive(Debug, Default, FromDeriveInput)]
@@ -19,6 +19,9 @@ struct KubeAttrs {
     singular: Option<String>,
     #[darling(default)]
     namespaced: bool,
+    /// `Namespaced` or `Cluster`, alternative to `namespaced`
+    #[darling(default)]
+    scope: Option<String>,
     #[darling(default = "default_apiext")]
     apiextensions: String,
     #[darling(multiple, rename = "derive")]
@@ -27,10 +30,31 @@ struct KubeAttrs {
     status: Option<String>,
     #[darling(multiple, rename = "shortname")]
     shortnames: Vec<String>,
+    #[darling(multiple, rename = "category")]
+    categories: Vec<String>,
     #[darling(multiple, rename = "printcolumn")]
     printcolums: Vec<String>,
     #[darling(default)]
     scale: Option<String>,
+    #[darling(multiple, rename = "validation")]
+    validations: Vec<String>,
+}
+
+/// Values we can parse from #[kube(attrs)] on fields of the spec struct
+#[derive(Debug, Default, FromField)]
+#[darling(attributes(kube), forward_attrs(serde))]
+struct KubeFieldAttrs {
+    ident: Option<Ident>,
+    attrs: Vec<Attribute>,
+    #[darling(default)]
+    preserve_unknown_fields: bool,
+    #[darling(default)]
+    embedded_resource: bool,
+    #[darling(multiple, rename = "validation")]
+    validations: Vec<String>,
+    /// json default value
+    #[darling(default)]
+    default: Option<String>,
 }
 
 fn default_apiext() -> String {
@@ -43,8 +67,8 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
         Ok(di) => di,
     };
     // Limit derive to structs
-    match derive_input.data {
-        Data::Struct(_) => {}
+    let fields = match &derive_input.data {
+        Data::Struct(data) => data.fields.clone(),
         _ => {
             return syn::Error::new_spanned(
                 &derive_input.ident,
@@ -52,11 +76,26 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
             )
             .to_compile_error()
         }
-    }
+    };
     let kube_attrs = match KubeAttrs::from_derive_input(&derive_input) {
         Err(err) => return err.write_errors(),
         Ok(attrs) => attrs,
     };
+    let field_attrs = match fields_attrs(&fields) {
+        Err(err) => return err.write_errors(),
+        Ok(attrs) => attrs,
+    };
+    for field in &field_attrs {
+        if let Some(default) = &field.default {
+            if let Err(err) = serde_json::from_str::<serde_json::Value>(default) {
+                return syn::Error::new_spanned(
+                    &field.ident,
+                    format!("#[kube(default = \"...\")] must be valid json: {}", err),
+                )
+                .to_compile_error();
+            }
+        }
+    }
 
     let KubeAttrs {
         group,
@@ -64,14 +103,17 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
         kind_struct,
         version,
         namespaced,
+        scope,
         derives,
         status,
         plural,
         singular,
         shortnames,
+        categories,
         printcolums,
         apiextensions,
         scale,
+        validations,
     } = kube_attrs;
 
     let struct_name = kind_struct.unwrap_or_else(|| kind.clone());
@@ -180,6 +222,25 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
     // 2. Implement Resource trait
     let name = singular.unwrap_or_else(|| kind.to_ascii_lowercase());
     let plural = plural.unwrap_or_else(|| to_plural(&name));
+    let namespaced = match scope.as_deref() {
+        None => namespaced,
+        Some("Namespaced") => true,
+        Some("Cluster") if !namespaced => false,
+        Some("Cluster") => {
+            return syn::Error::new_spanned(
+                &ident,
+                r#"#[kube(scope = "Cluster")] conflicts with #[kube(namespaced)]"#,
+            )
+            .to_compile_error()
+        }
+        Some(_) => {
+            return syn::Error::new_spanned(
+                &ident,
+                r#"#[kube(scope = "...")] must be "Namespaced" or "Cluster""#,
+            )
+            .to_compile_error()
+        }
+    };
     let scope = if namespaced { "Namespaced" } else { "Cluster" };
 
     let api_ver = format!("{}/{}", group, version);
@@ -253,6 +314,7 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
     };
 
     let short_json = serde_json::to_string(&shortnames).unwrap();
+    let categories_json = serde_json::to_string(&categories).unwrap();
     let crd_meta_name = format!("{}.{}", plural, group);
     let crd_meta = quote! { { "name": #crd_meta_name } };
 
@@ -274,11 +336,66 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
         }
     };
 
+    // Schema extensions that schemars can't express, patched into the generated schema
+    let schema_extensions = if schema_gen_enabled {
+        let rename_all = serde_rename_all(&derive_input.attrs);
+        let spec_validations = validation_rules(&validations);
+        let field_extensions = field_attrs.iter().filter_map(|field| {
+            let ident = field.ident.as_ref()?;
+            let name = serde_field_name(ident, &field.attrs, rename_all.as_deref());
+            let mut extensions = vec![];
+            if field.preserve_unknown_fields {
+                extensions.push(
+                    quote! { field["x-kubernetes-preserve-unknown-fields"] = serde_json::json!(true); },
+                );
+            }
+            if field.embedded_resource {
+                extensions
+                    .push(quote! { field["x-kubernetes-embedded-resource"] = serde_json::json!(true); });
+            }
+            if !field.validations.is_empty() {
+                let rules = validation_rules(&field.validations);
+                extensions.push(quote! { field["x-kubernetes-validations"] = #rules; });
+            }
+            if let Some(default) = &field.default {
+                extensions.push(quote! {
+                    field["default"] = serde_json::from_str(#default).expect("valid default json");
+                });
+            }
+            if extensions.is_empty() {
+                return None;
+            }
+            Some(quote! {
+                {
+                    let field = &mut spec_schema["properties"][#name];
+                    #(#extensions)*
+                }
+            })
+        });
+        let spec_validations = if validations.is_empty() {
+            quote! {}
+        } else {
+            quote! { spec_schema["x-kubernetes-validations"] = #spec_validations; }
+        };
+        quote! {
+            {
+                let spec_schema = &mut jsondata["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"];
+                #spec_validations
+                #(#field_extensions)*
+            }
+        }
+    } else {
+        quote! {}
+    };
+
+    let crd_api_version = format!("apiextensions.k8s.io/{}", apiextensions);
     let jsondata = if apiextensions == "v1" {
         quote! {
             #schemagen
 
-            let jsondata = serde_json::json!({
+            let mut jsondata = serde_json::json!({
+                "apiVersion": #crd_api_version,
+                "kind": "CustomResourceDefinition",
                 "metadata": #crd_meta,
                 "spec": {
                     "group": #group,
@@ -301,11 +418,14 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
                     }],
                 }
             });
+            #schema_extensions
         }
     } else {
         // TODO Include schema if enabled
         quote! {
-            let jsondata = serde_json::json!({
+            let mut jsondata = serde_json::json!({
+                "apiVersion": #crd_api_version,
+                "kind": "CustomResourceDefinition",
                 "metadata": #crd_meta,
                 "spec": {
                     "group": #group,
@@ -333,6 +453,17 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
     let impl_crd = quote! {
         impl #rootident {
             pub fn crd() -> #apiext::CustomResourceDefinition {
+                serde_json::from_value(Self::crd_json())
+                    .expect("valid custom resource from #[kube(attrs..)]")
+            }
+
+            /// The `CustomResourceDefinition` as a yaml manifest with sorted keys
+            pub fn crd_yaml() -> String {
+                kube::ops::to_yaml_manifest(&[Self::crd_json()]).expect("crd serializes to yaml")
+            }
+
+            /// The `CustomResourceDefinition` as json, including schema extensions unknown to `k8s_openapi`
+            pub fn crd_json() -> serde_json::Value {
                 let columns : Vec<#apiext::CustomResourceColumnDefinition> = serde_json::from_str(#printers).expect("valid printer column json");
                 let scale: Option<#apiext::CustomResourceSubresourceScale> = if #scale_code.is_empty() {
                     None
@@ -354,8 +485,11 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
                 };
 
                 #jsondata
-                serde_json::from_value(jsondata)
-                    .expect("valid custom resource from #[kube(attrs..)]")
+                let categories: Vec<String> = serde_json::from_str(#categories_json).expect("valid categories");
+                if !categories.is_empty() {
+                    jsondata["spec"]["names"]["categories"] = serde_json::json!(categories);
+                }
+                jsondata
             }
         }
     };
@@ -369,6 +503,69 @@ pub(crate) fn derive(input: proc_macro2::TokenStream) -> proc_macro2::TokenStrea
     }
 }
 
+fn fields_attrs(fields: &Fields) -> darling::Result<Vec<KubeFieldAttrs>> {
+    fields.iter().map(KubeFieldAttrs::from_field).collect()
+}
+
+/// `x-kubernetes-validations` for a list of CEL rules
+fn validation_rules(rules: &[String]) -> TokenStream {
+    quote! { serde_json::json!([#({ "rule": #rules }),*]) }
+}
+
+/// The `rename_all` rule of `#[serde(...)]` attributes
+fn serde_rename_all(attrs: &[Attribute]) -> Option<String> {
+    serde_name_value(attrs, "rename_all")
+}
+
+/// The serialized name of a field, following `#[serde(rename)]` and the `rename_all` rule of the struct
+fn serde_field_name(ident: &Ident, attrs: &[Attribute], rename_all: Option<&str>) -> String {
+    if let Some(name) = serde_name_value(attrs, "rename") {
+        return name;
+    }
+    let name = ident.to_string();
+    let name = name.trim_start_matches("r#");
+    let words = name.split('_').filter(|w| !w.is_empty());
+    let capitalize = |w: &str| {
+        let mut chars = w.chars();
+        chars
+            .next()
+            .map(|c| c.to_ascii_uppercase().to_string() + chars.as_str())
+            .unwrap_or_default()
+    };
+    match rename_all {
+        Some("camelCase") => words
+            .enumerate()
+            .map(|(i, w)| if i == 0 { w.to_string() } else { capitalize(w) })
+            .collect(),
+        Some("PascalCase") => words.map(capitalize).collect(),
+        Some("kebab-case") => name.replace('_', "-"),
+        Some("lowercase") => name.to_ascii_lowercase(),
+        Some("UPPERCASE") => name.to_ascii_uppercase(),
+        Some("SCREAMING_SNAKE_CASE") => name.to_ascii_uppercase(),
+        Some("SCREAMING-KEBAB-CASE") => name.to_ascii_uppercase().replace('_', "-"),
+        _ => name.to_string(),
+    }
+}
+
+/// Find `key = "value"` in `#[serde(...)]` attributes
+fn serde_name_value(attrs: &[Attribute], key: &str) -> Option<String> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("serde"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(Meta::List(list)) => Some(list.nested),
+            _ => None,
+        })
+        .flatten()
+        .find_map(|nested| match nested {
+            NestedMeta::Meta(Meta::NameValue(nv)) if nv.path.is_ident(key) => match nv.lit {
+                Lit::Str(s) => Some(s.value()),
+                _ => None,
+            },
+            _ => None,
+        })
+}
+
 // Simple pluralizer.
 // Duplicating the code from kube (without special casing) because it's simple enough.
 // Irregular plurals must be explicitly specified.
@@ -405,6 +602,33 @@ mod tests {
     use super::*;
     // TODO Unit test `derive`
 
+    #[test]
+    fn test_serde_field_names() {
+        let input: DeriveInput = syn::parse2(quote! {
+            #[serde(rename_all = "camelCase")]
+            struct FooSpec {
+                some_field: String,
+                #[serde(rename = "other")]
+                renamed_field: String,
+            }
+        })
+        .unwrap();
+        let rename_all = serde_rename_all(&input.attrs);
+        assert_eq!(rename_all.as_deref(), Some("camelCase"));
+        let names = match input.data {
+            Data::Struct(data) => data
+                .fields
+                .iter()
+                .map(|f| serde_field_name(f.ident.as_ref().unwrap(), &f.attrs, rename_all.as_deref()))
+                .collect::<Vec<_>>(),
+            _ => unreachable!(),
+        };
+        assert_eq!(names, vec!["someField", "other"]);
+        let ident = Ident::new("some_field", Span::call_site());
+        assert_eq!(serde_field_name(&ident, &[], None), "some_field");
+        assert_eq!(serde_field_name(&ident, &[], Some("kebab-case")), "some-field");
+    }
+
     #[test]
     fn test_apiextensions_default() {
         let input = quote! {
@@ -83,6 +83,9 @@ mod custom_resource;
 /// ### `#[kube(namespaced)]`
 /// To specify that this is a namespaced resource rather than cluster level.
 ///
+/// ### `#[kube(scope = "Cluster")]`
+/// To specify the scope explicitly, as either `"Namespaced"` or `"Cluster"` (the default).
+///
 /// ### `#[kube(struct = "StructName")]`
 /// Customize the name of the generated root struct (defaults to `kind`).
 ///
@@ -101,7 +104,37 @@ mod custom_resource;
 /// Allows adding straight json to [printcolumns](https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#additional-printer-columns).
 ///
 /// ### `#[kube(shortname = "sn")]`
-/// Add a single shortname to the generated crd.
+/// Add a single shortname to the generated crd. Can be repeated.
+///
+/// ### `#[kube(category = "all")]`
+/// Add the crd to a [category](https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#categories),
+/// so it is listed by e.g. `kubectl get all`. Can be repeated.
+///
+/// ### `#[kube(validation = "CEL rule")]`
+/// Add a [CEL validation rule](https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#validation-rules)
+/// to the schema of the spec as `x-kubernetes-validations`. Can be repeated.
+///
+/// ## Optional `#[kube]` attributes on fields
+///
+/// These set [kubernetes schema extensions](https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#specifying-a-structural-schema)
+/// on the schema of a field of the spec struct. Field names follow `#[serde(rename)]` and `#[serde(rename_all)]`.
+///
+/// ### `#[kube(validation = "CEL rule")]`
+/// Add a CEL validation rule to the field as `x-kubernetes-validations`. Can be repeated.
+///
+/// ### `#[kube(preserve_unknown_fields)]`
+/// Set `x-kubernetes-preserve-unknown-fields`, so the apiserver does not prune unknown fields of the value.
+///
+/// ### `#[kube(embedded_resource)]`
+/// Set `x-kubernetes-embedded-resource`, marking the value as an embedded object with `apiVersion`, `kind` and `metadata`.
+///
+/// ### `#[kube(default = r#"json"#)]`
+/// Set the `default` of the field in the schema, so the apiserver applies it on admission.
+/// Fields with `#[serde(default)]` or `#[serde(default = "func")]` get their default in the schema
+/// without this attribute, this is only needed when the schema default should differ from the serde default.
+///
+/// **NOTE**: `x-kubernetes-validations` is not known to the `CustomResourceDefinition` type of `k8s_openapi`, and is only
+/// included in the json returned by the generated `crd_json` method.
 ///
 /// ## Example with all properties
 ///
@@ -154,19 +187,47 @@ mod custom_resource;
 /// impl FooCrd {
 ///     pub fn new(name: &str, spec: FooSpec) -> Self { ... }
 ///     pub fn crd() -> k8s_openapi::...::CustomResourceDefinition { ... }
+///     pub fn crd_json() -> serde_json::Value { ... }
+///     pub fn crd_yaml() -> String { ... }
 /// }
 /// ```
 ///
+/// `crd_yaml` renders the `crd_json` with sorted keys, so manifests can be committed and diffed.
+/// To keep a committed manifest up to date from a `build.rs`, see `kube::ops::write_yaml_manifest`.
+///
 /// ## Customizing Schemas
 /// Should you need to customize the schemas, you can use:
 /// - [Serde/Schemars Attributes](https://graham.cool/schemars/examples/3-schemars_attrs/) (no need to duplicate serde renames)
 /// - [`#[schemars(schema_with = "func")]`](https://graham.cool/schemars/examples/7-custom_serialization/) (e.g. like in the [`crd_derive` example](https://github.com/clux/kube-rs/blob/master/examples/crd_derive.rs))
 /// - `impl JsonSchema` on a type / newtype around external type. See [#129](https://github.com/clux/kube-rs/issues/129#issuecomment-750852916)
 ///
+/// ## Validation
+/// Validation constraints on fields of the spec (or status) are emitted into the generated schema, so the
+/// apiserver validates objects on admission. Use the validation attributes of `schemars`, either as
+/// `#[schemars(...)]` or as the `#[validate(...)]` attributes of the `validator` crate:
+///
+/// ```rust
+/// use serde::{Serialize, Deserialize};
+/// use kube_derive::CustomResource;
+/// use schemars::JsonSchema;
+///
+/// #[derive(CustomResource, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+/// #[kube(group = "clux.dev", version = "v1", kind = "Foo", namespaced)]
+/// struct FooSpec {
+///     #[schemars(range(min = 1, max = 10))]         // minimum / maximum
+///     replicas: i32,
+///     #[schemars(regex(pattern = r"^[a-z]+$"))]     // pattern
+///     #[schemars(length(min = 1, max = 63))]        // minLength / maxLength
+///     name: String,
+///     #[schemars(length(max = 3))]                  // maxItems
+///     tags: Vec<String>,
+/// }
+/// ```
+///
 /// In general, you will need to override parts of the schemas (for fields in question) when you are:
 /// - **using complex enums**: enums do not currently generate [structural schemas](https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#specifying-a-structural-schema), so kubernetes won't support them by default
 /// - **customizing [merge-strategies](https://kubernetes.io/docs/reference/using-api/server-side-apply/#merge-strategy)** (e.g. like in the [`crd_derive_schema` example](https://github.com/clux/kube-rs/blob/master/examples/crd_derive_schema.rs))
-/// - **customizing [certain kubebuilder like validation rules](https://github.com/clux/kube-rs/issues/129#issuecomment-749463718)** (tail the issue for state of affairs)
+/// - **customizing [kubebuilder like validation rules](https://github.com/clux/kube-rs/issues/129#issuecomment-749463718)** that are not covered by the validation attributes above
 /// - **embedding k8s-openapi types** within your structs (see [k8s-openapi#86](https://github.com/Arnavion/k8s-openapi/issues/86))
 ///
 /// See [kubernetes openapi validation](https://kubernetes.io/docs/tasks/extend-kubernetes/custom-resources/custom-resource-definitions/#validation) for the format of the OpenAPI v3 schemas.
@@ -127,3 +127,223 @@ fn test_crd_schema_matches_expected() {
         .unwrap()
     );
 }
+
+#[derive(CustomResource, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[kube(group = "clux.dev", version = "v1", kind = "Validated", namespaced)]
+struct ValidatedSpec {
+    #[schemars(range(min = 1, max = 10))]
+    replicas: i32,
+    #[schemars(regex(pattern = r"^[a-z]+$"), length(min = 1, max = 63))]
+    name: String,
+    #[validate(length(max = 3))]
+    tags: Vec<String>,
+}
+
+#[test]
+fn test_crd_schema_includes_validation() {
+    let crd = Validated::crd();
+    let schema = crd.spec.versions[0]
+        .schema
+        .as_ref()
+        .and_then(|s| s.open_api_v3_schema.as_ref())
+        .unwrap();
+    let spec = serde_json::to_value(&schema.properties.as_ref().unwrap()["spec"]).unwrap();
+    assert_eq!(
+        spec["properties"],
+        serde_json::json!({
+            "replicas": {
+                "type": "integer",
+                "format": "int32",
+                "minimum": 1.0,
+                "maximum": 10.0
+            },
+            "name": {
+                "type": "string",
+                "pattern": "^[a-z]+$",
+                "minLength": 1,
+                "maxLength": 63
+            },
+            "tags": {
+                "type": "array",
+                "items": { "type": "string" },
+                "maxItems": 3
+            }
+        })
+    );
+}
+
+#[derive(CustomResource, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[kube(
+    group = "clux.dev",
+    version = "v1",
+    kind = "Extended",
+    namespaced,
+    validation = "self.minReplicas <= self.maxReplicas"
+)]
+#[serde(rename_all = "camelCase")]
+struct ExtendedSpec {
+    min_replicas: i32,
+    max_replicas: i32,
+    #[kube(preserve_unknown_fields)]
+    #[schemars(schema_with = "any_object")]
+    raw_config: serde_json::Value,
+    #[kube(embedded_resource)]
+    #[schemars(schema_with = "any_object")]
+    #[serde(rename = "template")]
+    pod_template: serde_json::Value,
+    #[kube(validation = "self.startsWith('v')", validation = "size(self) < 10")]
+    image_tag: String,
+}
+
+fn any_object(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+    serde_json::from_value(serde_json::json!({ "type": "object" })).unwrap()
+}
+
+#[test]
+fn test_crd_schema_includes_kubernetes_extensions() {
+    let crd = Extended::crd_json();
+    let spec = &crd["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"];
+    assert_eq!(
+        spec["x-kubernetes-validations"],
+        serde_json::json!([{ "rule": "self.minReplicas <= self.maxReplicas" }])
+    );
+    assert_eq!(
+        spec["properties"]["rawConfig"],
+        serde_json::json!({ "type": "object", "x-kubernetes-preserve-unknown-fields": true })
+    );
+    assert_eq!(
+        spec["properties"]["template"],
+        serde_json::json!({ "type": "object", "x-kubernetes-embedded-resource": true })
+    );
+    assert_eq!(
+        spec["properties"]["imageTag"]["x-kubernetes-validations"],
+        serde_json::json!([{ "rule": "self.startsWith('v')" }, { "rule": "size(self) < 10" }])
+    );
+
+    // extensions known to k8s-openapi survive in the typed crd
+    let typed = Extended::crd();
+    let schema = typed.spec.versions[0].schema.as_ref().unwrap();
+    let spec = &schema
+        .open_api_v3_schema
+        .as_ref()
+        .unwrap()
+        .properties
+        .as_ref()
+        .unwrap()["spec"];
+    let props = spec.properties.as_ref().unwrap();
+    assert_eq!(
+        props["rawConfig"].x_kubernetes_preserve_unknown_fields,
+        Some(true)
+    );
+    assert_eq!(props["template"].x_kubernetes_embedded_resource, Some(true));
+}
+
+#[derive(CustomResource, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[kube(group = "clux.dev", version = "v1", kind = "Defaulted", namespaced)]
+struct DefaultedSpec {
+    #[serde(default)]
+    replicas: i32,
+    #[kube(default = r#"{"level": "info"}"#)]
+    logging: Option<LoggingConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+struct LoggingConfig {
+    level: String,
+}
+
+#[test]
+fn test_crd_schema_includes_defaults() {
+    let crd = Defaulted::crd();
+    let schema = crd.spec.versions[0]
+        .schema
+        .as_ref()
+        .and_then(|s| s.open_api_v3_schema.as_ref())
+        .unwrap();
+    let spec = &schema.properties.as_ref().unwrap()["spec"];
+    let props = spec.properties.as_ref().unwrap();
+    assert_eq!(
+        props["replicas"].default.as_ref().map(|d| &d.0),
+        Some(&serde_json::json!(0))
+    );
+    assert_eq!(
+        props["logging"].default.as_ref().map(|d| &d.0),
+        Some(&serde_json::json!({ "level": "info" }))
+    );
+}
+
+#[test]
+fn test_crd_yaml_is_deterministic() {
+    let yaml = Foo::crd_yaml();
+    assert!(yaml.starts_with("---\napiVersion: apiextensions.k8s.io/v1\nkind: CustomResourceDefinition\n"));
+    assert_eq!(yaml, Foo::crd_yaml());
+    let parsed: serde_json::Value = serde_yaml::from_str(&yaml).unwrap();
+    assert_eq!(parsed, Foo::crd_json());
+}
+
+#[derive(CustomResource, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[kube(
+    group = "clux.dev",
+    version = "v1",
+    kind = "Octopus",
+    plural = "octopodes",
+    singular = "octo",
+    shortname = "oc",
+    shortname = "octo",
+    category = "all",
+    category = "sea",
+    namespaced
+)]
+struct OctopusSpec {
+    arms: u8,
+}
+
+#[test]
+fn test_crd_names() {
+    let names = Octopus::crd().spec.names;
+    assert_eq!(names.plural, "octopodes");
+    assert_eq!(names.singular.as_deref(), Some("octo"));
+    assert_eq!(
+        names.short_names,
+        Some(vec!["oc".to_string(), "octo".to_string()])
+    );
+    assert_eq!(names.categories, Some(vec!["all".to_string(), "sea".to_string()]));
+    assert_eq!(
+        Octopus::crd().metadata.name.as_deref(),
+        Some("octopodes.clux.dev")
+    );
+    assert_eq!(Foo::crd().spec.names.categories, None);
+}
+
+#[derive(CustomResource, Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[kube(group = "clux.dev", version = "v1", kind = "Scoped", scope = "Cluster")]
+struct ScopedSpec {
+    replicas: i32,
+}
+
+#[test]
+fn test_crd_builder_matches_derive() {
+    use kube::api::{CustomResourceDefinitionBuilder, NotUsed, Object, Resource};
+
+    assert_eq!(Scoped::crd().spec.scope, "Cluster");
+
+    let builder = CustomResourceDefinitionBuilder::new::<FooSpec>("clux.dev", "v1", "Foo").namespaced();
+    let built = builder.to_json();
+    let derived = Foo::crd_json();
+    assert_eq!(built["spec"]["names"], derived["spec"]["names"]);
+    assert_eq!(built["spec"]["scope"], derived["spec"]["scope"]);
+    assert_eq!(built["metadata"], derived["metadata"]);
+    let schema = |crd: &serde_json::Value| {
+        crd["spec"]["versions"][0]["schema"]["openAPIV3Schema"]["properties"]["spec"].clone()
+    };
+    assert_eq!(schema(&built), schema(&derived));
+    let crd: k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition =
+        builder.build().unwrap();
+    assert_eq!(crd.spec.names.plural, "foos");
+
+    let gvk = builder.gvk().unwrap();
+    assert_eq!(
+        Object::<FooSpec, NotUsed>::url_path(&gvk, Some("ns")),
+        Foo::url_path(&(), Some("ns"))
+    );
+}
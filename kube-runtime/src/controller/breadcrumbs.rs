@@ -0,0 +1,236 @@
+//! Records the outcome of reconciliations in an annotation of the reconciled object
+use super::ReconcilerAction;
+use k8s_openapi::chrono::{SecondsFormat, Utc};
+use kube::{
+    api::{Api, Patch, PatchParams, Resource, ResourceExt},
+    Client,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Default annotation for [`Breadcrumbs`]
+pub const DEFAULT_BREADCRUMB_ANNOTATION: &str = "kube-rs.io/last-reconciled";
+
+/// Configuration for recording reconcile breadcrumbs, see [`Controller::breadcrumbs`](super::Controller::breadcrumbs)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Breadcrumbs {
+    /// The annotation to record the breadcrumb in, `kube-rs.io/last-reconciled` by default
+    pub annotation: String,
+    /// The field manager that owns the annotation
+    pub field_manager: String,
+}
+
+impl Breadcrumbs {
+    /// Record breadcrumbs in the default annotation, owned by `field_manager`
+    #[must_use]
+    pub fn new(field_manager: &str) -> Self {
+        Self {
+            annotation: DEFAULT_BREADCRUMB_ANNOTATION.to_string(),
+            field_manager: field_manager.to_string(),
+        }
+    }
+
+    /// Record breadcrumbs in a custom annotation
+    #[must_use]
+    pub fn annotation(mut self, annotation: &str) -> Self {
+        self.annotation = annotation.to_string();
+        self
+    }
+}
+
+/// The content of a breadcrumb annotation
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Breadcrumb {
+    /// The `metadata.generation` of the reconciled object
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<i64>,
+    /// `Success` or `Error`
+    pub outcome: String,
+    /// Hash of the returned [`ReconcilerAction`], changes when the result changes
+    ///
+    /// Errors are not hashed, their messages often contain details that change on every attempt.
+    pub hash: String,
+    /// When the reconciliation finished, in RFC 3339 format
+    pub timestamp: String,
+}
+
+impl Breadcrumb {
+    /// Whether both breadcrumbs record the same result, regardless of when
+    fn same_result(&self, other: &Self) -> bool {
+        self.generation == other.generation && self.outcome == other.outcome && self.hash == other.hash
+    }
+}
+
+/// Parse the breadcrumb of an object, if it has one
+#[must_use]
+pub fn breadcrumb<K: Resource>(obj: &K, annotation: &str) -> Option<Breadcrumb> {
+    serde_json::from_str(obj.annotations().get(annotation)?).ok()
+}
+
+/// Patches breadcrumbs for a [`Controller`](super::Controller)
+pub(crate) struct Recorder<K: Resource> {
+    client: Client,
+    config: Breadcrumbs,
+    dyntype: K::DynamicType,
+}
+
+impl<K: Resource> Clone for Recorder<K>
+where
+    K::DynamicType: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+            config: self.config.clone(),
+            dyntype: self.dyntype.clone(),
+        }
+    }
+}
+
+/// The outcome of a reconciliation, and the details to hash
+pub(crate) fn outcome<E: Display>(result: &Result<ReconcilerAction, E>) -> (&'static str, String) {
+    match result {
+        Ok(action) => ("Success", format!("{:?}", action.requeue_after)),
+        // error messages tend to vary between attempts, which would patch (and so reconcile) again every time
+        Err(_) => ("Error", String::new()),
+    }
+}
+
+/// FNV-1a, which unlike the std hashers is stable across processes and releases
+fn hash_of(text: &str) -> String {
+    format!(
+        "{:016x}",
+        text.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    )
+}
+
+/// The parts of a reconciled object that are needed to record its breadcrumb
+pub(crate) struct Target {
+    name: Option<String>,
+    namespace: Option<String>,
+    generation: Option<i64>,
+    last: Option<Breadcrumb>,
+}
+
+impl<K> Recorder<K>
+where
+    K: Resource + Clone + serde::de::DeserializeOwned + std::fmt::Debug,
+    K::DynamicType: Clone,
+{
+    pub(crate) fn new(client: Client, config: Breadcrumbs, dyntype: K::DynamicType) -> Self {
+        Self {
+            client,
+            config,
+            dyntype,
+        }
+    }
+
+    /// Remember what is needed to record the result of reconciling `obj`
+    pub(crate) fn target(&self, obj: &K) -> Target {
+        Target {
+            name: obj.meta().name.clone(),
+            namespace: obj.meta().namespace.clone(),
+            generation: obj.meta().generation,
+            last: breadcrumb(obj, &self.config.annotation),
+        }
+    }
+
+    /// Record the result of reconciling `target`
+    ///
+    /// Breadcrumbs that only differ by their timestamp are not patched again, since the patch itself
+    /// triggers a new reconciliation. Failures to patch are ignored, breadcrumbs are best effort.
+    pub(crate) async fn record(&self, target: Target, (outcome, detail): (&'static str, String)) {
+        let crumb = Breadcrumb {
+            generation: target.generation,
+            outcome: outcome.to_string(),
+            hash: hash_of(&detail),
+            timestamp: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+        };
+        let name = match &target.name {
+            Some(name) if !matches!(&target.last, Some(old) if old.same_result(&crumb)) => name,
+            _ => return,
+        };
+        let api: Api<K> = match &target.namespace {
+            Some(ns) => Api::namespaced_with(self.client.clone(), ns, &self.dyntype),
+            None => Api::all_with(self.client.clone(), &self.dyntype),
+        };
+        let patch = serde_json::json!({
+            "apiVersion": K::api_version(&self.dyntype),
+            "kind": K::kind(&self.dyntype),
+            "metadata": {
+                "name": name,
+                "namespace": target.namespace,
+                "annotations": {
+                    &self.config.annotation: serde_json::to_string(&crumb).unwrap_or_default(),
+                }
+            }
+        });
+        let pp = PatchParams::apply(&self.config.field_manager);
+        let _ = api.patch(name, &pp, &Patch::Apply(patch)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{breadcrumb, hash_of, outcome, Breadcrumb, DEFAULT_BREADCRUMB_ANNOTATION};
+    use crate::controller::ReconcilerAction;
+    use std::time::Duration;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use kube::api::ResourceExt;
+
+    #[test]
+    fn breadcrumbs_roundtrip_through_annotations() {
+        let crumb = Breadcrumb {
+            generation: Some(3),
+            outcome: "Success".to_string(),
+            hash: "0123456789abcdef".to_string(),
+            timestamp: "2021-04-01T12:00:00Z".to_string(),
+        };
+        let mut cm = ConfigMap::default();
+        assert_eq!(breadcrumb(&cm, DEFAULT_BREADCRUMB_ANNOTATION), None);
+        cm.annotations_mut().insert(
+            DEFAULT_BREADCRUMB_ANNOTATION.to_string(),
+            serde_json::to_string(&crumb).unwrap(),
+        );
+        assert_eq!(
+            cm.annotations()[DEFAULT_BREADCRUMB_ANNOTATION],
+            r#"{"generation":3,"outcome":"Success","hash":"0123456789abcdef","timestamp":"2021-04-01T12:00:00Z"}"#
+        );
+        assert_eq!(
+            breadcrumb(&cm, DEFAULT_BREADCRUMB_ANNOTATION),
+            Some(crumb.clone())
+        );
+
+        let later = Breadcrumb {
+            timestamp: "2021-04-01T12:05:00Z".to_string(),
+            ..crumb.clone()
+        };
+        assert!(crumb.same_result(&later));
+        let failed = Breadcrumb {
+            outcome: "Error".to_string(),
+            ..later
+        };
+        assert!(!crumb.same_result(&failed));
+    }
+
+    #[test]
+    fn breadcrumb_hashes_are_stable() {
+        let ok = Ok::<_, std::io::Error>(ReconcilerAction {
+            requeue_after: Some(Duration::from_secs(300)),
+        });
+        let (_, detail) = outcome(&ok);
+        assert_eq!(hash_of(&detail), hash_of("Some(300s)"));
+        assert_eq!(hash_of(""), "cbf29ce484222325");
+        assert_eq!(hash_of("a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn errors_are_recorded_regardless_of_their_message() {
+        let first = Err::<ReconcilerAction, _>(std::io::Error::other("timed out after 10.2s"));
+        let second = Err::<ReconcilerAction, _>(std::io::Error::other("timed out after 10.7s"));
+        assert_eq!(outcome(&first), outcome(&second));
+    }
+}
@@ -0,0 +1,147 @@
+//! Pauses reconciliation until conditions are met, see [`Controller::gate`](super::Controller::gate)
+use crate::{
+    executor::Executor,
+    utils::CancelableJoinHandle,
+    watcher::{self, watcher},
+};
+use futures::{future, stream::BoxStream, Stream, StreamExt};
+use kube::api::{Api, ListParams, Resource};
+use serde::de::DeserializeOwned;
+use std::fmt::Debug;
+use tokio::sync::watch;
+
+/// A condition on a single object, for [`Controller::gate`](super::Controller::gate)
+///
+/// Watches the object `name` of `api`, and emits `condition` of its current state every time it changes:
+/// `Some(obj)` while the object exists, and `None` while it doesn't. Watch errors are retried
+/// by the next poll, and keep the last state.
+///
+/// ```no_run
+/// use kube::{api::{Api, ResourceExt}, Client};
+/// use kube_runtime::controller::object_condition;
+/// use k8s_openapi::api::core::v1::Secret;
+/// # fn scope(client: Client) {
+/// let secrets: Api<Secret> = Api::namespaced(client, "operator");
+/// let credentials_present = object_condition(secrets, "credentials", |secret| secret.is_some());
+/// # }
+/// ```
+pub fn object_condition<K>(
+    api: Api<K>,
+    name: &str,
+    condition: impl Fn(Option<&K>) -> bool + Send + 'static,
+) -> impl Stream<Item = bool> + Send
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    let lp = ListParams::default().fields(&["metadata.name=", name].concat());
+    watcher(api, lp).filter_map(move |event| {
+        future::ready(match event {
+            Ok(watcher::Event::Applied(obj)) => Some(condition(Some(&obj))),
+            Ok(watcher::Event::Deleted(_)) => Some(condition(None)),
+            Ok(watcher::Event::Restarted(objs)) => Some(condition(objs.first())),
+            Err(_) => None,
+        })
+    })
+}
+
+/// The conditions of a controller, which must all be met for reconciliations to run
+#[derive(Default)]
+pub(crate) struct Gates {
+    conditions: Vec<BoxStream<'static, bool>>,
+}
+
+impl Gates {
+    pub(crate) fn push(&mut self, condition: BoxStream<'static, bool>) {
+        self.conditions.push(condition);
+    }
+
+    /// Start tracking the conditions in the background
+    pub(crate) fn spawn(self, executor: &dyn Executor) -> Option<RunningGates> {
+        if self.conditions.is_empty() {
+            return None;
+        }
+        let mut receivers = vec![];
+        let mut drivers = vec![];
+        for mut condition in self.conditions {
+            // gates are closed until their condition says otherwise
+            let (tx, rx) = watch::channel(false);
+            receivers.push(rx);
+            drivers.push(CancelableJoinHandle::spawn_on(
+                async move {
+                    while let Some(open) = condition.next().await {
+                        if tx.send(open).is_err() {
+                            return;
+                        }
+                    }
+                },
+                executor,
+            ));
+        }
+        Some(RunningGates {
+            receivers,
+            _drivers: std::sync::Arc::new(drivers),
+        })
+    }
+}
+
+/// Tracks the conditions of [`Gates`], the conditions stop being tracked when the last clone is dropped
+#[derive(Clone)]
+pub(crate) struct RunningGates {
+    receivers: Vec<watch::Receiver<bool>>,
+    _drivers: std::sync::Arc<Vec<CancelableJoinHandle<()>>>,
+}
+
+impl RunningGates {
+    /// Whether all conditions are currently met
+    pub(crate) fn is_open(&self) -> bool {
+        self.receivers.iter().all(|rx| *rx.borrow())
+    }
+
+    /// Wait until all conditions are met
+    ///
+    /// A condition whose stream has ended keeps its last value.
+    pub(crate) async fn opened(mut self) {
+        for rx in &mut self.receivers {
+            while !*rx.borrow() {
+                if rx.changed().await.is_err() {
+                    // the condition will never change again
+                    future::pending::<()>().await;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Gates;
+    use crate::executor::TokioExecutor;
+    use futures::{channel::mpsc, FutureExt, SinkExt, StreamExt};
+
+    #[tokio::test]
+    async fn gates_open_when_all_conditions_are_met() {
+        let (mut first_tx, first) = mpsc::channel(1);
+        let (mut second_tx, second) = mpsc::channel(1);
+        let mut gates = Gates::default();
+        gates.push(first.boxed());
+        gates.push(second.boxed());
+        let gates = gates.spawn(&TokioExecutor::new()).unwrap();
+
+        let mut opened = Box::pin(gates.clone().opened());
+        tokio::task::yield_now().await;
+        assert!((&mut opened).now_or_never().is_none());
+        first_tx.send(true).await.unwrap();
+        second_tx.send(false).await.unwrap();
+        tokio::task::yield_now().await;
+        assert!((&mut opened).now_or_never().is_none());
+        second_tx.send(true).await.unwrap();
+        tokio::time::timeout(std::time::Duration::from_secs(1), opened)
+            .await
+            .unwrap();
+
+        // closing a gate again holds back later reconciliations
+        first_tx.send(false).await.unwrap();
+        tokio::task::yield_now().await;
+        assert!(Box::pin(gates.opened()).now_or_never().is_none());
+    }
+}
@@ -0,0 +1,234 @@
+//! Reports whether a running controller is keeping up, for readiness and liveness probes
+use crate::{
+    time::{default_clock, Clock},
+    watcher,
+};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// How many reconcile outcomes are kept for [`ControllerHealth::error_rate`]
+const MAX_OUTCOMES: usize = 1024;
+
+#[derive(Debug, Default)]
+struct State {
+    synced: bool,
+    /// The last error of every watch that has not recovered from it, by its index
+    watch_errors: HashMap<usize, String>,
+    last_watch_error: Option<String>,
+    last_success: Option<Instant>,
+    last_failure: Option<Instant>,
+    reconciles: u64,
+    failures: u64,
+    outcomes: VecDeque<(Instant, bool)>,
+}
+
+/// A snapshot of the health of a controller, see [`ControllerHealth::snapshot`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct HealthSnapshot {
+    /// Whether the initial list of the controlled objects has completed
+    pub synced: bool,
+    /// Whether the watches feeding the controller are currently connected
+    pub watch_connected: bool,
+    /// The last error of a watch, cleared once all watches recover
+    pub last_watch_error: Option<String>,
+    /// Time since the last successful reconciliation
+    pub since_last_success: Option<Duration>,
+    /// Time since the last failed reconciliation
+    pub since_last_failure: Option<Duration>,
+    /// Reconciliations since the controller started
+    pub reconciles: u64,
+    /// Failed reconciliations since the controller started
+    pub failures: u64,
+}
+
+/// A handle reporting the health of a running [`Controller`](crate::Controller)
+///
+/// Retrieve it with [`Controller::health`](crate::Controller::health) before starting the
+/// controller, and wire it into the readiness and liveness endpoints of the operator:
+///
+/// ```no_run
+/// # use kube_runtime::Controller;
+/// # use k8s_openapi::api::core::v1::ConfigMap;
+/// # use std::time::Duration;
+/// # fn scope(controller: Controller<ConfigMap>) {
+/// let health = controller.health();
+/// // e.g. from the http handlers of /readyz and /livez
+/// let ready = health.is_ready();
+/// let live = health.error_rate(Duration::from_secs(300)) < 0.5;
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ControllerHealth {
+    state: Arc<Mutex<State>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for ControllerHealth {
+    fn default() -> Self {
+        Self {
+            state: Arc::default(),
+            clock: default_clock(),
+        }
+    }
+}
+
+impl ControllerHealth {
+    /// Create a handle that has seen no watch events and no reconciliations yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The same handle, measuring time with `clock`
+    #[must_use]
+    pub fn with_clock(self, clock: Arc<dyn Clock>) -> Self {
+        Self {
+            state: self.state,
+            clock,
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Record an event of the root watch
+    ///
+    /// A `Restarted` event means that a list completed, so the cache is in sync.
+    pub(crate) fn record_root_watch<K>(&self, event: &Result<watcher::Event<K>, watcher::Error>) {
+        if matches!(event, Ok(watcher::Event::Restarted(_))) {
+            self.state().synced = true;
+        }
+    }
+
+    /// Record an item of the watch `watch` feeding the controller, with its error if it failed
+    ///
+    /// Watches are connected until they fail, and again from their next item on.
+    pub(crate) fn record_watch(&self, watch: usize, error: Option<&watcher::Error>) {
+        let mut state = self.state();
+        match error {
+            None => {
+                if state.watch_errors.remove(&watch).is_some() && state.watch_errors.is_empty() {
+                    state.last_watch_error = None;
+                }
+            }
+            Some(err) => {
+                let err = err.to_string();
+                state.watch_errors.insert(watch, err.clone());
+                state.last_watch_error = Some(err);
+            }
+        }
+    }
+
+    /// Record the outcome of a reconciliation
+    pub(crate) fn record_reconcile(&self, success: bool) {
+        let now = self.clock.now();
+        let mut state = self.state();
+        state.reconciles += 1;
+        if success {
+            state.last_success = Some(now);
+        } else {
+            state.failures += 1;
+            state.last_failure = Some(now);
+        }
+        if state.outcomes.len() == MAX_OUTCOMES {
+            state.outcomes.pop_front();
+        }
+        state.outcomes.push_back((now, success));
+    }
+
+    /// Whether the controller is ready to act, the initial list completed and all watches are connected
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        let state = self.state();
+        state.synced && state.watch_errors.is_empty()
+    }
+
+    /// Time since the last successful reconciliation, `None` if none succeeded yet
+    #[must_use]
+    pub fn since_last_success(&self) -> Option<Duration> {
+        let last = self.state().last_success?;
+        Some(self.clock.now().saturating_duration_since(last))
+    }
+
+    /// The share of reconciliations that failed within the last `window`, `0.0` if there were none
+    ///
+    /// At most the last 1024 reconciliations are taken into account.
+    #[must_use]
+    pub fn error_rate(&self, window: Duration) -> f64 {
+        let now = self.clock.now();
+        let state = self.state();
+        let recent = state
+            .outcomes
+            .iter()
+            .rev()
+            .take_while(|(at, _)| now.saturating_duration_since(*at) <= window);
+        let (total, failed) = recent.fold((0_u32, 0_u32), |(total, failed), (_, success)| {
+            (total + 1, failed + u32::from(!success))
+        });
+        if total == 0 {
+            0.0
+        } else {
+            f64::from(failed) / f64::from(total)
+        }
+    }
+
+    /// A snapshot of all health information, for example to serve as a status page
+    #[must_use]
+    pub fn snapshot(&self) -> HealthSnapshot {
+        let now = self.clock.now();
+        let state = self.state();
+        HealthSnapshot {
+            synced: state.synced,
+            watch_connected: state.watch_errors.is_empty(),
+            last_watch_error: state.last_watch_error.clone(),
+            since_last_success: state.last_success.map(|at| now.saturating_duration_since(at)),
+            since_last_failure: state.last_failure.map(|at| now.saturating_duration_since(at)),
+            reconciles: state.reconciles,
+            failures: state.failures,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ControllerHealth;
+    use crate::{time::MockClock, watcher};
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn tracks_watches_and_reconcile_outcomes() {
+        let clock = Arc::new(MockClock::new());
+        let health = ControllerHealth::new().with_clock(clock.clone());
+        assert!(!health.is_ready());
+
+        health.record_root_watch::<ConfigMap>(&Ok(watcher::Event::Restarted(vec![])));
+        health.record_watch(0, None);
+        assert!(health.is_ready());
+        health.record_watch(1, Some(&watcher::injected_error(500, "boom")));
+        assert!(!health.is_ready());
+        assert!(health.snapshot().last_watch_error.is_some());
+        // another watch recovering does not make up for the failed one
+        health.record_watch(0, None);
+        assert!(!health.is_ready());
+        health.record_watch(1, None);
+        assert!(health.is_ready());
+        assert_eq!(health.snapshot().last_watch_error, None);
+
+        health.record_reconcile(false);
+        clock.advance(Duration::from_secs(60));
+        health.record_reconcile(true);
+        health.record_reconcile(false);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(health.since_last_success(), Some(Duration::from_secs(10)));
+        assert!((health.error_rate(Duration::from_secs(30)) - 0.5).abs() < f64::EPSILON);
+        assert!((health.error_rate(Duration::from_secs(120)) - 2.0 / 3.0).abs() < f64::EPSILON);
+        let snapshot = health.snapshot();
+        assert_eq!((snapshot.reconciles, snapshot.failures), (3, 2));
+    }
+}
@@ -2,31 +2,59 @@
 
 use self::runner::Runner;
 use crate::{
+    executor::{default_executor, Executor},
     reflector::{
-        reflector,
+        resync_keys_with_clock,
         store::{Store, Writer},
         ObjectRef,
     },
-    scheduler::{self, scheduler, ScheduleRequest},
-    utils::{try_flatten_applied, try_flatten_touched, trystream_try_via, CancelableJoinHandle},
-    watcher::{self, watcher},
+    scheduler::{self, scheduler, QueueInspector, ScheduleRequest},
+    time::{default_clock, Clock},
+    utils::{try_flatten_touched, trystream_try_via, CancelableJoinHandle},
+    watcher::{
+        self, dynamic_watcher, dynamic_watcher_with, emit_tombstones, scoped_watcher,
+        scoped_watcher_with, watcher, NamespaceSet,
+    },
 };
 use derivative::Derivative;
 use futures::{
     channel, future,
-    stream::{self, SelectAll},
+    stream,
     FutureExt, SinkExt, Stream, StreamExt, TryFuture, TryFutureExt, TryStream, TryStreamExt,
 };
-use kube::api::{Api, DynamicObject, ListParams, Resource};
+use kube::{
+    api::{Api, DynamicObject, ListParams, Resource},
+    Client,
+};
 use serde::de::DeserializeOwned;
 use snafu::{futures::TryStreamExt as SnafuTryStreamExt, Backtrace, ResultExt, Snafu};
-use std::{fmt::Debug, hash::Hash, sync::Arc, time::Duration};
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
 use stream::BoxStream;
-use tokio::{runtime::Handle, time::Instant};
+use tracing::Instrument;
 
+mod breadcrumbs;
 mod future_hash_map;
+mod gate;
+mod health;
+mod multi;
+mod rate_limit;
+mod relations;
+mod request;
 mod runner;
 
+pub use breadcrumbs::{breadcrumb, Breadcrumb, Breadcrumbs, DEFAULT_BREADCRUMB_ANNOTATION};
+pub use gate::object_condition;
+pub use health::{ControllerHealth, HealthSnapshot};
+pub use multi::{MultiController, MultiControllerEvent};
+pub use rate_limit::RateLimiter;
+pub use relations::{RelationHandle, Relations};
+pub use request::{ReconcileReason, ReconcileRequest};
+
 #[derive(Snafu, Debug)]
 pub enum Error<ReconcilerErr: std::error::Error + 'static, QueueErr: std::error::Error + 'static> {
     ObjectNotFound {
@@ -87,6 +115,43 @@ where
     })
 }
 
+/// Caches the objects of `stream` in `writer`, and queues those that were applied, or left a tombstone
+///
+/// Deleted objects only leave tombstones for [`Controller::reconcile_deletions`], this includes the
+/// objects that are missing when the watch relists.
+fn trigger_cached<K, S>(
+    mut writer: Writer<K>,
+    stream: S,
+) -> impl Stream<Item = Result<ReconcileRequest<K>, watcher::Error>>
+where
+    K: Clone + Resource + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+    S: Stream<Item = watcher::Result<watcher::Event<K>>>,
+{
+    stream
+        .map_ok(move |event| {
+            let buried = writer.apply_watcher_event_burying(&event);
+            let applied = event.into_iter_applied().map(|obj| writer.key(&obj));
+            let requests = applied
+                .chain(buried)
+                .map(|obj_ref| Ok(ReconcileRequest::new(obj_ref, ReconcileReason::ObjectUpdated)));
+            stream::iter(requests.collect::<Vec<_>>())
+        })
+        .try_flatten()
+}
+
+/// Queues the objects referred to by `stream` for `reason`
+fn requests_for<K, S>(
+    stream: S,
+    reason: ReconcileReason,
+) -> impl Stream<Item = Result<ReconcileRequest<K>, S::Error>>
+where
+    S: TryStream<Ok = ObjectRef<K>>,
+    K: Resource,
+{
+    stream.map_ok(move |obj_ref| ReconcileRequest::new(obj_ref, reason.clone()))
+}
+
 /// Enqueues any owners of type `KOwner` for reconciliation
 pub fn trigger_owners<KOwner, S>(
     stream: S,
@@ -109,6 +174,52 @@ where
     })
 }
 
+/// Name of the index of the uids of an object's owners, registered by [`trigger_owned_on_deletion`]
+pub const OWNER_UID_INDEX: &str = "kube-rs.io/owner-uid";
+
+/// Enqueues the cached objects of `K` owned by objects of `KOwner` that are deleted
+///
+/// `stream` should be a [`watcher`] of the owners, best passed through [`emit_tombstones`] so that owners
+/// deleted while the watch was interrupted are not missed. Owned objects are found in `store` through
+/// an index of the uids of their owners, which this registers as [`OWNER_UID_INDEX`].
+pub fn trigger_owned_on_deletion<KOwner, K, S>(
+    stream: S,
+    store: Store<K>,
+    dyntype: K::DynamicType,
+) -> impl Stream<Item = Result<ObjectRef<K>, S::Error>>
+where
+    S: TryStream<Ok = watcher::Event<KOwner>>,
+    KOwner: Resource,
+    K: Clone + Resource + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    store.add_index(OWNER_UID_INDEX, |obj: &K| {
+        obj.meta()
+            .owner_references
+            .iter()
+            .flatten()
+            .map(|owner| owner.uid.clone())
+            .collect()
+    });
+    stream
+        .try_filter_map(|event| {
+            future::ready(Ok(match event {
+                watcher::Event::Deleted(owner) => owner.meta().uid.clone(),
+                _ => None,
+            }))
+        })
+        .map_ok(move |uid| {
+            let dyntype = dyntype.clone();
+            stream::iter(
+                store
+                    .get_by_index(OWNER_UID_INDEX, &uid)
+                    .into_iter()
+                    .map(move |owned| Ok(ObjectRef::from_obj_with(&owned, dyntype.clone()))),
+            )
+        })
+        .try_flatten()
+}
+
 /// A context data type that's passed through to the controllers callbacks
 ///
 /// `Context` gets passed to both the `reconciler` and the `error_policy` callbacks,
@@ -146,82 +257,188 @@ impl<T> Context<T> {
 /// the [`reflector`] (piped through [`trigger_self`]). If your core objects own any subobjects then you
 /// can also make them trigger reconciliations by [merging](`futures::stream::select`) the [`reflector`]
 /// with a [`watcher`](watcher()) or [`reflector`](reflector()) for the subobject.
+/// Plain [`ObjectRef`]s are queued with the [`External`](ReconcileReason::External) reason, queue
+/// [`ReconcileRequest`]s to tell why an object is reconciled. The reason is recorded on the tracing span of
+/// the reconciliation, and returned with its outcome.
 ///
 /// This is the "hard-mode" version of [`Controller`], which allows you some more customization
 /// (such as triggering from arbitrary [`Stream`]s), at the cost of being a bit more verbose.
 pub fn applier<K, QueueStream, ReconcilerFut, T>(
+    reconciler: impl FnMut(K, Context<T>) -> ReconcilerFut,
+    error_policy: impl FnMut(&ReconcilerFut::Error, Context<T>) -> ReconcilerAction,
+    context: Context<T>,
+    store: Store<K>,
+    queue: QueueStream,
+) -> impl Stream<
+    Item = Result<(ReconcileRequest<K>, ReconcilerAction), Error<ReconcilerFut::Error, QueueStream::Error>>,
+>
+where
+    K: Clone + Resource + 'static,
+    K::DynamicType: Debug + Eq + Hash + Clone + Unpin,
+    ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Unpin,
+    ReconcilerFut::Error: std::error::Error + 'static,
+    QueueStream: TryStream,
+    QueueStream::Ok: Into<ReconcileRequest<K>>,
+    QueueStream::Error: std::error::Error + 'static,
+{
+    applier_inspected(
+        reconciler,
+        error_policy,
+        context,
+        store,
+        queue,
+        None,
+        &default_clock(),
+        None,
+        None,
+    )
+}
+
+/// Like [`applier`], optionally mirroring the queued objects into `inspector` and delaying retries with `rate_limiter`
+#[allow(clippy::type_complexity, clippy::too_many_arguments, clippy::too_many_lines)]
+pub(crate) fn applier_inspected<K, QueueStream, ReconcilerFut, T>(
     mut reconciler: impl FnMut(K, Context<T>) -> ReconcilerFut,
     mut error_policy: impl FnMut(&ReconcilerFut::Error, Context<T>) -> ReconcilerAction,
     context: Context<T>,
     store: Store<K>,
     queue: QueueStream,
-) -> impl Stream<Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, QueueStream::Error>>>
+    inspector: Option<&QueueInspector<ReconcileRequest<K>>>,
+    clock: &Arc<dyn Clock>,
+    rate_limiter: Option<RateLimiter>,
+    gates: Option<gate::RunningGates>,
+) -> impl Stream<
+    Item = Result<(ReconcileRequest<K>, ReconcilerAction), Error<ReconcilerFut::Error, QueueStream::Error>>,
+>
 where
     K: Clone + Resource + 'static,
     K::DynamicType: Debug + Eq + Hash + Clone + Unpin,
     ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Unpin,
     ReconcilerFut::Error: std::error::Error + 'static,
-    QueueStream: TryStream<Ok = ObjectRef<K>>,
+    QueueStream: TryStream,
+    QueueStream::Ok: Into<ReconcileRequest<K>>,
     QueueStream::Error: std::error::Error + 'static,
 {
+    let inspector = inspector.cloned();
+    let (queue_clock, scheduler_clock, requeue_clock) = (clock.clone(), clock.clone(), clock.clone());
     let err_context = context.clone();
-    let (scheduler_tx, scheduler_rx) = channel::mpsc::channel::<ScheduleRequest<ObjectRef<K>>>(100);
+    let limits = rate_limiter.map(|config| Arc::new(Mutex::new(rate_limit::RateLimits::new(config))));
+    let (queue_limits, runner_limits, requeue_limits) = (limits.clone(), limits.clone(), limits);
+    let (scheduler_tx, scheduler_rx) = channel::mpsc::channel::<ScheduleRequest<ReconcileRequest<K>>>(100);
     // Create a stream of ObjectRefs that need to be reconciled
     trystream_try_via(
         // input: stream combining scheduled tasks and user specified inputs event
         Box::pin(stream::select(
             // 1. inputs from users queue stream
-            queue.context(QueueError).map_ok(|obj_ref| ScheduleRequest {
-                message: obj_ref,
-                run_at: Instant::now() + Duration::from_millis(1),
+            queue.context(QueueError).map_ok(move |request| {
+                let request = request.into();
+                let mut run_at = queue_clock.now() + Duration::from_millis(1);
+                // objects that failed wait for their backoff, even if they changed
+                if let Some(limits) = &queue_limits {
+                    let limits = limits.lock().unwrap_or_else(PoisonError::into_inner);
+                    if let Some(retry_at) = limits.retry_at(&request.obj_ref) {
+                        run_at = run_at.max(retry_at);
+                    }
+                }
+                ScheduleRequest {
+                    message: request,
+                    run_at,
+                }
             }),
             // 2. requests sent to scheduler_tx
             scheduler_rx.map(Ok),
         )),
         // all the Oks from the select gets passed through the scheduler stream, and are then executed
         move |s| {
-            Runner::new(scheduler(s), move |obj_ref| {
-                let obj_ref = obj_ref.clone();
-                match store.get(&obj_ref) {
-                    Some(obj) => reconciler(obj, context.clone())
-                        .into_future()
-                        // Reconciler errors are OK from the applier's PoV, we need to apply the error policy
-                        // to them separately
-                        .map(|res| Ok((obj_ref, res)))
-                        .left_future(),
+            let scheduler = scheduler(s).with_clock(scheduler_clock);
+            let scheduler = match &inspector {
+                Some(inspector) => scheduler.with_inspector(inspector),
+                None => scheduler,
+            };
+            let running = inspector.clone();
+            Runner::new(scheduler, move |request| {
+                let request = request.clone();
+                let running = running.as_ref().map(QueueInspector::start);
+                let obj_ref = &request.obj_ref;
+                let obj = store.get(obj_ref).map(|obj| (obj, false)).or_else(|| {
+                    // deleted objects are only reconciled with the tombstone, see `reconcile_deletions`
+                    store.tombstone(obj_ref).map(|obj| (obj, true))
+                });
+                if let (None, Some(limits)) = (&obj, &runner_limits) {
+                    // the object is gone for good, and so is its backoff
+                    limits.lock().unwrap_or_else(PoisonError::into_inner).forget(obj_ref);
+                }
+                match obj {
+                    Some((obj, buried)) => {
+                        let tombstones = store.clone();
+                        let span = tracing::info_span!(
+                            "reconciling object",
+                            object.ref = %request.obj_ref,
+                            object.reason = %request.reason,
+                        );
+                        span.in_scope(|| reconciler(obj, context.clone()))
+                            .into_future()
+                            .instrument(span)
+                            // Reconciler errors are OK from the applier's PoV, we need to apply the error policy
+                            // to them separately
+                            .map(move |res| {
+                                drop(running);
+                                if buried && res.is_ok() {
+                                    tombstones.forget_tombstone(&request.obj_ref);
+                                }
+                                Ok((request, res))
+                            })
+                            .left_future()
+                    }
                     None => future::err(
                         ObjectNotFound {
-                            obj_ref: obj_ref.erase(),
+                            obj_ref: request.obj_ref.erase(),
                         }
                         .build(),
                     )
                     .right_future(),
                 }
             })
+            .gates(gates)
             .context(SchedulerDequeueFailed)
             .map(|res| res.and_then(|x| x))
         },
     )
     // finally, for each completed reconcile call:
-    .and_then(move |(obj_ref, reconciler_result)| {
-        let ReconcilerAction { requeue_after } = match &reconciler_result {
-            Ok(action) => action.clone(),                       // do what user told us
-            Err(err) => error_policy(err, err_context.clone()), // reconciler fn call failed
+    .and_then(move |(request, reconciler_result)| {
+        let (ReconcilerAction { requeue_after }, reason) = match &reconciler_result {
+            // do what user told us
+            Ok(action) => (action.clone(), ReconcileReason::ReconcilerRequestedRetry),
+            // reconciler fn call failed
+            Err(err) => (
+                error_policy(err, err_context.clone()),
+                ReconcileReason::ErrorPolicyRequestedRetry,
+            ),
         };
         let mut scheduler_tx = scheduler_tx.clone();
+        let now = requeue_clock.now();
+        let mut requeue_at = requeue_after.map(|delay| now + delay);
+        if let Some(limits) = &requeue_limits {
+            let mut limits = limits.lock().unwrap_or_else(PoisonError::into_inner);
+            if reconciler_result.is_ok() {
+                limits.forget(&request.obj_ref);
+            } else {
+                let retry_at = limits.failed(request.obj_ref.clone(), now);
+                requeue_at = requeue_at.map(|at| at.max(retry_at));
+            }
+        }
         async move {
             // Transmit the requeue request to the scheduler (picked up again at top)
-            if let Some(delay) = requeue_after {
+            if let Some(run_at) = requeue_at {
                 scheduler_tx
                     .send(ScheduleRequest {
-                        message: obj_ref.clone(),
-                        run_at: Instant::now() + delay,
+                        message: ReconcileRequest::new(request.obj_ref.clone(), reason),
+                        run_at,
                     })
                     .await
                     .expect("Message could not be sent to scheduler_rx");
             }
             reconciler_result
-                .map(|action| (obj_ref, action))
+                .map(|action| (request, action))
                 .context(ReconcilerFailed)
         }
     })
@@ -300,9 +517,18 @@ where
 {
     // NB: Need to Unpin for stream::select_all
     // TODO: get an arbitrary std::error::Error in here?
-    selector: SelectAll<BoxStream<'static, Result<ObjectRef<K>, watcher::Error>>>,
+    selector: Vec<BoxStream<'static, Result<ReconcileRequest<K>, watcher::Error>>>,
     dyntype: K::DynamicType,
     reader: Store<K>,
+    inspector: QueueInspector<ReconcileRequest<K>>,
+    health: ControllerHealth,
+    breadcrumbs: Option<breadcrumbs::Recorder<K>>,
+    gates: gate::Gates,
+    clock: Arc<dyn Clock>,
+    executor: Arc<dyn Executor>,
+    relations: Relations<K>,
+    dynamic_triggers: relations::DynamicTriggers<K>,
+    rate_limiter: Option<RateLimiter>,
 }
 
 impl<K> Controller<K>
@@ -318,6 +544,38 @@ where
     pub fn new(owned_api: Api<K>, lp: ListParams) -> Self {
         Self::new_with(owned_api, lp, Default::default())
     }
+
+    /// Create a Controller on a type `K`, scoped by a [`watcher::Config`]
+    ///
+    /// Use this to restrict the root watch to a set of namespaces, for example when RBAC
+    /// does not allow watching `K` across the whole cluster.
+    #[must_use]
+    pub fn new_scoped(owned_api: Api<K>, config: watcher::Config) -> Self {
+        Self::new_scoped_with(owned_api, config, Default::default())
+    }
+
+    /// Create a Controller on a type `K`, watching a changing set of namespaces
+    ///
+    /// Namespaces can be added to and removed from `namespaces` while the controller is running,
+    /// see [`NamespaceSet`].
+    #[must_use]
+    pub fn new_dynamic(owned_api: Api<K>, config: watcher::Config, namespaces: &NamespaceSet) -> Self
+    where
+        K::DynamicType: Send,
+    {
+        Self::new_dynamic_with(owned_api, config, namespaces, Default::default())
+    }
+
+    /// Create a Controller on a type `K`, driven by an existing stream of watch events
+    ///
+    /// This is useful for sharing a [`watcher`] with other consumers, or for feeding the controller
+    /// scripted events in tests.
+    #[must_use]
+    pub fn for_stream(
+        trigger: impl Stream<Item = Result<watcher::Event<K>, watcher::Error>> + Send + 'static,
+    ) -> Self {
+        Self::for_stream_with(trigger, Default::default())
+    }
 }
 
 impl<K> Controller<K>
@@ -333,19 +591,73 @@ where
     /// Unlike `new`, this function accepts `K::DynamicType` so it can be used with dynamic
     /// resources.
     pub fn new_with(owned_api: Api<K>, lp: ListParams, dyntype: K::DynamicType) -> Self {
+        Self::new_scoped_with(owned_api, lp.into(), dyntype)
+    }
+
+    /// Create a Controller on a type `K`, scoped by a [`watcher::Config`]
+    ///
+    /// Unlike `new_scoped`, this function accepts `K::DynamicType` so it can be used with dynamic
+    /// resources.
+    pub fn new_scoped_with(owned_api: Api<K>, config: watcher::Config, dyntype: K::DynamicType) -> Self {
+        let executor = owned_api.clone().into_client().executor();
+        let watcher = scoped_watcher_with(owned_api, config, &dyntype);
+        Self::from_watcher(watcher, dyntype, executor)
+    }
+
+    /// Create a Controller on a type `K`, watching a changing set of namespaces
+    ///
+    /// Unlike `new_dynamic`, this function accepts `K::DynamicType` so it can be used with dynamic
+    /// resources.
+    pub fn new_dynamic_with(
+        owned_api: Api<K>,
+        config: watcher::Config,
+        namespaces: &NamespaceSet,
+        dyntype: K::DynamicType,
+    ) -> Self
+    where
+        K::DynamicType: Send,
+    {
+        let executor = owned_api.clone().into_client().executor();
+        let watcher = dynamic_watcher_with(owned_api, config, namespaces, dyntype.clone());
+        Self::from_watcher(watcher, dyntype, executor)
+    }
+
+    /// Create a Controller on a type `K`, driven by an existing stream of watch events
+    ///
+    /// Unlike `for_stream`, this function accepts `K::DynamicType` so it can be used with dynamic
+    /// resources.
+    pub fn for_stream_with(
+        trigger: impl Stream<Item = Result<watcher::Event<K>, watcher::Error>> + Send + 'static,
+        dyntype: K::DynamicType,
+    ) -> Self {
+        Self::from_watcher(trigger.boxed(), dyntype, default_executor())
+    }
+
+    fn from_watcher(
+        watcher: BoxStream<'static, Result<watcher::Event<K>, watcher::Error>>,
+        dyntype: K::DynamicType,
+        executor: Arc<dyn Executor>,
+    ) -> Self {
         let writer = Writer::<K>::new(dyntype.clone());
         let reader = writer.as_reader();
-        let mut selector = stream::SelectAll::new();
-        let self_watcher = trigger_self(
-            try_flatten_applied(reflector(writer, watcher(owned_api, lp))),
-            dyntype.clone(),
-        )
-        .boxed();
-        selector.push(self_watcher);
+        let health = ControllerHealth::new();
+        let root_health = health.clone();
+        let watcher = watcher.inspect(move |event| root_health.record_root_watch(event));
+        let selector = vec![trigger_cached(writer, watcher).boxed()];
+        let (relations, dynamic_triggers) = Relations::new(dyntype.clone());
         Self {
             selector,
             reader,
             dyntype,
+            inspector: QueueInspector::new(),
+            health,
+            breadcrumbs: None,
+            gates: gate::Gates::default(),
+            clock: default_clock(),
+            executor,
+            relations,
+            dynamic_triggers,
+            rate_limiter: None,
         }
     }
 
@@ -354,6 +666,41 @@ where
         self.reader.clone()
     }
 
+    /// Retrieve a handle to the queue of objects waiting to be reconciled
+    ///
+    /// ```no_run
+    /// # use kube_runtime::Controller;
+    /// # use k8s_openapi::api::core::v1::ConfigMap;
+    /// # fn scope(controller: Controller<ConfigMap>) {
+    /// let queue = controller.queue_inspector();
+    /// // e.g. from a metrics endpoint
+    /// println!("{} queued, {} running, oldest waiting for {:?}", queue.depth(), queue.running(), queue.oldest_age());
+    /// for queued in queue.next_scheduled(5) {
+    ///     println!("{} at {:?}", queued.message, queued.run_at);
+    /// }
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn queue_inspector(&self) -> QueueInspector<ReconcileRequest<K>> {
+        self.inspector.clone()
+    }
+
+    /// Retrieve a handle reporting the health of the controller once it runs
+    ///
+    /// See [`ControllerHealth`].
+    #[must_use]
+    pub fn health(&self) -> ControllerHealth {
+        self.health.clone()
+    }
+
+    /// Retrieve a handle for adding (and removing) relations while the controller is running
+    ///
+    /// See [`Relations`].
+    #[must_use]
+    pub fn relations(&self) -> Relations<K> {
+        self.relations.clone()
+    }
+
     /// Indicate child objets `K` owns and be notified when they change
     ///
     /// This type `Child` must have [`OwnerReference`] set to point back to `K`.
@@ -361,6 +708,9 @@ where
     /// only a subset of `Child` entries are required.
     /// The `api` must have the correct scope (cluster/all namespaces, or namespaced)
     ///
+    /// Children that are deleted while the watch is interrupted do not trigger their owners,
+    /// use [`owns_with_tombstones`](Self::owns_with_tombstones) for that.
+    ///
     /// [`OwnerReference`]: https://docs.rs/k8s-openapi/0.10.0/k8s_openapi/apimachinery/pkg/apis/meta/v1/struct.OwnerReference.html
     pub fn owns<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
         mut self,
@@ -371,13 +721,129 @@ where
         Child::DynamicType: Debug + Eq + Hash,
     {
         let child_watcher = trigger_owners(try_flatten_touched(watcher(api, lp)), self.dyntype.clone());
-        self.selector.push(child_watcher.boxed());
+        self.selector
+            .push(requests_for(child_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Indicate child objects `K` owns, and also trigger their owners when they are deleted while
+    /// the watch is interrupted
+    ///
+    /// Like [`owns`](Self::owns), but the children are passed through [`emit_tombstones`],
+    /// which keeps a copy of every watched child in memory.
+    #[must_use]
+    pub fn owns_with_tombstones<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
+        mut self,
+        api: Api<Child>,
+        lp: ListParams,
+    ) -> Self
+    where
+        Child::DynamicType: Debug + Eq + Hash,
+    {
+        let child_watcher = trigger_owners(
+            try_flatten_touched(emit_tombstones(watcher(api, lp))),
+            self.dyntype.clone(),
+        );
+        self.selector
+            .push(requests_for(child_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Indicate child objects `K` owns, scoped by a [`watcher::Config`]
+    ///
+    /// Like [`owns`](Self::owns), but the child watch can be restricted to a set of namespaces
+    /// independently of the root watch.
+    #[must_use]
+    pub fn owns_scoped<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
+        mut self,
+        api: Api<Child>,
+        config: watcher::Config,
+    ) -> Self
+    where
+        Child::DynamicType: Debug + Eq + Hash + Default,
+    {
+        let child_watcher = trigger_owners(
+            try_flatten_touched(scoped_watcher(api, config)),
+            self.dyntype.clone(),
+        );
+        self.selector
+            .push(requests_for(child_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Indicate child objects `K` owns, in a changing set of namespaces
+    ///
+    /// Like [`owns`](Self::owns), but the child watch follows a [`NamespaceSet`], which may be shared
+    /// with the root watch of [`new_dynamic`](Self::new_dynamic).
+    #[must_use]
+    pub fn owns_dynamic<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
+        mut self,
+        api: Api<Child>,
+        config: watcher::Config,
+        namespaces: &NamespaceSet,
+    ) -> Self
+    where
+        Child::DynamicType: Debug + Eq + Hash + Default + Send,
+    {
+        let child_watcher = trigger_owners(
+            try_flatten_touched(dynamic_watcher(api, config, namespaces)),
+            self.dyntype.clone(),
+        );
+        self.selector
+            .push(requests_for(child_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Reconcile the cached objects of `K` whose owner of type `Owner` is deleted
+    ///
+    /// This lets a controller of owned objects, like one of `Pod`s owned by a custom resource, run its
+    /// cleanup logic when the owner goes away, rather than relying on seeing the deletion of every owned
+    /// object. Owners deleted while the watch is interrupted are missed, use
+    /// [`reconcile_on_owner_deletion_with_tombstones`](Self::reconcile_on_owner_deletion_with_tombstones)
+    /// to catch them on the next relist. See [`trigger_owned_on_deletion`].
+    #[must_use]
+    pub fn reconcile_on_owner_deletion<
+        Owner: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+    >(
+        mut self,
+        api: Api<Owner>,
+        lp: ListParams,
+    ) -> Self {
+        let owned = trigger_owned_on_deletion(watcher(api, lp), self.reader.clone(), self.dyntype.clone());
+        self.selector
+            .push(requests_for(owned, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Reconcile the cached objects of `K` whose owner of type `Owner` is deleted, even while the watch
+    /// is interrupted
+    ///
+    /// Like [`reconcile_on_owner_deletion`](Self::reconcile_on_owner_deletion), but the owners are passed
+    /// through [`emit_tombstones`], which keeps a copy of every watched owner in memory.
+    #[must_use]
+    pub fn reconcile_on_owner_deletion_with_tombstones<
+        Owner: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+    >(
+        mut self,
+        api: Api<Owner>,
+        lp: ListParams,
+    ) -> Self {
+        let owned = trigger_owned_on_deletion(
+            emit_tombstones(watcher(api, lp)),
+            self.reader.clone(),
+            self.dyntype.clone(),
+        );
+        self.selector
+            .push(requests_for(owned, ReconcileReason::RelatedObjectUpdated).boxed());
         self
     }
 
     /// Indicate an object to watch with a custom mapper
     ///
     /// This mapper should return something like `Option<ObjectRef<K>>`
+    ///
+    /// `api` may belong to another cluster. The mapper can refer to objects of `K` in any cluster, use
+    /// [`ObjectRef::in_cluster`] for clusters added with [`with_cluster`](Self::with_cluster).
     pub fn watches<
         Other: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
         I: 'static + IntoIterator<Item = ObjectRef<K>>,
@@ -391,7 +857,251 @@ where
         I::IntoIter: Send,
     {
         let other_watcher = trigger_with(try_flatten_touched(watcher(api, lp)), mapper);
-        self.selector.push(other_watcher.boxed());
+        self.selector
+            .push(requests_for(other_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Indicate an object to watch with a custom mapper, and also map the objects deleted while
+    /// the watch is interrupted
+    ///
+    /// Like [`watches`](Self::watches), but the objects are passed through [`emit_tombstones`],
+    /// which keeps a copy of every watched object in memory.
+    #[must_use]
+    pub fn watches_with_tombstones<
+        Other: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+        I: 'static + IntoIterator<Item = ObjectRef<K>>,
+    >(
+        mut self,
+        api: Api<Other>,
+        lp: ListParams,
+        mapper: impl Fn(Other) -> I + Send + 'static,
+    ) -> Self
+    where
+        I::IntoIter: Send,
+    {
+        let other_watcher = trigger_with(try_flatten_touched(emit_tombstones(watcher(api, lp))), mapper);
+        self.selector
+            .push(requests_for(other_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Indicate an object to watch with a custom mapper, scoped by a [`watcher::Config`]
+    ///
+    /// Like [`watches`](Self::watches), but the watch can be restricted to a set of namespaces
+    /// independently of the root watch.
+    #[must_use]
+    pub fn watches_scoped<
+        Other: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+        I: 'static + IntoIterator<Item = ObjectRef<K>>,
+    >(
+        mut self,
+        api: Api<Other>,
+        config: watcher::Config,
+        mapper: impl Fn(Other) -> I + Send + 'static,
+    ) -> Self
+    where
+        I::IntoIter: Send,
+        Other::DynamicType: Default,
+    {
+        let other_watcher = trigger_with(try_flatten_touched(scoped_watcher(api, config)), mapper);
+        self.selector
+            .push(requests_for(other_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Also reconcile the objects of `K` in another cluster
+    ///
+    /// `api` should be created from a [`Client`](kube::Client) for the other cluster. Its objects are cached
+    /// in the same [`store`](Self::store), under [`ObjectRef`]s to the cluster. The requests returned by
+    /// [`run`](Self::run) carry the cluster in their [`ObjectRef`], and reconcilers can find the cluster of
+    /// an object with [`Store::key_of`].
+    #[must_use]
+    pub fn with_cluster(self, cluster: &str, api: Api<K>, lp: ListParams) -> Self {
+        self.with_cluster_stream(cluster, watcher(api, lp))
+    }
+
+    /// Also reconcile the objects of `K` in another cluster, driven by an existing stream of watch events
+    ///
+    /// See [`with_cluster`](Self::with_cluster).
+    #[must_use]
+    pub fn with_cluster_stream(
+        mut self,
+        cluster: &str,
+        trigger: impl Stream<Item = Result<watcher::Event<K>, watcher::Error>> + Send + 'static,
+    ) -> Self {
+        let writer = Writer::sharing(&self.reader, self.dyntype.clone(), Some(cluster.to_string()));
+        self.selector.push(trigger_cached(writer, trigger).boxed());
+        self
+    }
+
+    /// Indicate child objects `K` owns in another cluster
+    ///
+    /// Like [`owns`](Self::owns), but the children (and their owners) are in the cluster `api` belongs to,
+    /// which should have been added with [`with_cluster`](Self::with_cluster).
+    /// Owner references can not point across clusters.
+    #[must_use]
+    pub fn owns_in_cluster<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
+        mut self,
+        cluster: &str,
+        api: Api<Child>,
+        lp: ListParams,
+    ) -> Self
+    where
+        Child::DynamicType: Debug + Eq + Hash,
+    {
+        let cluster = cluster.to_string();
+        let child_watcher = trigger_owners(try_flatten_touched(watcher(api, lp)), self.dyntype.clone())
+            .map_ok(move |owner| owner.in_cluster(&cluster));
+        self.selector
+            .push(requests_for(child_watcher, ReconcileReason::RelatedObjectUpdated).boxed());
+        self
+    }
+
+    /// Reconcile every cached object of `K` every `period`, without contacting the apiserver
+    ///
+    /// This matches the resync of client-go, and can be used to correct drift in external
+    /// systems that do not trigger any watch events. See [`resync`].
+    #[must_use]
+    pub fn resync_every(mut self, period: Duration) -> Self {
+        // Keys rather than objects, which don't tell what cluster they are in
+        let resync = resync_keys_with_clock(self.reader.clone(), period, self.clock.clone())
+            .map(Ok::<_, watcher::Error>);
+        self.selector
+            .push(requests_for(resync, ReconcileReason::Resync).boxed());
+        self
+    }
+
+    /// Record the outcome of every reconciliation in an annotation of the reconciled object
+    ///
+    /// After each reconciliation, the controller server-side applies a [`Breadcrumb`] with the reconciled
+    /// `generation`, the outcome, a hash of the result and a timestamp, owned by the configured field manager.
+    /// This shows whether (and how) the controller processed an object with plain `kubectl`:
+    ///
+    /// ```text
+    /// kubectl get configmap foo -o jsonpath='{.metadata.annotations.kube-rs\.io/last-reconciled}'
+    /// ```
+    ///
+    /// The annotation is only patched when the generation, outcome or hash changed, so that the patch does not
+    /// keep retriggering reconciliations. Failures to patch are ignored.
+    #[must_use]
+    pub fn breadcrumbs(mut self, client: Client, config: Breadcrumbs) -> Self {
+        self.breadcrumbs = Some(breadcrumbs::Recorder::new(client, config, self.dyntype.clone()));
+        self
+    }
+
+    /// Schedule requeues and resyncs on `clock`, rather than the [`TokioClock`](crate::time::TokioClock)
+    ///
+    /// This lets tests control time with a [`MockClock`](crate::time::MockClock). Call this before
+    /// [`Controller::resync_every`], [`Controller::queue_inspector`], [`Controller::health`] and
+    /// [`Controller::reconcile_deletions`], which use the clock at the time they are called.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.inspector = QueueInspector::with_clock(clock.clone());
+        self.health = self.health.with_clock(clock.clone());
+        self.clock = clock;
+        self
+    }
+
+    /// Spawn reconciliations (and the drivers of [gates](Controller::gate)) with `executor`
+    ///
+    /// By default they are spawned with the [executor](kube::Client::with_executor) of the client of the
+    /// root `Api`, or onto the current Tokio runtime for [`for_stream`](Self::for_stream).
+    /// Combine this with [`Controller::with_clock`] to run the controller on another runtime.
+    #[must_use]
+    pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Reconcile objects once more after they are deleted, with their final state
+    ///
+    /// The reconciler is passed the tombstone of the deleted object, so cleanup logic can still see
+    /// its labels and spec. It can tell deleted objects apart by them missing from the [`store`](Self::store),
+    /// where their [`tombstone`](Store::tombstone) is kept until a reconciliation of it succeeds, the
+    /// object is recreated, or `ttl` has passed, after which retries of it fail with
+    /// [`ObjectNotFound`](Error::ObjectNotFound). Objects deleted while the watch was interrupted are
+    /// reconciled once it relists.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams}, Client};
+    /// use kube_runtime::{controller::{Context, Controller, ReconcilerAction}, reflector::{ObjectRef, Store}};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// use std::time::Duration;
+    /// # fn scope(client: Client) {
+    /// let controller = Controller::new(Api::<ConfigMap>::all(client), ListParams::default())
+    ///     .reconcile_deletions(Duration::from_secs(300));
+    /// let store = controller.store();
+    /// controller.run(
+    ///     |cm, ctx: Context<Store<ConfigMap>>| async move {
+    ///         if ctx.get_ref().get(&ObjectRef::from_obj(&cm)).is_none() {
+    ///             // clean up after the deleted `cm`
+    ///         }
+    ///         Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None })
+    ///     },
+    ///     |_err, _ctx| ReconcilerAction { requeue_after: None },
+    ///     Context::new(store),
+    /// );
+    /// # }
+    /// ```
+    ///
+    /// Deletions are not guaranteed to be seen, for example while the controller is not running.
+    /// Use finalizers for cleanup that must happen.
+    #[must_use]
+    pub fn reconcile_deletions(self, ttl: Duration) -> Self {
+        self.reader.retain_tombstones_with_clock(ttl, self.clock.clone());
+        self
+    }
+
+    /// Delay retries of objects that keep failing with `rate_limiter`
+    ///
+    /// By default, objects are retried as the `error_policy` says, and right away when they change.
+    /// With a rate limiter, the requeue of a failed object is delayed by its exponential backoff, and
+    /// changes do not trigger it before that, so flapping objects don't churn:
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams}, Client};
+    /// use kube_runtime::controller::{Controller, RateLimiter};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// use std::time::Duration;
+    /// # fn scope(client: Client) {
+    /// let controller = Controller::new(Api::<ConfigMap>::all(client), ListParams::default())
+    ///     .rate_limit(RateLimiter::new().item_backoff(Duration::from_secs(1), Duration::from_secs(300)));
+    /// # }
+    /// ```
+    ///
+    /// The `error_policy` still decides whether a failed object is retried at all.
+    #[must_use]
+    pub fn rate_limit(mut self, rate_limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Hold back reconciliations until `condition` is met
+    ///
+    /// The gate starts out closed, and opens or closes whenever `condition` emits `true` or `false`.
+    /// If `condition` ends, the gate stays as it was last. Reconciliations only start once all gates
+    /// of the controller are open, which replaces ad-hoc sleeps while dependencies (like a CRD being
+    /// established or a `Secret` being created) are bootstrapped. See [`object_condition`].
+    ///
+    /// Objects are queued as usual while the gates are closed, and are read from the cache once
+    /// the gates open, so reconciliations see their latest state. Reconciliations that already
+    /// started when a gate closes run to completion. The conditions are driven by tasks spawned
+    /// by [`Controller::run`] on the [executor](Controller::with_executor).
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams}, Client};
+    /// use kube_runtime::controller::{object_condition, Controller};
+    /// use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+    /// # fn scope(client: Client) {
+    /// let secrets: Api<Secret> = Api::namespaced(client.clone(), "operator");
+    /// let controller = Controller::new(Api::<ConfigMap>::all(client), ListParams::default())
+    ///     .gate(object_condition(secrets, "credentials", |secret| secret.is_some()));
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn gate(mut self, condition: impl Stream<Item = bool> + Send + 'static) -> Self {
+        self.gates.push(condition.boxed());
         self
     }
 
@@ -401,31 +1111,64 @@ where
     /// a specified `reconciler` and `error_policy` callbacks. Each of these will be called
     /// with a configurable [`Context`].
     pub fn run<ReconcilerFut, T>(
-        self,
+        mut self,
         mut reconciler: impl FnMut(K, Context<T>) -> ReconcilerFut,
         error_policy: impl FnMut(&ReconcilerFut::Error, Context<T>) -> ReconcilerAction,
         context: Context<T>,
-    ) -> impl Stream<Item = Result<(ObjectRef<K>, ReconcilerAction), Error<ReconcilerFut::Error, watcher::Error>>>
+    ) -> impl Stream<
+        Item = Result<(ReconcileRequest<K>, ReconcilerAction), Error<ReconcilerFut::Error, watcher::Error>>,
+    >
     where
         K::DynamicType: Debug + Unpin,
         ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Send + 'static,
         ReconcilerFut::Error: std::error::Error + Send + 'static,
     {
-        applier(
+        self.selector.push(self.dynamic_triggers.boxed());
+        let breadcrumbs = self.breadcrumbs;
+        let gates = self.gates.spawn(&*self.executor);
+        let executor = self.executor;
+        let health = self.health;
+        let selector = stream::select_all(self.selector.into_iter().enumerate().map(|(watch, trigger)| {
+            let health = health.clone();
+            trigger.inspect(move |item| health.record_watch(watch, item.as_ref().err()))
+        }));
+        applier_inspected(
             move |obj, ctx| {
-                CancelableJoinHandle::spawn(reconciler(obj, ctx).into_future(), &Handle::current())
+                let breadcrumbs = breadcrumbs.clone().map(|recorder| {
+                    let target = recorder.target(&obj);
+                    (recorder, target)
+                });
+                let reconciliation = reconciler(obj, ctx).into_future();
+                let health = health.clone();
+                CancelableJoinHandle::spawn_on(
+                    // keep the span of the reconciliation, with the object and why it is reconciled
+                    async move {
+                        let result = reconciliation.await;
+                        health.record_reconcile(result.is_ok());
+                        if let Some((recorder, target)) = breadcrumbs {
+                            recorder.record(target, breadcrumbs::outcome(&result)).await;
+                        }
+                        result
+                    }
+                    .in_current_span(),
+                    &*executor,
+                )
             },
             error_policy,
             context,
             self.reader,
-            self.selector,
+            selector,
+            Some(&self.inspector),
+            &self.clock,
+            self.rate_limiter,
+            gates,
         )
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Context, ReconcilerAction};
+    use super::{trigger_owned_on_deletion, Context, ReconcilerAction};
     use crate::Controller;
     use k8s_openapi::api::core::v1::ConfigMap;
     use kube::Api;
@@ -451,4 +1194,345 @@ mod tests {
             ),
         );
     }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_failures_back_off() {
+        use super::RateLimiter;
+        use crate::testing::Script;
+        use futures::StreamExt;
+        use std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        };
+        use tokio::time::Instant;
+
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("flapping".into());
+        let attempts = Arc::new(Mutex::new(vec![]));
+        let seen = attempts.clone();
+        let start = Instant::now();
+        Controller::for_stream(Script::new().applied(cm).into_stream())
+            .rate_limit(RateLimiter::new().item_backoff(Duration::from_secs(1), Duration::from_secs(60)))
+            .run(
+                move |_, _| {
+                    seen.lock().unwrap().push(start.elapsed().as_secs());
+                    async { Err::<ReconcilerAction, _>(kube::Error::RequestValidation("flapping".into())) }
+                },
+                |_, _| ReconcilerAction {
+                    requeue_after: Some(Duration::from_millis(1)),
+                },
+                Context::new(()),
+            )
+            .take(4)
+            .for_each(|_| async {})
+            .await;
+        assert_eq!(*attempts.lock().unwrap(), vec![0, 1, 3, 7]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn outcomes_tell_why_objects_were_reconciled() {
+        use super::ReconcileReason;
+        use crate::testing::Script;
+        use futures::StreamExt;
+        use std::time::Duration;
+
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("cm".into());
+        let reasons = Controller::for_stream(Script::new().applied(cm).into_stream())
+            .run(
+                |_, _| async {
+                    Ok::<_, kube::Error>(ReconcilerAction {
+                        requeue_after: Some(Duration::from_secs(1)),
+                    })
+                },
+                |_, _| ReconcilerAction { requeue_after: None },
+                Context::new(()),
+            )
+            .take(2)
+            .map(|res| res.unwrap().0.reason)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(reasons, vec![
+            ReconcileReason::ObjectUpdated,
+            ReconcileReason::ReconcilerRequestedRetry
+        ]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn updates_replace_the_reason_of_pending_retries() {
+        use super::ReconcileReason;
+        use crate::testing::Script;
+        use futures::StreamExt;
+        use std::{
+            sync::{
+                atomic::{AtomicBool, Ordering},
+                Arc,
+            },
+            time::Duration,
+        };
+
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("cm".into());
+        let script = Script::new()
+            .applied(cm.clone())
+            .advance(Duration::from_secs(1))
+            .applied(cm);
+        let failed = Arc::new(AtomicBool::new(false));
+        let results = Controller::for_stream(script.into_stream())
+            .run(
+                move |_, _| {
+                    let fail = !failed.swap(true, Ordering::SeqCst);
+                    async move {
+                        if fail {
+                            Err(kube::Error::RequestValidation("failing".into()))
+                        } else {
+                            Ok(ReconcilerAction { requeue_after: None })
+                        }
+                    }
+                },
+                |_, _| ReconcilerAction {
+                    requeue_after: Some(Duration::from_secs(10)),
+                },
+                Context::new(()),
+            )
+            .take(2)
+            .collect::<Vec<_>>()
+            .await;
+        assert!(results[0].is_err());
+        // the update came in while the retry was pending, and is reconciled right away
+        let (request, _) = results[1].as_ref().unwrap();
+        assert_eq!(request.reason, ReconcileReason::ObjectUpdated);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn queue_inspector_counts_running_reconciliations() {
+        use crate::testing::Script;
+        use futures::StreamExt;
+        use std::sync::{Arc, Mutex};
+
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("cm".into());
+        let controller = Controller::for_stream(Script::new().applied(cm).into_stream());
+        let queue = controller.queue_inspector();
+        let inspector = queue.clone();
+        let seen = Arc::new(Mutex::new(None));
+        let during = seen.clone();
+        controller
+            .run(
+                move |_, _| {
+                    *during.lock().unwrap() = Some((inspector.depth(), inspector.running()));
+                    async { Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None }) }
+                },
+                |_, _| ReconcilerAction { requeue_after: None },
+                Context::new(()),
+            )
+            .take(1)
+            .for_each(|_| async {})
+            .await;
+        assert_eq!(*seen.lock().unwrap(), Some((0, 1)));
+        assert_eq!((queue.depth(), queue.running()), (0, 0));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gated_reconciliations_see_the_latest_object() {
+        use crate::testing::Script;
+        use futures::StreamExt;
+        use std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        };
+
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("cm".into());
+        cm.data = Some([("v".to_string(), "1".to_string())].into());
+        let mut updated = cm.clone();
+        updated.data = Some([("v".to_string(), "2".to_string())].into());
+        let (open, condition) = futures::channel::mpsc::unbounded();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(10)).await;
+            open.unbounded_send(true).unwrap();
+        });
+        let seen = Arc::new(Mutex::new(vec![]));
+        let reconciled = seen.clone();
+        Controller::for_stream(Script::new().applied(cm).applied(updated).into_stream())
+            .gate(condition)
+            .run(
+                move |cm: ConfigMap, _| {
+                    reconciled.lock().unwrap().push(cm.data.unwrap()["v"].clone());
+                    async { Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None }) }
+                },
+                |_, _| ReconcilerAction { requeue_after: None },
+                Context::new(()),
+            )
+            .take(1)
+            .for_each(|_| async {})
+            .await;
+        assert_eq!(*seen.lock().unwrap(), vec!["2".to_string()]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn with_cluster_reconciles_objects_of_other_clusters() {
+        use crate::{
+            reflector::{ObjectRef, Store},
+            testing::Script,
+        };
+        use futures::StreamExt;
+        use std::sync::{Arc, Mutex};
+
+        let cm = |uid: &str| {
+            let mut cm = ConfigMap::default();
+            cm.metadata.name = Some("cm".into());
+            cm.metadata.uid = Some(uid.into());
+            cm
+        };
+        let controller = Controller::for_stream(Script::new().applied(cm("hub-uid")).into_stream())
+            .with_cluster_stream("spoke", Script::new().applied(cm("spoke-uid")).into_stream());
+        let store = controller.store();
+        let seen = Arc::new(Mutex::new(vec![]));
+        let sink = seen.clone();
+        let mut reconciled = controller
+            .run(
+                move |cm: ConfigMap, ctx: Context<Store<ConfigMap>>| {
+                    let key = ctx.get_ref().key_of(&cm).unwrap();
+                    sink.lock()
+                        .unwrap()
+                        .push((key.cluster().map(String::from), cm.metadata.uid.unwrap()));
+                    async { Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None }) }
+                },
+                |_, _| ReconcilerAction { requeue_after: None },
+                Context::new(store),
+            )
+            .take(2)
+            .map(|res| res.unwrap().0.obj_ref)
+            .collect::<Vec<_>>()
+            .await;
+        reconciled.sort_by_key(|obj_ref| obj_ref.cluster().map(String::from));
+        assert_eq!(reconciled, vec![
+            ObjectRef::new("cm"),
+            ObjectRef::new("cm").in_cluster("spoke")
+        ]);
+        let mut seen = seen.lock().unwrap().clone();
+        seen.sort();
+        assert_eq!(seen, vec![
+            (None, "hub-uid".to_string()),
+            (Some("spoke".to_string()), "spoke-uid".to_string())
+        ]);
+    }
+
+    /// The `state` label of the objects the reconciler was passed, and whether they were deleted
+    async fn reconciled_states(script: crate::testing::Script<ConfigMap>) -> Vec<(String, bool)> {
+        use crate::reflector::{ObjectRef, Store};
+        use futures::StreamExt;
+        use std::{
+            sync::{Arc, Mutex},
+            time::Duration,
+        };
+
+        let controller =
+            Controller::for_stream(script.into_stream()).reconcile_deletions(Duration::from_secs(30));
+        let store = controller.store();
+        let seen = Arc::new(Mutex::new(vec![]));
+        let sink = seen.clone();
+        controller
+            .run(
+                move |cm: ConfigMap, ctx: Context<Store<ConfigMap>>| {
+                    let deleted = ctx.get_ref().get(&ObjectRef::from_obj(&cm)).is_none();
+                    sink.lock()
+                        .unwrap()
+                        .push((cm.metadata.labels.unwrap()["state"].clone(), deleted));
+                    async { Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None }) }
+                },
+                |_, _| ReconcilerAction { requeue_after: None },
+                Context::new(store.clone()),
+            )
+            .take(2)
+            .for_each(|_| async {})
+            .await;
+        // successful reconciliations forget the tombstone
+        assert_eq!(store.tombstone(&ObjectRef::new("cm")), None);
+        let seen = seen.lock().unwrap().clone();
+        seen
+    }
+
+    fn cm_in_state(state: &str) -> ConfigMap {
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("cm".into());
+        cm.metadata.labels = Some(std::iter::once(("state".to_string(), state.to_string())).collect());
+        cm
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn deleted_objects_are_reconciled_with_their_tombstone() {
+        use crate::testing::Script;
+        use std::time::Duration;
+
+        let script = Script::new()
+            .applied(cm_in_state("alive"))
+            .advance(Duration::from_secs(1))
+            .deleted(cm_in_state("final"));
+        assert_eq!(reconciled_states(script).await, vec![
+            ("alive".to_string(), false),
+            ("final".to_string(), true)
+        ]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn objects_missing_from_a_relist_are_reconciled_with_their_tombstone() {
+        use crate::testing::Script;
+        use std::time::Duration;
+
+        let script = Script::new()
+            .applied(cm_in_state("alive"))
+            .advance(Duration::from_secs(1))
+            .restarted(vec![]);
+        assert_eq!(reconciled_states(script).await, vec![
+            ("alive".to_string(), false),
+            ("alive".to_string(), true)
+        ]);
+    }
+
+    #[tokio::test]
+    async fn deleted_owners_trigger_the_objects_they_own() {
+        use crate::{
+            reflector::{store::Writer, ObjectRef},
+            watcher,
+        };
+        use futures::{stream, TryStreamExt};
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
+
+        let owner = |name: &str| {
+            let mut owner = ConfigMap::default();
+            owner.metadata.name = Some(name.into());
+            owner.metadata.uid = Some(format!("uid-{}", name));
+            owner
+        };
+        let owned = |name: &str, owner: &str| {
+            let mut owned = ConfigMap::default();
+            owned.metadata.name = Some(name.into());
+            owned.metadata.owner_references = Some(vec![OwnerReference {
+                api_version: "v1".into(),
+                kind: "ConfigMap".into(),
+                name: owner.into(),
+                uid: format!("uid-{}", owner),
+                ..OwnerReference::default()
+            }]);
+            owned
+        };
+        let mut writer = Writer::<ConfigMap>::new(());
+        writer.apply_watcher_event(&watcher::Event::Restarted(vec![
+            owned("a", "x"),
+            owned("b", "x"),
+            owned("c", "y"),
+        ]));
+        let events = stream::iter(vec![
+            Ok::<_, std::convert::Infallible>(watcher::Event::Applied(owner("y"))),
+            Ok(watcher::Event::Deleted(owner("x"))),
+        ]);
+        let mut triggered = trigger_owned_on_deletion(events, writer.as_reader(), ())
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        triggered.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(triggered, vec![ObjectRef::new("a"), ObjectRef::new("b")]);
+    }
 }
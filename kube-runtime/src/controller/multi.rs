@@ -0,0 +1,316 @@
+use crate::{
+    executor::Executor,
+    utils::CancelableJoinHandle,
+    watcher::{self, watcher},
+};
+use futures::{stream, Future, Stream, StreamExt};
+use kube::{
+    api::{DynamicObject, ListParams, ResourceExt},
+    discovery::ApiResource,
+    Api, Client,
+};
+use std::{collections::HashMap, sync::Arc};
+
+/// A change to the set of controllers of a [`MultiController`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MultiControllerEvent {
+    /// A controller was started for a newly established CRD
+    Started(ApiResource),
+    /// The controller of a CRD was stopped, because the CRD was deleted or changed
+    Stopped(ApiResource),
+}
+
+/// Runs a dynamic controller for every `CustomResourceDefinition` matching a selector
+///
+/// CRDs are watched with `lp`, and once a CRD is established, `factory` is called with the
+/// [`ApiResource`] of its storage version. The returned future, typically running a
+/// [`Controller`](super::Controller) of [`DynamicObject`]s, is spawned on the
+/// [executor](Self::with_executor). It is cancelled (dropped) when the CRD is deleted, stops matching
+/// the selector, or changes its served kind or version, in which case a new one is started.
+/// This is the base machinery for "meta operators" such as composition engines.
+///
+/// ```no_run
+/// use futures::StreamExt;
+/// use kube::{api::{Api, DynamicObject, ListParams}, Client};
+/// use kube_runtime::controller::{Context, MultiController, ReconcilerAction};
+/// use kube_runtime::Controller;
+/// # async fn reconcile(obj: DynamicObject, ctx: Context<()>) -> Result<ReconcilerAction, kube::Error> {
+/// #     Ok(ReconcilerAction { requeue_after: None })
+/// # }
+/// # fn error_policy(err: &kube::Error, ctx: Context<()>) -> ReconcilerAction {
+/// #     ReconcilerAction { requeue_after: None }
+/// # }
+/// # async fn scope(client: Client) {
+/// let lp = ListParams::default().labels("composition.example.com/managed=true");
+/// MultiController::new(client, lp, |ar, client| {
+///     let api = Api::<DynamicObject>::all_with(client, &ar.to_gvk());
+///     Controller::new_with(api, ListParams::default(), ar.to_gvk())
+///         .run(reconcile, error_policy, Context::new(()))
+///         .for_each(|_| async {})
+/// })
+/// .run()
+/// .for_each(|event| async move { println!("{:?}", event) })
+/// .await;
+/// # }
+/// ```
+pub struct MultiController<F> {
+    client: Client,
+    lp: ListParams,
+    factory: F,
+    executor: Arc<dyn Executor>,
+}
+
+impl<F, Fut> MultiController<F>
+where
+    F: FnMut(ApiResource, Client) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    /// Run a controller built by `factory` for every CRD matching `lp`
+    pub fn new(client: Client, lp: ListParams, factory: F) -> Self {
+        MultiController {
+            executor: client.executor(),
+            client,
+            lp,
+            factory,
+        }
+    }
+
+    /// Spawn the controllers with `executor`, rather than with the [executor](Client::with_executor) of the client
+    #[must_use]
+    pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// Start watching CRDs
+    ///
+    /// The returned stream reports controllers starting and stopping, and the errors of the CRD watch.
+    /// Dropping it stops all controllers.
+    pub fn run(self) -> impl Stream<Item = Result<MultiControllerEvent, watcher::Error>> {
+        let crd = ApiResource {
+            group: "apiextensions.k8s.io".to_string(),
+            version: "v1".to_string(),
+            api_version: "apiextensions.k8s.io/v1".to_string(),
+            kind: "CustomResourceDefinition".to_string(),
+            plural: "customresourcedefinitions".to_string(),
+            namespaced: false,
+            verbs: vec!["list".to_string(), "watch".to_string()],
+        };
+        let gvk = crd.to_gvk();
+        let crds = Api::<DynamicObject>::all_with(self.client.clone(), &gvk);
+        let events = watcher(crds, self.lp.clone());
+        self.run_on(events)
+    }
+
+    fn run_on(
+        self,
+        events: impl Stream<Item = Result<watcher::Event<DynamicObject>, watcher::Error>>,
+    ) -> impl Stream<Item = Result<MultiControllerEvent, watcher::Error>> {
+        let mut running = Running {
+            client: self.client,
+            factory: self.factory,
+            executor: self.executor,
+            controllers: HashMap::new(),
+        };
+        events
+            .map(move |event| {
+                let changes = match event {
+                    Ok(event) => running.apply(event).into_iter().map(Ok).collect(),
+                    Err(err) => vec![Err(err)],
+                };
+                stream::iter(changes)
+            })
+            .flatten()
+    }
+}
+
+struct Running<F> {
+    client: Client,
+    factory: F,
+    executor: Arc<dyn Executor>,
+    /// Running controllers, by the name of their CRD
+    controllers: HashMap<String, (ApiResource, CancelableJoinHandle<()>)>,
+}
+
+impl<F, Fut> Running<F>
+where
+    F: FnMut(ApiResource, Client) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    fn apply(&mut self, event: watcher::Event<DynamicObject>) -> Vec<MultiControllerEvent> {
+        let mut changes = vec![];
+        match event {
+            watcher::Event::Applied(crd) => self.sync(&crd.name(), established_resource(&crd), &mut changes),
+            watcher::Event::Deleted(crd) => self.sync(&crd.name(), None, &mut changes),
+            watcher::Event::Restarted(crds) => {
+                let listed = crds.iter().map(ResourceExt::name).collect::<Vec<_>>();
+                let removed = self
+                    .controllers
+                    .keys()
+                    .filter(|name| !listed.contains(name))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                for name in removed {
+                    self.sync(&name, None, &mut changes);
+                }
+                for crd in &crds {
+                    self.sync(&crd.name(), established_resource(crd), &mut changes);
+                }
+            }
+        }
+        changes
+    }
+
+    /// Make sure that the controller for the CRD `name` runs for `resource`
+    fn sync(&mut self, name: &str, resource: Option<ApiResource>, changes: &mut Vec<MultiControllerEvent>) {
+        if let Some((running, _)) = self.controllers.get(name) {
+            if Some(running) == resource.as_ref() {
+                return;
+            }
+            // Dropping the handle cancels the controller
+            let (stopped, _) = self.controllers.remove(name).expect("controller is running");
+            changes.push(MultiControllerEvent::Stopped(stopped));
+        }
+        if let Some(resource) = resource {
+            let controller = (self.factory)(resource.clone(), self.client.clone());
+            let handle = CancelableJoinHandle::spawn_on(controller, &*self.executor);
+            self.controllers
+                .insert(name.to_string(), (resource.clone(), handle));
+            changes.push(MultiControllerEvent::Started(resource));
+        }
+    }
+}
+
+/// The resource served by a CRD, once it is established
+fn established_resource(crd: &DynamicObject) -> Option<ApiResource> {
+    let established = crd.data["status"]["conditions"]
+        .as_array()?
+        .iter()
+        .any(|c| c["type"] == "Established" && c["status"] == "True");
+    if !established {
+        return None;
+    }
+    let spec = &crd.data["spec"];
+    let versions = spec["versions"].as_array()?;
+    let served = versions.iter().filter(|v| v["served"] == true);
+    let version = served
+        .clone()
+        .find(|v| v["storage"] == true)
+        .or_else(|| served.clone().next())?["name"]
+        .as_str()?;
+    let group = spec["group"].as_str()?;
+    Some(ApiResource {
+        group: group.to_string(),
+        version: version.to_string(),
+        api_version: [group, "/", version].concat(),
+        kind: spec["names"]["kind"].as_str()?.to_string(),
+        plural: spec["names"]["plural"].as_str()?.to_string(),
+        namespaced: spec["scope"] == "Namespaced",
+        verbs: [
+            "create",
+            "delete",
+            "deletecollection",
+            "get",
+            "list",
+            "patch",
+            "update",
+            "watch",
+        ]
+        .iter()
+        .map(|verb| (*verb).to_string())
+        .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MultiController, MultiControllerEvent};
+    use crate::{
+        testing::{mock_client, MockResponse},
+        watcher,
+    };
+    use futures::{future, StreamExt};
+    use kube::api::{DynamicObject, ListParams};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    fn crd(name: &str, kind: &str, version: &str, established: bool) -> DynamicObject {
+        serde_json::from_value(serde_json::json!({
+            "apiVersion": "apiextensions.k8s.io/v1",
+            "kind": "CustomResourceDefinition",
+            "metadata": { "name": name },
+            "spec": {
+                "group": "example.com",
+                "scope": "Namespaced",
+                "names": { "kind": kind, "plural": name.split('.').next().unwrap() },
+                "versions": [
+                    { "name": "v1alpha1", "served": true, "storage": false },
+                    { "name": version, "served": true, "storage": true },
+                ],
+            },
+            "status": { "conditions": [{ "type": "Established", "status": if established { "True" } else { "False" } }] },
+        }))
+        .unwrap()
+    }
+
+    /// A controller that counts how many instances of it are running
+    struct Guard(Arc<AtomicUsize>);
+
+    impl Drop for Guard {
+        fn drop(&mut self) {
+            self.0.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn controllers_follow_crds() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let counter = running.clone();
+        let client = mock_client(|_| MockResponse::ok(serde_json::json!({})));
+        let multi = MultiController::new(client, ListParams::default(), move |_, _| {
+            counter.fetch_add(1, Ordering::SeqCst);
+            let guard = Guard(counter.clone());
+            async move {
+                let _guard = guard;
+                future::pending::<()>().await;
+            }
+        });
+        let (crds, events) = watcher::mock::<DynamicObject>();
+        let mut changes = multi.run_on(events).boxed();
+
+        crds.applied(crd("widgets.example.com", "Widget", "v1", false));
+        crds.applied(crd("widgets.example.com", "Widget", "v1", true));
+        let started = changes.next().await.unwrap().unwrap();
+        assert!(
+            matches!(&started, MultiControllerEvent::Started(ar) if ar.kind == "Widget" && ar.version == "v1")
+        );
+        tokio::task::yield_now().await;
+        assert_eq!(running.load(Ordering::SeqCst), 1);
+
+        // an unchanged CRD keeps its controller, a new version replaces it
+        crds.applied(crd("widgets.example.com", "Widget", "v1", true));
+        crds.applied(crd("widgets.example.com", "Widget", "v2", true));
+        assert!(
+            matches!(changes.next().await, Some(Ok(MultiControllerEvent::Stopped(ar))) if ar.version == "v1")
+        );
+        assert!(
+            matches!(changes.next().await, Some(Ok(MultiControllerEvent::Started(ar))) if ar.version == "v2")
+        );
+
+        crds.restarted(vec![crd("gadgets.example.com", "Gadget", "v1", true)]);
+        assert!(
+            matches!(changes.next().await, Some(Ok(MultiControllerEvent::Stopped(ar))) if ar.kind == "Widget")
+        );
+        assert!(
+            matches!(changes.next().await, Some(Ok(MultiControllerEvent::Started(ar))) if ar.kind == "Gadget")
+        );
+        tokio::task::yield_now().await;
+        assert_eq!(running.load(Ordering::SeqCst), 1);
+
+        drop(changes);
+        tokio::task::yield_now().await;
+        assert_eq!(running.load(Ordering::SeqCst), 0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Delays the reconciliations of objects that keep failing
+use std::{collections::HashMap, hash::Hash, time::Duration};
+use tokio::time::Instant;
+
+/// How the reconciliations of failing objects are delayed, see [`Controller::rate_limit`](crate::Controller::rate_limit)
+///
+/// This mirrors the default rate limiter of client-go's workqueue, the larger delay of:
+/// - a per-object exponential backoff, doubling from `base_delay` with every consecutive failure of
+///   the object, up to `max_delay`
+/// - a token bucket shared by all objects, allowing `burst` retries at once and `qps` retries per second after that
+///
+/// An object that failed is not reconciled again before its delay passed, even if it changes in the meantime.
+/// A successful reconciliation resets the backoff of the object, as does its deletion, or not being retried
+/// for `max_delay` after its delay passed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RateLimiter {
+    base_delay: Duration,
+    max_delay: Duration,
+    qps: f64,
+    burst: u32,
+}
+
+impl Default for RateLimiter {
+    /// A backoff from 5 milliseconds to 1000 seconds, and a bucket of 10 retries per second with a burst of 100
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(5),
+            max_delay: Duration::from_secs(1000),
+            qps: 10.0,
+            burst: 100,
+        }
+    }
+}
+
+impl RateLimiter {
+    /// Create a rate limiter with the defaults of client-go
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Back off from `base_delay` after the first failure of an object, doubling up to `max_delay`
+    #[must_use]
+    pub fn item_backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Allow `qps` retries per second across all objects, with bursts of up to `burst` retries
+    #[must_use]
+    pub fn bucket(mut self, qps: f64, burst: u32) -> Self {
+        self.qps = qps;
+        self.burst = burst;
+        self
+    }
+}
+
+/// The state of a [`RateLimiter`] for a running controller
+pub(crate) struct RateLimits<T> {
+    config: RateLimiter,
+    failures: HashMap<T, (u32, Instant)>,
+    tokens: f64,
+    refilled_at: Option<Instant>,
+}
+
+impl<T: Eq + Hash> RateLimits<T> {
+    pub(crate) fn new(config: RateLimiter) -> Self {
+        Self {
+            tokens: f64::from(config.burst),
+            config,
+            failures: HashMap::new(),
+            refilled_at: None,
+        }
+    }
+
+    /// Record a failure of `item`, returning when it may be retried
+    pub(crate) fn failed(&mut self, item: T, now: Instant) -> Instant {
+        // objects that were not retried are forgotten eventually, rather than kept around forever
+        let max_delay = self.config.max_delay;
+        self.failures
+            .retain(|_, (_, retry_at)| now.saturating_duration_since(*retry_at) < max_delay);
+        let failures = self.failures.get(&item).map_or(0, |(failures, _)| *failures);
+        let item_delay = self
+            .config
+            .base_delay
+            .checked_mul(2_u32.saturating_pow(failures))
+            .map_or(self.config.max_delay, |delay| delay.min(self.config.max_delay));
+        let retry_at = now + item_delay.max(self.take_token(now));
+        self.failures.insert(item, (failures.saturating_add(1), retry_at));
+        retry_at
+    }
+
+    /// Forget `item` after it succeeded or was deleted, resetting its backoff
+    pub(crate) fn forget(&mut self, item: &T) {
+        self.failures.remove(item);
+    }
+
+    /// When `item` may be retried, `None` if it did not fail
+    pub(crate) fn retry_at(&self, item: &T) -> Option<Instant> {
+        self.failures.get(item).map(|(_, retry_at)| *retry_at)
+    }
+
+    /// Reserve a token from the bucket, returning how long until it is available
+    fn take_token(&mut self, now: Instant) -> Duration {
+        if self.config.qps <= 0.0 {
+            return Duration::from_secs(0);
+        }
+        if let Some(refilled_at) = self.refilled_at {
+            let refill = now.saturating_duration_since(refilled_at).as_secs_f64() * self.config.qps;
+            self.tokens = (self.tokens + refill).min(f64::from(self.config.burst));
+        }
+        self.refilled_at = Some(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::from_secs(0)
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.config.qps)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, RateLimits};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    #[test]
+    fn failing_items_back_off_exponentially() {
+        let config = RateLimiter::new().item_backoff(Duration::from_secs(1), Duration::from_secs(5));
+        let mut limits = RateLimits::new(config);
+        let now = Instant::now();
+        let delays = (0..5).map(|_| limits.failed("a", now) - now).collect::<Vec<_>>();
+        assert_eq!(
+            delays,
+            [1, 2, 4, 5, 5]
+                .iter()
+                .map(|s| Duration::from_secs(*s))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(limits.failed("b", now), now + Duration::from_secs(1));
+        limits.forget(&"a");
+        assert_eq!(limits.retry_at(&"a"), None);
+        assert_eq!(limits.failed("a", now), now + Duration::from_secs(1));
+    }
+
+    #[test]
+    fn items_that_are_not_retried_are_forgotten() {
+        let config = RateLimiter::new().item_backoff(Duration::from_secs(1), Duration::from_secs(5));
+        let mut limits = RateLimits::new(config);
+        let now = Instant::now();
+        limits.failed("a", now);
+        limits.failed("a", now);
+        // "a" may be retried after 2 seconds, so it is still backing off shortly after
+        let soon = now + Duration::from_secs(6);
+        limits.failed("b", soon);
+        assert_eq!(limits.retry_at(&"a"), Some(now + Duration::from_secs(2)));
+        // but is forgotten once it was not retried for `max_delay`
+        let later = now + Duration::from_secs(7);
+        limits.failed("b", later);
+        assert_eq!(limits.retry_at(&"a"), None);
+        assert_eq!(limits.failures.len(), 1);
+    }
+
+    #[test]
+    fn bucket_limits_retries_across_items() {
+        let config = RateLimiter::new()
+            .item_backoff(Duration::from_millis(1), Duration::from_millis(1))
+            .bucket(2.0, 2);
+        let mut limits = RateLimits::new(config);
+        let now = Instant::now();
+        assert_eq!(limits.failed(1, now), now + Duration::from_millis(1));
+        assert_eq!(limits.failed(2, now), now + Duration::from_millis(1));
+        assert_eq!(limits.failed(3, now), now + Duration::from_millis(500));
+        assert_eq!(limits.failed(4, now), now + Duration::from_secs(1));
+        // the bucket refills over time
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limits.failed(5, later), later + Duration::from_millis(1));
+    }
+}
@@ -0,0 +1,199 @@
+use super::{requests_for, trigger_owners, trigger_with, ReconcileReason, ReconcileRequest};
+use crate::{
+    reflector::ObjectRef,
+    utils::try_flatten_touched,
+    watcher::{self, watcher},
+};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    stream::{self, AbortHandle, BoxStream, SelectAll},
+    Stream, StreamExt,
+};
+use kube::{
+    api::{ListParams, Resource},
+    Api,
+};
+use serde::de::DeserializeOwned;
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+type Trigger<K> = BoxStream<'static, Result<ReconcileRequest<K>, watcher::Error>>;
+
+/// Adds relations to a running [`Controller`](super::Controller)
+///
+/// Get one with [`Controller::relations`](super::Controller::relations) before calling
+/// [`run`](super::Controller::run). Relations added through it behave like the ones added with
+/// [`Controller::owns`](super::Controller::owns) and [`Controller::watches`](super::Controller::watches),
+/// but can be added at any time, and removed again with the returned [`RelationHandle`].
+/// This lets operators start watching kinds as they are discovered, e.g. when a CRD is installed.
+///
+/// ```no_run
+/// use kube::{api::{DynamicObject, GroupVersionKind, ListParams}, Api, Client};
+/// use kube_runtime::Controller;
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// # fn scope(client: Client, gvk: GroupVersionKind) {
+/// let controller = Controller::new(Api::<Deployment>::all(client.clone()), ListParams::default());
+/// let relations = controller.relations();
+/// // later, once the kind is known to exist
+/// let children: Api<DynamicObject> = Api::all_with(client, &gvk);
+/// let handle = relations.owns(children, ListParams::default());
+/// // and once it is gone again
+/// handle.remove();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Relations<K: Resource> {
+    tx: UnboundedSender<Trigger<K>>,
+    dyntype: K::DynamicType,
+}
+
+impl<K> Relations<K>
+where
+    K: Clone + Resource + Debug + Send + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    /// Create a set of relations, and the stream of the triggers added to it
+    pub(crate) fn new(dyntype: K::DynamicType) -> (Self, DynamicTriggers<K>) {
+        let (tx, rx) = mpsc::unbounded();
+        (Relations { tx, dyntype }, DynamicTriggers {
+            rx: Some(rx),
+            triggers: SelectAll::new(),
+        })
+    }
+
+    /// Indicate child objects `K` owns, see [`Controller::owns`](super::Controller::owns)
+    pub fn owns<Child: Clone + Resource + DeserializeOwned + Debug + Send + 'static>(
+        &self,
+        api: Api<Child>,
+        lp: ListParams,
+    ) -> RelationHandle
+    where
+        Child::DynamicType: Debug + Eq + Hash,
+    {
+        self.push(requests_for(
+            trigger_owners(try_flatten_touched(watcher(api, lp)), self.dyntype.clone()),
+            ReconcileReason::RelatedObjectUpdated,
+        ))
+    }
+
+    /// Indicate an object to watch with a custom mapper, see [`Controller::watches`](super::Controller::watches)
+    pub fn watches<
+        Other: Clone + Resource + DeserializeOwned + Debug + Send + 'static,
+        I: 'static + IntoIterator<Item = ObjectRef<K>>,
+    >(
+        &self,
+        api: Api<Other>,
+        lp: ListParams,
+        mapper: impl Fn(Other) -> I + Send + 'static,
+    ) -> RelationHandle
+    where
+        I::IntoIter: Send,
+    {
+        self.push(requests_for(
+            trigger_with(try_flatten_touched(watcher(api, lp)), mapper),
+            ReconcileReason::RelatedObjectUpdated,
+        ))
+    }
+
+    /// Add a custom stream of objects to reconcile
+    ///
+    /// The objects are reconciled with the [`External`](ReconcileReason::External) reason.
+    /// The relation is removed when `trigger` ends, or when it is removed with the handle.
+    /// If the controller has stopped, the trigger is dropped.
+    pub fn add(
+        &self,
+        trigger: impl Stream<Item = Result<ObjectRef<K>, watcher::Error>> + Send + 'static,
+    ) -> RelationHandle {
+        self.push(requests_for(trigger, ReconcileReason::External))
+    }
+
+    fn push(
+        &self,
+        trigger: impl Stream<Item = Result<ReconcileRequest<K>, watcher::Error>> + Send + 'static,
+    ) -> RelationHandle {
+        let (trigger, abort) = stream::abortable(trigger);
+        // An error means that the controller has stopped, so there is nothing to relate to anymore
+        let _ = self.tx.unbounded_send(trigger.boxed());
+        RelationHandle { abort }
+    }
+}
+
+/// A relation added with [`Relations`]
+///
+/// Dropping the handle keeps the relation, use [`RelationHandle::remove`] to remove it.
+#[derive(Debug)]
+pub struct RelationHandle {
+    abort: AbortHandle,
+}
+
+impl RelationHandle {
+    /// Stop watching the related objects
+    pub fn remove(self) {
+        self.abort.abort();
+    }
+}
+
+/// The triggers added through [`Relations`], merged into a single stream
+///
+/// Ends once all [`Relations`] handles are dropped and all added triggers have ended.
+pub(crate) struct DynamicTriggers<K: Resource> {
+    rx: Option<UnboundedReceiver<Trigger<K>>>,
+    triggers: SelectAll<Trigger<K>>,
+}
+
+impl<K: Resource> Stream for DynamicTriggers<K> {
+    type Item = Result<ReconcileRequest<K>, watcher::Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        if let Some(rx) = &mut this.rx {
+            loop {
+                match rx.poll_next_unpin(cx) {
+                    Poll::Ready(Some(trigger)) => this.triggers.push(trigger),
+                    Poll::Ready(None) => {
+                        this.rx = None;
+                        break;
+                    }
+                    Poll::Pending => break,
+                }
+            }
+        }
+        match this.triggers.poll_next_unpin(cx) {
+            // `SelectAll` ends whenever it is empty, but more triggers may be added later
+            Poll::Ready(None) if this.rx.is_some() => Poll::Pending,
+            poll => poll,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReconcileReason, Relations};
+    use crate::reflector::ObjectRef;
+    use futures::{channel::mpsc, FutureExt, StreamExt};
+    use k8s_openapi::api::core::v1::ConfigMap;
+
+    #[tokio::test]
+    async fn triggers_can_be_added_and_removed() {
+        let (relations, mut triggers) = Relations::<ConfigMap>::new(());
+        assert!(triggers.next().now_or_never().is_none());
+
+        let (tx, rx) = mpsc::unbounded();
+        let handle = relations.add(rx);
+        tx.unbounded_send(Ok(ObjectRef::new("a"))).unwrap();
+        let request = triggers.next().await.unwrap().unwrap();
+        assert_eq!(request.obj_ref, ObjectRef::new("a"));
+        assert_eq!(request.reason, ReconcileReason::External);
+
+        handle.remove();
+        tx.unbounded_send(Ok(ObjectRef::new("b"))).unwrap();
+        assert!(triggers.next().now_or_never().is_none());
+
+        drop(relations);
+        assert!(triggers.next().await.is_none());
+    }
+}
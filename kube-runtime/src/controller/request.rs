@@ -0,0 +1,124 @@
+//! What is queued for reconciliation, and why
+use crate::reflector::ObjectRef;
+use derivative::Derivative;
+use kube::api::Resource;
+use std::{
+    fmt::{self, Debug, Display},
+    hash::{Hash, Hasher},
+};
+
+/// Why an object was queued for reconciliation, see [`ReconcileRequest`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ReconcileReason {
+    /// The object itself was created, changed or deleted
+    ObjectUpdated,
+    /// An object it owns or relates to changed, see [`Controller::owns`](super::Controller::owns)
+    /// and [`Controller::watches`](super::Controller::watches)
+    RelatedObjectUpdated,
+    /// The last reconciliation succeeded and asked to be requeued
+    ReconcilerRequestedRetry,
+    /// The last reconciliation failed and the `error_policy` asked for a retry
+    ErrorPolicyRequestedRetry,
+    /// A periodic resync, see [`Controller::resync_every`](super::Controller::resync_every)
+    Resync,
+    /// A trigger the controller does not know about, like the queue of an [`applier`](super::applier)
+    /// or one added with [`Relations::add`](super::Relations::add)
+    External,
+}
+
+impl Display for ReconcileReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReconcileReason::ObjectUpdated => "object updated",
+            ReconcileReason::RelatedObjectUpdated => "related object updated",
+            ReconcileReason::ReconcilerRequestedRetry => "reconciler requested retry",
+            ReconcileReason::ErrorPolicyRequestedRetry => "error policy requested retry",
+            ReconcileReason::Resync => "resync",
+            ReconcileReason::External => "external trigger",
+        })
+    }
+}
+
+/// An object queued for reconciliation, with the reason it was queued
+///
+/// Requests are equal (and hash the same) when they refer to the same object, whatever their reason,
+/// so an object is queued at most once. If it is queued again before it is reconciled, the request that
+/// is due first is kept, with its reason. When both are due at the same time, the newer request wins.
+#[derive(Derivative)]
+#[derivative(
+    Debug(bound = "K::DynamicType: Debug"),
+    Clone(bound = "K::DynamicType: Clone")
+)]
+pub struct ReconcileRequest<K: Resource> {
+    /// The object to reconcile
+    pub obj_ref: ObjectRef<K>,
+    /// Why it is reconciled
+    pub reason: ReconcileReason,
+}
+
+impl<K: Resource> ReconcileRequest<K> {
+    /// A request to reconcile `obj_ref` for `reason`
+    #[must_use]
+    pub fn new(obj_ref: ObjectRef<K>, reason: ReconcileReason) -> Self {
+        Self { obj_ref, reason }
+    }
+}
+
+impl<K: Resource> From<ObjectRef<K>> for ReconcileRequest<K> {
+    fn from(obj_ref: ObjectRef<K>) -> Self {
+        Self::new(obj_ref, ReconcileReason::External)
+    }
+}
+
+impl<K: Resource> PartialEq for ReconcileRequest<K>
+where
+    K::DynamicType: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.obj_ref == other.obj_ref
+    }
+}
+
+impl<K: Resource> Eq for ReconcileRequest<K> where K::DynamicType: Eq {}
+
+impl<K: Resource> Hash for ReconcileRequest<K>
+where
+    K::DynamicType: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.obj_ref.hash(state);
+    }
+}
+
+impl<K: Resource> Display for ReconcileRequest<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.obj_ref, self.reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReconcileReason, ReconcileRequest};
+    use crate::reflector::ObjectRef;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use std::collections::HashSet;
+
+    #[test]
+    fn requests_for_the_same_object_are_equal() {
+        let updated = ReconcileRequest::new(
+            ObjectRef::<ConfigMap>::new("a").within("ns"),
+            ReconcileReason::ObjectUpdated,
+        );
+        let resync = ReconcileRequest::new(ObjectRef::new("a").within("ns"), ReconcileReason::Resync);
+        assert_eq!(updated, resync);
+        assert_eq!(
+            vec![updated.clone(), resync]
+                .into_iter()
+                .collect::<HashSet<_>>()
+                .len(),
+            1
+        );
+        assert_ne!(updated, ObjectRef::new("b").within("ns").into());
+        assert_eq!(updated.to_string(), "ConfigMap.v1./a.ns (object updated)");
+    }
+}
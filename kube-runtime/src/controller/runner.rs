@@ -1,6 +1,6 @@
-use super::future_hash_map::FutureHashMap;
+use super::{future_hash_map::FutureHashMap, gate::RunningGates};
 use crate::scheduler::{self, ScheduleRequest, Scheduler};
-use futures::{Future, Stream, StreamExt};
+use futures::{future::BoxFuture, Future, FutureExt, Stream, StreamExt};
 use pin_project::pin_project;
 use std::{
     hash::Hash,
@@ -14,12 +14,17 @@ use std::{
 /// If an item is to be emitted from the [`Scheduler`] while an equal item is
 /// already being processed then it will be held pending until the current item
 /// is finished.
+///
+/// With [`gates`](Self::gates), no new items are taken while a gate is closed.
 #[pin_project]
 pub struct Runner<T, R, F, MkF> {
     #[pin]
     scheduler: Scheduler<T, R>,
     run_msg: MkF,
     slots: FutureHashMap<T, F>,
+    gates: Option<RunningGates>,
+    /// Wakes the runner once closed `gates` open again
+    opening: Option<BoxFuture<'static, ()>>,
 }
 
 impl<T, R, F, MkF> Runner<T, R, F, MkF>
@@ -32,8 +37,16 @@ where
             scheduler,
             run_msg,
             slots: FutureHashMap::default(),
+            gates: None,
+            opening: None,
         }
     }
+
+    /// Only take items while all `gates` are open
+    pub(crate) fn gates(mut self, gates: Option<RunningGates>) -> Self {
+        self.gates = gates;
+        self
+    }
 }
 
 impl<T, R, F, MkF> Stream for Runner<T, R, F, MkF>
@@ -54,13 +67,24 @@ where
             Poll::Ready(None) => false,
             Poll::Pending => true,
         };
+        let gated = match this.gates {
+            Some(gates) if !gates.is_open() => {
+                let opening = this.opening.get_or_insert_with(|| gates.clone().opened().boxed());
+                opening.poll_unpin(cx).is_pending()
+            }
+            _ => false,
+        };
+        if !gated {
+            *this.opening = None;
+        }
         loop {
             // Try to take take a new message that isn't already being processed
             // leave the already-processing ones in the queue, so that we can take them once
             // we're free again.
+            // While gated, due messages are held until the gates open.
             let next_msg_poll = scheduler
                 .as_mut()
-                .hold_unless(|msg| !slots.contains_key(msg))
+                .hold_unless(|msg| !gated && !slots.contains_key(msg))
                 .poll_next_unpin(cx);
             match next_msg_poll {
                 Poll::Ready(Some(Ok(msg))) => {
@@ -0,0 +1,443 @@
+//! Publishes `Event`s about Kubernetes objects, with rate limiting and aggregation
+//!
+//! A [`Recorder`] publishes events about a single object. Repeats of an event are folded into the
+//! existing `Event` by bumping its `count` and `lastTimestamp`, and a token bucket limits how many
+//! writes a noisy reconciler can make, like the event broadcaster of client-go.
+use crate::time::{default_clock, Clock};
+use k8s_openapi::{
+    api::core::v1::{Event, EventSource, ObjectReference},
+    apimachinery::pkg::apis::meta::v1::{ObjectMeta, Time},
+    chrono::Utc,
+};
+use kube::{
+    api::{Api, Patch, PatchParams, PostParams, Resource},
+    Client,
+};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// Default time to regain one token of the rate limit
+const DEFAULT_REFILL_SECS: u64 = 300;
+/// Default window for aggregating repeated events
+const DEFAULT_WINDOW_SECS: u64 = 600;
+
+/// Whether an event is informational or a warning
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventType {
+    /// Something expected happened
+    Normal,
+    /// Something went wrong, and may need attention
+    Warning,
+}
+
+impl EventType {
+    fn as_str(self) -> &'static str {
+        match self {
+            EventType::Normal => "Normal",
+            EventType::Warning => "Warning",
+        }
+    }
+}
+
+/// An event to publish with a [`Recorder`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct NewEvent {
+    /// Whether the event is informational or a warning
+    pub type_: EventType,
+    /// A short, machine readable, `UpperCamelCase` reason for the event
+    pub reason: String,
+    /// A human readable description of the event
+    pub note: Option<String>,
+    /// What the controller did (or failed to do), e.g. `Reconciling`
+    pub action: String,
+}
+
+/// The controller that publishes events
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reporter {
+    /// The name of the controller, e.g. `my-operator`
+    pub controller: String,
+    /// The instance of the controller, e.g. the name of its pod
+    pub instance: Option<String>,
+}
+
+impl From<&str> for Reporter {
+    fn from(controller: &str) -> Self {
+        Reporter {
+            controller: controller.to_string(),
+            instance: None,
+        }
+    }
+}
+
+/// What to do with a published event
+#[derive(Debug, PartialEq, Eq)]
+enum Decision {
+    /// Create a new `Event`
+    Create,
+    /// Bump the count of an existing `Event`
+    Bump { name: String, count: i32 },
+    /// Fold into an `Event` that is still being created, the next bump includes it in the count
+    Pending,
+    /// Drop the event, the rate limit is exceeded
+    Drop,
+}
+
+struct Seen {
+    /// Name of the `Event` object, once created
+    name: Option<String>,
+    count: i32,
+    last: Instant,
+}
+
+/// Aggregation and rate limiting state of a [`Recorder`]
+struct Limiter {
+    burst: u32,
+    refill: Duration,
+    tokens: f64,
+    refilled_at: Instant,
+    window: Duration,
+    seen: HashMap<NewEvent, Seen>,
+}
+
+impl Limiter {
+    fn new(burst: u32, refill: Duration, window: Duration, now: Instant) -> Self {
+        Limiter {
+            burst,
+            refill,
+            tokens: f64::from(burst),
+            refilled_at: now,
+            window,
+            seen: HashMap::new(),
+        }
+    }
+
+    fn take_token(&mut self, now: Instant) -> bool {
+        let elapsed = now.saturating_duration_since(self.refilled_at).as_secs_f64();
+        let refills = elapsed / self.refill.as_secs_f64();
+        // Partial refills are kept, so that low rates still refill over time
+        self.tokens = (self.tokens + refills).min(f64::from(self.burst));
+        self.refilled_at = now;
+        if self.tokens < 1.0 {
+            return false;
+        }
+        self.tokens -= 1.0;
+        true
+    }
+
+    fn decide(&mut self, event: &NewEvent, now: Instant) -> Decision {
+        let window = self.window;
+        self.seen
+            .retain(|_, seen| now.saturating_duration_since(seen.last) < window);
+        // The first occurrence is still being created, don't create a duplicate
+        if let Some(Seen {
+            name: None,
+            count,
+            last,
+        }) = self.seen.get_mut(event)
+        {
+            *count += 1;
+            *last = now;
+            return Decision::Pending;
+        }
+        if !self.take_token(now) {
+            return Decision::Drop;
+        }
+        if let Some(Seen {
+            name: Some(name),
+            count,
+            last,
+        }) = self.seen.get_mut(event)
+        {
+            *count += 1;
+            *last = now;
+            return Decision::Bump {
+                name: name.clone(),
+                count: *count,
+            };
+        }
+        self.seen.insert(event.clone(), Seen {
+            name: None,
+            count: 1,
+            last: now,
+        });
+        Decision::Create
+    }
+
+    fn created(&mut self, event: &NewEvent, name: Option<String>) {
+        if let Some(seen) = self.seen.get_mut(event) {
+            seen.name = name;
+        }
+    }
+
+    /// Forget an event whose creation failed, so that the next occurrence creates it again
+    fn failed(&mut self, event: &NewEvent) {
+        if matches!(self.seen.get(event), Some(Seen { name: None, .. })) {
+            self.seen.remove(event);
+        }
+    }
+}
+
+/// Publishes events about a single object
+///
+/// Repeats of the same event (same type, reason, note and action) within the aggregation window
+/// (10 minutes by default) bump the `count` and `lastTimestamp` of the existing `Event` rather than
+/// creating a new one. All writes are limited by a token bucket, which allows a burst of 25 events and
+/// then one event per 5 minutes by default. Events over the limit are dropped.
+///
+/// Cloning produces a new handle sharing the same aggregation and rate limiting state.
+///
+/// ```no_run
+/// use kube::Client;
+/// use kube_runtime::events::{EventType, NewEvent, Recorder};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// # async fn scope(client: Client, cm: ConfigMap) -> Result<(), kube::Error> {
+/// let recorder = Recorder::for_object(client, "my-operator".into(), &cm, &());
+/// recorder
+///     .publish(NewEvent {
+///         type_: EventType::Normal,
+///         reason: "Synced".into(),
+///         note: Some("ConfigMap contents are up to date".into()),
+///         action: "Reconciling".into(),
+///     })
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Recorder {
+    events: Api<Event>,
+    reporter: Reporter,
+    reference: ObjectReference,
+    limiter: Arc<Mutex<Limiter>>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Recorder {
+    /// Create a recorder for events about the object `reference`
+    #[must_use]
+    pub fn new(client: Client, reporter: Reporter, reference: ObjectReference) -> Self {
+        let namespace = reference
+            .namespace
+            .clone()
+            .unwrap_or_else(|| "default".to_string());
+        let clock = default_clock();
+        let limiter = Limiter::new(
+            25,
+            Duration::from_secs(DEFAULT_REFILL_SECS),
+            Duration::from_secs(DEFAULT_WINDOW_SECS),
+            clock.now(),
+        );
+        Recorder {
+            events: Api::namespaced(client, &namespace),
+            reporter,
+            reference,
+            limiter: Arc::new(Mutex::new(limiter)),
+            clock,
+        }
+    }
+
+    /// Create a recorder for events about `obj`
+    #[must_use]
+    pub fn for_object<K: Resource>(
+        client: Client,
+        reporter: Reporter,
+        obj: &K,
+        dyntype: &K::DynamicType,
+    ) -> Self {
+        let meta = obj.meta();
+        let reference = ObjectReference {
+            api_version: Some(K::api_version(dyntype).into_owned()),
+            kind: Some(K::kind(dyntype).into_owned()),
+            name: meta.name.clone(),
+            namespace: meta.namespace.clone(),
+            uid: meta.uid.clone(),
+            resource_version: meta.resource_version.clone(),
+            ..ObjectReference::default()
+        };
+        Self::new(client, reporter, reference)
+    }
+
+    /// Configure the rate limit: a burst of `burst` events, then one event per `refill`
+    #[must_use]
+    pub fn with_rate_limit(self, burst: u32, refill: Duration) -> Self {
+        {
+            let mut limiter = self.lock();
+            limiter.burst = burst;
+            limiter.tokens = f64::from(burst);
+            limiter.refill = refill;
+        }
+        self
+    }
+
+    /// Configure how long repeats of an event are folded into the existing `Event`
+    #[must_use]
+    pub fn with_aggregation_window(self, window: Duration) -> Self {
+        self.lock().window = window;
+        self
+    }
+
+    /// Measure the rate limit and aggregation window on `clock`, rather than the Tokio clock
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.lock().refilled_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Publish an event
+    ///
+    /// Returns `Ok(())` without contacting the apiserver if the event is dropped by the rate limit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `Event` could not be created or updated.
+    pub async fn publish(&self, event: NewEvent) -> Result<(), kube::Error> {
+        let decision = self.lock().decide(&event, self.clock.now());
+        match decision {
+            Decision::Drop | Decision::Pending => Ok(()),
+            Decision::Bump { name, count } => {
+                let patch = serde_json::json!({
+                    "count": count,
+                    "lastTimestamp": Time(Utc::now()),
+                });
+                match self
+                    .events
+                    .patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+                    .await
+                {
+                    Ok(_) => Ok(()),
+                    // The event has been garbage collected, start over
+                    Err(kube::Error::Api(ae)) if ae.code == 404 => self.create(&event).await,
+                    Err(err) => Err(err),
+                }
+            }
+            Decision::Create => self.create(&event).await,
+        }
+    }
+
+    async fn create(&self, event: &NewEvent) -> Result<(), kube::Error> {
+        let now = Time(Utc::now());
+        let object = Event {
+            metadata: ObjectMeta {
+                generate_name: Some(format!(
+                    "{}.",
+                    self.reference
+                        .name
+                        .as_deref()
+                        .unwrap_or(&self.reporter.controller)
+                )),
+                ..ObjectMeta::default()
+            },
+            involved_object: self.reference.clone(),
+            type_: Some(event.type_.as_str().to_string()),
+            reason: Some(event.reason.clone()),
+            message: event.note.clone(),
+            action: Some(event.action.clone()),
+            count: Some(1),
+            first_timestamp: Some(now.clone()),
+            last_timestamp: Some(now),
+            reporting_component: Some(self.reporter.controller.clone()),
+            reporting_instance: self.reporter.instance.clone(),
+            source: Some(EventSource {
+                component: Some(self.reporter.controller.clone()),
+                host: None,
+            }),
+            ..Event::default()
+        };
+        match self.events.create(&PostParams::default(), &object).await {
+            Ok(created) => {
+                self.lock().created(event, created.metadata.name);
+                Ok(())
+            }
+            Err(err) => {
+                self.lock().failed(event);
+                Err(err)
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Limiter> {
+        // The lock is never held across user code, so poisoning can be ignored
+        self.limiter.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Decision, EventType, Limiter, NewEvent};
+    use std::time::Duration;
+    use tokio::time::Instant;
+
+    fn event(reason: &str) -> NewEvent {
+        NewEvent {
+            type_: EventType::Warning,
+            reason: reason.to_string(),
+            note: None,
+            action: "Reconciling".to_string(),
+        }
+    }
+
+    #[test]
+    fn repeats_are_aggregated() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(10, Duration::from_secs(1), Duration::from_secs(59), now);
+        assert_eq!(limiter.decide(&event("Failed"), now), Decision::Create);
+        limiter.created(&event("Failed"), Some("cm.1".to_string()));
+        assert_eq!(limiter.decide(&event("Failed"), now), Decision::Bump {
+            name: "cm.1".to_string(),
+            count: 2
+        });
+        assert_eq!(limiter.decide(&event("Other"), now), Decision::Create);
+        // outside of the window, a new event is created
+        let later = now + Duration::from_secs(59);
+        assert_eq!(limiter.decide(&event("Failed"), later), Decision::Create);
+    }
+
+    #[test]
+    fn bursts_are_rate_limited() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(2, Duration::from_secs(10), Duration::from_secs(60), now);
+        assert_eq!(limiter.decide(&event("A"), now), Decision::Create);
+        assert_eq!(limiter.decide(&event("B"), now), Decision::Create);
+        assert_eq!(limiter.decide(&event("C"), now), Decision::Drop);
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.decide(&event("C"), later), Decision::Create);
+        assert_eq!(limiter.decide(&event("D"), later), Decision::Drop);
+    }
+
+    #[test]
+    fn partial_refills_add_up() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(2, Duration::from_secs(10), Duration::from_secs(60), now);
+        assert_eq!(limiter.decide(&event("A"), now), Decision::Create);
+        assert_eq!(limiter.decide(&event("B"), now), Decision::Create);
+        let at = |secs| now + Duration::from_secs(secs);
+        assert_eq!(limiter.decide(&event("C"), at(15)), Decision::Create);
+        // half a token was left over from the previous refill
+        assert_eq!(limiter.decide(&event("D"), at(20)), Decision::Create);
+        assert_eq!(limiter.decide(&event("E"), at(25)), Decision::Drop);
+    }
+
+    #[test]
+    fn repeats_of_pending_events_are_not_created_again() {
+        let now = Instant::now();
+        let mut limiter = Limiter::new(10, Duration::from_secs(1), Duration::from_secs(60), now);
+        assert_eq!(limiter.decide(&event("Failed"), now), Decision::Create);
+        // the create is still in flight
+        assert_eq!(limiter.decide(&event("Failed"), now), Decision::Pending);
+        limiter.created(&event("Failed"), Some("cm.1".to_string()));
+        assert_eq!(limiter.decide(&event("Failed"), now), Decision::Bump {
+            name: "cm.1".to_string(),
+            count: 3
+        });
+
+        assert_eq!(limiter.decide(&event("Other"), now), Decision::Create);
+        limiter.failed(&event("Other"));
+        assert_eq!(limiter.decide(&event("Other"), now), Decision::Create);
+    }
+}
@@ -0,0 +1,78 @@
+//! Pluggable task spawning for the [`Controller`](crate::Controller), shared watches and reflectors
+//!
+//! This re-exports [`kube::executor`]. By default tasks are spawned with the executor of the
+//! [`Client`](kube::Client) they use (see [`Client::with_executor`](kube::Client::with_executor)),
+//! which defaults to the current Tokio runtime.
+//!
+//! The [`watcher`](crate::watcher()) does not spawn or wait on its own, it only needs one to send its requests.
+//!
+//! Only spawning is pluggable. The runtime still needs Tokio for the rest: timers and timestamps
+//! are `tokio::time` ones (also behind a [`time::Clock`](crate::time::Clock)), and gates, shared
+//! watches and channels use `tokio::sync`. Running without a Tokio runtime is not supported.
+pub use kube::executor::{default_executor, Executor, TokioExecutor};
+
+#[cfg(test)]
+mod tests {
+    use super::Executor;
+    use crate::{
+        reflector::{spawn_reflector_on, store::Writer},
+        time::default_clock,
+        utils::{tee_on, CancelableJoinHandle},
+        watcher,
+    };
+    use futures::{
+        future::{self, BoxFuture},
+        stream, StreamExt,
+    };
+    use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    /// Counts spawned tasks, and runs them on the current Tokio runtime
+    #[derive(Debug, Default)]
+    struct Counting(AtomicUsize);
+
+    impl Executor for Counting {
+        fn spawn(&self, future: BoxFuture<'static, ()>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            tokio::spawn(future);
+        }
+    }
+
+    #[tokio::test]
+    async fn join_handle_should_spawn_on_executor() {
+        let executor = Arc::new(Counting::default());
+        let handle = CancelableJoinHandle::spawn_on(async { 1 + 1 }, &*executor);
+        assert_eq!(executor.0.load(Ordering::SeqCst), 1);
+        assert_eq!(handle.await, 2);
+    }
+
+    #[tokio::test]
+    async fn shared_watches_should_spawn_on_executor() {
+        let executor = Arc::new(Counting::default());
+        let cm = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("a".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let events = stream::iter(vec![Ok::<_, watcher::Error>(watcher::Event::Restarted(vec![cm]))]);
+        let subscriber = tee_on(events, 1, 4, &*executor).pop().unwrap();
+        let cached = subscriber.filter_map(|item| future::ready(item.ok().map(Ok)));
+        let store = spawn_reflector_on(Writer::default(), cached, &*executor, default_clock());
+        assert_eq!(executor.0.load(Ordering::SeqCst), 2);
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while store.is_empty() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -18,8 +18,12 @@
 #![allow(clippy::type_repetition_in_bounds)]
 
 pub mod controller;
+pub mod events;
+pub mod executor;
 pub mod reflector;
 pub mod scheduler;
+#[cfg(any(test, feature = "testing"))] pub mod testing;
+pub mod time;
 pub mod utils;
 pub mod watcher;
 
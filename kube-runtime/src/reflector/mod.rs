@@ -1,13 +1,26 @@
 //! Caches objects in memory
 
 mod object_ref;
+mod projection;
 pub mod store;
 
-pub use self::object_ref::ObjectRef;
-use crate::watcher;
-use futures::{Stream, TryStreamExt};
+pub use self::{
+    object_ref::ObjectRef,
+    projection::{project, Projected},
+};
+use crate::{
+    executor::{Executor, TokioExecutor},
+    time::{default_clock, Clock},
+    watcher,
+};
+use futures::{future, pin_mut, stream, FutureExt, Stream, StreamExt, TryStreamExt};
 use kube::Resource;
-use std::hash::Hash;
+use std::{
+    hash::Hash,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+use store::ObjectChange;
 pub use store::Store;
 
 /// Caches objects from `watcher::Event`s to a local `Store`
@@ -16,6 +29,7 @@ pub use store::Store;
 ///
 /// Note: It is a bad idea to feed a single `reflector` from multiple `watcher`s, since
 /// the whole `Store` will be cleared whenever any of them emits a `Restarted` event.
+/// To share a single `watcher` between a `reflector` and other consumers, see [`tee`](crate::utils::tee).
 pub fn reflector<K, W>(mut store: store::Writer<K>, stream: W) -> impl Stream<Item = W::Item>
 where
     K: Resource + Clone,
@@ -25,18 +39,276 @@ where
     stream.inspect_ok(move |event| store.apply_watcher_event(event))
 }
 
+/// Delay before polling the watch of a [`spawn_reflector`] again after an error
+const SPAWNED_ERROR_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Caches objects from `watcher::Event`s to a local `Store`, in a background task
+///
+/// Like [`reflector`], but the stream is polled by a task spawned on the current Tokio runtime,
+/// so only the `Store` needs to be kept around. Errors are dropped, and the task waits for a second
+/// before polling the stream again, so that a failing [`watcher`](crate::watcher()) does not spin.
+/// The task runs until `stream` ends.
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams}, Client};
+/// use kube_runtime::{reflector::{spawn_reflector, store::Writer}, watcher};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// # fn scope(client: Client) {
+/// let cms: Api<ConfigMap> = Api::namespaced(client, "default");
+/// let store = spawn_reflector(Writer::default(), watcher(cms, ListParams::default()));
+/// // later
+/// println!("{} configmaps cached", store.len());
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime.
+pub fn spawn_reflector<K, W>(store: store::Writer<K>, stream: W) -> Store<K>
+where
+    K: Resource + Clone + Send + Sync + 'static,
+    K::DynamicType: Eq + Hash + Clone + Send + Sync,
+    W: Stream<Item = watcher::Result<watcher::Event<K>>> + Send + 'static,
+{
+    spawn_reflector_on(store, stream, &TokioExecutor::new(), default_clock())
+}
+
+/// Like [`spawn_reflector`], but the task is spawned with `executor`, and waits after errors on `clock`
+pub fn spawn_reflector_on<K, W>(
+    store: store::Writer<K>,
+    stream: W,
+    executor: &dyn Executor,
+    clock: Arc<dyn Clock>,
+) -> Store<K>
+where
+    K: Resource + Clone + Send + Sync + 'static,
+    K::DynamicType: Eq + Hash + Clone + Send + Sync,
+    W: Stream<Item = watcher::Result<watcher::Event<K>>> + Send + 'static,
+{
+    let reader = store.as_reader();
+    executor.spawn(
+        async move {
+            let events = reflector(store, stream);
+            pin_mut!(events);
+            while let Some(event) = events.next().await {
+                if event.is_err() {
+                    clock.sleep(SPAWNED_ERROR_BACKOFF).await;
+                }
+            }
+        }
+        .boxed(),
+    );
+    reader
+}
+
+/// Caches objects from `watcher::Event`s to a local `Store`, dropping `Applied` events that change nothing
+///
+/// Like [`reflector`], but an [`Applied`](watcher::Event::Applied) object that is equal to its cached copy
+/// (ignoring `resourceVersion`, `managedFields` and the `status`) is only used to update the `Store`, and is
+/// not passed on. This keeps no-op updates from the apiserver, and the controller's own status writes, from
+/// triggering reconciliations downstream.
+///
+/// Use [`reflector_dedup_by`] with [`store::eq_ignoring_volatile_fields`] to pass status updates on.
+pub fn reflector_dedup<K, W>(store: store::Writer<K>, stream: W) -> impl Stream<Item = W::Item>
+where
+    K: Resource + Clone + serde::Serialize,
+    K::DynamicType: Eq + Hash + Clone,
+    W: Stream<Item = watcher::Result<watcher::Event<K>>>,
+{
+    reflector_dedup_by(store, stream, store::eq_ignoring_status)
+}
+
+/// Like [`reflector_dedup`], but deciding whether an update changed anything with `eq`
+///
+/// `eq` is passed the cached copy, and then the updated object. [`store::eq_ignoring_volatile_fields`]
+/// passes on updates that only change the status, for controllers that act on the status of their objects:
+///
+/// ```
+/// use futures::stream;
+/// use kube_runtime::{reflector::{reflector_dedup_by, store::{self, Writer}}, watcher};
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// let events = stream::empty::<watcher::Result<watcher::Event<Deployment>>>();
+/// let deduped = reflector_dedup_by(Writer::default(), events, store::eq_ignoring_volatile_fields);
+/// ```
+pub fn reflector_dedup_by<K, W>(
+    mut store: store::Writer<K>,
+    stream: W,
+    mut eq: impl FnMut(&K, &K) -> bool,
+) -> impl Stream<Item = W::Item>
+where
+    K: Resource + Clone,
+    K::DynamicType: Eq + Hash + Clone,
+    W: Stream<Item = watcher::Result<watcher::Event<K>>>,
+{
+    stream.try_filter_map(move |event| {
+        let unchanged = matches!(&event, watcher::Event::Applied(obj) if store.is_unchanged_by(obj, &mut eq));
+        store.apply_watcher_event(&event);
+        future::ready(Ok(if unchanged { None } else { Some(event) }))
+    })
+}
+
+/// Caches objects from `watcher::Event`s to a local `Store`, emitting what changed in the `Store`
+///
+/// Like [`reflector`], but rather than passing on the watch events, this emits an [`ObjectChange`] for
+/// every change they make to the store: `Updated` changes carry both the previous and the new version
+/// of the object. `Restarted` events are translated into the individual additions, updates and deletions.
+/// Use [`ObjectChange::diff`] to find the fields that changed, for change-data-capture style pipelines.
+///
+/// ```
+/// use futures::{stream, TryStreamExt};
+/// use kube_runtime::{reflector::{reflector_changes, store::{ObjectChange, Writer}}, watcher};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// # async fn scope() -> Result<(), watcher::Error> {
+/// let events = stream::empty::<watcher::Result<watcher::Event<ConfigMap>>>();
+/// reflector_changes(Writer::default(), events)
+///     .try_for_each(|change| async move {
+///         if let ObjectChange::Updated { .. } = &change {
+///             for field in change.diff() {
+///                 println!("{} changed: {:?}", field.path(), field);
+///             }
+///         }
+///         Ok(())
+///     })
+///     .await
+/// # }
+/// ```
+pub fn reflector_changes<K, W>(
+    mut store: store::Writer<K>,
+    stream: W,
+) -> impl Stream<Item = watcher::Result<ObjectChange<K>>>
+where
+    K: Resource + Clone + Send + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+    W: Stream<Item = watcher::Result<watcher::Event<K>>>,
+{
+    let changes = Arc::new(Mutex::new(Vec::new()));
+    let sink = changes.clone();
+    store.on_change(move |change| {
+        sink.lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(change.cloned());
+    });
+    stream
+        .map_ok(move |event| {
+            store.apply_watcher_event(&event);
+            let changes = std::mem::take(&mut *changes.lock().unwrap_or_else(PoisonError::into_inner));
+            stream::iter(changes.into_iter().map(Ok))
+        })
+        .try_flatten()
+}
+
+/// Periodically re-emits every object in a `Store`, without contacting the apiserver
+///
+/// Every `period`, all objects currently in the store are emitted, like client-go's resync.
+/// Merge this into the trigger stream of an [`applier`](crate::applier) to correct drift in external
+/// systems, or use [`Controller::resync_every`](crate::Controller::resync_every).
+///
+/// The first resync happens one `period` after the stream is first polled.
+pub fn resync<K>(store: Store<K>, period: Duration) -> impl Stream<Item = K>
+where
+    K: Resource + Clone + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    resync_with_clock(store, period, default_clock())
+}
+
+/// Like [`resync`], but waiting on `clock` rather than the [`TokioClock`](crate::time::TokioClock)
+pub fn resync_with_clock<K>(store: Store<K>, period: Duration, clock: Arc<dyn Clock>) -> impl Stream<Item = K>
+where
+    K: Resource + Clone + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    resync_by(store, period, clock, Store::state)
+}
+
+/// Like [`resync_with_clock`], but emitting the keys the objects are cached under
+pub(crate) fn resync_keys_with_clock<K>(
+    store: Store<K>,
+    period: Duration,
+    clock: Arc<dyn Clock>,
+) -> impl Stream<Item = ObjectRef<K>>
+where
+    K: Resource + Clone + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    resync_by(store, period, clock, Store::keys)
+}
+
+fn resync_by<K, T>(
+    store: Store<K>,
+    period: Duration,
+    clock: Arc<dyn Clock>,
+    snapshot: fn(&Store<K>) -> Vec<T>,
+) -> impl Stream<Item = T>
+where
+    K: Resource + Clone + 'static,
+    K::DynamicType: Eq + Hash + Clone,
+{
+    // The first deadline is only set once the stream is polled
+    stream::unfold((None, clock, store), move |(next, clock, store)| async move {
+        let next = next.unwrap_or_else(|| clock.now() + period);
+        clock.sleep_until(next).await;
+        let items = snapshot(&store);
+        Some((stream::iter(items), (Some(next + period), clock, store)))
+    })
+    .flatten()
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{reflector, store, ObjectRef};
+    use super::{
+        reflector, reflector_changes, reflector_dedup, reflector_dedup_by, resync, spawn_reflector, store,
+        ObjectRef,
+    };
     use crate::watcher;
     use futures::{stream, StreamExt, TryStreamExt};
-    use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+    use k8s_openapi::{
+        api::core::v1::{ConfigMap, Container, Pod, PodSpec, PodStatus},
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    };
     use rand::{
         distributions::{Bernoulli, Uniform},
         Rng,
     };
     use std::collections::{BTreeMap, HashMap};
 
+    #[tokio::test]
+    async fn reflector_changes_should_pair_old_and_new_versions() {
+        let cm = |name: &str, mode: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(std::iter::once(("mode".to_string(), mode.to_string())).collect()),
+            ..ConfigMap::default()
+        };
+        let changes = reflector_changes(
+            store::Writer::default(),
+            stream::iter(vec![
+                Ok(watcher::Event::Applied(cm("a", "slow"))),
+                Ok(watcher::Event::Applied(cm("a", "fast"))),
+                Ok(watcher::Event::Restarted(vec![cm("b", "slow")])),
+            ]),
+        )
+        .try_collect::<Vec<_>>()
+        .await
+        .unwrap();
+        assert_eq!(changes, vec![
+            store::ObjectChange::Added(cm("a", "slow")),
+            store::ObjectChange::Updated {
+                old: cm("a", "slow"),
+                new: cm("a", "fast")
+            },
+            store::ObjectChange::Deleted(cm("a", "fast")),
+            store::ObjectChange::Added(cm("b", "slow")),
+        ]);
+        assert_eq!(changes[1].diff(), vec![kube::ops::FieldChange::Changed {
+            path: "/data/mode".to_string(),
+            old: serde_json::json!("slow"),
+            new: serde_json::json!("fast"),
+        }]);
+    }
+
     #[tokio::test]
     async fn reflector_applied_should_add_object() {
         let store_w = store::Writer::default();
@@ -184,4 +456,155 @@ mod tests {
             seen_objects.insert(obj.metadata.name.clone().unwrap(), obj);
         }
     }
+
+    #[tokio::test]
+    async fn reflector_dedup_should_drop_unchanged_objects() {
+        let store_w = store::Writer::default();
+        let store = store_w.as_reader();
+        let cm = |rv: &str, data: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("a".to_string()),
+                resource_version: Some(rv.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(std::iter::once(("k".to_string(), data.to_string())).collect()),
+            ..ConfigMap::default()
+        };
+        let passed = reflector_dedup(
+            store_w,
+            stream::iter(vec![
+                Ok(watcher::Event::Applied(cm("1", "x"))),
+                Ok(watcher::Event::Applied(cm("2", "x"))),
+                Ok(watcher::Event::Applied(cm("3", "y"))),
+            ]),
+        )
+        .map(|event| match event.unwrap() {
+            watcher::Event::Applied(obj) => obj.metadata.resource_version.unwrap(),
+            _ => unreachable!(),
+        })
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(passed, vec!["1", "3"]);
+        assert_eq!(store.get(&ObjectRef::new("a")), Some(cm("3", "y")));
+    }
+
+    #[tokio::test]
+    async fn reflector_dedup_should_drop_status_only_updates() {
+        let store_w = store::Writer::default();
+        let store = store_w.as_reader();
+        let pod = |rv: &str, phase: &str| Pod {
+            metadata: ObjectMeta {
+                name: Some("a".to_string()),
+                resource_version: Some(rv.to_string()),
+                ..ObjectMeta::default()
+            },
+            status: Some(PodStatus {
+                phase: Some(phase.to_string()),
+                ..PodStatus::default()
+            }),
+            ..Pod::default()
+        };
+        let passed = reflector_dedup(
+            store_w,
+            stream::iter(vec![
+                Ok(watcher::Event::Applied(pod("1", "Pending"))),
+                Ok(watcher::Event::Applied(pod("2", "Running"))),
+            ]),
+        )
+        .collect::<Vec<_>>()
+        .await;
+        assert_eq!(passed.len(), 1);
+        // the store still sees the new status
+        assert_eq!(store.get(&ObjectRef::new("a")), Some(pod("2", "Running")));
+    }
+
+    #[tokio::test]
+    async fn reflector_dedup_by_should_use_the_given_equality() {
+        let pod = |rv: &str, phase: &str, image: &str| Pod {
+            metadata: ObjectMeta {
+                name: Some("a".to_string()),
+                resource_version: Some(rv.to_string()),
+                ..ObjectMeta::default()
+            },
+            spec: Some(PodSpec {
+                containers: vec![Container {
+                    name: "app".to_string(),
+                    image: Some(image.to_string()),
+                    ..Container::default()
+                }],
+                ..PodSpec::default()
+            }),
+            status: Some(PodStatus {
+                phase: Some(phase.to_string()),
+                ..PodStatus::default()
+            }),
+        };
+        let events = || {
+            stream::iter(vec![
+                Ok(watcher::Event::Applied(pod("1", "Pending", "app:1"))),
+                Ok(watcher::Event::Applied(pod("2", "Running", "app:1"))),
+                Ok(watcher::Event::Applied(pod("3", "Running", "app:2"))),
+            ])
+        };
+        let versions = |events: Vec<watcher::Result<watcher::Event<Pod>>>| {
+            events
+                .into_iter()
+                .map(|event| match event.unwrap() {
+                    watcher::Event::Applied(obj) => obj.metadata.resource_version.unwrap(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let with_status =
+            reflector_dedup_by(store::Writer::default(), events(), store::eq_ignoring_volatile_fields)
+                .collect()
+                .await;
+        assert_eq!(versions(with_status), vec!["1", "2", "3"]);
+        let without_status = reflector_dedup(store::Writer::default(), events())
+            .collect()
+            .await;
+        assert_eq!(versions(without_status), vec!["1", "3"]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn resync_should_reemit_cached_objects() {
+        let mut store_w = store::Writer::default();
+        let cm = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("a".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm.clone()));
+        let resynced = resync(store_w.as_reader(), std::time::Duration::from_secs(30));
+        // the period starts when the stream is polled, not when it is created
+        tokio::time::advance(std::time::Duration::from_secs(10)).await;
+        let start = tokio::time::Instant::now();
+        let resynced = resynced.take(2).collect::<Vec<_>>().await;
+        assert_eq!(resynced, vec![cm.clone(), cm]);
+        assert_eq!(start.elapsed(), std::time::Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn spawn_reflector_should_fill_store_in_background() {
+        let cm = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("a".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let store = spawn_reflector(store::Writer::default(), rx);
+        tx.unbounded_send(Ok(watcher::Event::Applied(cm.clone())))
+            .unwrap();
+        for _ in 0..100 {
+            if !store.is_empty() {
+                break;
+            }
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(store.get(&ObjectRef::from_obj(&cm)), Some(cm));
+    }
 }
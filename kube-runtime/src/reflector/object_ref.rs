@@ -44,6 +44,7 @@ pub struct ObjectRef<K: Resource> {
     /// assert_ne!(ObjectRef::<ConfigMap>::new("foo"), ObjectRef::new("foo").within("bar"));
     /// ```
     pub namespace: Option<String>,
+    pub(crate) cluster: Option<String>,
 }
 
 impl<K: Resource> ObjectRef<K>
@@ -71,6 +72,7 @@ impl<K: Resource> ObjectRef<K> {
             dyntype,
             name: name.into(),
             namespace: None,
+            cluster: None,
         }
     }
 
@@ -80,9 +82,35 @@ impl<K: Resource> ObjectRef<K> {
         self
     }
 
+    /// Refer to the object in a named cluster, rather than the controller's own cluster
+    ///
+    /// References to the same object in different clusters are not considered equal:
+    ///
+    /// ```
+    /// # use kube_runtime::reflector::ObjectRef;
+    /// # use k8s_openapi::api::core::v1::ConfigMap;
+    /// assert_ne!(ObjectRef::<ConfigMap>::new("foo"), ObjectRef::new("foo").in_cluster("spoke"));
+    /// ```
+    #[must_use]
+    pub fn in_cluster(mut self, cluster: &str) -> Self {
+        self.cluster = Some(cluster.to_string());
+        self
+    }
+
+    /// The cluster of the object, for controllers that span multiple clusters
+    ///
+    /// `None` refers to the cluster of the controller's own `Api`, see
+    /// [`Controller::with_cluster`](crate::Controller::with_cluster).
+    #[must_use]
+    pub fn cluster(&self) -> Option<&str> {
+        self.cluster.as_deref()
+    }
+
     /// Creates ObjectRef from the resource and dynamic type.
     /// Panics if name is missing (name always exists if the object
     /// was returned from the apiserver)
+    ///
+    /// The reference is to the controller's own cluster, objects don't record which cluster they are in.
     #[must_use]
     pub fn from_obj_with(obj: &K, dyntype: K::DynamicType) -> Self
     where
@@ -92,6 +120,7 @@ impl<K: Resource> ObjectRef<K> {
             dyntype,
             name: obj.name(),
             namespace: obj.namespace(),
+            cluster: None,
         }
     }
 
@@ -109,6 +138,7 @@ impl<K: Resource> ObjectRef<K> {
                 dyntype,
                 name: owner.name.clone(),
                 namespace: namespace.map(String::from),
+                cluster: None,
             })
         } else {
             None
@@ -125,6 +155,7 @@ impl<K: Resource> ObjectRef<K> {
             dyntype: dt2,
             name: self.name,
             namespace: self.namespace,
+            cluster: self.cluster,
         }
     }
 
@@ -138,6 +169,7 @@ impl<K: Resource> ObjectRef<K> {
             .expect("valid gvk"),
             name: self.name,
             namespace: self.namespace,
+            cluster: self.cluster,
         }
     }
 }
@@ -155,6 +187,10 @@ impl<K: Resource> Display for ObjectRef<K> {
         if let Some(namespace) = &self.namespace {
             write!(f, ".{}", namespace)?;
         }
+        if let Some(cluster) = &self.cluster {
+            f.write_str("@")?;
+            f.write_str(cluster)?;
+        }
         Ok(())
     }
 }
@@ -184,6 +220,15 @@ mod tests {
             format!("{}", ObjectRef::<Node>::new("my-node")),
             "Node.v1./my-node"
         );
+        assert_eq!(
+            format!(
+                "{}",
+                ObjectRef::<Pod>::new("my-pod")
+                    .within("my-namespace")
+                    .in_cluster("spoke")
+            ),
+            "Pod.v1./my-pod.my-namespace@spoke"
+        );
     }
 
     #[test]
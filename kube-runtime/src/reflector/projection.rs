@@ -0,0 +1,159 @@
+use super::ObjectRef;
+use crate::watcher;
+use derivative::Derivative;
+use futures::{Stream, TryStreamExt};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use kube::Resource;
+use std::{borrow::Cow, marker::PhantomData, ops::Deref};
+
+/// A projection `P` of an object of kind `K`, as cached by a [`project`]ed reflector
+///
+/// Only the identifying metadata of the object is kept (name, namespace, cluster, uid and
+/// resource version), along with the projected value. This is a [`Resource`] of the same kind as `K`,
+/// so it can be stored in a [`Store`](super::Store) and looked up by [`ObjectRef`].
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: Clone"), Debug(bound = "P: std::fmt::Debug"))]
+pub struct Projected<K, P> {
+    metadata: ObjectMeta,
+    /// The projected value
+    pub value: P,
+    #[derivative(Debug = "ignore")]
+    kind: PhantomData<fn() -> K>,
+}
+
+impl<K: Resource, P> Projected<K, P> {
+    /// Project `obj` with `projection`
+    pub fn new(obj: K, projection: impl FnOnce(K) -> P) -> Self {
+        let meta = obj.meta();
+        let metadata = ObjectMeta {
+            name: meta.name.clone(),
+            namespace: meta.namespace.clone(),
+            cluster_name: meta.cluster_name.clone(),
+            uid: meta.uid.clone(),
+            resource_version: meta.resource_version.clone(),
+            ..ObjectMeta::default()
+        };
+        Projected {
+            metadata,
+            value: projection(obj),
+            kind: PhantomData,
+        }
+    }
+
+    /// Convert a reference to an object into a reference to its projection
+    #[must_use]
+    pub fn object_ref(obj_ref: ObjectRef<K>, dyntype: K::DynamicType) -> ObjectRef<Self> {
+        obj_ref.into_kind_unchecked(dyntype)
+    }
+}
+
+impl<K, P> Deref for Projected<K, P> {
+    type Target = P;
+
+    fn deref(&self) -> &P {
+        &self.value
+    }
+}
+
+impl<K: Resource, P> Resource for Projected<K, P> {
+    type DynamicType = K::DynamicType;
+
+    fn kind(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::kind(dt)
+    }
+
+    fn group(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::group(dt)
+    }
+
+    fn version(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::version(dt)
+    }
+
+    fn api_version(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::api_version(dt)
+    }
+
+    fn plural(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::plural(dt)
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
+/// Projects the objects of a watch, so that a reflector only caches the fields it needs
+///
+/// Feed the projected stream to a [`reflector`](super::reflector) with a `Writer<Projected<K, P>>`
+/// to cache only the projections, typically a small struct of the fields a controller reads.
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams}, Client};
+/// use kube_runtime::{
+///     reflector::{project, reflector, store::Writer, ObjectRef, Projected},
+///     watcher,
+/// };
+/// use k8s_openapi::api::core::v1::Pod;
+/// # fn scope(client: Client) {
+/// let pods: Api<Pod> = Api::namespaced(client, "default");
+/// let node_name = |pod: Pod| pod.spec.and_then(|spec| spec.node_name);
+/// let writer = Writer::<Projected<Pod, Option<String>>>::default();
+/// let store = writer.as_reader();
+/// let stream = reflector(writer, project(watcher(pods, ListParams::default()), node_name));
+/// // later
+/// let key = Projected::object_ref(ObjectRef::<Pod>::new("blog").within("default"), ());
+/// let node = store.get(&key).and_then(|pod| pod.value);
+/// # }
+/// ```
+pub fn project<K, P, S>(
+    stream: S,
+    projection: impl Fn(K) -> P,
+) -> impl Stream<Item = watcher::Result<watcher::Event<Projected<K, P>>>>
+where
+    K: Resource,
+    S: Stream<Item = watcher::Result<watcher::Event<K>>>,
+{
+    stream.map_ok(move |event| event.map(|obj| Projected::new(obj, &projection)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{project, Projected};
+    use crate::{
+        reflector::{reflector, store::Writer, ObjectRef},
+        watcher,
+    };
+    use futures::{stream, StreamExt};
+    use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::ObjectMeta};
+
+    #[tokio::test]
+    async fn projected_store_keeps_only_the_projection() {
+        let cm = ConfigMap {
+            metadata: ObjectMeta {
+                name: Some("settings".to_string()),
+                namespace: Some("default".to_string()),
+                labels: Some(std::iter::once(("app".to_string(), "blog".to_string())).collect()),
+                ..ObjectMeta::default()
+            },
+            data: Some(std::iter::once(("mode".to_string(), "fast".to_string())).collect()),
+            ..ConfigMap::default()
+        };
+        let writer = Writer::<Projected<ConfigMap, Option<String>>>::default();
+        let store = writer.as_reader();
+        let mode = |cm: ConfigMap| cm.data.and_then(|data| data.get("mode").cloned());
+        let events = stream::iter(vec![Ok(watcher::Event::Applied(cm))]);
+        reflector(writer, project(events, mode))
+            .for_each(|_| async {})
+            .await;
+
+        let key = Projected::object_ref(ObjectRef::<ConfigMap>::new("settings").within("default"), ());
+        let cached = store.get(&key).unwrap();
+        assert_eq!(cached.as_deref(), Some("fast"));
+        assert!(kube::Resource::meta(&cached).labels.is_none());
+    }
+}
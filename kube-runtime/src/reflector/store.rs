@@ -1,22 +1,252 @@
 use super::ObjectRef;
-use crate::watcher;
+use crate::{
+    time::{default_clock, Clock},
+    watcher,
+};
 use dashmap::DashMap;
 use derivative::Derivative;
-use kube::Resource;
-use std::{collections::HashMap, fmt::Debug, hash::Hash, sync::Arc};
+use kube::{
+    ops::{diff, FieldChange},
+    Resource,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Debug,
+    hash::Hash,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// A function computing the values an object is indexed by, see [`Store::add_index`]
+pub type IndexFn<K> = dyn Fn(&K) -> Vec<String> + Send + Sync;
+
+/// A secondary index, mapping index values to the objects that have them
+struct Index<K: Resource>
+where
+    K::DynamicType: Eq + Hash,
+{
+    func: Box<IndexFn<K>>,
+    entries: HashMap<String, HashSet<ObjectRef<K>>>,
+}
+
+impl<K: Resource> Index<K>
+where
+    K::DynamicType: Eq + Hash + Clone,
+{
+    fn insert(&mut self, key: &ObjectRef<K>, obj: &K) {
+        for value in (self.func)(obj) {
+            self.entries.entry(value).or_default().insert(key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &ObjectRef<K>, obj: &K) {
+        for value in (self.func)(obj) {
+            if let Some(keys) = self.entries.get_mut(&value) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.entries.remove(&value);
+                }
+            }
+        }
+    }
+}
+
+type Indexes<K> = RwLock<HashMap<String, Index<K>>>;
+
+type Objects<K> = DashMap<ObjectRef<K>, K>;
+
+/// The objects of a store, replaced as a whole when a writer relists
+///
+/// Writers hold the read lock while they modify the objects, so a swap never loses their changes.
+type Shared<K> = Arc<RwLock<Arc<Objects<K>>>>;
+
+fn current<K: Resource>(shared: &Shared<K>) -> Arc<Objects<K>>
+where
+    K::DynamicType: Eq + Hash,
+{
+    shared.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// The final states of deleted objects, see [`Store::retain_tombstones`]
+struct Tombstones<K: Resource>
+where
+    K::DynamicType: Eq + Hash,
+{
+    retention: Mutex<Retention>,
+    objects: DashMap<ObjectRef<K>, (K, Instant)>,
+}
+
+/// How long tombstones are kept for, and when expired tombstones are dropped next
+struct Retention {
+    ttl: Option<Duration>,
+    next_prune: Option<Instant>,
+    clock: Arc<dyn Clock>,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Self {
+            ttl: None,
+            next_prune: None,
+            clock: default_clock(),
+        }
+    }
+}
+
+impl<K: Resource> Default for Tombstones<K>
+where
+    K::DynamicType: Eq + Hash,
+{
+    fn default() -> Self {
+        Self {
+            retention: Mutex::default(),
+            objects: DashMap::new(),
+        }
+    }
+}
+
+impl<K: Resource> Tombstones<K>
+where
+    K::DynamicType: Eq + Hash,
+{
+    /// Drop expired tombstones, returning the `ttl` and the current time if tombstones are retained
+    fn prune(&self) -> Option<(Duration, Instant)> {
+        let (ttl, now, prune) = {
+            let mut retention = self.retention.lock().unwrap_or_else(PoisonError::into_inner);
+            let ttl = retention.ttl?;
+            let now = retention.clock.now();
+            // Expired tombstones are dropped at most once per `ttl`, so they are kept for up to twice as long
+            let prune = !matches!(retention.next_prune, Some(next) if next > now);
+            if prune {
+                retention.next_prune = Some(now + ttl);
+            }
+            (ttl, now, prune)
+        };
+        if prune {
+            self.objects
+                .retain(|_, (_, buried)| now.saturating_duration_since(*buried) < ttl);
+        }
+        Some((ttl, now))
+    }
+
+    /// Bury `obj`, returning whether tombstones are retained
+    fn bury(&self, key: ObjectRef<K>, obj: K) -> bool {
+        let retained = self.prune();
+        if let Some((_, now)) = retained {
+            self.objects.insert(key, (obj, now));
+        }
+        retained.is_some()
+    }
+
+    fn get(&self, key: &ObjectRef<K>) -> Option<K>
+    where
+        K: Clone,
+    {
+        let (ttl, now) = self.prune()?;
+        let entry = self.objects.get(key)?;
+        let (obj, buried) = entry.value();
+        if now.saturating_duration_since(*buried) < ttl {
+            Some(obj.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Drop the tombstone of `key`, returning whether there was one
+    fn forget(&self, key: &ObjectRef<K>) -> bool {
+        self.prune();
+        self.objects.remove(key).is_some()
+    }
+}
+
+type Hook<K> = Box<dyn FnMut(&Change<'_, K>) + Send>;
+
+/// A change to a [`Store`], passed to hooks registered with [`Writer::on_change`]
+#[derive(Debug)]
+pub enum Change<'a, K> {
+    /// An object was added to the store
+    Added(&'a K),
+    /// An object in the store was replaced
+    Updated {
+        /// The previous version of the object
+        old: &'a K,
+        /// The new version of the object
+        new: &'a K,
+    },
+    /// An object was removed from the store, this is the last known version of it
+    Deleted(&'a K),
+}
+
+impl<K: Clone> Change<'_, K> {
+    /// Clone the objects of the change
+    #[must_use]
+    pub fn cloned(&self) -> ObjectChange<K> {
+        match self {
+            Change::Added(obj) => ObjectChange::Added((*obj).clone()),
+            Change::Updated { old, new } => ObjectChange::Updated {
+                old: (*old).clone(),
+                new: (*new).clone(),
+            },
+            Change::Deleted(obj) => ObjectChange::Deleted((*obj).clone()),
+        }
+    }
+}
+
+/// An owned [`Change`], as emitted by [`reflector_changes`](super::reflector_changes)
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectChange<K> {
+    /// An object was added to the store
+    Added(K),
+    /// An object in the store was replaced
+    Updated {
+        /// The previous version of the object
+        old: K,
+        /// The new version of the object
+        new: K,
+    },
+    /// An object was removed from the store, this is the last known version of it
+    Deleted(K),
+}
+
+impl<K: serde::Serialize> ObjectChange<K> {
+    /// The fields that changed, see [`kube::ops::diff`]
+    ///
+    /// Added objects are diffed against `null`, and deleted objects against `null` as the new version.
+    /// Objects that can't be serialized are treated as `null`.
+    #[must_use]
+    pub fn diff(&self) -> Vec<FieldChange> {
+        let value = |obj: &K| serde_json::to_value(obj).unwrap_or_default();
+        match self {
+            ObjectChange::Added(obj) => diff(&serde_json::Value::Null, &value(obj)),
+            ObjectChange::Updated { old, new } => diff(&value(old), &value(new)),
+            ObjectChange::Deleted(obj) => diff(&value(obj), &serde_json::Value::Null),
+        }
+    }
+}
 
 /// A writable Store handle
 ///
 /// This is exclusive since it's not safe to share a single `Store` between multiple reflectors.
 /// In particular, `Restarted` events will clobber the state of other connected reflectors.
-#[derive(Debug, Derivative)]
-#[derivative(Default(bound = "K::DynamicType: Default"))]
+#[derive(Derivative)]
+#[derivative(
+    Default(bound = "K::DynamicType: Default"),
+    Debug(bound = "K: Debug, K::DynamicType: Debug")
+)]
 pub struct Writer<K: 'static + Resource>
 where
     K::DynamicType: Eq + Hash,
 {
-    store: Arc<DashMap<ObjectRef<K>, K>>,
+    store: Shared<K>,
+    #[derivative(Debug = "ignore")]
+    indexes: Arc<Indexes<K>>,
+    #[derivative(Debug = "ignore")]
+    tombstones: Arc<Tombstones<K>>,
+    #[derivative(Debug = "ignore")]
+    hooks: Vec<Hook<K>>,
     dyntype: K::DynamicType,
+    cluster: Option<String>,
 }
 
 impl<K: 'static + Resource + Clone> Writer<K>
@@ -30,10 +260,44 @@ where
     pub fn new(dyntype: K::DynamicType) -> Self {
         Writer {
             store: Default::default(),
+            indexes: Default::default(),
+            tombstones: Default::default(),
+            hooks: Vec::new(),
             dyntype,
+            cluster: None,
         }
     }
 
+    /// Create a writer for the objects of another cluster, sharing the store of `self`
+    ///
+    /// The objects are cached under [`ObjectRef`]s to the cluster, the objects themselves are not changed.
+    /// Writers for different clusters do not clobber each others objects on `Restarted` events,
+    /// so the store can cache a kind across several clusters.
+    /// Hooks registered with [`on_change`](Self::on_change) are not shared.
+    #[must_use]
+    pub fn for_cluster(&self, cluster: &str) -> Self {
+        Self::sharing(&self.as_reader(), self.dyntype.clone(), Some(cluster.to_string()))
+    }
+
+    /// Create a writer for the objects of a cluster, sharing the store behind `reader`
+    pub(crate) fn sharing(reader: &Store<K>, dyntype: K::DynamicType, cluster: Option<String>) -> Self {
+        Writer {
+            store: reader.objects.clone(),
+            indexes: reader.indexes.clone(),
+            tombstones: reader.tombstones.clone(),
+            hooks: Vec::new(),
+            dyntype,
+            cluster,
+        }
+    }
+
+    /// The reference `obj` is cached under, in the cluster of this writer
+    pub(crate) fn key(&self, obj: &K) -> ObjectRef<K> {
+        let mut key = ObjectRef::from_obj_with(obj, self.dyntype.clone());
+        key.cluster.clone_from(&self.cluster);
+        key
+    }
+
     /// Return a read handle to the store
     ///
     /// Multiple read handles may be obtained, by either calling `as_reader` multiple times,
@@ -41,31 +305,162 @@ where
     #[must_use]
     pub fn as_reader(&self) -> Store<K> {
         Store {
-            store: self.store.clone(),
+            objects: self.store.clone(),
+            indexes: self.indexes.clone(),
+            tombstones: self.tombstones.clone(),
         }
     }
 
+    /// Register a hook that is called for every change to the store
+    ///
+    /// Hooks run synchronously as the reflector applies events, after the store has been updated,
+    /// so they should be quick. Use them for side tasks that need to follow the cache (such as metrics
+    /// or search indexes), rather than starting a second watch.
+    ///
+    /// `Restarted` events are translated into the individual additions, updates and deletions.
+    ///
+    /// ```
+    /// use kube_runtime::reflector::store::{Change, Writer};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// let mut writer = Writer::<ConfigMap>::default();
+    /// writer.on_change(|change| {
+    ///     if let Change::Deleted(cm) = change {
+    ///         println!("deleted {:?}", cm.metadata.name);
+    ///     }
+    /// });
+    /// ```
+    pub fn on_change(&mut self, hook: impl FnMut(&Change<'_, K>) + Send + 'static) {
+        self.hooks.push(Box::new(hook));
+    }
+
     /// Applies a single watcher event to the store
     pub fn apply_watcher_event(&mut self, event: &watcher::Event<K>) {
+        self.apply_watcher_event_burying(event);
+    }
+
+    /// Applies a single watcher event to the store, returning the objects it left tombstones of
+    ///
+    /// Nothing is buried unless tombstones are [retained](Store::retain_tombstones).
+    pub(crate) fn apply_watcher_event_burying(&mut self, event: &watcher::Event<K>) -> Vec<ObjectRef<K>> {
         match event {
             watcher::Event::Applied(obj) => {
-                self.store
-                    .insert(ObjectRef::from_obj_with(&obj, self.dyntype.clone()), obj.clone());
+                self.apply(obj);
+                vec![]
             }
             watcher::Event::Deleted(obj) => {
-                self.store
-                    .remove(&ObjectRef::from_obj_with(&obj, self.dyntype.clone()));
+                let key = self.key(obj);
+                let old = self.modify(|objects| objects.remove(&key));
+                let buried = self.tombstones.bury(key.clone(), obj.clone());
+                if let Some((_, old)) = old {
+                    self.update_indexes(&key, Some(&old), None);
+                    self.notify(&Change::Deleted(&old));
+                }
+                if buried {
+                    vec![key]
+                } else {
+                    vec![]
+                }
+            }
+            watcher::Event::Restarted(new_objs) => self.replace(new_objs),
+        }
+    }
+
+    /// Swap in the relisted objects, so readers never see a partially replaced state
+    fn replace(&mut self, new_objs: &[K]) -> Vec<ObjectRef<K>> {
+        let fresh = new_objs
+            .iter()
+            .map(|obj| (self.key(obj), obj.clone()))
+            .collect::<Objects<K>>();
+        let old = {
+            // Indexes are locked first, like in `Store::add_index`
+            let mut indexes = self.indexes.write().unwrap_or_else(PoisonError::into_inner);
+            let old = {
+                let mut objects = self.store.write().unwrap_or_else(PoisonError::into_inner);
+                // The objects of other clusters are kept as they are
+                for entry in objects.iter() {
+                    if entry.key().cluster != self.cluster {
+                        fresh.insert(entry.key().clone(), entry.value().clone());
+                    }
+                }
+                std::mem::replace(&mut *objects, Arc::new(fresh))
+            };
+            let objects = current(&self.store);
+            for index in indexes.values_mut() {
+                index.entries.clear();
+                for entry in objects.iter() {
+                    index.insert(entry.key(), entry.value());
+                }
             }
-            watcher::Event::Restarted(new_objs) => {
-                let new_objs = new_objs
-                    .iter()
-                    .map(|obj| (ObjectRef::from_obj_with(obj, self.dyntype.clone()), obj))
-                    .collect::<HashMap<_, _>>();
-                // We can't do do the whole replacement atomically, but we should at least not delete objects that still exist
-                self.store.retain(|key, _old_value| new_objs.contains_key(key));
-                for (key, obj) in new_objs {
-                    self.store.insert(key, obj.clone());
+            old
+        };
+
+        let new_keys = new_objs
+            .iter()
+            .map(|obj| self.key(obj))
+            .collect::<HashSet<_>>();
+        let mut buried = vec![];
+        for entry in old.iter() {
+            if entry.key().cluster == self.cluster && !new_keys.contains(entry.key()) {
+                if self.tombstones.bury(entry.key().clone(), entry.value().clone()) {
+                    buried.push(entry.key().clone());
                 }
+                self.notify(&Change::Deleted(entry.value()));
+            }
+        }
+        for obj in new_objs {
+            let key = self.key(obj);
+            self.tombstones.objects.remove(&key);
+            match old.get(&key) {
+                Some(old) => self.notify(&Change::Updated {
+                    old: old.value(),
+                    new: obj,
+                }),
+                None => self.notify(&Change::Added(obj)),
+            }
+        }
+        buried
+    }
+
+    /// Modify the current objects, holding off swaps until done
+    fn modify<T>(&self, f: impl FnOnce(&Objects<K>) -> T) -> T {
+        let objects = self.store.read().unwrap_or_else(PoisonError::into_inner);
+        f(&objects)
+    }
+
+    /// Whether `obj` is equal to its cached copy according to `eq`, which is passed the cached copy first
+    pub(crate) fn is_unchanged_by(&self, obj: &K, eq: impl FnOnce(&K, &K) -> bool) -> bool {
+        let key = self.key(obj);
+        match current(&self.store).get(&key) {
+            Some(cached) => eq(cached.value(), obj),
+            None => false,
+        }
+    }
+
+    fn apply(&mut self, obj: &K) {
+        let key = self.key(obj);
+        let old = self.modify(|objects| objects.insert(key.clone(), obj.clone()));
+        self.tombstones.objects.remove(&key);
+        self.update_indexes(&key, old.as_ref(), Some(obj));
+        self.notify(&match &old {
+            Some(old) => Change::Updated { old, new: obj },
+            None => Change::Added(obj),
+        });
+    }
+
+    fn notify(&mut self, change: &Change<'_, K>) {
+        for hook in &mut self.hooks {
+            hook(change);
+        }
+    }
+
+    fn update_indexes(&self, key: &ObjectRef<K>, old: Option<&K>, new: Option<&K>) {
+        let mut indexes = self.indexes.write().unwrap_or_else(PoisonError::into_inner);
+        for index in indexes.values_mut() {
+            if let Some(old) = old {
+                index.remove(key, old);
+            }
+            if let Some(new) = new {
+                index.insert(key, new);
             }
         }
     }
@@ -83,7 +478,11 @@ pub struct Store<K: 'static + Resource>
 where
     K::DynamicType: Hash + Eq,
 {
-    store: Arc<DashMap<ObjectRef<K>, K>>,
+    objects: Shared<K>,
+    #[derivative(Debug = "ignore")]
+    indexes: Arc<Indexes<K>>,
+    #[derivative(Debug = "ignore")]
+    tombstones: Arc<Tombstones<K>>,
 }
 
 impl<K: 'static + Clone + Resource> Store<K>
@@ -101,11 +500,12 @@ where
     /// reasonable `error_policy`.
     #[must_use]
     pub fn get(&self, key: &ObjectRef<K>) -> Option<K> {
-        self.store
+        let objects = current(&self.objects);
+        objects
             .get(key)
             // Try to erase the namespace and try again, in case the object is cluster-scoped
             .or_else(|| {
-                self.store.get(&{
+                objects.get(&{
                     let mut cluster_key = key.clone();
                     cluster_key.namespace = None;
                     cluster_key
@@ -115,19 +515,211 @@ where
             .map(|entry| entry.value().clone())
     }
 
+    /// Keep the final state of objects for `ttl` after they are deleted, see [`tombstone`](Self::tombstone)
+    ///
+    /// This applies to the writer of the store and all its readers, from the next deletion on.
+    /// Calling it again changes the `ttl` of all tombstones.
+    pub fn retain_tombstones(&self, ttl: Duration) {
+        self.retain_tombstones_with_clock(ttl, default_clock());
+    }
+
+    /// Like [`retain_tombstones`](Self::retain_tombstones), but telling the age of tombstones with `clock`
+    pub fn retain_tombstones_with_clock(&self, ttl: Duration, clock: Arc<dyn Clock>) {
+        let mut retention = self
+            .tombstones
+            .retention
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        retention.ttl = Some(ttl);
+        retention.next_prune = None;
+        retention.clock = clock;
+    }
+
+    /// Retrieve a `clone()` of the final state of the deleted object referred to by `key`
+    ///
+    /// Only available after [`retain_tombstones`](Self::retain_tombstones) was called. Objects deleted
+    /// while the watch was interrupted get a tombstone when the watch relists, with the last state
+    /// the store saw. A tombstone is kept until it expires, the object is recreated, or it is
+    /// [forgotten](Self::forget_tombstone).
+    #[must_use]
+    pub fn tombstone(&self, key: &ObjectRef<K>) -> Option<K> {
+        self.tombstones.get(key).or_else(|| {
+            self.tombstones.get(&{
+                let mut cluster_key = key.clone();
+                cluster_key.namespace = None;
+                cluster_key
+            })
+        })
+    }
+
+    /// Drop the tombstone of `key`, once the deletion of the object has been handled
+    ///
+    /// `key.namespace` is ignored for cluster-scoped resources, like for [`tombstone`](Self::tombstone).
+    pub fn forget_tombstone(&self, key: &ObjectRef<K>) {
+        if !self.tombstones.forget(key) {
+            self.tombstones.forget(&{
+                let mut cluster_key = key.clone();
+                cluster_key.namespace = None;
+                cluster_key
+            });
+        }
+    }
+
+    /// The reference `obj` is cached under, which tells the cluster it is in
+    ///
+    /// The cached object with the same name, namespace and uid is looked up, this takes time linear
+    /// in the size of the store. Returns `None` if the object is not cached.
+    #[must_use]
+    pub fn key_of(&self, obj: &K) -> Option<ObjectRef<K>> {
+        let (name, namespace, uid) = (obj.meta().name.as_ref()?, &obj.meta().namespace, &obj.meta().uid);
+        current(&self.objects)
+            .iter()
+            .find(|entry| {
+                let key = entry.key();
+                &key.name == name && &key.namespace == namespace && &entry.value().meta().uid == uid
+            })
+            .map(|entry| entry.key().clone())
+    }
+
+    /// The references of all cached objects
+    pub(crate) fn keys(&self) -> Vec<ObjectRef<K>> {
+        current(&self.objects).iter().map(|entry| entry.key().clone()).collect()
+    }
+
     /// Return a full snapshot of the current values
     #[must_use]
     pub fn state(&self) -> Vec<K> {
-        self.store.iter().map(|eg| eg.value().clone()).collect()
+        current(&self.objects)
+            .iter()
+            .map(|eg| eg.value().clone())
+            .collect()
+    }
+
+    /// Call `f` with every object in the store, without cloning them
+    ///
+    /// Parts of the store are locked while `f` runs, so `f` should be quick and must not
+    /// call back into the store.
+    pub fn for_each(&self, mut f: impl FnMut(&K)) {
+        for entry in current(&self.objects).iter() {
+            f(entry.value());
+        }
+    }
+
+    /// Retrieve a `clone()` of the first object matching `predicate`
+    ///
+    /// Only the matching object is cloned. The same locking caveats as [`for_each`](Self::for_each) apply.
+    pub fn find(&self, mut predicate: impl FnMut(&K) -> bool) -> Option<K> {
+        current(&self.objects)
+            .iter()
+            .find(|entry| predicate(entry.value()))
+            .map(|entry| entry.value().clone())
+    }
+
+    /// The number of objects in the store
+    #[must_use]
+    pub fn len(&self) -> usize {
+        current(&self.objects).len()
+    }
+
+    /// Whether the store is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        current(&self.objects).is_empty()
+    }
+
+    /// Register a secondary index on the store
+    ///
+    /// `index` computes the values an object is indexed by, objects can then be looked up by any
+    /// of those values with [`get_by_index`](Self::get_by_index). Objects already in the store are
+    /// indexed immediately, and the index is kept up to date with later changes. Registering an
+    /// index with an existing name replaces it.
+    ///
+    /// ```
+    /// use kube_runtime::reflector::store::Writer;
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// let writer = Writer::<Pod>::default();
+    /// let store = writer.as_reader();
+    /// store.add_index("node", |pod: &Pod| {
+    ///     pod.spec.as_ref().and_then(|s| s.node_name.clone()).into_iter().collect()
+    /// });
+    /// assert!(store.get_by_index("node", "worker-1").is_empty());
+    /// ```
+    pub fn add_index(&self, name: &str, index: impl Fn(&K) -> Vec<String> + Send + Sync + 'static) {
+        let mut index = Index {
+            func: Box::new(index),
+            entries: HashMap::new(),
+        };
+        let mut indexes = self.indexes.write().unwrap_or_else(PoisonError::into_inner);
+        for entry in current(&self.objects).iter() {
+            index.insert(entry.key(), entry.value());
+        }
+        indexes.insert(name.to_string(), index);
+    }
+
+    /// Retrieve a `clone()` of every object with the value `value` in the index `index`
+    ///
+    /// Returns an empty `Vec` if no index with that name has been registered with
+    /// [`add_index`](Self::add_index). Like [`get`](Self::get), the results may be stale.
+    #[must_use]
+    pub fn get_by_index(&self, index: &str, value: &str) -> Vec<K> {
+        let keys = {
+            let indexes = self.indexes.read().unwrap_or_else(PoisonError::into_inner);
+            match indexes.get(index).and_then(|index| index.entries.get(value)) {
+                Some(keys) => keys.iter().cloned().collect::<Vec<_>>(),
+                None => return vec![],
+            }
+        };
+        let objects = current(&self.objects);
+        keys.iter()
+            .filter_map(|key| objects.get(key).map(|entry| entry.value().clone()))
+            .collect()
+    }
+}
+
+fn without_volatile_fields<K: Resource + Clone>(obj: &K) -> K {
+    let mut obj = obj.clone();
+    let meta = obj.meta_mut();
+    meta.resource_version = None;
+    meta.managed_fields = None;
+    obj
+}
+
+/// Whether two versions of an object are equal, ignoring `resourceVersion` and `managedFields`
+///
+/// For controllers that act on the status of their objects, with [`reflector_dedup_by`](super::reflector_dedup_by).
+pub fn eq_ignoring_volatile_fields<K: Resource + Clone + PartialEq>(old: &K, new: &K) -> bool {
+    without_volatile_fields(old) == without_volatile_fields(new)
+}
+
+/// Whether two versions of an object are equal, ignoring `resourceVersion`, `managedFields` and the `status`
+///
+/// This is the equality used by [`reflector_dedup`](super::reflector_dedup), for controllers that only act on
+/// the desired state of their objects, and so don't need to see status updates, like those written by the
+/// controller itself. Objects that can't be serialized are never equal.
+pub fn eq_ignoring_status<K: serde::Serialize>(old: &K, new: &K) -> bool {
+    let value = |obj: &K| {
+        let mut value = serde_json::to_value(obj).ok()?;
+        let obj = value.as_object_mut()?;
+        obj.remove("status");
+        if let Some(meta) = obj.get_mut("metadata").and_then(serde_json::Value::as_object_mut) {
+            meta.remove("resourceVersion");
+            meta.remove("managedFields");
+        }
+        Some(value)
+    };
+    match (value(old), value(new)) {
+        (Some(old), Some(new)) => old == new,
+        _ => false,
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Writer;
+    use super::{Change, Writer};
     use crate::{reflector::ObjectRef, watcher};
     use k8s_openapi::api::core::v1::ConfigMap;
-    use kube::api::ObjectMeta;
+    use kube::api::{ObjectMeta, ResourceExt};
+    use std::time::Duration;
 
     #[test]
     fn should_allow_getting_namespaced_object_by_namespaced_ref() {
@@ -145,6 +737,44 @@ mod tests {
         assert_eq!(store.get(&ObjectRef::from_obj(&cm)), Some(cm));
     }
 
+    #[test]
+    fn writers_for_clusters_share_the_store() {
+        let cm = |name: &str, uid: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some("ns".to_string()),
+                uid: Some(uid.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let mut hub = Writer::<ConfigMap>::default();
+        let mut spoke = hub.for_cluster("spoke");
+        hub.apply_watcher_event(&watcher::Event::Applied(cm("a", "hub-a")));
+        spoke.apply_watcher_event(&watcher::Event::Applied(cm("a", "spoke-a")));
+        let store = hub.as_reader();
+        assert_eq!(store.len(), 2);
+        assert_eq!(
+            store.get(&ObjectRef::new("a").within("ns").in_cluster("spoke")),
+            Some(cm("a", "spoke-a"))
+        );
+        let spoke_a = store.key_of(&cm("a", "spoke-a")).unwrap();
+        assert_eq!(spoke_a.cluster(), Some("spoke"));
+        assert_eq!(store.key_of(&cm("a", "hub-a")).unwrap().cluster(), None);
+        // the objects themselves don't record their cluster
+        assert_eq!(store.get(&spoke_a).unwrap().metadata.cluster_name, None);
+
+        // relisting one cluster leaves the objects of other clusters alone
+        spoke.apply_watcher_event(&watcher::Event::Restarted(vec![cm("b", "spoke-b")]));
+        assert_eq!(store.get(&ObjectRef::new("a").within("ns")), Some(cm("a", "hub-a")));
+        assert_eq!(
+            store.get(&ObjectRef::new("a").within("ns").in_cluster("spoke")),
+            None
+        );
+        hub.apply_watcher_event(&watcher::Event::Restarted(vec![]));
+        assert_eq!(store.state(), vec![cm("b", "spoke-b")]);
+    }
+
     #[test]
     fn should_not_allow_getting_namespaced_object_by_clusterscoped_ref() {
         let cm = ConfigMap {
@@ -196,4 +826,230 @@ mod tests {
         let store = store_w.as_reader();
         assert_eq!(store.get(&ObjectRef::from_obj(&nsed_cm)), Some(cm));
     }
+
+    #[test]
+    fn should_keep_indexes_up_to_date() {
+        let cm = |name: &str, app: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                labels: Some(std::iter::once(("app".to_string(), app.to_string())).collect()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let mut store_w = Writer::default();
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("a", "blog")));
+        let store = store_w.as_reader();
+        store.add_index("app", |cm: &ConfigMap| {
+            cm.labels().get("app").cloned().into_iter().collect()
+        });
+        assert_eq!(store.get_by_index("app", "blog"), vec![cm("a", "blog")]);
+
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("a", "shop")));
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("b", "blog")));
+        assert_eq!(store.get_by_index("app", "blog"), vec![cm("b", "blog")]);
+        assert_eq!(store.get_by_index("app", "shop"), vec![cm("a", "shop")]);
+
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("b", "blog")));
+        assert!(store.get_by_index("app", "blog").is_empty());
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![cm("c", "blog")]));
+        assert_eq!(store.get_by_index("app", "blog"), vec![cm("c", "blog")]);
+        assert!(store.get_by_index("app", "shop").is_empty());
+        assert!(store.get_by_index("missing", "blog").is_empty());
+    }
+
+    #[test]
+    fn should_query_without_cloning_state() {
+        let cm = |name: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let mut store_w = Writer::default();
+        let store = store_w.as_reader();
+        assert!(store.is_empty());
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![cm("a"), cm("b")]));
+        assert_eq!(store.len(), 2);
+        assert_eq!(store.find(|cm| cm.name() == "b"), Some(cm("b")));
+        assert_eq!(store.find(|cm| cm.name() == "c"), None);
+        let mut names = vec![];
+        store.for_each(|cm| names.push(cm.name()));
+        names.sort();
+        assert_eq!(names, vec!["a", "b"]);
+    }
+
+    #[test]
+    fn should_notify_hooks_of_changes() {
+        use std::sync::{Arc, Mutex};
+        let cm = |name: &str, data: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(std::iter::once(("k".to_string(), data.to_string())).collect()),
+            ..ConfigMap::default()
+        };
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut store_w = Writer::default();
+        store_w.on_change({
+            let seen = seen.clone();
+            move |change: &Change<'_, ConfigMap>| {
+                let desc = match change {
+                    Change::Added(new) => format!("added {}", new.name()),
+                    Change::Updated { old, new } => {
+                        format!(
+                            "updated {} {} -> {}",
+                            new.name(),
+                            old.data.as_ref().unwrap()["k"],
+                            new.data.as_ref().unwrap()["k"]
+                        )
+                    }
+                    Change::Deleted(old) => format!("deleted {}", old.name()),
+                };
+                seen.lock().unwrap().push(desc);
+            }
+        });
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("a", "1")));
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("a", "2")));
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("b", "1")));
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("b", "1")));
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("b", "1")));
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![cm("c", "1")]));
+        assert_eq!(*seen.lock().unwrap(), vec![
+            "added a",
+            "updated a 1 -> 2",
+            "added b",
+            "deleted b",
+            "deleted a",
+            "added c",
+        ]);
+    }
+
+    #[test]
+    fn restarts_swap_in_the_relisted_state() {
+        use std::sync::{Arc, Mutex};
+        let cm = |name: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        };
+        let mut store_w = Writer::default();
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![cm("a"), cm("b"), cm("c")]));
+        let store = store_w.as_reader();
+        let before = store.state();
+        store.add_index("name", |cm: &ConfigMap| vec![cm.name()]);
+
+        // hooks see the complete relisted state for every change
+        let observed = Arc::new(Mutex::new(vec![]));
+        let (reader, sink) = (store.clone(), observed.clone());
+        store_w.on_change(move |_| {
+            let mut names = reader.state().into_iter().map(|cm| cm.name()).collect::<Vec<_>>();
+            names.sort();
+            sink.lock().unwrap().push(names);
+        });
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![cm("c"), cm("d")]));
+        let observed = observed.lock().unwrap();
+        assert_eq!(observed.len(), 4);
+        assert!(observed.iter().all(|names| names == &["c", "d"]));
+        // earlier snapshots are unaffected, and indexes follow the swap
+        assert_eq!(before.len(), 3);
+        assert_eq!(store.get_by_index("name", "d"), vec![cm("d")]);
+        assert!(store.get_by_index("name", "a").is_empty());
+        drop(observed);
+
+        // readers racing the swaps see either the old or the new objects, never a mix
+        let reader = store.clone();
+        let racing = std::thread::spawn(move || {
+            (0..1000)
+                .map(|_| {
+                    let mut names = reader.state().into_iter().map(|cm| cm.name()).collect::<Vec<_>>();
+                    names.sort();
+                    names
+                })
+                .collect::<Vec<_>>()
+        });
+        for i in 0..200 {
+            let objs = if i % 2 == 0 {
+                vec![cm("a"), cm("b"), cm("c")]
+            } else {
+                vec![cm("c"), cm("d")]
+            };
+            store_w.apply_watcher_event(&watcher::Event::Restarted(objs));
+        }
+        for names in racing.join().unwrap() {
+            assert!(names == ["a", "b", "c"] || names == ["c", "d"], "{:?}", names);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn tombstones_keep_the_final_state_of_deleted_objects() {
+        let cm = |name: &str, value: &str| ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            data: Some(std::iter::once(("k".to_string(), value.to_string())).collect()),
+            ..ConfigMap::default()
+        };
+        let mut store_w = Writer::default();
+        let store = store_w.as_reader();
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("a", "untracked")));
+        assert_eq!(store.tombstone(&ObjectRef::new("a")), None);
+
+        store.retain_tombstones(Duration::from_secs(60));
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![cm("a", "1"), cm("b", "1")]));
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("a", "final")));
+        assert_eq!(store.tombstone(&ObjectRef::new("a")), Some(cm("a", "final")));
+        // objects that are missing from a relist are buried with their last known state
+        store_w.apply_watcher_event(&watcher::Event::Restarted(vec![]));
+        assert_eq!(store.tombstone(&ObjectRef::new("b")), Some(cm("b", "1")));
+        store.forget_tombstone(&ObjectRef::new("b"));
+        assert_eq!(store.tombstone(&ObjectRef::new("b")), None);
+        // recreated objects are alive again
+        store_w.apply_watcher_event(&watcher::Event::Applied(cm("a", "2")));
+        assert_eq!(store.tombstone(&ObjectRef::new("a")), None);
+
+        // tombstones expire, and are dropped by later burials
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("a", "3")));
+        tokio::time::advance(Duration::from_secs(61)).await;
+        assert_eq!(store.tombstone(&ObjectRef::new("a")), None);
+        store_w.apply_watcher_event(&watcher::Event::Deleted(cm("c", "1")));
+        assert_eq!(store.tombstones.objects.len(), 1);
+        assert_eq!(store.tombstone(&ObjectRef::new("c")), Some(cm("c", "1")));
+    }
+
+    #[test]
+    fn tombstones_expire_on_the_given_clock() {
+        use crate::time::MockClock;
+        use k8s_openapi::api::core::v1::Namespace;
+        use std::sync::Arc;
+
+        let ns = |name: &str| Namespace {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..Namespace::default()
+        };
+        let clock = MockClock::new();
+        let mut store_w = Writer::default();
+        let store = store_w.as_reader();
+        store.retain_tombstones_with_clock(Duration::from_secs(60), Arc::new(clock.clone()));
+        store_w.apply_watcher_event(&watcher::Event::Deleted(ns("a")));
+        store_w.apply_watcher_event(&watcher::Event::Deleted(ns("b")));
+        // cluster-scoped tombstones are forgotten with namespaced keys too, like they are looked up
+        store.forget_tombstone(&ObjectRef::new("b").within("ns"));
+        assert_eq!(store.tombstone(&ObjectRef::new("b")), None);
+
+        clock.advance(Duration::from_secs(59));
+        assert_eq!(store.tombstone(&ObjectRef::new("a")), Some(ns("a")));
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(store.tombstone(&ObjectRef::new("a")), None);
+        // expired tombstones are dropped by reads, not only by later burials
+        assert_eq!(store.tombstones.objects.len(), 0);
+    }
 }
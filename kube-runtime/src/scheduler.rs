@@ -1,28 +1,31 @@
 //! Delays and deduplicates [`Stream`] items
 
+use crate::time::{default_clock, Clock};
 use futures::{
+    future::BoxFuture,
     stream::{Fuse, FusedStream},
-    Stream, StreamExt,
+    FutureExt, Stream, StreamExt,
 };
 use pin_project::pin_project;
-use snafu::{Backtrace, ResultExt, Snafu};
+use snafu::Snafu;
 use std::{
-    collections::{hash_map::Entry, HashMap, HashSet},
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
     hash::Hash,
     pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, PoisonError,
+    },
     task::{Context, Poll},
+    time::Duration,
 };
-use tokio::time::{self, Instant};
-use tokio_util::time::delay_queue::{self, DelayQueue};
+use tokio::time::Instant;
 
+/// Errors of a [`Scheduler`]
+///
+/// The timers of a [`Clock`] can not fail, so there are none.
 #[derive(Debug, Snafu)]
-pub enum Error {
-    #[snafu(display("timer failure: {}", source))]
-    TimerError {
-        source: time::error::Error,
-        backtrace: Backtrace,
-    },
-}
+pub enum Error {}
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 /// A request to re-emit `message` at a given `Instant` (`run_at`).
@@ -32,10 +35,170 @@ pub struct ScheduleRequest<T> {
     pub run_at: Instant,
 }
 
+/// Position of a message in the queue, ordered by deadline, then by insertion
+type QueueKey = (Instant, u64);
+
 /// Internal metadata for a scheduled message.
 struct ScheduledEntry {
     run_at: Instant,
-    queue_key: delay_queue::Key,
+    queue_key: QueueKey,
+}
+
+/// A message waiting in a [`Scheduler`], as seen by a [`QueueInspector`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QueuedMessage<T> {
+    pub message: T,
+    /// When the message was first scheduled
+    pub enqueued_at: Instant,
+    /// When the message is due to be emitted
+    pub run_at: Instant,
+    /// Whether the message is due but held back, see [`Scheduler::hold_unless`]
+    ///
+    /// For a [`Controller`](crate::Controller) this means that the object is still being reconciled.
+    pub held: bool,
+}
+
+/// A read handle to the messages waiting in a [`Scheduler`]
+///
+/// Use this to export backlog metrics, or to debug stuck reconciliations,
+/// see [`Controller::queue_inspector`](crate::Controller::queue_inspector).
+///
+/// Each message is counted once, however often it was requested: requests for a message that is already
+/// waiting only move its `run_at`. Messages that have been emitted leave the queue, a [`Controller`](crate::Controller)
+/// counts them as [`running`](Self::running) until their reconciliation finishes. If the object changes in
+/// the meantime it is queued again, and then counted in both.
+#[derive(Debug)]
+pub struct QueueInspector<T> {
+    queued: Arc<Mutex<HashMap<T, QueuedMessage<T>>>>,
+    running: Arc<AtomicUsize>,
+    clock: Arc<dyn Clock>,
+}
+
+impl<T> Clone for QueueInspector<T> {
+    fn clone(&self) -> Self {
+        Self {
+            queued: self.queued.clone(),
+            running: self.running.clone(),
+            clock: self.clock.clone(),
+        }
+    }
+}
+
+impl<T> Default for QueueInspector<T> {
+    fn default() -> Self {
+        Self {
+            queued: Arc::default(),
+            running: Arc::default(),
+            clock: default_clock(),
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> QueueInspector<T> {
+    /// Create an inspector, to be attached with [`Scheduler::with_inspector`]
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an inspector that measures ages with `clock`
+    ///
+    /// This should be the clock of the [`Scheduler`] it is attached to.
+    #[must_use]
+    pub fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        Self {
+            queued: Arc::default(),
+            running: Arc::default(),
+            clock,
+        }
+    }
+
+    /// The number of waiting messages, including those that are due but [`held`](QueuedMessage::held)
+    #[must_use]
+    pub fn depth(&self) -> usize {
+        self.lock().len()
+    }
+
+    /// The number of emitted messages that are still being handled
+    ///
+    /// Only tracked by a [`Controller`](crate::Controller), where this is the number of running reconciliations.
+    #[must_use]
+    pub fn running(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// How long the oldest waiting message has been waiting
+    #[must_use]
+    pub fn oldest_age(&self) -> Option<Duration> {
+        let now = self.clock.now();
+        self.lock()
+            .values()
+            .map(|queued| now.saturating_duration_since(queued.enqueued_at))
+            .max()
+    }
+
+    /// The next `limit` messages to be emitted, in order
+    #[must_use]
+    pub fn next_scheduled(&self, limit: usize) -> Vec<QueuedMessage<T>> {
+        let mut queued = self.snapshot();
+        queued.sort_by_key(|queued| queued.run_at);
+        queued.truncate(limit);
+        queued
+    }
+
+    /// All waiting messages, in no particular order
+    #[must_use]
+    pub fn snapshot(&self) -> Vec<QueuedMessage<T>> {
+        self.lock().values().cloned().collect()
+    }
+
+    fn scheduled(&self, message: &T, run_at: Instant) {
+        let mut queued = self.lock();
+        match queued.get_mut(message) {
+            Some(entry) => {
+                entry.message = message.clone();
+                entry.run_at = run_at;
+            }
+            None => {
+                queued.insert(message.clone(), QueuedMessage {
+                    message: message.clone(),
+                    enqueued_at: self.clock.now(),
+                    run_at,
+                    held: false,
+                });
+            }
+        }
+    }
+
+    fn held(&self, message: &T) {
+        if let Some(entry) = self.lock().get_mut(message) {
+            entry.held = true;
+        }
+    }
+
+    fn emitted(&self, message: &T) {
+        self.lock().remove(message);
+    }
+
+    /// Count an emitted message as running, until the returned guard is dropped
+    pub(crate) fn start(&self) -> RunningGuard {
+        self.running.fetch_add(1, Ordering::SeqCst);
+        RunningGuard(self.running.clone())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<T, QueuedMessage<T>>> {
+        // The lock is never held across user code, so poisoning can be ignored
+        self.queued.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// See [`QueueInspector::start`]
+pub(crate) struct RunningGuard(Arc<AtomicUsize>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
 }
 
 #[pin_project(project = SchedulerProj)]
@@ -44,7 +207,12 @@ pub struct Scheduler<T, R> {
     ///
     /// To ensure that the metadata is kept up-to-date, use `schedule_message` and
     /// `poll_pop_queue_message` rather than manipulating this directly.
-    queue: DelayQueue<T>,
+    queue: BTreeMap<QueueKey, T>,
+    /// Tiebreaker for messages scheduled at the same `Instant`, so they are emitted in order
+    next_seq: u64,
+    /// Timer for the head of the queue, and its deadline
+    timer: Option<(Instant, BoxFuture<'static, ()>)>,
+    clock: Arc<dyn Clock>,
     /// Metadata for all currently scheduled messages. Used to detect duplicate messages.
     scheduled: HashMap<T, ScheduledEntry>,
     /// Messages that are scheduled to have happened, but have been held using `hold_unless`.
@@ -52,34 +220,78 @@ pub struct Scheduler<T, R> {
     /// Incoming queue of scheduling requests.
     #[pin]
     requests: Fuse<R>,
+    /// Mirror of the queued messages, for introspection
+    inspector: Option<QueueInspector<T>>,
 }
 
 impl<T, R: Stream> Scheduler<T, R> {
     fn new(requests: R) -> Self {
         Self {
-            queue: DelayQueue::new(),
+            queue: BTreeMap::new(),
+            next_seq: 0,
+            timer: None,
+            clock: default_clock(),
             scheduled: HashMap::new(),
             pending: HashSet::new(),
             requests: requests.fuse(),
+            inspector: None,
         }
     }
+
+    /// Mirror the waiting messages into `inspector`
+    #[must_use]
+    pub fn with_inspector(mut self, inspector: &QueueInspector<T>) -> Self {
+        self.inspector = Some(inspector.clone());
+        self
+    }
+
+    /// Wait on `clock` rather than the [`TokioClock`](crate::time::TokioClock)
+    ///
+    /// The `run_at` of requests should be computed from the same clock.
+    #[must_use]
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+}
+
+/// Insert a message into the queue of a [`Scheduler`], after any messages with the same `run_at`
+fn insert_at<T>(
+    queue: &mut BTreeMap<QueueKey, T>,
+    next_seq: &mut u64,
+    message: T,
+    run_at: Instant,
+) -> QueueKey {
+    let key = (run_at, *next_seq);
+    *next_seq += 1;
+    queue.insert(key, message);
+    key
 }
 
 impl<'a, T: Hash + Eq + Clone, R> SchedulerProj<'a, T, R> {
     /// Attempt to schedule a message into the queue.
     ///
-    /// If the message is already in the queue then the earlier `request.run_at` takes precedence.
+    /// If the message is already in the queue then the earlier `request.run_at` takes precedence, along with
+    /// the message of that request. On a tie the new message wins.
     fn schedule_message(&mut self, request: ScheduleRequest<T>) {
         if self.pending.contains(&request.message) {
             // Message is already pending, so we can't even expedite it
             return;
         }
-        match self.scheduled.entry(request.message) {
+        match self.scheduled.entry(request.message.clone()) {
             Entry::Occupied(mut old_entry) if old_entry.get().run_at >= request.run_at => {
                 // Old entry will run after the new request, so replace it..
-                let entry = old_entry.get_mut();
+                if let Some(inspector) = self.inspector.as_ref() {
+                    inspector.scheduled(&request.message, request.run_at);
+                }
+                let old_key = old_entry.get().queue_key;
+                self.queue.remove(&old_key).expect(
+                    "Scheduled message was tracked in the metadata map, but was not in the Scheduler queue",
+                );
                 // TODO: this should add a little delay here to actually debounce
-                self.queue.reset_at(&entry.queue_key, request.run_at);
+                let queue_key = insert_at(self.queue, self.next_seq, request.message, request.run_at);
+                let entry = old_entry.get_mut();
+                entry.queue_key = queue_key;
                 entry.run_at = request.run_at;
             }
             Entry::Occupied(_old_entry) => {
@@ -87,38 +299,73 @@ impl<'a, T: Hash + Eq + Clone, R> SchedulerProj<'a, T, R> {
             }
             Entry::Vacant(entry) => {
                 // No old entry, we're free to go!
-                let message = entry.key().clone();
+                let message = request.message;
+                if let Some(inspector) = self.inspector.as_ref() {
+                    inspector.scheduled(&message, request.run_at);
+                }
+                let queue_key = insert_at(self.queue, self.next_seq, message, request.run_at);
                 entry.insert(ScheduledEntry {
                     run_at: request.run_at,
-                    queue_key: self.queue.insert_at(message, request.run_at),
+                    queue_key,
                 });
             }
         }
     }
 
+    /// Pop the head of the queue once it is due, or register for a wakeup when it will be
+    fn poll_expired(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            let head = self.queue.keys().next().copied();
+            match head {
+                None => {
+                    *self.timer = None;
+                    return Poll::Ready(None);
+                }
+                Some(key) if key.0 <= self.clock.now() => return Poll::Ready(self.queue.remove(&key)),
+                Some((deadline, _)) => {
+                    if !matches!(self.timer, Some((timer_deadline, _)) if *timer_deadline == deadline) {
+                        *self.timer = Some((deadline, self.clock.sleep_until(deadline)));
+                    }
+                    let (_, timer) = self.timer.as_mut().unwrap();
+                    match timer.poll_unpin(cx) {
+                        Poll::Ready(()) => *self.timer = None,
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+            }
+        }
+    }
+
     /// Attempt to retrieve a message from the queue.
     fn poll_pop_queue_message(
         &mut self,
         cx: &mut Context<'_>,
         can_take_message: impl Fn(&T) -> bool,
-    ) -> Poll<Option<Result<T, time::error::Error>>> {
+    ) -> Poll<Option<T>> {
         if let Some(msg) = self.pending.iter().find(|msg| can_take_message(*msg)).cloned() {
-            return Poll::Ready(Some(Ok(self.pending.take(&msg).unwrap())));
+            if let Some(inspector) = self.inspector.as_ref() {
+                inspector.emitted(&msg);
+            }
+            return Poll::Ready(Some(self.pending.take(&msg).unwrap()));
         }
 
         loop {
-            match self.queue.poll_expired(cx) {
-                Poll::Ready(Some(Ok(msg))) => {
-                    let msg = msg.into_inner();
+            match self.poll_expired(cx) {
+                Poll::Ready(Some(msg)) => {
                     self.scheduled.remove(&msg).expect(
                     "Expired message was popped from the Scheduler queue, but was not in the metadata map",
                 );
                     if can_take_message(&msg) {
-                        break Poll::Ready(Some(Ok(msg)));
+                        if let Some(inspector) = self.inspector.as_ref() {
+                            inspector.emitted(&msg);
+                        }
+                        break Poll::Ready(Some(msg));
+                    }
+                    if let Some(inspector) = self.inspector.as_ref() {
+                        inspector.held(&msg);
                     }
                     self.pending.insert(msg);
                 }
-                Poll::Ready(Some(Err(err))) => break Poll::Ready(Some(Err(err))),
                 Poll::Ready(None) => {
                     break if self.pending.is_empty() {
                         Poll::Ready(None)
@@ -157,7 +404,7 @@ where
         }
 
         match scheduler.poll_pop_queue_message(cx, &can_take_message) {
-            Poll::Ready(Some(expired)) => Poll::Ready(Some(expired.context(TimerError))),
+            Poll::Ready(Some(expired)) => Poll::Ready(Some(Ok(expired))),
             Poll::Ready(None) => {
                 if scheduler.requests.is_terminated() {
                     // The source queue has terminated, and all outstanding requests are done, so terminate
@@ -227,9 +474,10 @@ pub fn scheduler<T: Eq + Hash + Clone, S: Stream<Item = ScheduleRequest<T>>>(req
 
 #[cfg(test)]
 mod tests {
-    use super::{scheduler, ScheduleRequest};
+    use super::{scheduler, QueueInspector, ScheduleRequest};
+    use crate::time::{Clock, MockClock};
     use futures::{channel::mpsc, poll, stream, FutureExt, SinkExt, StreamExt};
-    use std::task::Poll;
+    use std::{sync::Arc, task::Poll};
     use tokio::time::{advance, pause, Duration, Instant};
 
     fn unwrap_poll<T>(poll: Poll<T>) -> T {
@@ -330,6 +578,20 @@ mod tests {
         assert!(scheduler.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn scheduler_should_wait_on_its_clock() {
+        let clock = MockClock::new();
+        let mut scheduler = scheduler(stream::iter(vec![ScheduleRequest {
+            message: 1_u8,
+            run_at: clock.now() + Duration::from_secs(3600),
+        }]))
+        .with_clock(Arc::new(clock.clone()));
+        assert!(poll!(scheduler.next()).is_pending());
+        clock.advance(Duration::from_secs(3600));
+        assert_eq!(scheduler.next().now_or_never().unwrap().unwrap().unwrap(), 1);
+        assert!(scheduler.next().await.is_none());
+    }
+
     #[tokio::test]
     async fn scheduler_dedupe_should_keep_earlier_item() {
         pause();
@@ -350,6 +612,47 @@ mod tests {
         assert!(scheduler.next().await.is_none());
     }
 
+    #[tokio::test]
+    async fn inspector_should_mirror_queued_items() {
+        pause();
+        let inspector = QueueInspector::new();
+        let mut scheduler = Box::pin(
+            scheduler(stream::iter(vec![
+                ScheduleRequest {
+                    message: 1_u8,
+                    run_at: Instant::now() + Duration::from_secs(2),
+                },
+                ScheduleRequest {
+                    message: 2_u8,
+                    run_at: Instant::now() + Duration::from_secs(1),
+                },
+            ]))
+            .with_inspector(&inspector),
+        );
+        assert!(poll!(scheduler.as_mut().hold_unless(|_| true).next()).is_pending());
+        assert_eq!(inspector.depth(), 2);
+        let next = inspector.next_scheduled(1);
+        assert_eq!(next.len(), 1);
+        assert_eq!(next[0].message, 2);
+        assert!(!next[0].held);
+
+        advance(Duration::from_millis(1500)).await;
+        assert_eq!(inspector.oldest_age(), Some(Duration::from_millis(1500)));
+        // 2 is due, but held back
+        assert!(poll!(scheduler.as_mut().hold_unless(|msg| *msg != 2).next()).is_pending());
+        assert!(inspector
+            .snapshot()
+            .iter()
+            .any(|queued| queued.message == 2 && queued.held));
+        assert_eq!(
+            unwrap_poll(poll!(scheduler.as_mut().hold_unless(|_| true).next()))
+                .unwrap()
+                .unwrap(),
+            2
+        );
+        assert_eq!(inspector.depth(), 1);
+    }
+
     #[tokio::test]
     async fn scheduler_dedupe_should_replace_later_item() {
         pause();
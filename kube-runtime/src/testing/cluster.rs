@@ -0,0 +1,485 @@
+//! Ephemeral clusters and namespaces for end to end tests of controllers
+//!
+//! [`TestCluster::from_env`] attaches to the kubeconfig context named by `K8S_TEST_CONTEXT`
+//! when it is set, and otherwise provisions a throwaway cluster with the provider named by
+//! `K8S_TEST_PROVIDER`, `kind` (the default) or `k3d`, whose binary must be on the `PATH`.
+//! The cluster is shared by all tests of the process: the first call sets it up, and later calls
+//! connect to it. A shared cluster that was provisioned outlives the tests, unless it is deleted
+//! with [`TestCluster::teardown_shared`] once they are done.
+//!
+//! Clusters of [`TestCluster::provision`] belong to their handle instead, and are deleted by
+//! [`TestCluster::teardown`], or in the background when the handle is dropped.
+//!
+//! Every test then gets a namespace of its own, which is deleted once the test finished,
+//! making the body of a `#[tokio::test]` look like:
+//!
+//! ```no_run
+//! use k8s_openapi::api::core::v1::ConfigMap;
+//! use kube::api::{Api, ListParams};
+//! use kube_runtime::testing::cluster::TestCluster;
+//! # async fn scope() {
+//! let cluster = TestCluster::from_env().await.unwrap();
+//! cluster
+//!     .with_namespace("config-maps", |ns| async move {
+//!         let cms: Api<ConfigMap> = ns.api();
+//!         assert!(cms.list(&ListParams::default()).await.unwrap().items.is_empty());
+//!     })
+//!     .await
+//!     .unwrap();
+//! # }
+//! ```
+use derivative::Derivative;
+use futures::{Future, FutureExt};
+use kube::{
+    api::Resource,
+    config::{KubeConfigOptions, Kubeconfig},
+    Api, Client, Config,
+};
+use snafu::{ResultExt, Snafu};
+use std::{
+    collections::BTreeMap,
+    convert::TryFrom,
+    env,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, PoisonError,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::OnceCell;
+
+/// The environment variable naming a kubeconfig context to run the tests against
+pub const CONTEXT_ENV: &str = "K8S_TEST_CONTEXT";
+/// The environment variable naming the [`Provider`] of provisioned clusters
+pub const PROVIDER_ENV: &str = "K8S_TEST_PROVIDER";
+/// The label marking the namespaces created by [`TestCluster::namespace`]
+pub const TEST_NAMESPACE_LABEL: &str = "kube-runtime.kube-rs.io/test";
+
+const FIELD_MANAGER: &str = "kube-runtime-testing";
+const NAMESPACE_DELETION_TIMEOUT: Duration = Duration::from_secs(90);
+const NAMESPACE_FINALIZERS_TIMEOUT: Duration = Duration::from_secs(45);
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("unknown cluster provider {}, expected kind or k3d", provider))]
+    UnknownProvider { provider: String },
+    #[snafu(display("failed to run {}: {}", command, source))]
+    SpawnFailed { command: String, source: std::io::Error },
+    #[snafu(display("{} failed: {}", command, stderr))]
+    CommandFailed { command: String, stderr: String },
+    #[snafu(display("failed to write the kubeconfig of the cluster: {}", source))]
+    WriteKubeconfigFailed { source: std::io::Error },
+    #[snafu(display("failed to load the kubeconfig: {}", source))]
+    LoadKubeconfigFailed { source: kube::Error },
+    #[snafu(display("failed to create a client: {}", source))]
+    CreateClientFailed { source: kube::Error },
+    #[snafu(display("failed to manage the test namespace: {}", source))]
+    NamespaceFailed { source: kube::Error },
+}
+pub type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// A tool that runs Kubernetes clusters in containers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Provider {
+    /// [kind](https://kind.sigs.k8s.io/)
+    Kind,
+    /// [k3d](https://k3d.io/)
+    K3d,
+}
+
+impl Provider {
+    fn binary(self) -> &'static str {
+        match self {
+            Provider::Kind => "kind",
+            Provider::K3d => "k3d",
+        }
+    }
+
+    fn create_args(self, name: &str, kubeconfig: &str) -> Vec<String> {
+        let args: &[&str] = match self {
+            // kind writes the context of a new cluster to the kubeconfig it is given
+            Provider::Kind => &[
+                "create",
+                "cluster",
+                "--name",
+                name,
+                "--wait",
+                "120s",
+                "--kubeconfig",
+                kubeconfig,
+            ],
+            Provider::K3d => &[
+                "cluster",
+                "create",
+                name,
+                "--wait",
+                "--kubeconfig-update-default=false",
+                "--kubeconfig-switch-context=false",
+            ],
+        };
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    fn kubeconfig_args(self, name: &str) -> Vec<String> {
+        let args: &[&str] = match self {
+            Provider::Kind => &["get", "kubeconfig", "--name", name],
+            Provider::K3d => &["kubeconfig", "get", name],
+        };
+        args.iter().map(ToString::to_string).collect()
+    }
+
+    fn delete_args(self, name: &str) -> Vec<String> {
+        let args: &[&str] = match self {
+            Provider::Kind => &["delete", "cluster", "--name", name],
+            Provider::K3d => &["cluster", "delete", name],
+        };
+        args.iter().map(ToString::to_string).collect()
+    }
+}
+
+impl FromStr for Provider {
+    type Err = Error;
+
+    fn from_str(provider: &str) -> Result<Self> {
+        match provider {
+            "kind" => Ok(Provider::Kind),
+            "k3d" => Ok(Provider::K3d),
+            _ => UnknownProvider { provider }.fail(),
+        }
+    }
+}
+
+/// The cluster of [`TestCluster::from_env`], shared by the tests of the process
+static SHARED: OnceCell<Shared> = OnceCell::const_new();
+
+/// How to connect to the shared cluster, and the cluster to delete once the tests are done
+struct Shared {
+    config: Config,
+    provisioned: Mutex<Option<(Provider, String)>>,
+}
+
+/// A cluster to run end to end tests against, see the [module documentation](self)
+#[derive(Derivative)]
+#[derivative(Debug)]
+pub struct TestCluster {
+    #[derivative(Debug = "ignore")]
+    client: Client,
+    #[derivative(Debug = "ignore")]
+    config: Config,
+    provisioned: Option<(Provider, String)>,
+}
+
+impl TestCluster {
+    /// Attach to the context in `K8S_TEST_CONTEXT`, or provision a cluster with `K8S_TEST_PROVIDER`
+    ///
+    /// Only the first call of the process sets up the cluster, later calls connect to the same one.
+    /// Every handle has a client of its own, so tests can run on runtimes of their own.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the provider is unknown, or the cluster cannot be provisioned or connected to.
+    pub async fn from_env() -> Result<Self> {
+        let shared = SHARED.get_or_try_init(Self::shared_from_env).await?;
+        Ok(Self {
+            client: Client::try_from(shared.config.clone()).context(CreateClientFailed)?,
+            config: shared.config.clone(),
+            provisioned: None,
+        })
+    }
+
+    async fn shared_from_env() -> Result<Shared> {
+        let mut cluster = if let Ok(context) = env::var(CONTEXT_ENV) {
+            Self::attach(&context).await?
+        } else {
+            let provider = match env::var(PROVIDER_ENV) {
+                Ok(provider) => provider.parse()?,
+                Err(_) => Provider::Kind,
+            };
+            Self::provision(provider, &unique_name("kube-rs-test")).await?
+        };
+        Ok(Shared {
+            config: cluster.config.clone(),
+            provisioned: Mutex::new(cluster.provisioned.take()),
+        })
+    }
+
+    /// Delete the cluster that [`from_env`](Self::from_env) provisioned for the process, if any
+    ///
+    /// Call this once all tests are done, like at the end of a custom test harness.
+    /// Later calls of `from_env` keep connecting to the deleted cluster.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the provider fails to delete the cluster.
+    pub async fn teardown_shared() -> Result<()> {
+        let provisioned = SHARED
+            .get()
+            .and_then(|shared| shared.provisioned.lock().unwrap_or_else(PoisonError::into_inner).take());
+        match provisioned {
+            Some((provider, name)) => delete(provider, &name).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Run the tests against an existing cluster, the kubeconfig context `context`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the context cannot be loaded from the kubeconfig.
+    pub async fn attach(context: &str) -> Result<Self> {
+        let options = KubeConfigOptions {
+            context: Some(context.to_string()),
+            ..KubeConfigOptions::default()
+        };
+        let config = Config::from_kubeconfig(&options)
+            .await
+            .context(LoadKubeconfigFailed)?;
+        Ok(Self {
+            client: Client::try_from(config.clone()).context(CreateClientFailed)?,
+            config,
+            provisioned: None,
+        })
+    }
+
+    /// Provision the cluster `name` with `provider`, waiting until it is ready
+    ///
+    /// The kubeconfig of the user is left untouched.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the provider fails to create the cluster, which is deleted again if it was
+    /// created but cannot be connected to.
+    pub async fn provision(provider: Provider, name: &str) -> Result<Self> {
+        let kubeconfig = env::temp_dir().join(name).with_extension("kubeconfig");
+        let created = run(
+            provider.binary(),
+            &provider.create_args(name, &kubeconfig.to_string_lossy()),
+        )
+        .await;
+        let connected = match created {
+            Ok(_) => connect(provider, name, kubeconfig).await,
+            Err(err) => Err(err),
+        };
+        match connected {
+            Ok((config, client)) => Ok(Self {
+                client,
+                config,
+                provisioned: Some((provider, name.to_string())),
+            }),
+            Err(err) => {
+                // the cluster may have been created partially, and would otherwise be leaked
+                let _ = delete(provider, name).await;
+                Err(err)
+            }
+        }
+    }
+
+    /// A client for the cluster
+    #[must_use]
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+
+    /// Create a namespace with a unique name starting with `prefix`
+    ///
+    /// # Errors
+    ///
+    /// Fails if the namespace cannot be created.
+    pub async fn namespace(&self, prefix: &str) -> Result<TestNamespace> {
+        let name = unique_name(prefix);
+        let mut labels = BTreeMap::new();
+        labels.insert(TEST_NAMESPACE_LABEL.to_string(), "true".to_string());
+        kube::ops::ensure_namespace(&self.client, &name, &labels, FIELD_MANAGER)
+            .await
+            .context(NamespaceFailed)?;
+        Ok(TestNamespace {
+            client: self.client.clone(),
+            name,
+        })
+    }
+
+    /// Run `test` in a namespace of its own, deleting the namespace afterwards
+    ///
+    /// The namespace is deleted even if `test` panics, and the panic is resumed afterwards.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the namespace cannot be created or deleted.
+    pub async fn with_namespace<F, Fut>(&self, prefix: &str, test: F) -> Result<Fut::Output>
+    where
+        F: FnOnce(TestNamespace) -> Fut,
+        Fut: Future,
+    {
+        let ns = self.namespace(prefix).await?;
+        let cleanup = TestNamespace {
+            client: ns.client.clone(),
+            name: ns.name.clone(),
+        };
+        let result = AssertUnwindSafe(test(ns)).catch_unwind().await;
+        let deleted = cleanup.delete().await;
+        match result {
+            Ok(output) => deleted.map(|()| output),
+            Err(panic) => panic::resume_unwind(panic),
+        }
+    }
+
+    /// Delete the cluster if it was provisioned, a no-op for attached and shared clusters
+    ///
+    /// # Errors
+    ///
+    /// Fails if the provider fails to delete the cluster.
+    pub async fn teardown(mut self) -> Result<()> {
+        match self.provisioned.take() {
+            Some((provider, name)) => delete(provider, &name).await,
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for TestCluster {
+    fn drop(&mut self) {
+        let Some((provider, name)) = self.provisioned.take() else { return };
+        let delete = move || {
+            let _ = std::process::Command::new(provider.binary())
+                .args(provider.delete_args(&name))
+                .output();
+        };
+        // on the blocking pool, which the runtime waits for when it shuts down
+        match tokio::runtime::Handle::try_current() {
+            Ok(runtime) => drop(runtime.spawn_blocking(delete)),
+            Err(_) => delete(),
+        }
+    }
+}
+
+/// A namespace of a [`TestCluster`], for a single test
+#[derive(Clone, Derivative)]
+#[derivative(Debug)]
+pub struct TestNamespace {
+    #[derivative(Debug = "ignore")]
+    client: Client,
+    name: String,
+}
+
+impl TestNamespace {
+    /// The name of the namespace
+    #[must_use]
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// A client for the cluster of the namespace
+    #[must_use]
+    pub fn client(&self) -> Client {
+        self.client.clone()
+    }
+
+    /// An [`Api`] for resources in the namespace
+    #[must_use]
+    pub fn api<K: Resource>(&self) -> Api<K>
+    where
+        K::DynamicType: Default,
+    {
+        Api::namespaced(self.client.clone(), &self.name)
+    }
+
+    /// Delete the namespace and wait until it is gone
+    ///
+    /// Finalizers that are still left after 45 seconds are cleared, see
+    /// [`delete_namespace_and_wait`](kube::ops::delete_namespace_and_wait).
+    ///
+    /// # Errors
+    ///
+    /// Fails if the namespace is not gone within 90 seconds.
+    pub async fn delete(self) -> Result<()> {
+        kube::ops::delete_namespace_and_wait(
+            &self.client,
+            &self.name,
+            NAMESPACE_DELETION_TIMEOUT,
+            Some(NAMESPACE_FINALIZERS_TIMEOUT),
+        )
+        .await
+        .context(NamespaceFailed)
+    }
+}
+
+/// Connect to a cluster that `provider` created
+async fn connect(provider: Provider, name: &str, kubeconfig: PathBuf) -> Result<(Config, Client)> {
+    let yaml = run(provider.binary(), &provider.kubeconfig_args(name)).await?;
+    tokio::fs::write(&kubeconfig, yaml)
+        .await
+        .context(WriteKubeconfigFailed)?;
+    let loaded = Kubeconfig::read_from(&kubeconfig).context(LoadKubeconfigFailed);
+    let _ = tokio::fs::remove_file(&kubeconfig).await;
+    let config = Config::from_custom_kubeconfig(loaded?, &KubeConfigOptions::default())
+        .await
+        .context(LoadKubeconfigFailed)?;
+    let client = Client::try_from(config.clone()).context(CreateClientFailed)?;
+    Ok((config, client))
+}
+
+/// Delete the cluster `name` of `provider`
+async fn delete(provider: Provider, name: &str) -> Result<()> {
+    run(provider.binary(), &provider.delete_args(name)).await.map(|_| ())
+}
+
+/// Run `binary` with `args`, returning its stdout
+async fn run(binary: &str, args: &[String]) -> Result<String> {
+    let command = format!("{} {}", binary, args.join(" "));
+    let output = tokio::process::Command::new(binary)
+        .args(args)
+        .output()
+        .await
+        .context(SpawnFailed {
+            command: command.clone(),
+        })?;
+    if !output.status.success() {
+        return CommandFailed {
+            command,
+            stderr: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        }
+        .fail();
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// A name starting with `prefix` that is unique across test runs, a valid DNS label
+fn unique_name(prefix: &str) -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    format!(
+        "{}-{:x}-{:x}-{}",
+        prefix,
+        seconds,
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{unique_name, Provider};
+
+    #[test]
+    fn providers_are_parsed_from_their_binary_name() {
+        assert_eq!("kind".parse::<Provider>().unwrap(), Provider::Kind);
+        assert_eq!("k3d".parse::<Provider>().unwrap(), Provider::K3d);
+        assert!("minikube".parse::<Provider>().is_err());
+        assert_eq!(Provider::K3d.delete_args("e2e"), vec!["cluster", "delete", "e2e"]);
+    }
+
+    #[test]
+    fn names_are_unique_dns_labels() {
+        let (a, b) = (unique_name("e2e"), unique_name("e2e"));
+        assert_ne!(a, b);
+        assert!(a.len() <= 63);
+        assert!(a
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'));
+    }
+}
@@ -0,0 +1,534 @@
+//! Deterministic testing of controllers against scripted watch events and apiserver responses
+//!
+//! Requires the `testing` feature, which also enables the `test-util` feature of Tokio, so that
+//! tests can run on a paused clock (`#[tokio::test(start_paused = true)]`). With a paused clock, Tokio
+//! skips ahead whenever all tasks are waiting on timers, so requeues and backoffs resolve
+//! instantly and in a deterministic order.
+//!
+//! A test typically combines:
+//! - a [`Script`] of watch events, fed to [`Controller::for_stream`](crate::Controller::for_stream)
+//!   (or a [`watcher::mock`] for events sent by the test itself)
+//! - a [`mock_client`] for the reconciler, which answers (or fails) the requests it makes
+//!
+//! End to end tests against a real cluster are covered by the `cluster` module instead, which
+//! requires the `testing-cluster` feature.
+//!
+//! ```
+//! use futures::StreamExt;
+//! use k8s_openapi::api::core::v1::ConfigMap;
+//! use kube::api::{Api, ObjectMetaBuilder, PostParams};
+//! use kube_runtime::{
+//!     controller::{Context, Controller, ReconcilerAction},
+//!     testing::{mock_client, MockResponse, Script},
+//! };
+//! use std::{sync::{atomic::{AtomicUsize, Ordering}, Arc}, time::Duration};
+//!
+//! #[tokio::main(flavor = "current_thread", start_paused = true)]
+//! async fn main() {
+//!     let cm = ConfigMap {
+//!         metadata: ObjectMetaBuilder::new("cfg").namespace("default").build(),
+//!         ..ConfigMap::default()
+//!     };
+//!     // the first write fails, the second succeeds
+//!     let writes = Arc::new(AtomicUsize::new(0));
+//!     let client = mock_client(move |req| match writes.fetch_add(1, Ordering::SeqCst) {
+//!         0 => MockResponse::error(500, "InternalError", "injected failure"),
+//!         _ => MockResponse::ok(serde_json::from_slice(&req.body).unwrap()),
+//!     });
+//!
+//!     let results = Controller::for_stream(Script::new().applied(cm).into_stream())
+//!         .run(
+//!             |cm: ConfigMap, ctx: Context<kube::Client>| async move {
+//!                 let api: Api<ConfigMap> = Api::namespaced(ctx.get_ref().clone(), "default");
+//!                 api.replace("cfg", &PostParams::default(), &cm).await?;
+//!                 Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None })
+//!             },
+//!             |_err, _ctx| ReconcilerAction { requeue_after: Some(Duration::from_secs(10)) },
+//!             Context::new(client),
+//!         )
+//!         .take(2)
+//!         .collect::<Vec<_>>()
+//!         .await;
+//!     assert!(results[0].is_err());
+//!     assert!(results[1].is_ok());
+//! }
+//! ```
+#[cfg(any(test, feature = "testing-cluster"))] pub mod cluster;
+
+use crate::{
+    controller::{Context, ReconcilerAction},
+    reflector::ObjectRef,
+    watcher,
+};
+use derivative::Derivative;
+use futures::{future::BoxFuture, stream, FutureExt, Stream, TryFuture, TryFutureExt};
+use kube::{api::Resource, Client, Service};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::{Debug, Display},
+    hash::Hash,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+/// A step of a [`Script`]
+enum Step<K> {
+    Event(watcher::Event<K>),
+    Error(watcher::Error),
+    Advance(Duration),
+}
+
+/// A scripted sequence of watch events, injected watch failures and clock advances
+///
+/// The steps are run in order as the stream is polled. Once all steps are done, the stream stays
+/// open without emitting anything, like a quiet watch, so that requeues keep being processed.
+pub struct Script<K> {
+    steps: VecDeque<Step<K>>,
+}
+
+impl<K> Default for Script<K> {
+    fn default() -> Self {
+        Script {
+            steps: VecDeque::new(),
+        }
+    }
+}
+
+impl<K: Send + 'static> Script<K> {
+    /// Create an empty script
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emit an [`Applied`](watcher::Event::Applied) event
+    #[must_use]
+    pub fn applied(self, obj: K) -> Self {
+        self.event(watcher::Event::Applied(obj))
+    }
+
+    /// Emit a [`Deleted`](watcher::Event::Deleted) event
+    #[must_use]
+    pub fn deleted(self, obj: K) -> Self {
+        self.event(watcher::Event::Deleted(obj))
+    }
+
+    /// Emit a [`Restarted`](watcher::Event::Restarted) event
+    #[must_use]
+    pub fn restarted(self, objs: Vec<K>) -> Self {
+        self.event(watcher::Event::Restarted(objs))
+    }
+
+    /// Emit an arbitrary watch event
+    #[must_use]
+    pub fn event(mut self, event: watcher::Event<K>) -> Self {
+        self.steps.push_back(Step::Event(event));
+        self
+    }
+
+    /// Emit a [`watcher::Error::WatchError`], as if the apiserver failed the watch with `code`
+    #[must_use]
+    pub fn watch_error(mut self, code: u16, message: &str) -> Self {
+        self.steps
+            .push_back(Step::Error(watcher::injected_error(code, message)));
+        self
+    }
+
+    /// Let `duration` pass before running the next step
+    ///
+    /// On a paused Tokio clock, time only moves on once all other work is blocked, so everything the
+    /// previous steps triggered (including timers that fire within `duration`) runs first.
+    #[must_use]
+    pub fn advance(mut self, duration: Duration) -> Self {
+        self.steps.push_back(Step::Advance(duration));
+        self
+    }
+
+    /// The watch stream running the script
+    pub fn into_stream(self) -> impl Stream<Item = watcher::Result<watcher::Event<K>>> + Send {
+        stream::unfold(self.steps, |mut steps| async move {
+            loop {
+                match steps.pop_front() {
+                    Some(Step::Event(event)) => return Some((Ok(event), steps)),
+                    Some(Step::Error(err)) => return Some((Err(err), steps)),
+                    Some(Step::Advance(duration)) => tokio::time::sleep(duration).await,
+                    None => futures::future::pending::<()>().await,
+                }
+            }
+        })
+    }
+}
+
+/// A request received by a [`mock_client`]
+#[derive(Clone, Debug)]
+pub struct MockRequest {
+    /// The http method, e.g. `PATCH`
+    pub method: String,
+    /// The path that was requested, e.g. `/api/v1/namespaces/default/configmaps/cfg`
+    pub path: String,
+    /// The query string, e.g. `fieldManager=my-operator`
+    pub query: Option<String>,
+    /// The request body
+    pub body: Vec<u8>,
+}
+
+/// A response returned by a [`mock_client`]
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    /// The http status code
+    pub status: u16,
+    /// The json body
+    pub body: serde_json::Value,
+}
+
+impl MockResponse {
+    /// A `200 OK` response with `body`
+    #[must_use]
+    pub fn ok(body: serde_json::Value) -> Self {
+        MockResponse { status: 200, body }
+    }
+
+    /// A failure `Status` response, like the apiserver returns for errors
+    #[must_use]
+    pub fn error(code: u16, reason: &str, message: &str) -> Self {
+        MockResponse {
+            status: code,
+            body: serde_json::json!({
+                "kind": "Status",
+                "apiVersion": "v1",
+                "status": "Failure",
+                "message": message,
+                "reason": reason,
+                "code": code,
+            }),
+        }
+    }
+}
+
+/// A [`Client`] that answers every request with `handler` instead of contacting an apiserver
+///
+/// Use this to inject failures into reconcilers (see the [module docs](self)), or to record the
+/// requests they make. Watches are not supported, feed the controller a [`Script`] instead.
+pub fn mock_client(handler: impl Fn(MockRequest) -> MockResponse + Send + Sync + 'static) -> Client {
+    let handler = Arc::new(handler);
+    let service = tower::service_fn(move |req: http::Request<hyper::Body>| {
+        let handler = handler.clone();
+        async move {
+            let (parts, body) = req.into_parts();
+            let body = hyper::body::to_bytes(body).await?;
+            let response = handler(MockRequest {
+                method: parts.method.to_string(),
+                path: parts.uri.path().to_string(),
+                query: parts.uri.query().map(ToString::to_string),
+                body: body.to_vec(),
+            });
+            let response = http::Response::builder()
+                .status(response.status)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(hyper::Body::from(serde_json::to_vec(&response.body)?))?;
+            Ok::<_, tower::BoxError>(response)
+        }
+    });
+    Client::new(Service::new(service))
+}
+
+/// A finished reconciliation, recorded by a [`ReconcileLog`]
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "K::DynamicType: Clone"),
+    Debug(bound = "K::DynamicType: Debug")
+)]
+pub struct Reconciliation<K: Resource> {
+    /// The reconciled object
+    pub obj_ref: ObjectRef<K>,
+    /// The position of the reconciliation among all that were started, counting from 0
+    ///
+    /// Reconciliations of different objects run concurrently, so they can finish in another order.
+    pub started: usize,
+    /// The action returned by the reconciler, or the message of its error
+    pub result: Result<ReconcilerAction, String>,
+}
+
+#[derive(Derivative)]
+#[derivative(Debug(bound = "K::DynamicType: Debug"))]
+struct LogState<K: Resource> {
+    started: usize,
+    finished: Vec<Reconciliation<K>>,
+}
+
+/// Records the reconciliations of a controller, for assertions in tests
+///
+/// Wrap the reconciler passed to [`Controller::run`](crate::Controller::run) with [`ReconcileLog::wrap`],
+/// then check which objects were reconciled, how often, in which order and with which results:
+///
+/// ```
+/// use futures::StreamExt;
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// use kube::api::ObjectMetaBuilder;
+/// use kube_runtime::{
+///     controller::{Context, Controller, ReconcilerAction},
+///     reflector::ObjectRef,
+///     testing::{ReconcileLog, Script},
+/// };
+///
+/// #[tokio::main(flavor = "current_thread", start_paused = true)]
+/// async fn main() {
+///     let cm = ConfigMap {
+///         metadata: ObjectMetaBuilder::new("cfg").namespace("default").build(),
+///         ..ConfigMap::default()
+///     };
+///     let log = ReconcileLog::<ConfigMap>::new();
+///     Controller::for_stream(Script::new().applied(cm).into_stream())
+///         .run(
+///             log.wrap(|_cm, _ctx: Context<()>| async { Ok::<_, kube::Error>(ReconcilerAction { requeue_after: None }) }),
+///             |_err, _ctx| ReconcilerAction { requeue_after: None },
+///             Context::new(()),
+///         )
+///         .take(1)
+///         .for_each(|_| async {})
+///         .await;
+///     assert_eq!(log.reconciled(), vec![ObjectRef::new("cfg").within("default")]);
+///     assert_eq!(log.failures(), 0);
+/// }
+/// ```
+#[derive(Derivative)]
+#[derivative(Debug(bound = "K::DynamicType: Debug"))]
+pub struct ReconcileLog<K: Resource> {
+    state: Arc<Mutex<LogState<K>>>,
+    dyntype: K::DynamicType,
+}
+
+impl<K: Resource> Clone for ReconcileLog<K>
+where
+    K::DynamicType: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            state: self.state.clone(),
+            dyntype: self.dyntype.clone(),
+        }
+    }
+}
+
+impl<K> ReconcileLog<K>
+where
+    K: Resource + 'static,
+    K::DynamicType: Default + Clone + Eq + Hash,
+{
+    /// Create an empty log
+    #[must_use]
+    pub fn new() -> Self {
+        Self::new_with(K::DynamicType::default())
+    }
+}
+
+impl<K> Default for ReconcileLog<K>
+where
+    K: Resource + 'static,
+    K::DynamicType: Default + Clone + Eq + Hash,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K> ReconcileLog<K>
+where
+    K: Resource + 'static,
+    K::DynamicType: Clone + Eq + Hash,
+{
+    /// Create an empty log, for a `K` with a dynamic type
+    #[must_use]
+    pub fn new_with(dyntype: K::DynamicType) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(LogState {
+                started: 0,
+                finished: vec![],
+            })),
+            dyntype,
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, LogState<K>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Wrap `reconciler`, recording every reconciliation it runs
+    pub fn wrap<T, ReconcilerFut>(
+        &self,
+        mut reconciler: impl FnMut(K, Context<T>) -> ReconcilerFut,
+    ) -> impl FnMut(K, Context<T>) -> BoxFuture<'static, Result<ReconcilerAction, ReconcilerFut::Error>>
+    where
+        ReconcilerFut: TryFuture<Ok = ReconcilerAction> + Send + 'static,
+        ReconcilerFut::Error: Display,
+    {
+        let log = self.clone();
+        move |obj, ctx| {
+            let obj_ref = ObjectRef::from_obj_with(&obj, log.dyntype.clone());
+            let started = {
+                let mut state = log.state();
+                state.started += 1;
+                state.started - 1
+            };
+            let state = log.state.clone();
+            reconciler(obj, ctx)
+                .into_future()
+                .inspect(move |result| {
+                    let result = match result {
+                        Ok(action) => Ok(action.clone()),
+                        Err(err) => Err(err.to_string()),
+                    };
+                    state
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .finished
+                        .push(Reconciliation {
+                            obj_ref,
+                            started,
+                            result,
+                        });
+                })
+                .boxed()
+        }
+    }
+
+    /// All finished reconciliations, in the order they finished
+    #[must_use]
+    pub fn entries(&self) -> Vec<Reconciliation<K>> {
+        self.state().finished.clone()
+    }
+
+    /// The reconciled objects, in the order their reconciliations finished
+    #[must_use]
+    pub fn reconciled(&self) -> Vec<ObjectRef<K>> {
+        self.state().finished.iter().map(|r| r.obj_ref.clone()).collect()
+    }
+
+    /// How often each object was reconciled
+    #[must_use]
+    pub fn counts(&self) -> HashMap<ObjectRef<K>, usize> {
+        let mut counts = HashMap::new();
+        for reconciliation in &self.state().finished {
+            *counts.entry(reconciliation.obj_ref.clone()).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// The number of reconciliations that failed
+    #[must_use]
+    pub fn failures(&self) -> usize {
+        self.state().finished.iter().filter(|r| r.result.is_err()).count()
+    }
+
+    /// Forget all recorded reconciliations, e.g. after a controller settled
+    pub fn clear(&self) {
+        let mut state = self.state();
+        state.started = 0;
+        state.finished.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{mock_client, MockResponse, ReconcileLog, Script};
+    use crate::{
+        controller::{Context, ReconcilerAction},
+        reflector::ObjectRef,
+        watcher, Controller,
+    };
+    use futures::StreamExt;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use kube::Api;
+    use std::time::Duration;
+
+    #[tokio::test(start_paused = true)]
+    async fn script_runs_steps_in_order() {
+        let start = tokio::time::Instant::now();
+        let mut events = Box::pin(
+            Script::new()
+                .applied(ConfigMap::default())
+                .advance(Duration::from_secs(30))
+                .watch_error(410, "too old resource version")
+                .deleted(ConfigMap::default())
+                .into_stream(),
+        );
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(watcher::Event::Applied(_)))
+        ));
+        assert_eq!(start.elapsed(), Duration::from_secs(0));
+        match events.next().await {
+            Some(Err(watcher::Error::WatchError { source, .. })) => assert_eq!(source.code, 410),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(start.elapsed(), Duration::from_secs(30));
+        assert!(matches!(
+            events.next().await,
+            Some(Ok(watcher::Event::Deleted(_)))
+        ));
+        // the script is done, but the watch stays open
+        assert!(tokio::time::timeout(Duration::from_secs(3600), events.next())
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn mock_client_injects_failures() {
+        let client = mock_client(|req| {
+            assert_eq!(req.method, "GET");
+            assert_eq!(req.path, "/api/v1/namespaces/default/configmaps/cfg");
+            MockResponse::error(409, "Conflict", "injected conflict")
+        });
+        let api: Api<ConfigMap> = Api::namespaced(client, "default");
+        match api.get("cfg").await {
+            Err(kube::Error::Api(err)) => {
+                assert_eq!(err.code, 409);
+                assert_eq!(err.message, "injected conflict");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reconcile_log_records_every_reconciliation() {
+        let cm = |name: &str| {
+            let mut cm = ConfigMap::default();
+            cm.metadata.name = Some(name.into());
+            cm
+        };
+        let log = ReconcileLog::<ConfigMap>::new();
+        let script = Script::new()
+            .applied(cm("a"))
+            .advance(Duration::from_secs(1))
+            .applied(cm("b"))
+            .advance(Duration::from_secs(1))
+            .applied(cm("a"));
+        Controller::for_stream(script.into_stream())
+            .run(
+                log.wrap(|cm: ConfigMap, _ctx: Context<()>| async move {
+                    match cm.metadata.name.as_deref() {
+                        Some("b") => Err(kube::Error::RequestValidation("injected".into())),
+                        _ => Ok(ReconcilerAction { requeue_after: None }),
+                    }
+                }),
+                |_err, _ctx| ReconcilerAction { requeue_after: None },
+                Context::new(()),
+            )
+            .take(3)
+            .for_each(|_| async {})
+            .await;
+
+        assert_eq!(log.reconciled(), vec![
+            ObjectRef::new("a"),
+            ObjectRef::new("b"),
+            ObjectRef::new("a")
+        ]);
+        assert_eq!(log.counts()[&ObjectRef::new("a")], 2);
+        assert_eq!(log.failures(), 1);
+        let entries = log.entries();
+        assert_eq!(entries[2].started, 2);
+        assert!(entries[1].result.as_ref().unwrap_err().contains("injected"));
+        log.clear();
+        assert!(log.entries().is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! Injectable clocks for the [`scheduler`](crate::scheduler()) and [`Controller`](crate::Controller)
+//!
+//! Everything that waits or reads the current time goes through a [`Clock`]. By default this is the
+//! [`TokioClock`], which also works with `tokio::time::pause`. A [`MockClock`] only moves when
+//! advanced explicitly, which keeps time fully under the control of a test.
+use futures::{future::BoxFuture, FutureExt};
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex, PoisonError},
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+/// A source of time and timers
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// The current time
+    fn now(&self) -> Instant;
+
+    /// A future that completes once the clock reaches `deadline`
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()>;
+
+    /// A future that completes after `duration` has passed on the clock
+    fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        self.sleep_until(self.now() + duration)
+    }
+}
+
+/// The clock of the Tokio runtime
+///
+/// This follows `tokio::time::pause` and `tokio::time::advance`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        tokio::time::sleep_until(deadline).boxed()
+    }
+}
+
+/// The default clock, a [`TokioClock`]
+#[must_use]
+pub fn default_clock() -> Arc<dyn Clock> {
+    Arc::new(TokioClock)
+}
+
+#[derive(Debug)]
+struct MockState {
+    now: Instant,
+    /// The waker of every pending [`MockSleep`], by its id
+    sleepers: HashMap<u64, Waker>,
+    next_sleeper: u64,
+}
+
+/// A clock that only moves when [`MockClock::advance`] is called
+///
+/// Clones share the same time.
+#[derive(Clone, Debug)]
+pub struct MockClock {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockClock {
+    /// Create a clock, starting at the current time
+    #[must_use]
+    pub fn new() -> Self {
+        MockClock {
+            state: Arc::new(Mutex::new(MockState {
+                now: Instant::now(),
+                sleepers: HashMap::new(),
+                next_sleeper: 0,
+            })),
+        }
+    }
+
+    /// Move the clock forward by `duration`, waking any timers that are now due
+    pub fn advance(&self, duration: Duration) {
+        let sleepers = {
+            let mut state = self.lock();
+            state.now += duration;
+            std::mem::take(&mut state.sleepers)
+        };
+        // timers that are not due yet register themselves again
+        for sleeper in sleepers.into_values() {
+            sleeper.wake();
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        // The lock is never held across user code, so poisoning can be ignored
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for MockClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.lock().now
+    }
+
+    fn sleep_until(&self, deadline: Instant) -> BoxFuture<'static, ()> {
+        let id = {
+            let mut state = self.lock();
+            state.next_sleeper += 1;
+            state.next_sleeper
+        };
+        MockSleep {
+            clock: self.clone(),
+            deadline,
+            id,
+        }
+        .boxed()
+    }
+}
+
+struct MockSleep {
+    clock: MockClock,
+    deadline: Instant,
+    id: u64,
+}
+
+impl Future for MockSleep {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let mut state = self.clock.lock();
+        if state.now >= self.deadline {
+            state.sleepers.remove(&self.id);
+            Poll::Ready(())
+        } else {
+            // only the waker of the latest poll needs to be woken
+            state.sleepers.insert(self.id, cx.waker().clone());
+            Poll::Pending
+        }
+    }
+}
+
+impl Drop for MockSleep {
+    fn drop(&mut self) {
+        self.clock.lock().sleepers.remove(&self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, MockClock};
+    use futures::{poll, FutureExt};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn mock_clock_only_moves_when_advanced() {
+        let clock = MockClock::new();
+        let start = clock.now();
+        let mut sleep = clock.sleep(Duration::from_secs(10));
+        assert!(poll!(&mut sleep).is_pending());
+        clock.advance(Duration::from_secs(5));
+        assert!(poll!(&mut sleep).is_pending());
+        clock.advance(Duration::from_secs(5));
+        assert!(sleep.now_or_never().is_some());
+        assert_eq!(clock.now() - start, Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn mock_sleeps_keep_one_waker() {
+        let clock = MockClock::new();
+        let mut sleep = clock.sleep(Duration::from_secs(10));
+        for _ in 0..3 {
+            assert!(poll!(&mut sleep).is_pending());
+        }
+        assert_eq!(clock.lock().sleepers.len(), 1);
+        drop(sleep);
+        assert!(clock.lock().sleepers.is_empty());
+    }
+}
@@ -1,18 +1,26 @@
-use crate::watcher;
+use crate::{
+    executor::{Executor, TokioExecutor},
+    watcher,
+};
 use futures::{
+    future::RemoteHandle,
     pin_mut,
     stream::{self, Peekable},
     Future, FutureExt, Stream, StreamExt, TryStream, TryStreamExt,
 };
 use pin_project::pin_project;
+use snafu::Snafu;
 use std::{
     fmt::Debug,
     pin::Pin,
     sync::{Arc, Mutex},
-    task::Poll,
+    task::{Context, Poll},
 };
 use stream::IntoStream;
-use tokio::{runtime::Handle, task::JoinHandle};
+use tokio::{
+    runtime::Handle,
+    sync::{broadcast, mpsc},
+};
 
 /// Flattens each item in the list following the rules of [`watcher::Event::into_iter_applied`].
 pub fn try_flatten_applied<K, S: TryStream<Ok = watcher::Event<K>>>(
@@ -32,6 +40,156 @@ pub fn try_flatten_touched<K, S: TryStream<Ok = watcher::Event<K>>>(
         .try_flatten()
 }
 
+/// Sends every item of `stream` to `sender`
+///
+/// The returned future finishes when `stream` ends, or when the receiving half of the channel is closed.
+/// Items are sent one at a time, so a full channel applies backpressure to `stream`.
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams}, Client};
+/// use kube_runtime::{utils::forward_to_channel, watcher};
+/// use k8s_openapi::api::core::v1::Pod;
+/// # async fn scope(client: Client) {
+/// let (tx, mut rx) = tokio::sync::mpsc::channel(16);
+/// tokio::spawn(forward_to_channel(watcher(Api::<Pod>::all(client), ListParams::default()), tx));
+/// while let Some(event) = rx.recv().await {
+///     println!("{:?}", event);
+/// }
+/// # }
+/// ```
+pub async fn forward_to_channel<S: Stream>(stream: S, sender: mpsc::Sender<S::Item>) {
+    pin_mut!(stream);
+    while let Some(item) = stream.next().await {
+        if sender.send(item).await.is_err() {
+            return;
+        }
+    }
+}
+
+/// An error received by a [`Tee`]
+#[derive(Snafu, Debug)]
+pub enum TeeError<E: std::error::Error + 'static> {
+    /// The subscriber fell behind, and the oldest `skipped` items were dropped
+    #[snafu(display("subscriber lagged behind, {} items were skipped", skipped))]
+    Lagged { skipped: u64 },
+    /// The source stream returned an error
+    #[snafu(display("{}", source))]
+    Source { source: Arc<E> },
+}
+
+/// A subscriber of a stream shared with [`tee`]
+#[pin_project]
+pub struct Tee<T, E: std::error::Error + 'static> {
+    #[pin]
+    items: stream::BoxStream<'static, Result<T, TeeError<E>>>,
+    _driver: Arc<CancelableJoinHandle<()>>,
+}
+
+impl<T, E: std::error::Error + 'static> Stream for Tee<T, E> {
+    type Item = Result<T, TeeError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().items.poll_next(cx)
+    }
+}
+
+/// Shares a single `TryStream` (such as a [`watcher`](crate::watcher())) between `subscribers` consumers
+///
+/// The stream is polled by a task spawned on the current Tokio runtime, and every item is cloned to
+/// each subscriber. Errors are shared through an [`Arc`].
+///
+/// Each subscriber buffers up to `capacity` items. A slow subscriber does not hold back the others:
+/// once its buffer is full the oldest items are dropped, and it receives a [`TeeError::Lagged`] before the
+/// next item. A subscriber feeding a [`reflector`](crate::reflector()) that has lagged may be missing
+/// updates until the next [`Restarted`](watcher::Event::Restarted) event, so size `capacity` generously.
+///
+/// The task stops when the stream ends, or when all subscribers are dropped.
+///
+/// ```no_run
+/// use futures::{StreamExt, TryStreamExt};
+/// use kube::{api::{Api, ListParams}, Client};
+/// use kube_runtime::{reflector::{reflector, store::Writer}, utils::tee, watcher};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// # async fn scope(client: Client) {
+/// let cms: Api<ConfigMap> = Api::namespaced(client, "default");
+/// let mut subscribers = tee(watcher(cms, ListParams::default()), 2, 64);
+/// let (events, cached) = (subscribers.pop().unwrap(), subscribers.pop().unwrap());
+/// let writer = Writer::default();
+/// let store = writer.as_reader();
+/// // the reflector only needs the events, watch errors are handled by the other subscriber
+/// let cached = cached.filter_map(|item| futures::future::ready(item.ok().map(Ok)));
+/// tokio::spawn(reflector(writer, cached).for_each(|_| futures::future::ready(())));
+/// events
+///     .try_for_each(|event| async move {
+///         println!("{:?}", event);
+///         Ok(())
+///     })
+///     .await
+///     .ok();
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// Panics if called outside of a Tokio runtime, or if `capacity` is 0.
+pub fn tee<S>(stream: S, subscribers: usize, capacity: usize) -> Vec<Tee<S::Ok, S::Error>>
+where
+    S: TryStream + Send + 'static,
+    S::Ok: Clone + Send + 'static,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    tee_on(stream, subscribers, capacity, &TokioExecutor::new())
+}
+
+/// Like [`tee`], but the stream is polled by a task spawned with `executor`
+///
+/// # Panics
+///
+/// Panics if `capacity` is 0.
+pub fn tee_on<S>(
+    stream: S,
+    subscribers: usize,
+    capacity: usize,
+    executor: &dyn Executor,
+) -> Vec<Tee<S::Ok, S::Error>>
+where
+    S: TryStream + Send + 'static,
+    S::Ok: Clone + Send + 'static,
+    S::Error: std::error::Error + Send + Sync + 'static,
+{
+    let (tx, _) = broadcast::channel::<Result<S::Ok, Arc<S::Error>>>(capacity);
+    let receivers = (0..subscribers).map(|_| tx.subscribe()).collect::<Vec<_>>();
+    let driver = Arc::new(CancelableJoinHandle::spawn_on(
+        async move {
+            let stream = stream.into_stream();
+            pin_mut!(stream);
+            while let Some(item) = stream.next().await {
+                if tx.send(item.map_err(Arc::new)).is_err() {
+                    // all subscribers are gone
+                    return;
+                }
+            }
+        },
+        executor,
+    ));
+    receivers
+        .into_iter()
+        .map(|rx| Tee {
+            items: stream::unfold(rx, |mut rx| async move {
+                let item = match rx.recv().await {
+                    Ok(Ok(item)) => Ok(item),
+                    Ok(Err(source)) => Err(TeeError::Source { source }),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => Err(TeeError::Lagged { skipped }),
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                };
+                Some((item, rx))
+            })
+            .boxed(),
+            _driver: driver.clone(),
+        })
+        .collect()
+}
+
 /// Allows splitting a `Stream` into several streams that each emit a disjoint subset of the input stream's items,
 /// like a streaming variant of pattern matching.
 ///
@@ -140,36 +298,89 @@ where
     stream::select(via.into_stream(), errs.map(Err)) // recombine
 }
 
-/// A [`JoinHandle`] that cancels the [`Future`] when dropped, rather than detaching it
+/// A handle to a spawned [`Future`] that cancels it when dropped, rather than detaching it
 pub struct CancelableJoinHandle<T> {
-    inner: JoinHandle<T>,
+    inner: RemoteHandle<T>,
 }
 
 impl<T> CancelableJoinHandle<T>
 where
     T: Send + 'static,
 {
+    /// Spawn `future` onto a Tokio runtime
     pub fn spawn(future: impl Future<Output = T> + Send + 'static, runtime: &Handle) -> Self {
-        CancelableJoinHandle {
-            inner: runtime.spawn(future),
-        }
+        Self::spawn_on(future, &TokioExecutor::with_handle(runtime.clone()))
     }
-}
 
-impl<T> Drop for CancelableJoinHandle<T> {
-    fn drop(&mut self) {
-        self.inner.abort()
+    /// Spawn `future` onto an arbitrary [`Executor`]
+    pub fn spawn_on(future: impl Future<Output = T> + Send + 'static, executor: &dyn Executor) -> Self {
+        let (task, inner) = future.remote_handle();
+        executor.spawn(task.boxed());
+        CancelableJoinHandle { inner }
     }
 }
 
-impl<T> Future for CancelableJoinHandle<T> {
+impl<T: 'static> Future for CancelableJoinHandle<T> {
     type Output = T;
 
     fn poll(mut self: Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
-        self.inner.poll_unpin(cx).map(
-            // JoinError => underlying future was either aborted (which should only happen when the handle is dropped), or
-            // panicked (which should be propagated)
-            Result::unwrap,
-        )
+        // Panics of the underlying future are propagated
+        self.inner.poll_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{forward_to_channel, tee, TeeError};
+    use futures::{stream, StreamExt};
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn forward_to_channel_should_send_all_items() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let forwarder = tokio::spawn(forward_to_channel(stream::iter(vec![1, 2, 3]), tx));
+        let mut received = vec![];
+        while let Some(item) = rx.recv().await {
+            received.push(item);
+        }
+        assert_eq!(received, vec![1, 2, 3]);
+        forwarder.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn forward_to_channel_should_stop_when_receiver_is_closed() {
+        let (tx, rx) = mpsc::channel(1);
+        drop(rx);
+        forward_to_channel(stream::repeat(1), tx).await;
+    }
+
+    #[tokio::test]
+    async fn tee_should_clone_items_to_all_subscribers() {
+        let items = stream::iter(vec![Ok(1), Err(std::fmt::Error), Ok(2)]);
+        let subscribers = tee(items, 2, 4);
+        for subscriber in subscribers {
+            let received = subscriber.collect::<Vec<_>>().await;
+            assert_eq!(received.len(), 3);
+            assert_eq!(*received[0].as_ref().unwrap(), 1);
+            assert!(matches!(received[1], Err(TeeError::Source { .. })));
+            assert_eq!(*received[2].as_ref().unwrap(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn tee_should_report_lagging_subscribers() {
+        let items = stream::iter((0..5).map(Ok::<_, std::fmt::Error>));
+        let mut subscribers = tee(items, 1, 2);
+        // let the source run ahead
+        tokio::task::yield_now().await;
+        let received = subscribers.pop().unwrap().collect::<Vec<_>>().await;
+        assert!(matches!(received[0], Err(TeeError::Lagged { skipped: 3 })));
+        assert_eq!(
+            received[1..]
+                .iter()
+                .map(|r| *r.as_ref().unwrap())
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
     }
 }
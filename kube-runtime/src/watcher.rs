@@ -1,7 +1,11 @@
 //! Watches a Kubernetes Resource for changes, with error recovery
 
 use derivative::Derivative;
-use futures::{stream::BoxStream, Stream, StreamExt};
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    stream::{self, AbortHandle, BoxStream, SelectAll},
+    Stream, StreamExt, TryStreamExt,
+};
 use kube::{
     api::{ListParams, Resource, ResourceExt, WatchEvent},
     Api,
@@ -9,7 +13,14 @@ use kube::{
 use serde::de::DeserializeOwned;
 use smallvec::SmallVec;
 use snafu::{Backtrace, ResultExt, Snafu};
-use std::{clone::Clone, fmt::Debug};
+use std::{
+    clone::Clone,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt::Debug,
+    pin::Pin,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    task::{Context, Poll},
+};
 
 #[derive(Snafu, Debug)]
 pub enum Error {
@@ -51,7 +62,8 @@ pub enum Event<K> {
     /// Should be used as a signal to replace the store contents atomically.
     ///
     /// Any objects that were previously [`Applied`](Event::Applied) but are not listed in this event
-    /// should be assumed to have been [`Deleted`](Event::Deleted).
+    /// should be assumed to have been [`Deleted`](Event::Deleted). Use [`emit_tombstones`] to get
+    /// explicit `Deleted` events for them.
     Restarted(Vec<K>),
 }
 
@@ -69,6 +81,15 @@ impl<K> Event<K> {
         .into_iter()
     }
 
+    /// Map every object of the event with `f`
+    pub fn map<K2>(self, mut f: impl FnMut(K) -> K2) -> Event<K2> {
+        match self {
+            Event::Applied(obj) => Event::Applied(f(obj)),
+            Event::Deleted(obj) => Event::Deleted(f(obj)),
+            Event::Restarted(objs) => Event::Restarted(objs.into_iter().map(f).collect()),
+        }
+    }
+
     /// Flattens out all objects that were added, modified, or deleted in the event.
     ///
     /// Note that `Deleted` events may be missed when restarting the stream. Use finalizers
@@ -125,6 +146,10 @@ async fn step_trampolined<K: Resource + Clone + DeserializeOwned + Debug + Send
                 resource_version,
                 stream: stream.boxed(),
             }),
+            // The resource version is too old to watch from, start over with a relist
+            Err(kube::Error::Api(err)) if err.code == 410 => {
+                (Some(Err(err).context(WatchError)), State::Empty)
+            }
             Err(err) => (Some(Err(err).context(WatchStartFailed)), State::InitListed {
                 resource_version,
             }),
@@ -151,18 +176,15 @@ async fn step_trampolined<K: Resource + Clone + DeserializeOwned + Debug + Send
                 resource_version: bm.metadata.resource_version,
                 stream,
             }),
-            Some(Ok(WatchEvent::Error(err))) => {
-                // HTTP GONE, means we have desynced and need to start over and re-list :(
-                let new_state = if err.code == 410 {
-                    State::Empty
-                } else {
-                    State::Watching {
-                        resource_version,
-                        stream,
-                    }
-                };
-                (Some(Err(err).context(WatchError)), new_state)
-            }
+            Some(Ok(WatchEvent::Error(err))) => watch_error(err, resource_version, stream),
+            Some(Ok(WatchEvent::ErrorStatus(status))) => watch_error(status.into(), resource_version, stream),
+            // Events added by later versions of kube are skipped
+            Some(Ok(_)) => (None, State::Watching {
+                resource_version,
+                stream,
+            }),
+            // A watch call failing with an error status only fails once its body is read
+            Some(Err(kube::Error::Api(err))) if err.code == 410 => watch_error(err, resource_version, stream),
             Some(Err(err)) => (Some(Err(err).context(WatchFailed)), State::Watching {
                 resource_version,
                 stream,
@@ -172,6 +194,24 @@ async fn step_trampolined<K: Resource + Clone + DeserializeOwned + Debug + Send
     }
 }
 
+/// Handle an `ERROR` event of a watch
+fn watch_error<K: Resource + Clone>(
+    err: kube::error::ErrorResponse,
+    resource_version: String,
+    stream: BoxStream<'static, kube::Result<WatchEvent<K>>>,
+) -> (Option<Result<Event<K>>>, State<K>) {
+    // HTTP GONE, means we have desynced and need to start over and re-list :(
+    let new_state = if err.code == 410 {
+        State::Empty
+    } else {
+        State::Watching {
+            resource_version,
+            stream,
+        }
+    };
+    (Some(Err(err).context(WatchError)), new_state)
+}
+
 /// Trampoline helper for `step_trampolined`
 async fn step<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
     api: &Api<K>,
@@ -236,11 +276,1030 @@ pub fn watcher<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
     api: Api<K>,
     list_params: ListParams,
 ) -> impl Stream<Item = Result<Event<K>>> + Send {
-    futures::stream::unfold(
-        (api, list_params, State::Empty),
-        |(api, list_params, state)| async {
-            let (event, state) = step(&api, &list_params, state).await;
-            Some((event, (api, list_params, state)))
-        },
-    )
+    watch_from(api, list_params, State::Empty)
+}
+
+/// Watches a Kubernetes Resource for changes continuously, resuming from a known resource version
+///
+/// Like [`watcher`], but starts watching from `resource_version` instead of listing all objects first.
+/// This is for controllers that restore a persisted cache, and only need the changes since it was saved.
+/// No [`Event::Restarted`] is emitted for the initial state.
+///
+/// If `resource_version` is too old to resume from (`410 Gone`), the error is propagated and the watcher
+/// falls back to a relist, like [`watcher`] does when it falls out of the watch window.
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams}, Client};
+/// use kube_runtime::watcher;
+/// use k8s_openapi::api::core::v1::Pod;
+/// # fn scope(client: Client, persisted_version: &str) {
+/// let pods: Api<Pod> = Api::namespaced(client, "apps");
+/// let watcher = watcher::start_at(pods, ListParams::default(), persisted_version);
+/// # }
+/// ```
+pub fn start_at<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
+    api: Api<K>,
+    list_params: ListParams,
+    resource_version: &str,
+) -> impl Stream<Item = Result<Event<K>>> + Send {
+    watch_from(api, list_params, State::InitListed {
+        resource_version: resource_version.to_string(),
+    })
+}
+
+/// Watches a single object by name, yielding its latest state, or `None` while it does not exist
+///
+/// Uses a field selector on `metadata.name`, so only the one object is listed and watched,
+/// with the same recovery as [`watcher`]. Every relist yields the current state again.
+///
+/// ```no_run
+/// use futures::{StreamExt, TryStreamExt};
+/// use kube::{Api, Client};
+/// use kube_runtime::watcher;
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// # async fn scope(client: Client) -> Result<(), watcher::Error> {
+/// let cms: Api<ConfigMap> = Api::namespaced(client, "apps");
+/// let mut config = watcher::watch_object(cms, "app-config").boxed();
+/// while let Some(cm) = config.try_next().await? {
+///     match cm {
+///         Some(cm) => println!("config is now {:?}", cm.data),
+///         None => println!("config was deleted"),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub fn watch_object<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
+    api: Api<K>,
+    name: &str,
+) -> impl Stream<Item = Result<Option<K>>> + Send {
+    let list_params = ListParams::default().fields(&["metadata.name=", name].concat());
+    watcher(api, list_params).map_ok(|event| match event {
+        Event::Applied(obj) => Some(obj),
+        Event::Deleted(_) => None,
+        Event::Restarted(objs) => objs.into_iter().last(),
+    })
+}
+
+fn watch_from<K: Resource + Clone + DeserializeOwned + Debug + Send + 'static>(
+    api: Api<K>,
+    list_params: ListParams,
+    state: State<K>,
+) -> impl Stream<Item = Result<Event<K>>> + Send {
+    stream::unfold((api, list_params, state), |(api, list_params, state)| async {
+        let (event, state) = step(&api, &list_params, state).await;
+        Some((event, (api, list_params, state)))
+    })
+}
+
+/// Emits synthetic [`Event::Deleted`] events for objects that disappeared while the watch was interrupted
+///
+/// When the [`watcher`] has to relist, objects that were deleted in the meantime are simply missing from the
+/// [`Event::Restarted`]. This adapter remembers the last known version of every object, and emits an
+/// [`Event::Deleted`] with it for every object that is missing from a relist, before passing on the
+/// `Restarted` event itself.
+///
+/// This keeps a copy of every watched object in memory.
+pub fn emit_tombstones<K, S>(stream: S) -> impl Stream<Item = Result<Event<K>>> + Send
+where
+    K: Resource + Clone + Send + 'static,
+    S: Stream<Item = Result<Event<K>>> + Send,
+{
+    let known = HashMap::<(Option<String>, String), K>::new();
+    let key = |obj: &K| (obj.namespace(), obj.name());
+    stream
+        .scan(known, move |known, event| {
+            let mut events = vec![];
+            if let Ok(event) = &event {
+                match event {
+                    Event::Applied(obj) => {
+                        known.insert(key(obj), obj.clone());
+                    }
+                    Event::Deleted(obj) => {
+                        known.remove(&key(obj));
+                    }
+                    Event::Restarted(objs) => {
+                        let listed = objs.iter().map(|obj| (key(obj), obj.clone())).collect();
+                        let previous = std::mem::replace(known, listed);
+                        events.extend(
+                            previous
+                                .into_iter()
+                                .filter(|(key, _)| !known.contains_key(key))
+                                .map(|(_, obj)| Ok(Event::Deleted(obj))),
+                        );
+                    }
+                }
+            }
+            events.push(event);
+            futures::future::ready(Some(stream::iter(events)))
+        })
+        .flatten()
+}
+
+/// A handle for feeding events to a [`mock`] watch
+///
+/// Dropping the handle (and all its clones) ends the watch stream.
+#[derive(Clone)]
+pub struct MockWatch<K> {
+    tx: UnboundedSender<Result<Event<K>>>,
+}
+
+impl<K> MockWatch<K> {
+    /// Emit an [`Event::Applied`]
+    pub fn applied(&self, obj: K) {
+        self.send(Ok(Event::Applied(obj)));
+    }
+
+    /// Emit an [`Event::Deleted`]
+    pub fn deleted(&self, obj: K) {
+        self.send(Ok(Event::Deleted(obj)));
+    }
+
+    /// Emit an [`Event::Restarted`]
+    pub fn restarted(&self, objs: Vec<K>) {
+        self.send(Ok(Event::Restarted(objs)));
+    }
+
+    /// Emit an [`Error::WatchError`], as if the apiserver failed the watch with `code`
+    pub fn error(&self, code: u16, message: &str) {
+        self.send(Err(injected_error(code, message)));
+    }
+
+    /// Emit an arbitrary item
+    ///
+    /// Items sent after the stream has been dropped are discarded.
+    pub fn send(&self, item: Result<Event<K>>) {
+        let _ = self.tx.unbounded_send(item);
+    }
+}
+
+/// A channel-backed stand-in for a [`watcher`], for unit tests
+///
+/// The stream has the same shape as a [`watcher`], so it can be fed to a [`reflector`](crate::reflector())
+/// or [`Controller::for_stream`](crate::Controller::for_stream), but it only emits the events sent
+/// through the returned [`MockWatch`].
+///
+/// ```
+/// use futures::StreamExt;
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// use kube::api::ObjectMetaBuilder;
+/// use kube_runtime::{reflector::{reflector, store::Writer}, watcher};
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let (handle, events) = watcher::mock::<ConfigMap>();
+/// let writer = Writer::default();
+/// let store = writer.as_reader();
+/// handle.restarted(vec![ConfigMap {
+///     metadata: ObjectMetaBuilder::new("cfg").build(),
+///     ..ConfigMap::default()
+/// }]);
+/// drop(handle);
+/// reflector(writer, events).for_each(|_| futures::future::ready(())).await;
+/// assert_eq!(store.len(), 1);
+/// # }
+/// ```
+#[must_use]
+pub fn mock<K: Send + 'static>() -> (MockWatch<K>, BoxStream<'static, Result<Event<K>>>) {
+    let (tx, rx) = mpsc::unbounded();
+    (MockWatch { tx }, rx.boxed())
+}
+
+/// An [`Error::WatchError`] with a `Failure` status, for injecting failures in tests
+pub(crate) fn injected_error(code: u16, message: &str) -> Error {
+    snafu::IntoError::into_error(WatchError, kube::error::ErrorResponse::new(code, "", message))
+}
+
+/// An object that exceeded the size limit of [`limit_size`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Oversized {
+    /// Name of the object
+    pub name: String,
+    /// Namespace of the object, if it is namespaced
+    pub namespace: Option<String>,
+    /// Serialized size of the object, in bytes
+    pub size: usize,
+    /// The configured limit, in bytes
+    pub max_bytes: usize,
+    /// Whether the object was truncated (rather than dropped)
+    pub truncated: bool,
+}
+
+type Truncate<K> = Arc<dyn Fn(&mut K) + Send + Sync>;
+type OversizedHandler = Arc<dyn Fn(&Oversized) + Send + Sync>;
+
+/// Configuration for [`limit_size`]
+#[derive(Derivative)]
+#[derivative(Clone(bound = ""), Debug(bound = ""))]
+pub struct SizeLimit<K> {
+    max_bytes: usize,
+    #[derivative(Debug = "ignore")]
+    truncate: Option<Truncate<K>>,
+    #[derivative(Debug = "ignore")]
+    on_oversized: Option<OversizedHandler>,
+}
+
+impl<K> SizeLimit<K> {
+    /// Drop objects whose serialized size exceeds `max_bytes`
+    #[must_use]
+    pub fn new(max_bytes: usize) -> Self {
+        SizeLimit {
+            max_bytes,
+            truncate: None,
+            on_oversized: None,
+        }
+    }
+
+    /// Pass oversized objects on after trimming them with `truncate`, rather than dropping them
+    ///
+    /// For example, clear the `data` of a `Secret` but keep its metadata.
+    #[must_use]
+    pub fn truncate_with(mut self, truncate: impl Fn(&mut K) + Send + Sync + 'static) -> Self {
+        self.truncate = Some(Arc::new(truncate));
+        self
+    }
+
+    /// Call `handler` for every oversized object, for example to log or publish a warning
+    #[must_use]
+    pub fn on_oversized(mut self, handler: impl Fn(&Oversized) + Send + Sync + 'static) -> Self {
+        self.on_oversized = Some(Arc::new(handler));
+        self
+    }
+
+    /// Check the size of `obj`, returning it unless it is dropped
+    fn check(&self, mut obj: K) -> Option<K>
+    where
+        K: Resource + serde::Serialize,
+    {
+        // Objects that can't be serialized are passed on, the apiserver had no trouble with them
+        let size = serde_json::to_vec(&obj).map_or(0, |bytes| bytes.len());
+        if size <= self.max_bytes {
+            return Some(obj);
+        }
+        if let Some(handler) = &self.on_oversized {
+            handler(&Oversized {
+                name: obj.name(),
+                namespace: obj.namespace(),
+                size,
+                max_bytes: self.max_bytes,
+                truncated: self.truncate.is_some(),
+            });
+        }
+        let truncate = self.truncate.as_ref()?;
+        truncate(&mut obj);
+        Some(obj)
+    }
+}
+
+/// Keeps objects over a size limit out of a watch
+///
+/// A single object of a megabyte or more (typically a `Secret` or `ConfigMap`) can dominate the
+/// memory of a reflector's cache. This measures the serialized size of every [`Event::Applied`] and
+/// [`Event::Restarted`] object, and drops oversized objects, or [truncates](SizeLimit::truncate_with)
+/// them. [`Event::Deleted`] objects are passed on regardless, so that stores forget them.
+///
+/// A dropped update does not remove the object from stores, an object that grows beyond the limit keeps
+/// its last accepted version. Truncate objects instead to keep stores up to date.
+///
+/// ```no_run
+/// use kube::{api::ListParams, Api, Client};
+/// use kube_runtime::watcher::{limit_size, watcher, SizeLimit};
+/// use k8s_openapi::api::core::v1::Secret;
+/// # async fn scope(client: Client) {
+/// let limit = SizeLimit::new(256 * 1024)
+///     .truncate_with(|secret: &mut Secret| secret.data = None)
+///     .on_oversized(|oversized| eprintln!("{} is {} bytes", oversized.name, oversized.size));
+/// let secrets = limit_size(watcher(Api::<Secret>::all(client), ListParams::default()), limit);
+/// # }
+/// ```
+pub fn limit_size<K, S>(stream: S, limit: SizeLimit<K>) -> impl Stream<Item = Result<Event<K>>> + Send
+where
+    K: Resource + serde::Serialize + Send + 'static,
+    S: Stream<Item = Result<Event<K>>> + Send,
+{
+    stream.filter_map(move |event| {
+        let event = match event {
+            Ok(Event::Applied(obj)) => limit.check(obj).map(|obj| Ok(Event::Applied(obj))),
+            Ok(Event::Restarted(objs)) => Some(Ok(Event::Restarted(
+                objs.into_iter().filter_map(|obj| limit.check(obj)).collect(),
+            ))),
+            other => Some(other),
+        };
+        futures::future::ready(event)
+    })
+}
+
+/// Scoping for a [`scoped_watcher`]
+///
+/// Unlike [`ListParams`], this can restrict the watch to a set of namespaces. This is useful when the
+/// service account is only allowed to list and watch in some namespaces, and a cluster-wide
+/// watch would be forbidden.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Config {
+    /// A selector to restrict the watched objects by their labels
+    pub label_selector: Option<String>,
+    /// A selector to restrict the watched objects by their fields
+    pub field_selector: Option<String>,
+    /// Namespaces to watch, one watch is started per namespace
+    ///
+    /// When empty, the scope of the [`Api`] passed to the watcher is used.
+    pub namespaces: Vec<String>,
+    /// Timeout for the list/watch calls, see [`ListParams::timeout`]
+    pub timeout: Option<u32>,
+}
+
+impl Config {
+    /// Configure the label selector
+    #[must_use]
+    pub fn labels(mut self, label_selector: &str) -> Self {
+        self.label_selector = Some(label_selector.to_string());
+        self
+    }
+
+    /// Configure the field selector
+    #[must_use]
+    pub fn fields(mut self, field_selector: &str) -> Self {
+        self.field_selector = Some(field_selector.to_string());
+        self
+    }
+
+    /// Restrict the watch to the given namespaces
+    #[must_use]
+    pub fn namespaces<I: IntoIterator<Item = S>, S: Into<String>>(mut self, namespaces: I) -> Self {
+        self.namespaces = namespaces.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Configure the timeout for list/watch calls
+    #[must_use]
+    pub fn timeout(mut self, timeout_secs: u32) -> Self {
+        self.timeout = Some(timeout_secs);
+        self
+    }
+
+    /// The `ListParams` used by each underlying watch
+    #[must_use]
+    pub fn list_params(&self) -> ListParams {
+        self.clone().into()
+    }
+}
+
+impl From<Config> for ListParams {
+    fn from(config: Config) -> Self {
+        ListParams {
+            label_selector: config.label_selector,
+            field_selector: config.field_selector,
+            timeout: config.timeout,
+            ..ListParams::default()
+        }
+    }
+}
+
+impl From<ListParams> for Config {
+    fn from(lp: ListParams) -> Self {
+        Config {
+            label_selector: lp.label_selector,
+            field_selector: lp.field_selector,
+            namespaces: vec![],
+            timeout: lp.timeout,
+        }
+    }
+}
+
+/// Watches a Kubernetes Resource for changes, scoped by a [`Config`]
+///
+/// See [`scoped_watcher_with`] for details.
+#[must_use]
+pub fn scoped_watcher<K>(api: Api<K>, config: Config) -> BoxStream<'static, Result<Event<K>>>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+    K::DynamicType: Default,
+{
+    scoped_watcher_with(api, config, &K::DynamicType::default())
+}
+
+/// Watches a Kubernetes Resource for changes, scoped by a [`Config`]
+///
+/// Behaves like [`watcher`] when [`Config::namespaces`] is empty. Otherwise, one [`watcher`] is run for
+/// every namespace, and their events are merged. A restart of a single namespace's watch is reported as
+/// an [`Event::Restarted`] containing the last known objects of every watched namespace, so the
+/// merged stream can still be used to drive a [`reflector`](super::reflector::reflector).
+///
+/// To build those events, the merged stream keeps its own copy of every watched object, so watching
+/// several namespaces takes about as much memory again as a [`Store`](super::reflector::Store) of them.
+/// A single namespace (or none) is watched directly, without the copy.
+///
+/// ```no_run
+/// use kube::{Api, Client};
+/// use kube_runtime::watcher::{scoped_watcher, Config};
+/// use k8s_openapi::api::core::v1::Pod;
+/// # async fn scope(client: Client) {
+/// let pods: Api<Pod> = Api::all(client);
+/// let config = Config::default().labels("app=blog").namespaces(vec!["blog-dev", "blog-prod"]);
+/// let stream = scoped_watcher(pods, config);
+/// # }
+/// ```
+pub fn scoped_watcher_with<K>(
+    api: Api<K>,
+    config: Config,
+    dyntype: &K::DynamicType,
+) -> BoxStream<'static, Result<Event<K>>>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+{
+    let lp = config.list_params();
+    match config.namespaces.as_slice() {
+        [] => return watcher(api, lp).boxed(),
+        [ns] => return watcher(Api::namespaced_with(api.into_client(), ns, dyntype), lp).boxed(),
+        _ => {}
+    }
+    let client = api.into_client();
+    let watches = config.namespaces.into_iter().map(|ns| {
+        let api = Api::namespaced_with(client.clone(), &ns, dyntype);
+        watcher(api, lp.clone())
+            .map(move |event| (ns.clone(), event))
+            .boxed()
+    });
+    merge_namespaces(stream::select_all(watches)).boxed()
+}
+
+/// Merge the events of per-namespace watches, keeping `Restarted` events complete
+pub(crate) fn merge_namespaces<K, S>(watches: S) -> impl Stream<Item = Result<Event<K>>> + Send
+where
+    K: Resource + Clone + Send + 'static,
+    S: Stream<Item = (String, Result<Event<K>>)> + Send,
+{
+    let known = KnownObjects::<K>::new();
+    watches.scan(known, |known, (ns, event)| {
+        futures::future::ready(Some(event.map(|event| merge_event(known, ns, event))))
+    })
+}
+
+/// Last known objects of every namespace in a merged watch
+type KnownObjects<K> = BTreeMap<String, BTreeMap<String, K>>;
+
+fn merge_event<K: Resource + Clone>(known: &mut KnownObjects<K>, ns: String, event: Event<K>) -> Event<K> {
+    match event {
+        Event::Applied(obj) => {
+            known.entry(ns).or_default().insert(obj.name(), obj.clone());
+            Event::Applied(obj)
+        }
+        Event::Deleted(obj) => {
+            known.entry(ns).or_default().remove(&obj.name());
+            Event::Deleted(obj)
+        }
+        Event::Restarted(objs) => {
+            known.insert(ns, objs.into_iter().map(|obj| (obj.name(), obj)).collect());
+            all_known(known)
+        }
+    }
+}
+
+fn all_known<K: Clone>(known: &KnownObjects<K>) -> Event<K> {
+    Event::Restarted(known.values().flat_map(BTreeMap::values).cloned().collect())
+}
+
+#[derive(Clone)]
+enum NamespaceChange {
+    Add(String),
+    Remove(String),
+}
+
+#[derive(Default)]
+struct NamespaceSetInner {
+    namespaces: BTreeSet<String>,
+    subscribers: Vec<UnboundedSender<NamespaceChange>>,
+}
+
+/// A set of namespaces that can be changed while watchers are running
+///
+/// Pass the set to [`dynamic_watcher`] (or [`Controller::new_dynamic`](crate::Controller::new_dynamic)),
+/// and call [`insert`](Self::insert) and [`remove`](Self::remove) to start and stop watching namespaces.
+/// Only the watches of the affected namespaces are started or stopped.
+///
+/// Cloning produces a new handle to the same set.
+#[derive(Clone, Default)]
+pub struct NamespaceSet {
+    inner: Arc<Mutex<NamespaceSetInner>>,
+}
+
+impl NamespaceSet {
+    /// Create a set with some initial namespaces
+    pub fn new<I: IntoIterator<Item = S>, S: Into<String>>(namespaces: I) -> Self {
+        let inner = NamespaceSetInner {
+            namespaces: namespaces.into_iter().map(Into::into).collect(),
+            subscribers: vec![],
+        };
+        NamespaceSet {
+            inner: Arc::new(Mutex::new(inner)),
+        }
+    }
+
+    /// Start watching a namespace, does nothing if it is already watched
+    pub fn insert(&self, namespace: &str) {
+        self.change(&NamespaceChange::Add(namespace.to_string()));
+    }
+
+    /// Stop watching a namespace, does nothing if it is not watched
+    ///
+    /// Watchers emit an [`Event::Restarted`] without the objects of the namespace.
+    pub fn remove(&self, namespace: &str) {
+        self.change(&NamespaceChange::Remove(namespace.to_string()));
+    }
+
+    fn change(&self, change: &NamespaceChange) {
+        let mut inner = self.lock();
+        let changed = match change {
+            NamespaceChange::Add(ns) => inner.namespaces.insert(ns.clone()),
+            NamespaceChange::Remove(ns) => inner.namespaces.remove(ns),
+        };
+        if changed {
+            inner
+                .subscribers
+                .retain(|tx| tx.unbounded_send(change.clone()).is_ok());
+        }
+    }
+
+    /// The namespaces currently in the set
+    #[must_use]
+    pub fn namespaces(&self) -> Vec<String> {
+        self.lock().namespaces.iter().cloned().collect()
+    }
+
+    fn subscribe(&self) -> (Vec<String>, UnboundedReceiver<NamespaceChange>) {
+        let (tx, rx) = mpsc::unbounded();
+        let mut inner = self.lock();
+        inner.subscribers.push(tx);
+        (inner.namespaces.iter().cloned().collect(), rx)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, NamespaceSetInner> {
+        // The lock is never held across user code, so poisoning can be ignored
+        self.inner.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Debug for NamespaceSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("NamespaceSet").field(&self.namespaces()).finish()
+    }
+}
+
+/// Watches a Kubernetes Resource for changes in a changing set of namespaces
+///
+/// See [`dynamic_watcher_with`] for details.
+#[must_use]
+pub fn dynamic_watcher<K>(
+    api: Api<K>,
+    config: Config,
+    namespaces: &NamespaceSet,
+) -> BoxStream<'static, Result<Event<K>>>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+    K::DynamicType: Default + Send,
+{
+    dynamic_watcher_with(api, config, namespaces, K::DynamicType::default())
+}
+
+/// Watches a Kubernetes Resource for changes in a changing set of namespaces
+///
+/// Like [`scoped_watcher_with`], but the namespaces are taken from a [`NamespaceSet`] rather than
+/// [`Config::namespaces`]. Adding a namespace to the set starts a watch for it, removing it stops
+/// the watch and emits an [`Event::Restarted`] without the objects of that namespace.
+/// Like for [`scoped_watcher_with`], the stream keeps a copy of every watched object to build those events.
+///
+/// ```no_run
+/// use kube::{Api, Client};
+/// use kube_runtime::watcher::{dynamic_watcher, Config, NamespaceSet};
+/// use k8s_openapi::api::core::v1::Pod;
+/// # async fn scope(client: Client) {
+/// let namespaces = NamespaceSet::new(vec!["tenant-a"]);
+/// let stream = dynamic_watcher(Api::<Pod>::all(client), Config::default(), &namespaces);
+/// // later, e.g. when a Namespace is labelled for this operator
+/// namespaces.insert("tenant-b");
+/// # }
+/// ```
+pub fn dynamic_watcher_with<K>(
+    api: Api<K>,
+    config: Config,
+    namespaces: &NamespaceSet,
+    dyntype: K::DynamicType,
+) -> BoxStream<'static, Result<Event<K>>>
+where
+    K: Resource + Clone + DeserializeOwned + Debug + Send + 'static,
+    K::DynamicType: Send,
+{
+    let lp = ListParams::from(config);
+    let client = api.into_client();
+    let (initial, changes) = namespaces.subscribe();
+    DynamicWatch::new(initial, changes, move |ns| {
+        watcher(Api::namespaced_with(client.clone(), ns, &dyntype), lp.clone()).boxed()
+    })
+    .boxed()
+}
+
+/// A merged watch over a changing set of namespaces
+struct DynamicWatch<K: Resource + Clone> {
+    changes: UnboundedReceiver<NamespaceChange>,
+    changes_closed: bool,
+    #[allow(clippy::type_complexity)]
+    start_watch: Box<dyn Fn(&str) -> BoxStream<'static, Result<Event<K>>> + Send>,
+    watches: SelectAll<BoxStream<'static, (String, Result<Event<K>>)>>,
+    aborts: BTreeMap<String, AbortHandle>,
+    known: KnownObjects<K>,
+}
+
+// Nothing is structurally pinned
+impl<K: Resource + Clone> Unpin for DynamicWatch<K> {}
+
+impl<K: Resource + Clone + Send + 'static> DynamicWatch<K> {
+    fn new(
+        initial: Vec<String>,
+        changes: UnboundedReceiver<NamespaceChange>,
+        start_watch: impl Fn(&str) -> BoxStream<'static, Result<Event<K>>> + Send + 'static,
+    ) -> Self {
+        let mut watch = DynamicWatch {
+            changes,
+            changes_closed: false,
+            start_watch: Box::new(start_watch),
+            watches: SelectAll::new(),
+            aborts: BTreeMap::new(),
+            known: KnownObjects::new(),
+        };
+        for ns in initial {
+            watch.start(ns);
+        }
+        watch
+    }
+
+    fn start(&mut self, ns: String) {
+        if self.aborts.contains_key(&ns) {
+            return;
+        }
+        let (stream, abort) = stream::abortable((self.start_watch)(&ns));
+        let tag = ns.clone();
+        self.watches
+            .push(stream.map(move |event| (tag.clone(), event)).boxed());
+        self.aborts.insert(ns, abort);
+    }
+
+    fn stop(&mut self, ns: &str) -> Option<Event<K>> {
+        self.aborts.remove(ns)?.abort();
+        self.known.remove(ns).map(|_| all_known(&self.known))
+    }
+}
+
+impl<K: Resource + Clone + Send + 'static> Stream for DynamicWatch<K> {
+    type Item = Result<Event<K>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        while !this.changes_closed {
+            match this.changes.poll_next_unpin(cx) {
+                Poll::Ready(Some(NamespaceChange::Add(ns))) => this.start(ns),
+                Poll::Ready(Some(NamespaceChange::Remove(ns))) => {
+                    if let Some(event) = this.stop(&ns) {
+                        return Poll::Ready(Some(Ok(event)));
+                    }
+                }
+                Poll::Ready(None) => this.changes_closed = true,
+                Poll::Pending => break,
+            }
+        }
+        match this.watches.poll_next_unpin(cx) {
+            Poll::Ready(Some((ns, event))) => {
+                Poll::Ready(Some(event.map(|event| merge_event(&mut this.known, ns, event))))
+            }
+            // No namespaces are watched right now, but more may be added later
+            Poll::Ready(None) if !this.changes_closed => Poll::Pending,
+            other => other.map(|_| None),
+        }
+    }
+}
+
+/// Watches of several kinds merged into one stream of a user defined event type
+///
+/// Every watch keeps its own recovery: an error of one watch is passed on and that watch retries
+/// when polled again, without affecting the others. A relist of one watch only restarts its own kind.
+/// Events are mapped with a function per watch, typically an enum variant:
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams}, Client};
+/// use kube_runtime::watcher::{watcher, Event, MergedWatch};
+/// use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+/// # fn scope(client: Client) {
+/// enum Watched {
+///     ConfigMap(Event<ConfigMap>),
+///     Secret(Event<Secret>),
+/// }
+///
+/// let cms: Api<ConfigMap> = Api::all(client.clone());
+/// let secrets: Api<Secret> = Api::all(client);
+/// let events = MergedWatch::new()
+///     .with(watcher(cms, ListParams::default()), Watched::ConfigMap)
+///     .with(watcher(secrets, ListParams::default()), Watched::Secret);
+/// # }
+/// ```
+///
+/// Watches of [`DynamicObject`](kube::api::DynamicObject)s can share a variant, and be told
+/// apart by their `ApiResource`.
+pub struct MergedWatch<E> {
+    watches: SelectAll<BoxStream<'static, Result<E>>>,
+}
+
+impl<E> Default for MergedWatch<E> {
+    fn default() -> Self {
+        Self {
+            watches: SelectAll::new(),
+        }
+    }
+}
+
+impl<E: Send + 'static> MergedWatch<E> {
+    /// Create a merged watch without any watches yet
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a watch, whose events are mapped to `E` with `map`
+    #[must_use]
+    pub fn with<K, S>(mut self, watch: S, map: impl Fn(Event<K>) -> E + Send + 'static) -> Self
+    where
+        S: Stream<Item = Result<Event<K>>> + Send + 'static,
+    {
+        self.watches.push(watch.map_ok(map).boxed());
+        self
+    }
+}
+
+impl<E> Stream for MergedWatch<E> {
+    type Item = Result<E>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.watches.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        emit_tombstones, limit_size, merge_namespaces, mock, start_at, watch_object, Config, DynamicWatch,
+        Error, Event, MergedWatch, NamespaceSet, SizeLimit,
+    };
+    use crate::testing::{mock_client, MockResponse};
+    use futures::{stream, FutureExt, StreamExt};
+    use k8s_openapi::{
+        api::core::v1::{ConfigMap, Secret},
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    };
+    use kube::api::{ListParams, ResourceExt};
+
+    fn cm(ns: &str, name: &str) -> ConfigMap {
+        ConfigMap {
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                namespace: Some(ns.to_string()),
+                ..ObjectMeta::default()
+            },
+            ..ConfigMap::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn limit_size_drops_or_truncates_oversized_objects() {
+        let mut big = cm("a", "big");
+        big.data = Some(std::iter::once(("key".to_string(), "x".repeat(1024))).collect());
+        let events = || {
+            stream::iter(vec![
+                Ok(Event::Applied(big.clone())),
+                Ok(Event::Restarted(vec![cm("a", "small"), big.clone()])),
+                Ok(Event::Deleted(big.clone())),
+            ])
+        };
+        let seen = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let sink = seen.clone();
+        let rejecting = SizeLimit::new(512).on_oversized(move |o| sink.lock().unwrap().push(o.clone()));
+        let rejected = limit_size(events(), rejecting).collect::<Vec<_>>().await;
+        assert_eq!(rejected.len(), 2);
+        assert!(
+            matches!(&rejected[0], Ok(Event::Restarted(objs)) if objs.len() == 1 && objs[0].name() == "small")
+        );
+        assert!(matches!(&rejected[1], Ok(Event::Deleted(obj)) if obj.data.is_some()));
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 2);
+        assert_eq!(seen[0].name, "big");
+        assert!(seen[0].size > 1024 && !seen[0].truncated);
+
+        let truncating = SizeLimit::new(512).truncate_with(|cm: &mut ConfigMap| cm.data = None);
+        let truncated = limit_size(events(), truncating).collect::<Vec<_>>().await;
+        assert_eq!(truncated.len(), 3);
+        assert!(matches!(&truncated[0], Ok(Event::Applied(obj)) if obj.data.is_none()));
+        assert!(matches!(&truncated[1], Ok(Event::Restarted(objs)) if objs.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn start_at_falls_back_to_relist_when_gone() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let log = requests.clone();
+        let client = mock_client(move |req| {
+            let query = req.query.unwrap_or_default();
+            log.lock().unwrap().push(query.clone());
+            if query.contains("resourceVersion=5") {
+                MockResponse::ok(serde_json::json!({
+                    "type": "ERROR",
+                    "object": {"status": "Failure", "message": "too old", "reason": "Expired", "code": 410}
+                }))
+            } else {
+                MockResponse::ok(serde_json::json!({
+                    "metadata": {"resourceVersion": "10"},
+                    "items": [cm("a", "1")]
+                }))
+            }
+        });
+        let api = kube::Api::<ConfigMap>::namespaced(client, "a");
+        let mut events = start_at(api, ListParams::default(), "5").boxed();
+        assert!(
+            matches!(events.next().await, Some(Err(Error::WatchError { source, .. })) if source.code == 410)
+        );
+        assert!(matches!(events.next().await, Some(Ok(Event::Restarted(objs))) if objs.len() == 1));
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("watch=true"));
+        assert!(!requests[1].contains("watch=true"));
+    }
+
+    #[tokio::test]
+    async fn start_at_falls_back_to_relist_when_the_watch_call_is_gone() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let log = requests.clone();
+        let client = mock_client(move |req| {
+            let query = req.query.unwrap_or_default();
+            log.lock().unwrap().push(query.clone());
+            if query.contains("resourceVersion=5") {
+                MockResponse::error(410, "Expired", "too old")
+            } else {
+                MockResponse::ok(serde_json::json!({
+                    "metadata": {"resourceVersion": "10"},
+                    "items": [cm("a", "1")]
+                }))
+            }
+        });
+        let api = kube::Api::<ConfigMap>::namespaced(client, "a");
+        let mut events = start_at(api, ListParams::default(), "5").boxed();
+        assert!(
+            matches!(events.next().await, Some(Err(Error::WatchError { source, .. })) if source.code == 410)
+        );
+        assert!(matches!(events.next().await, Some(Ok(Event::Restarted(objs))) if objs.len() == 1));
+        let requests = requests.lock().unwrap();
+        assert!(requests[0].contains("watch=true"));
+        assert!(!requests[1].contains("watch=true"));
+    }
+
+    #[tokio::test]
+    async fn watch_object_follows_a_single_object() {
+        let requests = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+        let log = requests.clone();
+        let client = mock_client(move |req| {
+            let query = req.query.unwrap_or_default();
+            log.lock().unwrap().push(query.clone());
+            if query.contains("watch=true") {
+                let mut deleted = cm("a", "1");
+                deleted.metadata.resource_version = Some("11".to_string());
+                MockResponse::ok(serde_json::json!({ "type": "DELETED", "object": deleted }))
+            } else {
+                MockResponse::ok(serde_json::json!({
+                    "metadata": {"resourceVersion": "10"},
+                    "items": [cm("a", "1")]
+                }))
+            }
+        });
+        let api = kube::Api::<ConfigMap>::namespaced(client, "a");
+        let mut updates = watch_object(api, "1").boxed();
+        assert!(matches!(updates.next().await, Some(Ok(Some(obj))) if obj.name() == "1"));
+        assert!(matches!(updates.next().await, Some(Ok(None))));
+        assert!(requests.lock().unwrap()[0].contains("fieldSelector=metadata.name%3D1"));
+    }
+
+    #[tokio::test]
+    async fn mock_emits_sent_events() {
+        let (handle, events) = mock::<ConfigMap>();
+        handle.applied(cm("a", "1"));
+        handle.error(500, "injected");
+        handle.deleted(cm("a", "1"));
+        drop(handle);
+        let events = events.collect::<Vec<_>>().await;
+        assert_eq!(events.len(), 3);
+        assert!(matches!(events[0], Ok(Event::Applied(_))));
+        assert!(matches!(&events[1], Err(Error::WatchError { source, .. }) if source.code == 500));
+        assert!(matches!(events[2], Ok(Event::Deleted(_))));
+    }
+
+    #[tokio::test]
+    async fn merged_watches_keep_their_kind() {
+        #[derive(Debug)]
+        enum Watched {
+            ConfigMap(Event<ConfigMap>),
+            Secret(Event<Secret>),
+        }
+        let (cms, cm_events) = mock::<ConfigMap>();
+        let (secrets, secret_events) = mock::<Secret>();
+        let mut merged = MergedWatch::new()
+            .with(cm_events, Watched::ConfigMap)
+            .with(secret_events, Watched::Secret);
+        cms.restarted(vec![cm("a", "1")]);
+        assert!(matches!(
+            merged.next().await,
+            Some(Ok(Watched::ConfigMap(Event::Restarted(objs)))) if objs.len() == 1
+        ));
+        secrets.error(500, "injected");
+        assert!(matches!(merged.next().await, Some(Err(Error::WatchError { .. }))));
+        secrets.applied(Secret::default());
+        assert!(matches!(
+            merged.next().await,
+            Some(Ok(Watched::Secret(Event::Applied(_))))
+        ));
+        drop(cms);
+        drop(secrets);
+        assert!(merged.next().await.is_none());
+    }
+
+    #[test]
+    fn config_list_params() {
+        let config = Config::from(ListParams::default().labels("app=blog").timeout(10)).fields("a=b");
+        let lp = config.list_params();
+        assert_eq!(lp.label_selector.as_deref(), Some("app=blog"));
+        assert_eq!(lp.field_selector.as_deref(), Some("a=b"));
+        assert_eq!(lp.timeout, Some(10));
+        assert_eq!(config.namespaces(vec!["a", "b"]).namespaces, vec!["a", "b"]);
+    }
+
+    #[tokio::test]
+    async fn merged_restarts_keep_other_namespaces() {
+        let events = vec![
+            ("a".to_string(), Ok(Event::Restarted(vec![cm("a", "1")]))),
+            ("b".to_string(), Ok(Event::Restarted(vec![cm("b", "2")]))),
+            ("b".to_string(), Ok(Event::Applied(cm("b", "3")))),
+            ("a".to_string(), Ok(Event::Restarted(vec![]))),
+        ];
+        let merged = merge_namespaces(stream::iter(events)).collect::<Vec<_>>().await;
+        let names = |event: &Event<ConfigMap>| match event {
+            Event::Restarted(objs) => objs.iter().map(ResourceExt::name).collect::<Vec<_>>(),
+            Event::Applied(obj) | Event::Deleted(obj) => vec![obj.name()],
+        };
+        let merged = merged
+            .iter()
+            .map(|e| names(e.as_ref().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(merged, vec![vec!["1"], vec!["1", "2"], vec!["3"], vec!["2", "3"]]);
+    }
+
+    #[tokio::test]
+    async fn dynamic_namespaces_start_and_stop_watches() {
+        let set = NamespaceSet::new(vec!["a"]);
+        let (initial, changes) = set.subscribe();
+        let mut watch = DynamicWatch::new(initial, changes, |ns| {
+            stream::iter(vec![Ok(Event::Restarted(vec![cm(ns, ns)]))])
+                .chain(stream::pending())
+                .boxed()
+        });
+        let mut next_names = || {
+            let event = watch.next().now_or_never().flatten().map(Result::unwrap);
+            event.map(|event| match event {
+                Event::Restarted(objs) => objs.iter().map(ResourceExt::name).collect::<Vec<_>>(),
+                _ => unreachable!(),
+            })
+        };
+        assert_eq!(next_names(), Some(vec!["a".to_string()]));
+        set.insert("b");
+        set.insert("b");
+        assert_eq!(next_names(), Some(vec!["a".to_string(), "b".to_string()]));
+        set.remove("a");
+        assert_eq!(next_names(), Some(vec!["b".to_string()]));
+        assert_eq!(next_names(), None);
+        assert_eq!(set.namespaces(), vec!["b"]);
+    }
+
+    #[tokio::test]
+    async fn relist_gaps_emit_tombstones() {
+        let events = vec![
+            Ok(Event::Restarted(vec![cm("a", "1"), cm("a", "2")])),
+            Ok(Event::Applied(cm("a", "3"))),
+            Ok(Event::Deleted(cm("a", "1"))),
+            Ok(Event::Restarted(vec![cm("a", "2")])),
+        ];
+        let out = emit_tombstones(stream::iter(events))
+            .map(|event| match event.unwrap() {
+                Event::Applied(obj) => format!("applied {}", obj.name()),
+                Event::Deleted(obj) => format!("deleted {}", obj.name()),
+                Event::Restarted(objs) => format!("restarted {}", objs.len()),
+            })
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(out, vec![
+            "restarted 2",
+            "applied 3",
+            "deleted 1",
+            "deleted 3",
+            "restarted 1"
+        ]);
+    }
 }
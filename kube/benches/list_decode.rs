@@ -0,0 +1,149 @@
+//! Decoding large lists and watches, and the cost of caching their metadata
+//!
+//! Run with `cargo bench -p kube --bench list_decode`, set `KUBE_BENCH_OBJECTS` to change
+//! the number of objects (default 20000).
+use futures::{stream, StreamExt};
+use http::{Request, Response};
+use hyper::Body;
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::{
+    api::{CompactMeta, MetaInterner, ObjectList, ObjectMeta, WatchEvent},
+    Client, Service,
+};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+/// Counts allocations, to compare representations
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn object(i: usize) -> serde_json::Value {
+    serde_json::json!({
+        "apiVersion": "v1",
+        "kind": "ConfigMap",
+        "metadata": {
+            "name": format!("config-{}", i),
+            "namespace": format!("team-{}", i % 20),
+            "uid": format!("3f9c0b8e-{:08}", i),
+            "resourceVersion": (100_000 + i).to_string(),
+            "labels": {"app": format!("app-{}", i % 50), "tier": "backend", "managed-by": "operator"},
+            "annotations": {"example.com/revision": (i % 7).to_string()},
+            "ownerReferences": [{
+                "apiVersion": "apps/v1",
+                "kind": "Deployment",
+                "name": format!("app-{}", i % 50),
+                "uid": format!("8a1d-{:04}", i % 50),
+                "controller": true,
+            }],
+        },
+        "data": {"config.yaml": "x".repeat(256)},
+    })
+}
+
+/// Time `f` and count its allocations and the bytes it leaves allocated
+fn measure<T>(name: &str, objects: usize, f: impl FnOnce() -> T) -> T {
+    let (allocations, allocated) = (
+        ALLOCATIONS.load(Ordering::Relaxed),
+        ALLOCATED.load(Ordering::Relaxed),
+    );
+    let start = Instant::now();
+    let out = f();
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    let retained = ALLOCATED.load(Ordering::Relaxed).saturating_sub(allocated);
+    println!(
+        "{:<28} {:>10.2?} {:>8.2} allocs/object {:>10} bytes/object retained",
+        name,
+        elapsed,
+        allocations as f64 / objects as f64,
+        retained / objects
+    );
+    out
+}
+
+fn main() {
+    let n = std::env::var("KUBE_BENCH_OBJECTS")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(20_000);
+    let items = (0..n).map(object).collect::<Vec<_>>();
+    let list = serde_json::to_vec(&serde_json::json!({
+        "apiVersion": "v1",
+        "kind": "ConfigMapList",
+        "metadata": {"resourceVersion": "1"},
+        "items": items,
+    }))
+    .unwrap();
+    let mut events = vec![];
+    for item in items {
+        serde_json::to_writer(&mut events, &serde_json::json!({"type": "ADDED", "object": item})).unwrap();
+        events.push(b'\n');
+    }
+    println!("{} objects, list of {} KiB", n, list.len() / 1024);
+
+    let list = measure("list decode", n, || {
+        serde_json::from_slice::<ObjectList<ConfigMap>>(&list).unwrap()
+    });
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let decoded = measure("watch decode (16 KiB chunks)", n, || {
+        runtime.block_on(watch(events))
+    });
+    assert_eq!(decoded, n);
+
+    let metas = measure("cache ObjectMeta", n, || {
+        list.items
+            .iter()
+            .map(|cm| cm.metadata.clone())
+            .collect::<Vec<ObjectMeta>>()
+    });
+    let interner = MetaInterner::new();
+    let compact = measure("cache CompactMeta", n, || {
+        metas
+            .iter()
+            .map(|meta| CompactMeta::new(meta, &interner))
+            .collect::<Vec<_>>()
+    });
+    assert_eq!(compact.len(), metas.len());
+}
+
+async fn watch(events: Vec<u8>) -> usize {
+    let svc = tower::service_fn(move |_req: Request<Body>| {
+        let chunks = events
+            .chunks(16 * 1024)
+            .map(|chunk| Ok::<_, std::io::Error>(chunk.to_vec()))
+            .collect::<Vec<_>>();
+        async move { Ok::<_, tower::BoxError>(Response::new(Body::wrap_stream(stream::iter(chunks)))) }
+    });
+    let client = Client::new(Service::new(svc));
+    let req = Request::get("/api/v1/configmaps?watch=true")
+        .body(vec![])
+        .unwrap();
+    let events = client.request_events::<ConfigMap>(req).await.unwrap();
+    events
+        .fold(0, |n, event| async move {
+            n + matches!(event, Ok(WatchEvent::Added(_))) as usize
+        })
+        .await
+}
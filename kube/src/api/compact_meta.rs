@@ -0,0 +1,325 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference, Time};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex, PoisonError},
+};
+
+/// Shared storage for the strings of [`CompactMeta`]s
+///
+/// Namespaces, label keys and values, annotation keys, finalizers and owner kinds repeat across
+/// most objects of a cache, so interning them stores each distinct string once. Clones of an
+/// interner share its strings.
+#[cfg_attr(docsrs, doc(cfg(feature = "compact-meta")))]
+#[derive(Clone, Default)]
+pub struct MetaInterner {
+    strings: Arc<Mutex<HashSet<Arc<str>>>>,
+}
+
+impl MetaInterner {
+    /// Create an empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The shared copy of `s`
+    pub fn intern(&self, s: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(shared) = strings.get(s) {
+            return shared.clone();
+        }
+        let shared: Arc<str> = Arc::from(s);
+        strings.insert(shared.clone());
+        shared
+    }
+
+    /// The number of distinct strings stored
+    pub fn len(&self) -> usize {
+        self.strings.lock().unwrap_or_else(PoisonError::into_inner).len()
+    }
+
+    /// Whether no strings are stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop the strings that no [`CompactMeta`] uses anymore
+    pub fn purge(&self) {
+        let mut strings = self.strings.lock().unwrap_or_else(PoisonError::into_inner);
+        strings.retain(|s| Arc::strong_count(s) > 1);
+    }
+}
+
+impl fmt::Debug for MetaInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetaInterner").field("len", &self.len()).finish()
+    }
+}
+
+/// A reference to an owner in a [`CompactMeta`]
+#[cfg_attr(docsrs, doc(cfg(feature = "compact-meta")))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompactOwner {
+    /// API version of the owner
+    pub api_version: Arc<str>,
+    /// Kind of the owner
+    pub kind: Arc<str>,
+    /// Name of the owner
+    pub name: Box<str>,
+    /// UID of the owner
+    pub uid: Box<str>,
+    /// Whether the owner is the managing controller
+    pub controller: Option<bool>,
+    /// Whether the owner can only be deleted after this object
+    pub block_owner_deletion: Option<bool>,
+}
+
+/// A compact representation of the [`ObjectMeta`] of a cached object
+///
+/// An `ObjectMeta` allocates a `String` for every name, label and annotation, and a `BTreeMap`
+/// node for every few entries. `CompactMeta` stores the strings that repeat across objects in a
+/// [`MetaInterner`] and keeps labels and annotations in sorted boxed slices, which cuts the
+/// allocations and the memory per object. The `list_decode` benchmark of this crate compares both.
+///
+/// Reflector stores keep whole objects, so this is for caches that keep the metadata of many
+/// objects themselves, like a map of names to the `CompactMeta` and the fields of the spec
+/// that a controller reads.
+///
+/// Only the fields controllers commonly read are kept: `managedFields`, `generateName`,
+/// `selfLink` and `creationTimestamp` are dropped.
+///
+/// ```
+/// use kube::api::{CompactMeta, MetaInterner, ObjectMeta};
+/// let interner = MetaInterner::new();
+/// let meta = ObjectMeta {
+///     name: Some("blog".into()),
+///     namespace: Some("apps".into()),
+///     labels: Some(std::iter::once(("app".to_string(), "blog".to_string())).collect()),
+///     ..ObjectMeta::default()
+/// };
+/// let compact = CompactMeta::new(&meta, &interner);
+/// assert_eq!(compact.label("app"), Some("blog"));
+/// assert_eq!(compact.to_meta().labels, meta.labels);
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "compact-meta")))]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompactMeta {
+    /// Name of the object
+    pub name: Box<str>,
+    /// Namespace of the object
+    pub namespace: Option<Arc<str>>,
+    /// Cluster of the object, see [`ObjectMeta::cluster_name`]
+    pub cluster: Option<Arc<str>>,
+    /// UID of the object
+    pub uid: Option<Box<str>>,
+    /// Resource version of the object
+    pub resource_version: Option<Box<str>>,
+    /// Generation of the object
+    pub generation: Option<i64>,
+    /// When the object is going to be deleted
+    pub deletion_timestamp: Option<Time>,
+    labels: Box<[(Arc<str>, Arc<str>)]>,
+    annotations: Box<[(Arc<str>, Box<str>)]>,
+    /// Finalizers of the object
+    pub finalizers: Box<[Arc<str>]>,
+    /// Owners of the object
+    pub owner_references: Box<[CompactOwner]>,
+}
+
+impl CompactMeta {
+    /// Compact `meta`, sharing repeated strings through `interner`
+    pub fn new(meta: &ObjectMeta, interner: &MetaInterner) -> Self {
+        let owned = |s: &Option<String>| s.as_deref().map(Box::from);
+        let interned = |s: &Option<String>| s.as_deref().map(|s| interner.intern(s));
+        CompactMeta {
+            name: meta.name.as_deref().unwrap_or_default().into(),
+            namespace: interned(&meta.namespace),
+            cluster: interned(&meta.cluster_name),
+            uid: owned(&meta.uid),
+            resource_version: owned(&meta.resource_version),
+            generation: meta.generation,
+            deletion_timestamp: meta.deletion_timestamp.clone(),
+            // BTreeMaps iterate in order, so the slices are sorted for lookups
+            labels: meta
+                .labels
+                .iter()
+                .flatten()
+                .map(|(k, v)| (interner.intern(k), interner.intern(v)))
+                .collect(),
+            annotations: meta
+                .annotations
+                .iter()
+                .flatten()
+                .map(|(k, v)| (interner.intern(k), Box::from(v.as_str())))
+                .collect(),
+            finalizers: meta
+                .finalizers
+                .iter()
+                .flatten()
+                .map(|f| interner.intern(f))
+                .collect(),
+            owner_references: meta
+                .owner_references
+                .iter()
+                .flatten()
+                .map(|owner| CompactOwner {
+                    api_version: interner.intern(&owner.api_version),
+                    kind: interner.intern(&owner.kind),
+                    name: owner.name.as_str().into(),
+                    uid: owner.uid.as_str().into(),
+                    controller: owner.controller,
+                    block_owner_deletion: owner.block_owner_deletion,
+                })
+                .collect(),
+        }
+    }
+
+    /// The value of the label `key`
+    pub fn label(&self, key: &str) -> Option<&str> {
+        let i = self.labels.binary_search_by(|(k, _)| (**k).cmp(key)).ok()?;
+        Some(&self.labels[i].1)
+    }
+
+    /// The labels, sorted by key
+    pub fn labels(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.labels.iter().map(|(k, v)| (&**k, &**v))
+    }
+
+    /// The value of the annotation `key`
+    pub fn annotation(&self, key: &str) -> Option<&str> {
+        let i = self.annotations.binary_search_by(|(k, _)| (**k).cmp(key)).ok()?;
+        Some(&self.annotations[i].1)
+    }
+
+    /// The annotations, sorted by key
+    pub fn annotations(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.annotations.iter().map(|(k, v)| (&**k, &**v))
+    }
+
+    /// The owner that is the managing controller, if any
+    pub fn controller(&self) -> Option<&CompactOwner> {
+        self.owner_references
+            .iter()
+            .find(|owner| owner.controller == Some(true))
+    }
+
+    /// Expand into an [`ObjectMeta`] with the kept fields
+    pub fn to_meta(&self) -> ObjectMeta {
+        let string = |s: &str| s.to_string();
+        let map = |entries: Vec<(String, String)>| {
+            if entries.is_empty() {
+                None
+            } else {
+                Some(entries.into_iter().collect::<BTreeMap<_, _>>())
+            }
+        };
+        ObjectMeta {
+            name: Some(string(&self.name)),
+            namespace: self.namespace.as_deref().map(string),
+            cluster_name: self.cluster.as_deref().map(string),
+            uid: self.uid.as_deref().map(string),
+            resource_version: self.resource_version.as_deref().map(string),
+            generation: self.generation,
+            deletion_timestamp: self.deletion_timestamp.clone(),
+            labels: map(self.labels().map(|(k, v)| (string(k), string(v))).collect()),
+            annotations: map(self.annotations().map(|(k, v)| (string(k), string(v))).collect()),
+            finalizers: if self.finalizers.is_empty() {
+                None
+            } else {
+                Some(self.finalizers.iter().map(|f| string(f)).collect())
+            },
+            owner_references: if self.owner_references.is_empty() {
+                None
+            } else {
+                Some(
+                    self.owner_references
+                        .iter()
+                        .map(|owner| OwnerReference {
+                            api_version: string(&owner.api_version),
+                            kind: string(&owner.kind),
+                            name: string(&owner.name),
+                            uid: string(&owner.uid),
+                            controller: owner.controller,
+                            block_owner_deletion: owner.block_owner_deletion,
+                        })
+                        .collect(),
+                )
+            },
+            ..ObjectMeta::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CompactMeta, MetaInterner};
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+    use std::sync::Arc;
+
+    #[test]
+    fn repeated_strings_are_shared() {
+        let interner = MetaInterner::new();
+        let meta = |name: &str| ObjectMeta {
+            name: Some(name.into()),
+            namespace: Some("apps".into()),
+            labels: Some(
+                vec![("app", "blog"), ("tier", "web")]
+                    .into_iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect(),
+            ),
+            annotations: Some(std::iter::once(("note".to_string(), name.to_string())).collect()),
+            finalizers: Some(vec!["example.com/cleanup".into()]),
+            owner_references: Some(vec![OwnerReference {
+                api_version: "apps/v1".into(),
+                kind: "ReplicaSet".into(),
+                name: "blog-1".into(),
+                uid: "1234".into(),
+                controller: Some(true),
+                block_owner_deletion: None,
+            }]),
+            ..ObjectMeta::default()
+        };
+        let a = CompactMeta::new(&meta("a"), &interner);
+        let b = CompactMeta::new(&meta("b"), &interner);
+        assert!(Arc::ptr_eq(
+            a.namespace.as_ref().unwrap(),
+            b.namespace.as_ref().unwrap()
+        ));
+        // apps, app, blog, tier, web, note, the finalizer, apps/v1 and ReplicaSet
+        assert_eq!(interner.len(), 9);
+        assert_eq!(b.label("tier"), Some("web"));
+        assert_eq!(b.label("missing"), None);
+        assert_eq!(b.annotation("note"), Some("b"));
+        assert_eq!(b.controller().map(|o| &*o.name), Some("blog-1"));
+        assert_eq!(b.to_meta(), meta("b"));
+
+        drop((a, b));
+        interner.purge();
+        assert!(interner.is_empty());
+    }
+
+    #[test]
+    fn owner_flags_roundtrip() {
+        let owner = |controller, block_owner_deletion| OwnerReference {
+            api_version: "v1".into(),
+            kind: "ConfigMap".into(),
+            name: "settings".into(),
+            uid: "5678".into(),
+            controller,
+            block_owner_deletion,
+        };
+        let meta = ObjectMeta {
+            name: Some("a".into()),
+            owner_references: Some(vec![
+                owner(None, None),
+                owner(Some(false), Some(true)),
+                owner(Some(true), Some(false)),
+            ]),
+            ..ObjectMeta::default()
+        };
+        let compact = CompactMeta::new(&meta, &MetaInterner::new());
+        assert_eq!(compact.controller().map(|o| o.block_owner_deletion), Some(Some(false)));
+        assert_eq!(compact.to_meta(), meta);
+    }
+}
@@ -0,0 +1,156 @@
+//! Helpers for the standard `conditions` of object statuses
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{Condition, Time};
+
+/// Status of a [`Condition`] that is true
+pub const CONDITION_TRUE: &str = "True";
+/// Status of a [`Condition`] that is false
+pub const CONDITION_FALSE: &str = "False";
+/// Status of a [`Condition`] that could not be determined
+pub const CONDITION_UNKNOWN: &str = "Unknown";
+
+/// Create a [`Condition`] whose `lastTransitionTime` is now
+///
+/// `status` should be one of [`CONDITION_TRUE`], [`CONDITION_FALSE`] or [`CONDITION_UNKNOWN`].
+pub fn new_condition(type_: &str, status: &str, reason: &str, message: &str) -> Condition {
+    Condition {
+        type_: type_.to_string(),
+        status: status.to_string(),
+        reason: reason.to_string(),
+        message: message.to_string(),
+        last_transition_time: Time(chrono::Utc::now()),
+        observed_generation: None,
+    }
+}
+
+/// Reads and updates the `conditions` of a status, following the [API conventions](https://github.com/kubernetes/community/blob/master/contributors/devel/sig-architecture/api-conventions.md#typical-status-properties)
+///
+/// Implemented for the `Vec<Condition>` (and `Option<Vec<Condition>>`) found in statuses, so
+/// it can be used on the status of any object:
+///
+/// ```
+/// use kube::api::{new_condition, Conditions, CONDITION_TRUE};
+/// use k8s_openapi::apimachinery::pkg::apis::meta::v1::Condition;
+///
+/// let mut conditions: Option<Vec<Condition>> = None;
+/// let changed = conditions.set_condition(new_condition("Ready", CONDITION_TRUE, "Synced", "all good"));
+/// assert!(changed);
+/// assert!(conditions.is_condition_true("Ready"));
+/// ```
+///
+/// Requires kubernetes >= 1.19.
+pub trait Conditions {
+    /// The condition of type `type_`, if any
+    fn condition(&self, type_: &str) -> Option<&Condition>;
+
+    /// Set a condition, replacing any existing condition of the same type
+    ///
+    /// When the existing condition has the same status, its `lastTransitionTime` is kept, so that it
+    /// records when the status last changed rather than when the condition was last written.
+    /// Returns whether the conditions changed, so callers can skip no-op status updates.
+    fn set_condition(&mut self, condition: Condition) -> bool;
+
+    /// Remove the condition of type `type_`, returning it
+    fn remove_condition(&mut self, type_: &str) -> Option<Condition>;
+
+    /// Whether the condition of type `type_` exists and has status `True`
+    fn is_condition_true(&self, type_: &str) -> bool {
+        self.has_condition_status(type_, CONDITION_TRUE)
+    }
+
+    /// Whether the condition of type `type_` exists and has status `False`
+    fn is_condition_false(&self, type_: &str) -> bool {
+        self.has_condition_status(type_, CONDITION_FALSE)
+    }
+
+    /// Whether the condition of type `type_` has status `Unknown`, or is missing
+    fn is_condition_unknown(&self, type_: &str) -> bool {
+        match self.condition(type_) {
+            Some(c) => c.status == CONDITION_UNKNOWN,
+            None => true,
+        }
+    }
+
+    /// Whether the condition of type `type_` exists and has `status`
+    ///
+    /// Statuses are compared exactly, as the API conventions require `True`, `False` or `Unknown`.
+    fn has_condition_status(&self, type_: &str, status: &str) -> bool {
+        matches!(self.condition(type_), Some(c) if c.status == status)
+    }
+}
+
+impl Conditions for Vec<Condition> {
+    fn condition(&self, type_: &str) -> Option<&Condition> {
+        self.iter().find(|c| c.type_ == type_)
+    }
+
+    fn set_condition(&mut self, mut condition: Condition) -> bool {
+        match self.iter_mut().find(|c| c.type_ == condition.type_) {
+            Some(existing) => {
+                if existing.status == condition.status {
+                    condition.last_transition_time = existing.last_transition_time.clone();
+                }
+                if *existing == condition {
+                    return false;
+                }
+                *existing = condition;
+            }
+            None => self.push(condition),
+        }
+        true
+    }
+
+    fn remove_condition(&mut self, type_: &str) -> Option<Condition> {
+        let index = self.iter().position(|c| c.type_ == type_)?;
+        Some(self.remove(index))
+    }
+}
+
+impl Conditions for Option<Vec<Condition>> {
+    fn condition(&self, type_: &str) -> Option<&Condition> {
+        self.as_ref()?.condition(type_)
+    }
+
+    fn set_condition(&mut self, condition: Condition) -> bool {
+        self.get_or_insert_with(Vec::new).set_condition(condition)
+    }
+
+    fn remove_condition(&mut self, type_: &str) -> Option<Condition> {
+        self.as_mut()?.remove_condition(type_)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{new_condition, Conditions, CONDITION_FALSE, CONDITION_TRUE};
+    use k8s_openapi::{
+        apimachinery::pkg::apis::meta::v1::{Condition, Time},
+        chrono::{TimeZone, Utc},
+    };
+
+    #[test]
+    fn set_condition_tracks_transitions() {
+        let mut conditions: Vec<Condition> = vec![];
+        let mut ready = new_condition("Ready", CONDITION_FALSE, "Starting", "waiting for pods");
+        let started = Time(Utc.timestamp_opt(1_600_000_000, 0).unwrap());
+        ready.last_transition_time = started.clone();
+        assert!(conditions.set_condition(ready.clone()));
+        assert!(conditions.is_condition_false("Ready"));
+        assert!(!conditions.set_condition(ready));
+
+        // same status, new message: the transition time is kept
+        let waiting = new_condition("Ready", CONDITION_FALSE, "Starting", "1/3 pods ready");
+        assert!(conditions.set_condition(waiting));
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].message, "1/3 pods ready");
+        assert_eq!(conditions[0].last_transition_time, started);
+
+        // new status: the transition time is updated
+        assert!(conditions.set_condition(new_condition("Ready", CONDITION_TRUE, "Running", "")));
+        assert!(conditions.is_condition_true("Ready"));
+        assert_ne!(conditions[0].last_transition_time, started);
+
+        assert!(conditions.is_condition_unknown("Degraded"));
+        assert!(conditions.remove_condition("Ready").is_some());
+        assert!(conditions.condition("Ready").is_none());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{api::GroupVersionKind, Result};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+/// Builds a `CustomResourceDefinition` for a spec type that can not `#[derive(CustomResource)]`
+///
+/// This produces the same definition as the derive for types defined in other crates.
+/// Objects of the resource can be handled as an [`Object`](crate::api::Object) with the
+/// [`GroupVersionKind`] returned by [`gvk`](Self::gvk).
+///
+/// ```no_run
+/// use kube::api::{Api, CustomResourceDefinitionBuilder, NotUsed, Object};
+/// # #[derive(Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
+/// # struct ForeignSpec { replicas: i32 }
+/// # async fn scope(client: kube::Client) -> Result<(), kube::Error> {
+/// let builder = CustomResourceDefinitionBuilder::new::<ForeignSpec>("clux.dev", "v1", "Foreign")
+///     .namespaced()
+///     .short_name("fg");
+/// let crd = builder.to_json(); // apply this to the cluster
+/// let foreigns: Api<Object<ForeignSpec, NotUsed>> = Api::namespaced_with(client, "default", &builder.gvk()?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CustomResourceDefinitionBuilder {
+    group: String,
+    version: String,
+    kind: String,
+    plural: Option<String>,
+    singular: Option<String>,
+    namespaced: bool,
+    short_names: Vec<String>,
+    categories: Vec<String>,
+    spec_schema: Value,
+    status_schema: Option<Value>,
+}
+
+impl CustomResourceDefinitionBuilder {
+    /// Start building a cluster scoped definition with the schema of `S` as its `spec`
+    pub fn new<S: JsonSchema>(group: &str, version: &str, kind: &str) -> Self {
+        Self {
+            group: group.to_string(),
+            version: version.to_string(),
+            kind: kind.to_string(),
+            plural: None,
+            singular: None,
+            namespaced: false,
+            short_names: vec![],
+            categories: vec![],
+            spec_schema: schema_for::<S>(),
+            status_schema: None,
+        }
+    }
+
+    /// Use the schema of `U` as the `status`, and enable the status subresource
+    pub fn status<U: JsonSchema>(mut self) -> Self {
+        self.status_schema = Some(schema_for::<U>());
+        self
+    }
+
+    /// Set the plural name, inferred from the singular name by default
+    pub fn plural(mut self, plural: &str) -> Self {
+        self.plural = Some(plural.to_string());
+        self
+    }
+
+    /// Set the singular name, the lowercased kind by default
+    pub fn singular(mut self, singular: &str) -> Self {
+        self.singular = Some(singular.to_string());
+        self
+    }
+
+    /// Make the resource namespaced rather than cluster scoped
+    pub fn namespaced(mut self) -> Self {
+        self.namespaced = true;
+        self
+    }
+
+    /// Add a short name
+    pub fn short_name(mut self, short_name: &str) -> Self {
+        self.short_names.push(short_name.to_string());
+        self
+    }
+
+    /// Add the resource to a category, like `all`
+    pub fn category(mut self, category: &str) -> Self {
+        self.categories.push(category.to_string());
+        self
+    }
+
+    fn singular_name(&self) -> String {
+        self.singular
+            .clone()
+            .unwrap_or_else(|| self.kind.to_ascii_lowercase())
+    }
+
+    fn plural_name(&self) -> String {
+        self.plural
+            .clone()
+            .unwrap_or_else(|| crate::api::metadata::to_plural(&self.singular_name()))
+    }
+
+    /// The [`GroupVersionKind`] of the resource, for use with [`Api`](crate::Api)
+    pub fn gvk(&self) -> Result<GroupVersionKind> {
+        Ok(GroupVersionKind::gvk(&self.group, &self.version, &self.kind)?.plural(&self.plural_name()))
+    }
+
+    /// The `apiextensions.k8s.io/v1` `CustomResourceDefinition` as json
+    pub fn to_json(&self) -> Value {
+        let plural = self.plural_name();
+        let mut properties = json!({ "spec": self.spec_schema });
+        let mut subresources = json!({});
+        if let Some(status) = &self.status_schema {
+            properties["status"] = status.clone();
+            subresources["status"] = json!({});
+        }
+        let mut crd = json!({
+            "apiVersion": "apiextensions.k8s.io/v1",
+            "kind": "CustomResourceDefinition",
+            "metadata": { "name": format!("{}.{}", plural, self.group) },
+            "spec": {
+                "group": self.group,
+                "scope": if self.namespaced { "Namespaced" } else { "Cluster" },
+                "names": {
+                    "plural": plural,
+                    "singular": self.singular_name(),
+                    "kind": self.kind,
+                    "shortNames": self.short_names,
+                },
+                "versions": [{
+                    "name": self.version,
+                    "served": true,
+                    "storage": true,
+                    "additionalPrinterColumns": [],
+                    "schema": {
+                        "openAPIV3Schema": {
+                            "description": format!("Auto-generated derived type for {} via `CustomResourceDefinitionBuilder`", self.kind),
+                            "title": self.kind,
+                            "type": "object",
+                            "properties": properties,
+                            "required": ["spec"],
+                        }
+                    },
+                    "subresources": subresources,
+                }],
+            }
+        });
+        if !self.categories.is_empty() {
+            crd["spec"]["names"]["categories"] = json!(self.categories);
+        }
+        crd
+    }
+
+    /// Deserialize the definition into a typed `CustomResourceDefinition`
+    ///
+    /// ```no_run
+    /// # use kube::api::CustomResourceDefinitionBuilder;
+    /// use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+    /// # fn scope(builder: CustomResourceDefinitionBuilder) -> Result<(), kube::Error> {
+    /// let crd: CustomResourceDefinition = builder.build()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn build<T: serde::de::DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_value(self.to_json())?)
+    }
+}
+
+/// The schema of a type, generated like kube-derive does
+fn schema_for<T: JsonSchema>() -> Value {
+    // Don't use definitions and don't include `$schema` because these are not allowed.
+    let gen = schemars::gen::SchemaSettings::openapi3()
+        .with(|s| {
+            s.inline_subschemas = true;
+            s.meta_schema = None;
+        })
+        .into_generator();
+    let mut schema = serde_json::to_value(gen.into_root_schema_for::<T>()).unwrap_or_default();
+    if let Some(map) = schema.as_object_mut() {
+        // the root schema is named after the rust type, which is not meaningful on a field
+        map.remove("title");
+    }
+    schema
+}
@@ -10,16 +10,16 @@ use std::borrow::Cow;
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct GroupVersionKind {
     /// API group
-    group: String,
+    pub(crate) group: String,
     /// Version
-    version: String,
+    pub(crate) version: String,
     /// Kind
-    kind: String,
+    pub(crate) kind: String,
     /// Concatenation of group and version
     #[serde(default)]
-    api_version: String,
+    pub(crate) api_version: String,
     /// Optional plural/resource
-    plural: Option<String>,
+    pub(crate) plural: Option<String>,
 }
 
 impl GroupVersionKind {
@@ -0,0 +1,183 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ObjectMeta, OwnerReference};
+
+/// A builder for [`ObjectMeta`], avoiding the nested `Some(..)`s of building it by hand
+///
+/// ```
+/// use kube::api::{ObjectMetaBuilder, Resource};
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// # fn scope(parent: Deployment) {
+/// let meta = ObjectMetaBuilder::new("blog")
+///     .namespace("prod")
+///     .label("app", "blog")
+///     .annotation("example.com/owner", "team-a")
+///     .owner_ref(parent.controller_owner_ref(&()).unwrap())
+///     .build();
+/// # }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ObjectMetaBuilder {
+    meta: ObjectMeta,
+}
+
+impl ObjectMetaBuilder {
+    /// Start building the metadata of an object named `name`
+    pub fn new(name: &str) -> Self {
+        ObjectMetaBuilder {
+            meta: ObjectMeta {
+                name: Some(name.to_string()),
+                ..ObjectMeta::default()
+            },
+        }
+    }
+
+    /// Start building the metadata of an object whose name is generated by the apiserver from `prefix`
+    pub fn generate_name(prefix: &str) -> Self {
+        ObjectMetaBuilder {
+            meta: ObjectMeta {
+                generate_name: Some(prefix.to_string()),
+                ..ObjectMeta::default()
+            },
+        }
+    }
+
+    /// Set the namespace
+    #[must_use]
+    pub fn namespace(mut self, namespace: &str) -> Self {
+        self.meta.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Add a label
+    #[must_use]
+    pub fn label(mut self, key: &str, value: &str) -> Self {
+        self.meta
+            .labels
+            .get_or_insert_with(Default::default)
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Add several labels
+    #[must_use]
+    pub fn labels<'a>(self, labels: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        labels.into_iter().fold(self, |this, (k, v)| this.label(k, v))
+    }
+
+    /// Add an annotation
+    #[must_use]
+    pub fn annotation(mut self, key: &str, value: &str) -> Self {
+        self.meta
+            .annotations
+            .get_or_insert_with(Default::default)
+            .insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Add several annotations
+    #[must_use]
+    pub fn annotations<'a>(self, annotations: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        annotations
+            .into_iter()
+            .fold(self, |this, (k, v)| this.annotation(k, v))
+    }
+
+    /// Add an owner reference, see [`Resource::owner_ref`](crate::Resource::owner_ref)
+    #[must_use]
+    pub fn owner_ref(mut self, owner: OwnerReference) -> Self {
+        self.meta
+            .owner_references
+            .get_or_insert_with(Vec::new)
+            .push(owner);
+        self
+    }
+
+    /// Add a finalizer
+    #[must_use]
+    pub fn finalizer(mut self, finalizer: &str) -> Self {
+        self.meta
+            .finalizers
+            .get_or_insert_with(Vec::new)
+            .push(finalizer.to_string());
+        self
+    }
+
+    /// Finish building the metadata
+    pub fn build(self) -> ObjectMeta {
+        self.meta
+    }
+}
+
+impl From<ObjectMetaBuilder> for ObjectMeta {
+    fn from(builder: ObjectMetaBuilder) -> Self {
+        builder.build()
+    }
+}
+
+/// Construct a k8s-openapi style object, wrapping its optional fields in `Some`
+///
+/// `metadata` accepts an [`ObjectMeta`] or an [`ObjectMetaBuilder`](crate::api::ObjectMetaBuilder).
+/// Every other field is converted with [`Into`] and wrapped in `Some`, which matches k8s-openapi structs,
+/// where almost all fields are optional. Omitted fields use their defaults.
+///
+/// ```
+/// use kube::{api::ObjectMetaBuilder, object};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// use std::collections::BTreeMap;
+///
+/// let mut data = BTreeMap::new();
+/// data.insert("key".to_string(), "value".to_string());
+/// let cm = object!(ConfigMap {
+///     metadata: ObjectMetaBuilder::new("config").label("app", "blog"),
+///     data: data,
+/// });
+/// assert_eq!(cm.metadata.name.as_deref(), Some("config"));
+/// assert_eq!(cm.data.unwrap()["key"], "value");
+/// ```
+#[macro_export]
+macro_rules! object {
+    ($($kind:ident)::+ { metadata: $meta:expr $(, $field:ident : $value:expr)* $(,)? }) => {
+        $($kind)::+ {
+            metadata: ::std::convert::Into::into($meta),
+            $($field: ::std::option::Option::Some(::std::convert::Into::into($value)),)*
+            ..::std::default::Default::default()
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::ObjectMetaBuilder;
+    use crate::Resource;
+    use k8s_openapi::{
+        api::{apps::v1::Deployment, core::v1::ConfigMap},
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    };
+
+    #[test]
+    fn builds_meta_with_owner() {
+        let parent = Deployment {
+            metadata: ObjectMeta {
+                name: Some("blog".to_string()),
+                uid: Some("1234".to_string()),
+                ..ObjectMeta::default()
+            },
+            ..Deployment::default()
+        };
+        let cm: ConfigMap = object!(ConfigMap {
+            metadata: ObjectMetaBuilder::new("blog-config")
+                .namespace("prod")
+                .labels(vec![("app", "blog"), ("tier", "web")])
+                .owner_ref(parent.controller_owner_ref(&()).unwrap()),
+        });
+        let meta = cm.metadata;
+        assert_eq!(meta.namespace.as_deref(), Some("prod"));
+        assert_eq!(meta.labels.unwrap().len(), 2);
+        let owner = &meta.owner_references.unwrap()[0];
+        assert_eq!(owner.api_version, "apps/v1");
+        assert_eq!(owner.kind, "Deployment");
+        assert_eq!(owner.uid, "1234");
+        assert_eq!(owner.controller, Some(true));
+
+        assert!(ConfigMap::default().owner_ref(&()).is_none());
+    }
+}
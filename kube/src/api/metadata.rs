@@ -1,7 +1,7 @@
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::OwnerReference;
 pub use k8s_openapi::apimachinery::pkg::apis::meta::v1::{ListMeta, ObjectMeta};
 use once_cell::sync::Lazy;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{borrow::Cow, collections::BTreeMap};
 
 /// An accessor trait for a kubernetes Resource.
@@ -79,6 +79,32 @@ pub trait Resource {
     fn meta(&self) -> &ObjectMeta;
     /// Metadata that all persisted resources must have
     fn meta_mut(&mut self) -> &mut ObjectMeta;
+
+    /// Generates an owner reference pointing to this resource
+    ///
+    /// Returns `None` if the object has no name or uid, i.e. if it has not been created yet.
+    fn owner_ref(&self, dt: &Self::DynamicType) -> Option<OwnerReference> {
+        let meta = self.meta();
+        Some(OwnerReference {
+            api_version: Self::api_version(dt).into_owned(),
+            kind: Self::kind(dt).into_owned(),
+            name: meta.name.clone()?,
+            uid: meta.uid.clone()?,
+            ..OwnerReference::default()
+        })
+    }
+
+    /// Generates a controller owner reference pointing to this resource
+    ///
+    /// Like [`Resource::owner_ref`], but marks this resource as the managing controller,
+    /// and blocks deletion of this resource until the owned object is deleted in foreground deletion.
+    fn controller_owner_ref(&self, dt: &Self::DynamicType) -> Option<OwnerReference> {
+        Some(OwnerReference {
+            controller: Some(true),
+            block_owner_deletion: Some(true),
+            ..self.owner_ref(dt)?
+        })
+    }
 }
 
 /// Helper methods for resources.
@@ -115,6 +141,31 @@ pub trait ResourceExt: Resource {
     fn finalizers(&self) -> &[String];
     /// Provides mutable access to the finalizers
     fn finalizers_mut(&mut self) -> &mut Vec<String>;
+
+    /// Clears the metadata populated by the apiserver
+    ///
+    /// This removes `resourceVersion`, `uid`, `creationTimestamp`, `generation`, `selfLink`,
+    /// `managedFields` and the deletion markers, so that a live object can be created again,
+    /// e.g. when copying it to another namespace or cluster.
+    /// Owner references are kept, and may need to be replaced when copying across namespaces.
+    fn clear_server_fields(&mut self);
+
+    /// Returns a copy of the object without its server populated metadata and `status`
+    ///
+    /// Like [`clear_server_fields`](ResourceExt::clear_server_fields), but also drops the `status`,
+    /// which the apiserver ignores on create.
+    fn without_server_fields(&self) -> serde_json::Result<Self>
+    where
+        Self: Serialize + DeserializeOwned + Sized,
+    {
+        let mut value = serde_json::to_value(self)?;
+        if let Some(object) = value.as_object_mut() {
+            object.remove("status");
+        }
+        let mut cleared: Self = serde_json::from_value(value)?;
+        cleared.clear_server_fields();
+        Ok(cleared)
+    }
 }
 
 // TODO: replace with ordinary static when BTreeMap::new() is no longer
@@ -169,6 +220,18 @@ impl<K: Resource> ResourceExt for K {
     fn finalizers_mut(&mut self) -> &mut Vec<String> {
         self.meta_mut().finalizers.get_or_insert_with(Vec::new)
     }
+
+    fn clear_server_fields(&mut self) {
+        let meta = self.meta_mut();
+        meta.resource_version = None;
+        meta.uid = None;
+        meta.creation_timestamp = None;
+        meta.generation = None;
+        meta.self_link = None;
+        meta.managed_fields = None;
+        meta.deletion_timestamp = None;
+        meta.deletion_grace_period_seconds = None;
+    }
 }
 
 /// Implement accessor trait for any ObjectMeta-using Kubernetes Resource
@@ -216,6 +279,21 @@ pub struct TypeMeta {
     pub kind: String,
 }
 
+/// Type information and metadata of an object, without its spec or status
+///
+/// This is what the apiserver returns for each item when asked for a `PartialObjectMetadataList`.
+/// See [`Api::list_metadata`](crate::Api::list_metadata).
+#[derive(Deserialize, Serialize, Clone, Default, Debug, PartialEq)]
+pub struct PartialObjectMetadata {
+    /// The type fields, not always present
+    #[serde(flatten, default)]
+    pub types: Option<TypeMeta>,
+
+    /// Standard object's metadata
+    #[serde(default)]
+    pub metadata: ObjectMeta,
+}
+
 // Simple pluralizer. Handles the special cases.
 pub(crate) fn to_plural(word: &str) -> String {
     if word == "endpoints" || word == "endpointslices" {
@@ -253,6 +331,44 @@ pub(crate) fn to_plural(word: &str) -> String {
     format!("{}s", word)
 }
 
+#[test]
+fn test_without_server_fields() {
+    use k8s_openapi::{
+        api::core::v1::{Pod, PodStatus},
+        apimachinery::pkg::apis::meta::v1::{ManagedFieldsEntry, Time},
+    };
+    let mut pod = Pod {
+        metadata: ObjectMeta {
+            name: Some("blog".to_string()),
+            namespace: Some("prod".to_string()),
+            resource_version: Some("42".to_string()),
+            uid: Some("1234".to_string()),
+            creation_timestamp: Some(Time(chrono::Utc::now())),
+            managed_fields: Some(vec![ManagedFieldsEntry::default()]),
+            ..ObjectMeta::default()
+        },
+        status: Some(PodStatus {
+            phase: Some("Running".to_string()),
+            ..PodStatus::default()
+        }),
+        ..Pod::default()
+    };
+    pod.labels_mut().insert("app".to_string(), "blog".to_string());
+
+    let cleared = pod.without_server_fields().unwrap();
+    assert_eq!(cleared.metadata, ObjectMeta {
+        name: Some("blog".to_string()),
+        namespace: Some("prod".to_string()),
+        labels: pod.metadata.labels.clone(),
+        ..ObjectMeta::default()
+    });
+    assert!(cleared.status.is_none());
+
+    pod.clear_server_fields();
+    assert_eq!(pod.metadata, cleared.metadata);
+    assert!(pod.status.is_some());
+}
+
 #[test]
 fn test_to_plural_native() {
     // Extracted from `swagger.json`
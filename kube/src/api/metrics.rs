@@ -0,0 +1,172 @@
+//! Types for the resource metrics api, `metrics.k8s.io`
+//!
+//! This api is served by an aggregated apiserver, usually [metrics-server](https://github.com/kubernetes-sigs/metrics-server),
+//! and is what `kubectl top` reads. It supports `get` and `list` (including label selectors), but not `watch`.
+use crate::api::{ObjectMeta, Resource};
+use k8s_openapi::apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::Time};
+use serde::{Deserialize, Serialize};
+use std::{borrow::Cow, collections::BTreeMap};
+
+/// Group of the resource metrics api
+const GROUP: &str = "metrics.k8s.io";
+/// Version of the resource metrics api
+const VERSION: &str = "v1beta1";
+
+/// Resource usage of a node, keyed by resource name (`cpu`, `memory`)
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams, metrics::NodeMetrics}, Client};
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let nodes: Api<NodeMetrics> = Api::all(client);
+/// for node in nodes.list(&ListParams::default().labels("node-role.kubernetes.io/worker")).await? {
+///     println!("{:?}: {:?}", node.metadata.name, node.usage.get("cpu"));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeMetrics {
+    /// Metadata of the node
+    pub metadata: ObjectMeta,
+    /// End of the window the usage was collected over
+    pub timestamp: Option<Time>,
+    /// Length of the window the usage was collected over, as a duration string like `10.5s`
+    #[serde(default)]
+    pub window: String,
+    /// Resource usage of the node
+    #[serde(default)]
+    pub usage: BTreeMap<String, Quantity>,
+}
+
+/// Resource usage of the containers of a pod
+///
+/// ```no_run
+/// use kube::{api::{Api, metrics::PodMetrics}, Client};
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let pods: Api<PodMetrics> = Api::namespaced(client, "default");
+/// let blog = pods.get("blog").await?;
+/// for container in &blog.containers {
+///     println!("{}: {:?}", container.name, container.usage.get("memory"));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct PodMetrics {
+    /// Metadata of the pod
+    pub metadata: ObjectMeta,
+    /// End of the window the usage was collected over
+    pub timestamp: Option<Time>,
+    /// Length of the window the usage was collected over, as a duration string like `10.5s`
+    #[serde(default)]
+    pub window: String,
+    /// Resource usage of every container of the pod
+    #[serde(default)]
+    pub containers: Vec<ContainerMetrics>,
+}
+
+/// Resource usage of a container, keyed by resource name (`cpu`, `memory`)
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct ContainerMetrics {
+    /// Name of the container
+    pub name: String,
+    /// Resource usage of the container
+    #[serde(default)]
+    pub usage: BTreeMap<String, Quantity>,
+}
+
+impl Resource for NodeMetrics {
+    type DynamicType = ();
+
+    fn kind(_: &()) -> Cow<'_, str> {
+        "NodeMetrics".into()
+    }
+
+    fn group(_: &()) -> Cow<'_, str> {
+        GROUP.into()
+    }
+
+    fn version(_: &()) -> Cow<'_, str> {
+        VERSION.into()
+    }
+
+    fn plural(_: &()) -> Cow<'_, str> {
+        "nodes".into()
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
+impl Resource for PodMetrics {
+    type DynamicType = ();
+
+    fn kind(_: &()) -> Cow<'_, str> {
+        "PodMetrics".into()
+    }
+
+    fn group(_: &()) -> Cow<'_, str> {
+        GROUP.into()
+    }
+
+    fn version(_: &()) -> Cow<'_, str> {
+        VERSION.into()
+    }
+
+    fn plural(_: &()) -> Cow<'_, str> {
+        "pods".into()
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{NodeMetrics, PodMetrics};
+    use crate::api::{ObjectList, Resource};
+
+    #[test]
+    fn metrics_urls() {
+        assert_eq!(
+            NodeMetrics::url_path(&(), None),
+            "/apis/metrics.k8s.io/v1beta1/nodes"
+        );
+        assert_eq!(
+            PodMetrics::url_path(&(), Some("default")),
+            "/apis/metrics.k8s.io/v1beta1/namespaces/default/pods"
+        );
+        assert_eq!(PodMetrics::api_version(&()), "metrics.k8s.io/v1beta1");
+    }
+
+    #[test]
+    fn pod_metrics_list_deserializes() {
+        let list: ObjectList<PodMetrics> = serde_json::from_value(serde_json::json!({
+            "kind": "PodMetricsList",
+            "apiVersion": "metrics.k8s.io/v1beta1",
+            "metadata": {},
+            "items": [{
+                "metadata": { "name": "blog", "namespace": "default", "labels": { "app": "blog" } },
+                "timestamp": "2021-04-01T10:00:00Z",
+                "window": "10.062s",
+                "containers": [{ "name": "app", "usage": { "cpu": "1351126n", "memory": "24048Ki" } }]
+            }]
+        }))
+        .unwrap();
+        let blog = &list.items[0];
+        assert_eq!(blog.window, "10.062s");
+        assert_eq!(blog.containers[0].usage["memory"].0, "24048Ki");
+    }
+}
@@ -11,7 +11,8 @@ pub struct NotUsed {}
 
 pub(crate) mod params;
 pub use params::{
-    DeleteParams, ListParams, Patch, PatchParams, PostParams, Preconditions, PropagationPolicy,
+    DeleteParams, GetParams, ListParams, Patch, PatchParams, PostParams, Preconditions, PropagationPolicy,
+    ValidationDirective, VersionMatch,
 };
 mod request;
 pub use request::Request;
@@ -19,21 +20,57 @@ pub use request::Request;
 pub(crate) mod typed;
 pub use typed::Api;
 
+#[cfg(feature = "schema")] mod crd;
+#[cfg(feature = "schema")]
+#[cfg_attr(docsrs, doc(cfg(feature = "schema")))]
+pub use crd::CustomResourceDefinitionBuilder;
+
 mod dynamic;
 pub use dynamic::{DynamicObject, GroupVersionKind, GroupVersionResource};
 
 #[cfg(feature = "ws")] mod remote_command;
 #[cfg(feature = "ws")] pub use remote_command::AttachedProcess;
+#[cfg(feature = "ws")] mod portforward;
+#[cfg(feature = "ws")] pub use portforward::PortforwardStream;
 
 mod subresource;
 #[cfg(feature = "ws")]
-pub use subresource::{AttachParams, Attachable, Executable};
-pub use subresource::{EvictParams, Evictable, LogParams, Loggable, ScaleSpec, ScaleStatus};
+pub use subresource::{AttachParams, Attachable, Executable, Portforwardable};
+pub use subresource::{
+    EvictParams, Evictable, LogParams, Loggable, ScaleSpec, ScaleStatus, TokenRequestable,
+};
 
 pub(crate) mod object;
 pub use self::object::{Object, ObjectList, WatchEvent};
 
 mod metadata;
-pub use self::metadata::{ListMeta, ObjectMeta, Resource, ResourceExt, TypeMeta};
+pub use self::metadata::{ListMeta, ObjectMeta, PartialObjectMetadata, Resource, ResourceExt, TypeMeta};
+
+mod meta_builder;
+pub use meta_builder::ObjectMetaBuilder;
+
+mod strategic;
+pub use strategic::StrategicPatch;
+
+#[cfg(feature = "compact-meta")] mod compact_meta;
+#[cfg(feature = "compact-meta")]
+#[cfg_attr(docsrs, doc(cfg(feature = "compact-meta")))]
+pub use compact_meta::{CompactMeta, CompactOwner, MetaInterner};
+
+pub mod metrics;
+
+mod preserved;
+pub use preserved::Preserved;
+
+k8s_openapi::k8s_if_ge_1_19! {
+    mod conditions;
+    pub use conditions::{new_condition, Conditions, CONDITION_FALSE, CONDITION_TRUE, CONDITION_UNKNOWN};
+}
+
+mod registry;
+pub use registry::TypeRegistry;
+
+mod table;
+pub use table::{Table, TableColumnDefinition, TableRow, TableRowCondition};
 
 #[cfg(feature = "admission")] pub mod admission;
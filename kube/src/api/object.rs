@@ -1,15 +1,26 @@
 use crate::{
-    api::metadata::{ListMeta, ObjectMeta, TypeMeta},
+    api::{
+        metadata::{ListMeta, ObjectMeta, TypeMeta},
+        GroupVersionKind, Resource,
+    },
+    client::Status,
     error::ErrorResponse,
 };
 use serde::{Deserialize, Serialize};
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug};
 
 /// A raw event returned from a watch query
 ///
 /// Note that a watch query returns many of these as newline separated JSON.
 #[derive(Deserialize, Serialize, Clone)]
-#[serde(tag = "type", content = "object", rename_all = "UPPERCASE")]
+#[serde(
+    tag = "type",
+    content = "object",
+    rename_all = "UPPERCASE",
+    from = "RawWatchEvent<K>",
+    bound(deserialize = "K: Deserialize<'de>")
+)]
+#[non_exhaustive]
 pub enum WatchEvent<K> {
     /// Resource was added
     Added(K),
@@ -25,6 +36,11 @@ pub enum WatchEvent<K> {
     Bookmark(Bookmark),
     /// There was some kind of error
     Error(ErrorResponse),
+    /// There was an error, reported with a `Status` that is not a complete [`ErrorResponse`]
+    ///
+    /// The `status` and `code` of a `Status` are optional, without them the error would be lost.
+    #[serde(rename = "ERROR")]
+    ErrorStatus(Status),
 }
 
 impl<K> Debug for WatchEvent<K> {
@@ -35,6 +51,38 @@ impl<K> Debug for WatchEvent<K> {
             WatchEvent::Deleted(_) => write!(f, "Deleted event"),
             WatchEvent::Bookmark(_) => write!(f, "Bookmark event"),
             WatchEvent::Error(e) => write!(f, "Error event: {:?}", e),
+            WatchEvent::ErrorStatus(s) => write!(f, "Error status event: {:?}", s),
+        }
+    }
+}
+
+/// The wire format of [`WatchEvent`], where both error variants are `ERROR` events
+#[derive(Deserialize)]
+#[serde(tag = "type", content = "object", rename_all = "UPPERCASE")]
+enum RawWatchEvent<K> {
+    Added(K),
+    Modified(K),
+    Deleted(K),
+    Bookmark(Bookmark),
+    Error(ErrorObject),
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ErrorObject {
+    Response(ErrorResponse),
+    Status(Status),
+}
+
+impl<K> From<RawWatchEvent<K>> for WatchEvent<K> {
+    fn from(raw: RawWatchEvent<K>) -> Self {
+        match raw {
+            RawWatchEvent::Added(obj) => WatchEvent::Added(obj),
+            RawWatchEvent::Modified(obj) => WatchEvent::Modified(obj),
+            RawWatchEvent::Deleted(obj) => WatchEvent::Deleted(obj),
+            RawWatchEvent::Bookmark(bookmark) => WatchEvent::Bookmark(bookmark),
+            RawWatchEvent::Error(ErrorObject::Response(err)) => WatchEvent::Error(err),
+            RawWatchEvent::Error(ErrorObject::Status(status)) => WatchEvent::ErrorStatus(status),
         }
     }
 }
@@ -64,9 +112,10 @@ pub struct BookmarkMeta {
 
 /// A standard Kubernetes object with `.spec` and `.status`.
 ///
-/// This is a convenience struct provided for serialization/deserialization
-/// It is not useful within the library anymore, because it can not easily implement
-/// the [`k8s_openapi`] traits.
+/// This is a convenience struct provided for serialization/deserialization.
+/// It implements [`Resource`] with a [`GroupVersionKind`] as its dynamic type, so it can be used
+/// with an [`Api`](crate::Api) for spec types that can not `#[derive(CustomResource)]`,
+/// e.g. because they are defined in another crate.
 ///
 /// This is what Kubernetes maintainers tell you the world looks like.
 /// It's.. generally true.
@@ -121,6 +170,67 @@ where
     }
 }
 
+impl<P, U> Object<P, U>
+where
+    P: Clone,
+    U: Clone,
+{
+    /// A constructor for a resource only known at runtime
+    pub fn new_with(name: &str, gvk: &GroupVersionKind, spec: P) -> Self {
+        Self {
+            types: TypeMeta {
+                api_version: gvk.api_version.clone(),
+                kind: gvk.kind.clone(),
+            },
+            metadata: ObjectMeta {
+                name: Some(name.to_string()),
+                ..Default::default()
+            },
+            spec,
+            status: None,
+        }
+    }
+}
+
+impl<P, U> Resource for Object<P, U>
+where
+    P: Clone,
+    U: Clone,
+{
+    type DynamicType = GroupVersionKind;
+
+    fn group(dt: &GroupVersionKind) -> Cow<'_, str> {
+        dt.group.as_str().into()
+    }
+
+    fn version(dt: &GroupVersionKind) -> Cow<'_, str> {
+        dt.version.as_str().into()
+    }
+
+    fn kind(dt: &GroupVersionKind) -> Cow<'_, str> {
+        dt.kind.as_str().into()
+    }
+
+    fn api_version(dt: &GroupVersionKind) -> Cow<'_, str> {
+        dt.api_version.as_str().into()
+    }
+
+    fn plural(dt: &GroupVersionKind) -> Cow<'_, str> {
+        match &dt.plural {
+            Some(plural) => plural.into(),
+            None => crate::api::metadata::to_plural(&dt.kind.to_ascii_lowercase()).into(),
+        }
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        &self.metadata
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        &mut self.metadata
+    }
+}
+
 /// A generic Kubernetes object list
 ///
 /// This is used instead of a full struct for `DeploymentList`, `PodList`, etc.
@@ -215,3 +325,40 @@ impl<'a, T: Clone> IntoIterator for &'a mut ObjectList<T> {
         self.items.iter_mut()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::WatchEvent;
+    use k8s_openapi::api::core::v1::ConfigMap;
+
+    #[test]
+    fn watch_events_keep_bookmarks_and_error_statuses() {
+        let parse = |json: &str| serde_json::from_str::<WatchEvent<ConfigMap>>(json).unwrap();
+        let bookmark = parse(
+            r#"{"type":"BOOKMARK","object":{"kind":"ConfigMap","apiVersion":"v1","metadata":{"resourceVersion":"12"}}}"#,
+        );
+        assert!(matches!(bookmark, WatchEvent::Bookmark(b) if b.metadata.resource_version == "12"));
+
+        let gone = parse(
+            r#"{"type":"ERROR","object":{"kind":"Status","status":"Failure","message":"too old","reason":"Expired","code":410}}"#,
+        );
+        assert!(matches!(gone, WatchEvent::Error(e) if e.code == 410 && e.reason == "Expired"));
+
+        let partial = parse(
+            r#"{"object":{"kind":"Status","message":"internal error","details":{"retryAfterSeconds":2}},"type":"ERROR"}"#,
+        );
+        let status = match partial {
+            WatchEvent::ErrorStatus(status) => status,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(status.message, "internal error");
+        assert_eq!(status.details.map(|d| d.retry_after_seconds), Some(2));
+
+        // both error variants are sent as `ERROR` events
+        let json = serde_json::to_value(WatchEvent::<ConfigMap>::ErrorStatus(
+            serde_json::from_str("{}").unwrap(),
+        ))
+        .unwrap();
+        assert_eq!(json["type"], "ERROR");
+    }
+}
@@ -45,6 +45,14 @@ pub struct ListParams {
     ///
     /// After listing results with a limit, a continue token can be used to fetch another page of results.
     pub continue_token: Option<String>,
+
+    /// The resource version to list at, see [`ListParams::at`]
+    ///
+    /// Unset lists the most recent version, with a quorum read.
+    pub resource_version: Option<String>,
+
+    /// How `resource_version` is interpreted, see [`ListParams::matching`]
+    pub version_match: Option<VersionMatch>,
 }
 
 impl Default for ListParams {
@@ -58,11 +66,22 @@ impl Default for ListParams {
             timeout: None,
             limit: None,
             continue_token: None,
+            resource_version: None,
+            version_match: None,
         }
     }
 }
 
 impl ListParams {
+    pub(crate) fn validate_version(&self) -> Result<()> {
+        if self.version_match.is_some() && self.resource_version.is_none() {
+            return Err(Error::RequestValidation(
+                "ListParams::version_match requires a resource_version".into(),
+            ));
+        }
+        Ok(())
+    }
+
     pub(crate) fn validate(&self) -> Result<()> {
         if let Some(to) = &self.timeout {
             // https://github.com/kubernetes/kubernetes/issues/6513
@@ -134,6 +153,106 @@ impl ListParams {
         self.continue_token = Some(token.to_string());
         self
     }
+
+    /// List at a resource version
+    ///
+    /// By default, a version that is not older than `resource_version` is returned.
+    /// Use `"0"` to allow any version, which the apiserver can serve from its watch cache.
+    /// Ignored by watch calls, which take their version separately.
+    pub fn at(mut self, resource_version: &str) -> Self {
+        self.resource_version = Some(resource_version.into());
+        self
+    }
+
+    /// Set how the resource version of [`ListParams::at`] is matched
+    ///
+    /// Requires kubernetes >= 1.19.
+    pub fn matching(mut self, version_match: VersionMatch) -> Self {
+        self.version_match = Some(version_match);
+        self
+    }
+}
+
+/// How the resource version of a list call is interpreted
+///
+/// See the [kubernetes docs](https://kubernetes.io/docs/reference/using-api/api-concepts/#semantics-for-get-and-list).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VersionMatch {
+    /// Return data at least as new as the resource version
+    NotOlderThan,
+    /// Return data at exactly the resource version
+    ///
+    /// Fails with `410 Gone` if that version has been compacted away.
+    Exact,
+}
+
+impl VersionMatch {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::NotOlderThan => "NotOlderThan",
+            Self::Exact => "Exact",
+        }
+    }
+}
+
+/// Common query parameters for get calls
+///
+/// ```
+/// use kube::api::GetParams;
+/// // Allow a (possibly stale) read from the watch cache of the apiserver
+/// let gp = GetParams::any();
+/// ```
+#[derive(Default, Clone, Debug, PartialEq, Eq)]
+pub struct GetParams {
+    /// The resource version to read
+    ///
+    /// Unset reads the most recent version, with a quorum read (the default of [`Api::get`](crate::Api::get)).
+    /// `"0"` allows any version, and other versions ask for a version that is not older.
+    pub resource_version: Option<String>,
+}
+
+impl GetParams {
+    /// Read any version of the object, which the apiserver can serve from its cache
+    pub fn any() -> Self {
+        Self::at("0")
+    }
+
+    /// Read a version of the object that is not older than `resource_version`
+    pub fn at(resource_version: &str) -> Self {
+        GetParams {
+            resource_version: Some(resource_version.into()),
+        }
+    }
+
+    pub(crate) fn populate_qp(&self, qp: &mut url::form_urlencoded::Serializer<String>) {
+        if let Some(rv) = &self.resource_version {
+            qp.append_pair("resourceVersion", rv);
+        }
+    }
+}
+
+/// How the apiserver should treat unknown or duplicate fields in a request body
+///
+/// Requires kubernetes >= 1.25 (or the `ServerSideFieldValidation` feature gate on earlier versions).
+/// Older apiservers ignore the parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ValidationDirective {
+    /// Fail the request on unknown or duplicate fields
+    Strict,
+    /// Accept the request, but return a `Warning` header for each unknown or duplicate field
+    Warn,
+    /// Silently drop unknown fields, the legacy behaviour
+    Ignore,
+}
+
+impl ValidationDirective {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Strict => "Strict",
+            Self::Warn => "Warn",
+            Self::Ignore => "Ignore",
+        }
+    }
 }
 
 /// Common query parameters for put/post calls
@@ -143,6 +262,8 @@ pub struct PostParams {
     pub dry_run: bool,
     /// fieldManager is a name of the actor that is making changes
     pub field_manager: Option<String>,
+    /// How to treat unknown fields in the object, defaults to the apiserver's behaviour
+    pub field_validation: Option<ValidationDirective>,
 }
 
 impl PostParams {
@@ -158,6 +279,36 @@ impl PostParams {
         }
         Ok(())
     }
+
+    pub(crate) fn populate_qp(&self, qp: &mut url::form_urlencoded::Serializer<String>) {
+        if self.dry_run {
+            qp.append_pair("dryRun", "All");
+        }
+        if let Some(ref field_manager) = self.field_manager {
+            qp.append_pair("fieldManager", field_manager);
+        }
+        if let Some(validation) = self.field_validation {
+            qp.append_pair("fieldValidation", validation.as_str());
+        }
+    }
+
+    /// Set the field manager of the request
+    pub fn field_manager(mut self, manager: &str) -> Self {
+        self.field_manager = Some(manager.into());
+        self
+    }
+
+    /// Perform a dryRun only
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Set how the apiserver should treat unknown fields
+    pub fn field_validation(mut self, validation: ValidationDirective) -> Self {
+        self.field_validation = Some(validation);
+        self
+    }
 }
 
 /// Describes changes that should be applied to a resource
@@ -226,6 +377,14 @@ pub enum Patch<T: Serialize> {
     Strategic(T),
 }
 
+#[cfg(feature = "jsonpatch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jsonpatch")))]
+impl<T: Serialize> From<json_patch::Patch> for Patch<T> {
+    fn from(patch: json_patch::Patch) -> Self {
+        Patch::Json(patch)
+    }
+}
+
 impl<T: Serialize> Patch<T> {
     pub(crate) fn is_apply(&self) -> bool {
         matches!(self, Patch::Apply(_))
@@ -267,6 +426,8 @@ pub struct PatchParams {
     /// fieldManager is a name of the actor that is making changes. Required for [`Patch::Apply`]
     /// optional for everything else.
     pub field_manager: Option<String>,
+    /// How to treat unknown fields in the patch, defaults to the apiserver's behaviour
+    pub field_validation: Option<ValidationDirective>,
 }
 
 impl PatchParams {
@@ -288,7 +449,7 @@ impl PatchParams {
 
     pub(crate) fn populate_qp(&self, qp: &mut url::form_urlencoded::Serializer<String>) {
         if self.dry_run {
-            qp.append_pair("dryRun", "true");
+            qp.append_pair("dryRun", "All");
         }
         if self.force {
             qp.append_pair("force", "true");
@@ -296,6 +457,9 @@ impl PatchParams {
         if let Some(ref field_manager) = self.field_manager {
             qp.append_pair("fieldManager", &field_manager);
         }
+        if let Some(validation) = self.field_validation {
+            qp.append_pair("fieldValidation", validation.as_str());
+        }
     }
 
     /// Construct `PatchParams` for server-side apply
@@ -319,6 +483,12 @@ impl PatchParams {
         self.dry_run = true;
         self
     }
+
+    /// Set how the apiserver should treat unknown fields
+    pub fn field_validation(mut self, validation: ValidationDirective) -> Self {
+        self.field_validation = Some(validation);
+        self
+    }
 }
 
 /// Common query parameters for delete calls
@@ -354,8 +524,16 @@ pub struct DeleteParams {
     pub preconditions: Option<Preconditions>,
 }
 
+impl DeleteParams {
+    /// Perform a dryRun only
+    pub fn dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+}
+
 // dryRun serialization differ when used as body parameters and query strings:
-// query strings are either true/false
+// query strings use dryRun=All
 // body params allow only: missing field, or ["All"]
 // The latter is a very awkward API causing users to do to
 // dp.dry_run = vec!["All".into()];
@@ -377,7 +555,7 @@ where
 }
 #[cfg(test)]
 mod test {
-    use super::DeleteParams;
+    use super::{DeleteParams, PatchParams, PostParams, ValidationDirective};
     #[test]
     fn delete_param_serialize() {
         let mut dp = DeleteParams::default();
@@ -390,6 +568,39 @@ mod test {
         //println!("ser is: {}", ser);
         assert_eq!(ser, "{\"dryRun\":[\"All\"]}");
     }
+
+    #[test]
+    fn dry_run_builders() {
+        assert!(PostParams::default().dry_run().dry_run);
+        assert!(PatchParams::default().dry_run().dry_run);
+        assert!(DeleteParams::default().dry_run().dry_run);
+    }
+
+    #[test]
+    fn dry_run_query_params() {
+        let mut qp = url::form_urlencoded::Serializer::new(String::new());
+        PostParams::default().dry_run().populate_qp(&mut qp);
+        assert_eq!(qp.finish(), "dryRun=All");
+
+        let mut qp = url::form_urlencoded::Serializer::new(String::new());
+        PatchParams::apply("kube").dry_run().populate_qp(&mut qp);
+        assert_eq!(qp.finish(), "dryRun=All&fieldManager=kube");
+    }
+
+    #[test]
+    fn field_validation_query_params() {
+        let mut qp = url::form_urlencoded::Serializer::new(String::new());
+        PostParams::default()
+            .field_validation(ValidationDirective::Strict)
+            .populate_qp(&mut qp);
+        assert_eq!(qp.finish(), "fieldValidation=Strict");
+
+        let mut qp = url::form_urlencoded::Serializer::new(String::new());
+        PatchParams::apply("kube")
+            .field_validation(ValidationDirective::Warn)
+            .populate_qp(&mut qp);
+        assert_eq!(qp.finish(), "fieldManager=kube&fieldValidation=Warn");
+    }
 }
 
 /// Preconditions must be fulfilled before an operation (update, delete, etc.) is carried out.
@@ -0,0 +1,184 @@
+use std::{
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+};
+
+use futures::{future, FutureExt, SinkExt, StreamExt};
+use hyper::client::connect::{Connected, Connection};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream, ReadBuf};
+use tokio_tungstenite::{tungstenite as ws, WebSocketStream};
+
+use crate::executor::Executor;
+
+const MAX_BUF_SIZE: usize = 1024;
+
+const DATA_CHANNEL: u8 = 0;
+const ERROR_CHANNEL: u8 = 1;
+
+/// A connection to a port of a pod, opened with [`Api::portforward`](crate::Api::portforward)
+///
+/// Bytes written to it are sent to the port, and bytes sent by the port can be read from it.
+/// If the forwarding fails, e.g. because nothing listens on the port, reads return the error
+/// reported by the kubelet once the received data is drained.
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+pub struct PortforwardStream {
+    pipe: DuplexStream,
+    error: Arc<Mutex<Option<String>>>,
+}
+
+impl PortforwardStream {
+    pub(crate) fn new<S>(stream: WebSocketStream<S>, executor: &dyn Executor) -> Self
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
+    {
+        let (pipe, remote) = tokio::io::duplex(MAX_BUF_SIZE);
+        let error = Arc::new(Mutex::new(None));
+        executor.spawn(forward(stream, remote, error.clone()).boxed());
+        PortforwardStream { pipe, error }
+    }
+}
+
+impl AsyncRead for PortforwardStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        match Pin::new(&mut self.pipe).poll_read(cx, buf) {
+            // An EOF after a forwarding error is reported as that error
+            Poll::Ready(Ok(())) if buf.filled().len() == filled => match self.error.lock().unwrap().take() {
+                Some(error) => Poll::Ready(Err(io::Error::other(error))),
+                None => Poll::Ready(Ok(())),
+            },
+            poll => poll,
+        }
+    }
+}
+
+impl AsyncWrite for PortforwardStream {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.pipe).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.pipe).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.pipe).poll_shutdown(cx)
+    }
+}
+
+/// Lets hyper use forwarded ports as connections
+impl Connection for PortforwardStream {
+    fn connected(&self) -> Connected {
+        Connected::new()
+    }
+}
+
+// Every message is prefixed with its channel, and the first message of each channel
+// carries the forwarded port as two little endian bytes.
+// See [`kubelet/cri/streaming/portforward/websocket.go`](https://github.com/kubernetes/kubernetes/blob/master/pkg/kubelet/cri/streaming/portforward/websocket.go).
+async fn forward<S>(stream: WebSocketStream<S>, pipe: DuplexStream, error: Arc<Mutex<Option<String>>>)
+where
+    S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
+{
+    let (mut server_send, mut server_recv) = stream.split();
+    let (mut reader, mut writer) = tokio::io::split(pipe);
+
+    let upstream = async move {
+        let mut buf = vec![0; MAX_BUF_SIZE];
+        buf[0] = DATA_CHANNEL;
+        loop {
+            match reader.read(&mut buf[1..]).await {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    if server_send
+                        .send(ws::Message::Binary(buf[..=n].to_vec()))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+        }
+        let _ = server_send.close().await;
+    };
+
+    let downstream = async move {
+        let mut port_received = [false; 2];
+        while let Some(Ok(message)) = server_recv.next().await {
+            let bin = match message {
+                ws::Message::Binary(bin) if !bin.is_empty() => bin,
+                ws::Message::Close(_) => break,
+                _ => continue,
+            };
+            let channel = bin[0];
+            let mut payload = &bin[1..];
+            if let Some(received) = port_received.get_mut(usize::from(channel)) {
+                if !*received {
+                    *received = true;
+                    payload = payload.get(2..).unwrap_or_default();
+                }
+            }
+            if channel == ERROR_CHANNEL && !payload.is_empty() {
+                *error.lock().unwrap() = Some(String::from_utf8_lossy(payload).into_owned());
+                break;
+            }
+            if channel == DATA_CHANNEL && writer.write_all(payload).await.is_err() {
+                break;
+            }
+        }
+        let _ = writer.shutdown().await;
+    };
+
+    future::join(upstream, downstream).await;
+}
+
+#[cfg(test)]
+mod test {
+    use super::PortforwardStream;
+    use crate::executor::TokioExecutor;
+    use futures::{SinkExt, StreamExt};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_tungstenite::{
+        tungstenite::{protocol::Role, Message},
+        WebSocketStream,
+    };
+
+    #[tokio::test]
+    async fn forwards_data_channel() {
+        let (client, server) = tokio::io::duplex(1024);
+        let client = WebSocketStream::from_raw_socket(client, Role::Client, None).await;
+        let mut server = WebSocketStream::from_raw_socket(server, Role::Server, None).await;
+        let mut stream = PortforwardStream::new(client, &TokioExecutor::new());
+
+        for channel in &[0, 1] {
+            server
+                .send(Message::Binary(vec![*channel, 0x50, 0]))
+                .await
+                .unwrap();
+        }
+        server.send(Message::Binary(b"\x00hello".to_vec())).await.unwrap();
+        let mut buf = [0; 5];
+        stream.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+
+        stream.write_all(b"ping").await.unwrap();
+        assert_eq!(
+            server.next().await.unwrap().unwrap(),
+            Message::Binary(b"\x00ping".to_vec())
+        );
+
+        server
+            .send(Message::Binary(b"\x01connection refused".to_vec()))
+            .await
+            .unwrap();
+        let err = stream.read(&mut buf).await.unwrap_err();
+        assert_eq!(err.to_string(), "connection refused");
+    }
+}
@@ -0,0 +1,257 @@
+use crate::api::{ObjectMeta, Resource};
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
+use std::{
+    borrow::Cow,
+    ops::{Deref, DerefMut},
+};
+
+/// A typed object that keeps the fields its type doesn't know about
+///
+/// Typed custom resources silently drop fields that their struct doesn't declare. When the struct
+/// lags behind the schema of the CRD (because another version of the operator, or a user, set newer
+/// fields), replacing the object would then wipe those fields. `Preserved` remembers the fields that
+/// did not survive deserializing into `K`, and adds them back when serializing.
+///
+/// Only fields of objects are kept. Arrays that `K` knows are serialized as they are, since their elements
+/// may have been reordered, added or removed, and unknown fields of an element could end up on another one.
+///
+/// This applies to `k8s_openapi` types as much as to custom resources.
+/// Use it in place of `K` with [`Api`](crate::Api), it dereferences to `K`:
+///
+/// ```no_run
+/// use kube::{api::{Api, Preserved, PostParams}, Client};
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let deploys: Api<Preserved<Deployment>> = Api::namespaced(client, "default");
+/// let mut blog = deploys.get("blog").await?;
+/// blog.spec.as_mut().unwrap().replicas = Some(3);
+/// // fields newer than the k8s-openapi version in use are kept
+/// deploys.replace("blog", &PostParams::default(), &blog).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Preserved<K> {
+    inner: K,
+    unknown: Value,
+}
+
+impl<K> Preserved<K> {
+    /// Wrap an object, without any unknown fields
+    pub fn new(inner: K) -> Self {
+        Self {
+            inner,
+            unknown: Value::Null,
+        }
+    }
+
+    /// The typed object
+    pub fn into_inner(self) -> K {
+        self.inner
+    }
+
+    /// The fields that `K` did not keep, as a sparse json object (`null` when there are none)
+    pub fn unknown_fields(&self) -> &Value {
+        &self.unknown
+    }
+}
+
+impl<K> From<K> for Preserved<K> {
+    fn from(inner: K) -> Self {
+        Self::new(inner)
+    }
+}
+
+impl<K> Deref for Preserved<K> {
+    type Target = K;
+
+    fn deref(&self) -> &K {
+        &self.inner
+    }
+}
+
+impl<K> DerefMut for Preserved<K> {
+    fn deref_mut(&mut self) -> &mut K {
+        &mut self.inner
+    }
+}
+
+impl<'de, K: DeserializeOwned + Serialize> Deserialize<'de> for Preserved<K> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = Value::deserialize(deserializer)?;
+        let inner: K = serde_json::from_value(raw.clone()).map_err(serde::de::Error::custom)?;
+        let known = serde_json::to_value(&inner).map_err(serde::de::Error::custom)?;
+        Ok(Self {
+            unknown: unknown_fields(&raw, &known),
+            inner,
+        })
+    }
+}
+
+impl<K: Serialize> Serialize for Preserved<K> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut value = serde_json::to_value(&self.inner).map_err(serde::ser::Error::custom)?;
+        add_unknown_fields(&mut value, &self.unknown);
+        value.serialize(serializer)
+    }
+}
+
+impl<K: Resource> Resource for Preserved<K> {
+    type DynamicType = K::DynamicType;
+
+    fn kind(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::kind(dt)
+    }
+
+    fn group(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::group(dt)
+    }
+
+    fn version(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::version(dt)
+    }
+
+    fn api_version(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::api_version(dt)
+    }
+
+    fn plural(dt: &Self::DynamicType) -> Cow<'_, str> {
+        K::plural(dt)
+    }
+
+    fn url_path(dt: &Self::DynamicType, namespace: Option<&str>) -> String {
+        K::url_path(dt, namespace)
+    }
+
+    fn meta(&self) -> &ObjectMeta {
+        self.inner.meta()
+    }
+
+    fn meta_mut(&mut self) -> &mut ObjectMeta {
+        self.inner.meta_mut()
+    }
+}
+
+/// The parts of `raw` that are missing from `known`
+///
+/// Objects are compared by key. Arrays known to `K` are left out, see [`Preserved`].
+fn unknown_fields(raw: &Value, known: &Value) -> Value {
+    match (raw, known) {
+        (Value::Object(raw), Value::Object(known)) => {
+            let mut unknown = serde_json::Map::new();
+            for (key, value) in raw {
+                match known.get(key) {
+                    None if !value.is_null() => {
+                        unknown.insert(key.clone(), value.clone());
+                    }
+                    Some(known) => {
+                        let nested = unknown_fields(value, known);
+                        if !nested.is_null() {
+                            unknown.insert(key.clone(), nested);
+                        }
+                    }
+                    None => {}
+                }
+            }
+            if unknown.is_empty() {
+                Value::Null
+            } else {
+                Value::Object(unknown)
+            }
+        }
+        _ => Value::Null,
+    }
+}
+
+/// Add the `unknown` fields to `value`, without overwriting anything `value` has
+fn add_unknown_fields(value: &mut Value, unknown: &Value) {
+    if let (Value::Object(value), Value::Object(unknown)) = (value, unknown) {
+        for (key, field) in unknown {
+            match value.get_mut(key) {
+                Some(existing) => add_unknown_fields(existing, field),
+                None => {
+                    value.insert(key.clone(), field.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Preserved;
+    use crate::api::ObjectMeta;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    struct Spec {
+        replicas: i32,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        paused: Option<bool>,
+        #[serde(default)]
+        ports: Vec<Port>,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    struct Port {
+        port: u16,
+    }
+
+    #[derive(Clone, Debug, Deserialize, Serialize)]
+    struct Foo {
+        metadata: ObjectMeta,
+        spec: Spec,
+    }
+
+    #[test]
+    fn unknown_fields_roundtrip() {
+        let raw = serde_json::json!({
+            "apiVersion": "clux.dev/v1",
+            "kind": "Foo",
+            "metadata": { "name": "blog" },
+            "spec": {
+                "replicas": 1,
+                "paused": true,
+                "strategy": { "type": "Recreate" },
+                "ports": [{ "port": 80, "protocol": "TCP" }]
+            }
+        });
+        let mut foo: Preserved<Foo> = serde_json::from_value(raw).unwrap();
+        assert_eq!(foo.metadata.name.as_deref(), Some("blog"));
+        foo.spec.replicas = 3;
+        // known fields can still be removed
+        foo.spec.paused = None;
+        let json = serde_json::to_value(&foo).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "apiVersion": "clux.dev/v1",
+                "kind": "Foo",
+                "metadata": { "name": "blog" },
+                "spec": {
+                    "replicas": 3,
+                    "strategy": { "type": "Recreate" },
+                    "ports": [{ "port": 80 }]
+                }
+            })
+        );
+        assert!(Preserved::new(foo.into_inner()).unknown_fields().is_null());
+    }
+
+    #[test]
+    fn unknown_fields_do_not_move_between_array_elements() {
+        let raw = serde_json::json!({
+            "metadata": { "name": "blog" },
+            "spec": {
+                "replicas": 1,
+                "ports": [{ "port": 80, "protocol": "TCP" }, { "port": 53, "protocol": "UDP" }]
+            }
+        });
+        let mut foo: Preserved<Foo> = serde_json::from_value(raw).unwrap();
+        foo.spec.ports.remove(0);
+        foo.spec.ports.push(Port { port: 443 });
+        let json = serde_json::to_value(&foo).unwrap();
+        assert_eq!(json["spec"]["ports"], serde_json::json!([{ "port": 53 }, { "port": 443 }]));
+    }
+}
@@ -0,0 +1,202 @@
+use crate::{api::DynamicObject, client::decode, Error, Resource, Result};
+use serde::de::DeserializeOwned;
+use std::{any::Any, collections::HashMap, fmt};
+
+type Decoder<T> = Box<dyn Fn(&[u8]) -> Result<T> + Send + Sync>;
+
+/// Recovers typed objects from [`DynamicObject`]s, by their `apiVersion` and `kind`
+///
+/// Register the types a manifest processor or discovery based tool understands, and deserialize
+/// objects of mixed kinds into them. By default objects are returned as a `Box<dyn Any + Send>`:
+///
+/// ```
+/// use kube::api::{DynamicObject, TypeRegistry};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// let registry = TypeRegistry::new().register::<ConfigMap>();
+/// let obj: DynamicObject = serde_json::from_value(serde_json::json!({
+///     "apiVersion": "v1",
+///     "kind": "ConfigMap",
+///     "metadata": { "name": "cfg" },
+///     "data": { "key": "value" }
+/// })).unwrap();
+/// let typed = registry.deserialize(obj).unwrap();
+/// let cm = typed.downcast::<ConfigMap>().unwrap();
+/// assert_eq!(cm.data.unwrap()["key"], "value");
+/// ```
+///
+/// Use [`TypeRegistry::register_with`] to dispatch into an enum instead:
+///
+/// ```
+/// use kube::api::TypeRegistry;
+/// use k8s_openapi::api::{apps::v1::Deployment, core::v1::ConfigMap};
+/// enum Known {
+///     ConfigMap(ConfigMap),
+///     Deployment(Deployment),
+/// }
+/// let registry = TypeRegistry::<Known>::new()
+///     .register_with(Known::ConfigMap)
+///     .register_with(Known::Deployment);
+/// ```
+pub struct TypeRegistry<T = Box<dyn Any + Send>> {
+    decoders: HashMap<(String, String), Decoder<T>>,
+}
+
+impl<T> TypeRegistry<T> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        TypeRegistry {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Register a k8s-openapi or derived type, converting deserialized objects with `wrap`
+    #[must_use]
+    pub fn register_with<K>(self, wrap: impl Fn(K) -> T + Send + Sync + 'static) -> Self
+    where
+        K: Resource<DynamicType = ()> + DeserializeOwned,
+    {
+        let (api_version, kind) = (K::api_version(&()), K::kind(&()));
+        self.register_kind_with(&api_version, &kind, wrap)
+    }
+
+    /// Register a type for an explicit `apiVersion` and `kind`, converting deserialized objects with `wrap`
+    ///
+    /// Use this for types that do not implement [`Resource`] statically. Registering the same
+    /// `apiVersion` and `kind` again replaces the previous type.
+    #[must_use]
+    pub fn register_kind_with<K>(
+        mut self,
+        api_version: &str,
+        kind: &str,
+        wrap: impl Fn(K) -> T + Send + Sync + 'static,
+    ) -> Self
+    where
+        K: DeserializeOwned,
+    {
+        self.decoders.insert(
+            (api_version.to_string(), kind.to_string()),
+            Box::new(move |data| {
+                // mismatched objects are expected by callers that try several types, so don't warn
+                let obj = serde_json::from_slice(data).map_err(|e| {
+                    let err = decode::with_context(data, e);
+                    tracing::debug!("{}", err);
+                    err
+                })?;
+                Ok(wrap(obj))
+            }),
+        );
+        self
+    }
+
+    /// Whether a type is registered for `apiVersion` and `kind`
+    pub fn contains(&self, api_version: &str, kind: &str) -> bool {
+        self.decoders
+            .contains_key(&(api_version.to_string(), kind.to_string()))
+    }
+
+    /// Deserialize an object into its registered type
+    ///
+    /// Returns [`Error::DynamicType`] if the object has no type information, or no type has been
+    /// registered for it, and [`Error::Deserialize`] if the object does not match its type.
+    pub fn deserialize(&self, obj: DynamicObject) -> Result<T> {
+        let key = match &obj.types {
+            Some(types) => (types.api_version.clone(), types.kind.clone()),
+            None => return Err(Error::DynamicType("object is missing apiVersion and kind".into())),
+        };
+        let decoder = self
+            .decoders
+            .get(&key)
+            .ok_or_else(|| Error::DynamicType(format!("no type registered for {} {}", key.0, key.1)))?;
+        decoder(&serde_json::to_vec(&obj)?)
+    }
+}
+
+impl TypeRegistry {
+    /// Register a k8s-openapi or derived type, returned as a `Box<dyn Any + Send>`
+    #[must_use]
+    pub fn register<K>(self) -> Self
+    where
+        K: Resource<DynamicType = ()> + DeserializeOwned + Send + 'static,
+    {
+        self.register_with(|obj: K| Box::new(obj) as Box<dyn Any + Send>)
+    }
+}
+
+impl<T> Default for TypeRegistry<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for TypeRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TypeRegistry")
+            .field("types", &self.decoders.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TypeRegistry;
+    use crate::{api::DynamicObject, Error};
+    use k8s_openapi::api::{apps::v1::Deployment, core::v1::ConfigMap};
+
+    #[derive(Debug)]
+    #[allow(clippy::large_enum_variant)]
+    enum Known {
+        ConfigMap(ConfigMap),
+        Deployment(Deployment),
+    }
+
+    fn object(value: serde_json::Value) -> DynamicObject {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn dispatches_by_type() {
+        let registry = TypeRegistry::<Known>::new()
+            .register_with(Known::ConfigMap)
+            .register_with(Known::Deployment);
+        assert!(registry.contains("apps/v1", "Deployment"));
+
+        let deploy = object(serde_json::json!({
+            "apiVersion": "apps/v1",
+            "kind": "Deployment",
+            "metadata": { "name": "blog" },
+            "spec": { "replicas": 2, "selector": {}, "template": {} }
+        }));
+        match registry.deserialize(deploy).unwrap() {
+            Known::Deployment(d) => assert_eq!(d.spec.unwrap().replicas, Some(2)),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let cm = object(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": { "name": "cfg" },
+            "data": { "key": "value" }
+        }));
+        match registry.deserialize(cm).unwrap() {
+            Known::ConfigMap(cm) => assert_eq!(cm.data.unwrap()["key"], "value"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let unknown = object(serde_json::json!({ "apiVersion": "v1", "kind": "Pod", "metadata": {} }));
+        assert!(matches!(
+            registry.deserialize(unknown),
+            Err(Error::DynamicType(_))
+        ));
+
+        let invalid = object(serde_json::json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": { "name": "cfg" },
+            "data": { "key": 1 }
+        }));
+        assert!(matches!(
+            registry.deserialize(invalid),
+            Err(Error::Deserialize(_))
+        ));
+    }
+}
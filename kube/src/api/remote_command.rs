@@ -12,12 +12,13 @@ use futures::{
         select,
         Either::{Left, Right},
     },
-    SinkExt, StreamExt,
+    FutureExt, SinkExt, StreamExt,
 };
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, DuplexStream};
 use tokio_tungstenite::{tungstenite as ws, WebSocketStream};
 
 use super::AttachParams;
+use crate::executor::Executor;
 
 // Internal state of an attached process
 struct AttachedProcessState {
@@ -48,7 +49,7 @@ pub struct AttachedProcess {
 }
 
 impl AttachedProcess {
-    pub(crate) fn new<S>(stream: WebSocketStream<S>, ap: &AttachParams) -> Self
+    pub(crate) fn new<S>(stream: WebSocketStream<S>, ap: &AttachParams, executor: &dyn Executor) -> Self
     where
         S: AsyncRead + AsyncWrite + Unpin + Sized + Send + 'static,
     {
@@ -77,16 +78,19 @@ impl AttachedProcess {
             stderr_reader,
         }));
         let shared_state = state.clone();
-        tokio::spawn(async move {
-            let status = start_message_loop(stream, stdin_reader, stdout_writer, stderr_writer).await;
+        executor.spawn(
+            async move {
+                let status = start_message_loop(stream, stdin_reader, stdout_writer, stderr_writer).await;
 
-            let mut shared = shared_state.lock().unwrap();
-            shared.finished = true;
-            shared.status = status;
-            if let Some(waker) = shared.waker.take() {
-                waker.wake()
+                let mut shared = shared_state.lock().unwrap();
+                shared.finished = true;
+                shared.status = status;
+                if let Some(waker) = shared.waker.take() {
+                    waker.wake()
+                }
             }
-        });
+            .boxed(),
+        );
 
         AttachedProcess {
             has_stdin: ap.stdin,
@@ -1,6 +1,12 @@
-use super::params::{DeleteParams, ListParams, Patch, PatchParams, PostParams};
+use super::params::{DeleteParams, GetParams, ListParams, Patch, PatchParams, PostParams};
 use crate::{Error, Result};
 
+/// Accept header asking for a `meta.k8s.io/v1` `PartialObjectMetadataList`
+///
+/// Falls back to plain json for (aggregated) apiservers that do not support it.
+pub(crate) const ACCEPT_METADATA_LIST: &str =
+    "application/json;as=PartialObjectMetadataList;v=v1;g=meta.k8s.io,application/json";
+
 /// A Kubernetes request builder
 ///
 /// Takes a base_path and supplies constructors for common operations
@@ -26,6 +32,7 @@ impl Request {
 impl Request {
     /// List a collection of a resource
     pub fn list(&self, lp: &ListParams) -> Result<http::Request<Vec<u8>>> {
+        lp.validate_version()?;
         let target = format!("{}?", self.url_path);
         let mut qp = url::form_urlencoded::Serializer::new(target);
 
@@ -41,12 +48,28 @@ impl Request {
         if let Some(continue_token) = &lp.continue_token {
             qp.append_pair("continue", continue_token);
         }
+        if let Some(rv) = &lp.resource_version {
+            qp.append_pair("resourceVersion", rv);
+        }
+        if let Some(version_match) = &lp.version_match {
+            qp.append_pair("resourceVersionMatch", version_match.as_str());
+        }
 
         let urlstr = qp.finish();
         let req = http::Request::get(urlstr);
         req.body(vec![]).map_err(Error::HttpError)
     }
 
+    /// List a collection of a resource, asking only for the metadata of each item
+    pub fn list_metadata(&self, lp: &ListParams) -> Result<http::Request<Vec<u8>>> {
+        let mut req = self.list(lp)?;
+        req.headers_mut().insert(
+            http::header::ACCEPT,
+            http::HeaderValue::from_static(ACCEPT_METADATA_LIST),
+        );
+        Ok(req)
+    }
+
     /// Watch a resource at a given version
     pub fn watch(&self, lp: &ListParams, ver: &str) -> Result<http::Request<Vec<u8>>> {
         let target = format!("{}?", self.url_path);
@@ -85,8 +108,17 @@ impl Request {
 
     /// Get a single instance
     pub fn get(&self, name: &str) -> Result<http::Request<Vec<u8>>> {
-        let target = format!("{}/{}", self.url_path, name);
+        self.get_with(name, &GetParams::default())
+    }
+
+    /// Get a single instance, at a resource version
+    pub fn get_with(&self, name: &str, gp: &GetParams) -> Result<http::Request<Vec<u8>>> {
+        let target = match gp.resource_version {
+            Some(_) => format!("{}/{}?", self.url_path, name),
+            None => format!("{}/{}", self.url_path, name),
+        };
         let mut qp = url::form_urlencoded::Serializer::new(target);
+        gp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::get(urlstr);
         req.body(vec![]).map_err(Error::HttpError)
@@ -97,9 +129,7 @@ impl Request {
         pp.validate()?;
         let target = format!("{}?", self.url_path);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::post(urlstr);
         req.body(data).map_err(Error::HttpError)
@@ -157,11 +187,10 @@ impl Request {
     ///
     /// Requires `metadata.resourceVersion` set in data
     pub fn replace(&self, name: &str, pp: &PostParams, data: Vec<u8>) -> Result<http::Request<Vec<u8>>> {
+        pp.validate()?;
         let target = format!("{}/{}?", self.url_path, name);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::put(urlstr);
         req.body(data).map_err(Error::HttpError)
@@ -179,6 +208,25 @@ impl Request {
         req.body(vec![]).map_err(Error::HttpError)
     }
 
+    /// Create an instance of the subresource
+    ///
+    /// Used by action-like subresources such as `token`, `eviction`, or `binding`.
+    pub fn create_subresource(
+        &self,
+        subresource_name: &str,
+        name: &str,
+        pp: &PostParams,
+        data: Vec<u8>,
+    ) -> Result<http::Request<Vec<u8>>> {
+        pp.validate()?;
+        let target = format!("{}/{}/{}?", self.url_path, name, subresource_name);
+        let mut qp = url::form_urlencoded::Serializer::new(target);
+        pp.populate_qp(&mut qp);
+        let urlstr = qp.finish();
+        let req = http::Request::post(urlstr);
+        req.body(data).map_err(Error::HttpError)
+    }
+
     /// Patch an instance of the subresource
     pub fn patch_subresource<P: serde::Serialize>(
         &self,
@@ -208,11 +256,10 @@ impl Request {
         pp: &PostParams,
         data: Vec<u8>,
     ) -> Result<http::Request<Vec<u8>>> {
+        pp.validate()?;
         let target = format!("{}/{}/{}?", self.url_path, name, subresource_name);
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         let req = http::Request::put(urlstr);
         req.body(data).map_err(Error::HttpError)
@@ -244,6 +291,34 @@ mod test {
     // NB: stable requires >= 1.17
     use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1 as apiextsv1;
 
+    #[test]
+    fn list_metadata_accept() {
+        let url = corev1::Pod::url_path(&(), Some("ns"));
+        let lp = ListParams::default().labels("app=blog");
+        let req = Request::new(url).list_metadata(&lp).unwrap();
+        assert_eq!(req.uri(), "/api/v1/namespaces/ns/pods?&labelSelector=app%3Dblog");
+        assert_eq!(req.headers().get("Accept").unwrap(), super::ACCEPT_METADATA_LIST);
+    }
+
+    #[test]
+    fn get_and_list_at_resource_version() {
+        use crate::api::{GetParams, ListParams, VersionMatch};
+        let req = Request::new(corev1::Pod::url_path(&(), Some("ns")));
+        assert_eq!(req.get("blog").unwrap().uri(), "/api/v1/namespaces/ns/pods/blog");
+        assert_eq!(
+            req.get_with("blog", &GetParams::any()).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods/blog?&resourceVersion=0"
+        );
+        let lp = ListParams::default().at("42").matching(VersionMatch::Exact);
+        assert_eq!(
+            req.list(&lp).unwrap().uri(),
+            "/api/v1/namespaces/ns/pods?&resourceVersion=42&resourceVersionMatch=Exact"
+        );
+        assert!(req
+            .list(&ListParams::default().matching(VersionMatch::NotOlderThan))
+            .is_err());
+    }
+
     // TODO: fixturize these tests
     #[test]
     fn api_url_secret() {
@@ -372,6 +447,47 @@ mod test {
         assert_eq!(req.uri(), "/apis/apps/v1/daemonsets/myds?&dryRun=All");
     }
 
+    #[test]
+    fn patch_dry_run_path() {
+        let url = appsv1::DaemonSet::url_path(&(), None);
+        let pp = PatchParams::apply("kube").dry_run();
+        let req = Request::new(url).patch("myds", &pp, &Patch::Apply(())).unwrap();
+        assert_eq!(
+            req.uri(),
+            "/apis/apps/v1/daemonsets/myds?&dryRun=All&fieldManager=kube"
+        );
+    }
+
+    #[test]
+    fn create_dry_run_path() {
+        let url = corev1::ConfigMap::url_path(&(), Some("ns"));
+        let pp = PostParams::default().dry_run().field_manager("kube");
+        let req = Request::new(url).create(&pp, vec![]).unwrap();
+        assert_eq!(
+            req.uri(),
+            "/api/v1/namespaces/ns/configmaps?&dryRun=All&fieldManager=kube"
+        );
+    }
+
+    #[test]
+    fn delete_dry_run_body() {
+        let url = corev1::ConfigMap::url_path(&(), Some("ns"));
+        let dp = DeleteParams::default().dry_run();
+        let req = Request::new(url).delete("cm", &dp).unwrap();
+        assert_eq!(req.body(), br#"{"dryRun":["All"]}"#);
+    }
+
+    #[test]
+    fn create_subresource_path() {
+        let url = corev1::ServiceAccount::url_path(&(), Some("ns"));
+        let pp = PostParams::default();
+        let req = Request::new(url)
+            .create_subresource("token", "sa", &pp, vec![])
+            .unwrap();
+        assert_eq!(req.uri(), "/api/v1/namespaces/ns/serviceaccounts/sa/token?");
+        assert_eq!(req.method(), "POST");
+    }
+
     #[test]
     fn delete_path() {
         let url = appsv1::ReplicaSet::url_path(&(), Some("ns"));
@@ -0,0 +1,279 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+use crate::{api::Patch, Error, Result};
+
+/// The lists of core types that strategic merge patches merge by a key, with the key
+///
+/// Paths are matched by their last segments, `*` matches any list index.
+/// These mirror the `patchMergeKey` tags of the upstream types.
+const MERGE_KEYS: &[(&[&str], &str)] = &[
+    (&["containers"], "name"),
+    (&["initContainers"], "name"),
+    (&["ephemeralContainers"], "name"),
+    (&["containers", "*", "env"], "name"),
+    (&["initContainers", "*", "env"], "name"),
+    (&["containers", "*", "ports"], "containerPort"),
+    (&["initContainers", "*", "ports"], "containerPort"),
+    (&["volumeMounts"], "mountPath"),
+    (&["volumeDevices"], "devicePath"),
+    (&["volumes"], "name"),
+    (&["imagePullSecrets"], "name"),
+    (&["hostAliases"], "ip"),
+    (&["readinessGates"], "conditionType"),
+    (&["topologySpreadConstraints"], "topologyKey"),
+    (&["spec", "ports"], "port"),
+    (&["ownerReferences"], "uid"),
+    (&["conditions"], "type"),
+];
+
+fn merge_key(path: &[String]) -> Option<&'static str> {
+    MERGE_KEYS.iter().find_map(|(suffix, key)| {
+        let matches = path.len() >= suffix.len()
+            && path[path.len() - suffix.len()..]
+                .iter()
+                .zip(suffix.iter())
+                .all(|(segment, expected)| {
+                    *expected == segment || (*expected == "*" && segment.parse::<usize>().is_ok())
+                });
+        if matches {
+            Some(*key)
+        } else {
+            None
+        }
+    })
+}
+
+/// A strategic merge patch for core types, built from a partially filled object
+///
+/// Strategic merge patches merge the lists of core types by a key instead of replacing them,
+/// so a patch with a single container only changes the container of that name. Building one from
+/// a partial `k8s_openapi` object is easy to get subtly wrong: required fields serialize as empty
+/// values, and list items without their merge key are rejected by the apiserver.
+///
+/// `from_partial` drops the empty objects and merge keyed lists that unset required fields
+/// serialize to, and checks that every item of a merge keyed list has a non-empty key. Directives to
+/// remove list items and fields can be added afterwards.
+///
+/// ```
+/// use kube::api::{Patch, StrategicPatch};
+/// use k8s_openapi::api::{apps::v1::{Deployment, DeploymentSpec}, core::v1::{Container, PodSpec, PodTemplateSpec}};
+/// let partial = Deployment {
+///     spec: Some(DeploymentSpec {
+///         template: PodTemplateSpec {
+///             spec: Some(PodSpec {
+///                 containers: vec![Container {
+///                     name: "app".into(),
+///                     image: Some("app:1.2".into()),
+///                     ..Container::default()
+///                 }],
+///                 ..PodSpec::default()
+///             }),
+///             ..PodTemplateSpec::default()
+///         },
+///         ..DeploymentSpec::default()
+///     }),
+///     ..Deployment::default()
+/// };
+/// let patch = StrategicPatch::from_partial(&partial)?
+///     .delete_item("/spec/template/spec/containers", "sidecar")?
+///     .into_patch();
+/// # Ok::<(), kube::Error>(())
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategicPatch {
+    value: Value,
+}
+
+impl StrategicPatch {
+    /// Build a patch from the set fields of `partial`
+    ///
+    /// Fails if an item of a merge keyed list, like a container, does not have its key.
+    pub fn from_partial<T: Serialize>(partial: &T) -> Result<Self> {
+        let mut value = serde_json::to_value(partial)?;
+        prune(&mut value, &mut vec![])?;
+        if value.is_null() {
+            value = Value::Object(Map::new());
+        }
+        Ok(Self { value })
+    }
+
+    /// Remove the item whose merge key is `key` from the list at `list`, a JSON pointer into the object
+    ///
+    /// For example the container `sidecar` from `/spec/template/spec/containers`.
+    /// Fails if the list is not merged by a key.
+    pub fn delete_item(mut self, list: &str, key: &str) -> Result<Self> {
+        let path = segments(list);
+        let merge_key = merge_key(&path)
+            .ok_or_else(|| Error::RequestValidation(format!("{} is not a list merged by a key", list)))?;
+        let mut directive = Map::new();
+        directive.insert(merge_key.to_string(), Value::String(key.to_string()));
+        directive.insert("$patch".to_string(), Value::String("delete".to_string()));
+        let items = self.entry(&path);
+        if !items.is_array() {
+            *items = Value::Array(vec![]);
+        }
+        if let Value::Array(items) = items {
+            items.retain(|item| item.get(merge_key) != Some(&Value::String(key.to_string())));
+            items.push(Value::Object(directive));
+        }
+        Ok(self)
+    }
+
+    /// Remove the field at `field`, a JSON pointer into the object, like `/metadata/labels/app`
+    pub fn remove_field(mut self, field: &str) -> Self {
+        *self.entry(&segments(field)) = Value::Null;
+        self
+    }
+
+    /// The patch, to pass to [`Api::patch`](crate::Api::patch)
+    pub fn into_patch(self) -> Patch<Value> {
+        Patch::Strategic(self.value)
+    }
+
+    /// The patch as json
+    pub fn as_value(&self) -> &Value {
+        &self.value
+    }
+
+    /// The value at `path`, creating objects on the way
+    fn entry(&mut self, path: &[String]) -> &mut Value {
+        let mut value = &mut self.value;
+        for segment in path {
+            if !value.is_object() {
+                *value = Value::Object(Map::new());
+            }
+            value = match value {
+                Value::Object(map) => map.entry(segment.as_str()).or_insert(Value::Null),
+                _ => unreachable!("replaced by an object above"),
+            };
+        }
+        value
+    }
+}
+
+/// The unescaped segments of a JSON pointer
+fn segments(pointer: &str) -> Vec<String> {
+    pointer
+        .split('/')
+        .skip(1)
+        .map(|segment| segment.replace("~1", "/").replace("~0", "~"))
+        .collect()
+}
+
+/// Drop nulls and empty objects, and empty merge keyed lists, which are no-ops in a strategic merge patch
+///
+/// Leaves `Value::Null` in place of a value that was dropped entirely.
+fn prune(value: &mut Value, path: &mut Vec<String>) -> Result<()> {
+    match value {
+        Value::Object(map) => {
+            for (key, field) in map.iter_mut() {
+                path.push(key.clone());
+                prune(field, path)?;
+                path.pop();
+            }
+            map.retain(|_, field| !field.is_null());
+            if map.is_empty() {
+                *value = Value::Null;
+            }
+        }
+        Value::Array(items) => {
+            let key = merge_key(path);
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                prune(item, path)?;
+                path.pop();
+                if let Some(key) = key {
+                    if matches!(item.get(key), None | Some(Value::Null)) || item[key] == "" {
+                        return Err(Error::RequestValidation(format!(
+                            "item {} of /{} has no merge key {}",
+                            i,
+                            path.join("/"),
+                            key
+                        )));
+                    }
+                }
+            }
+            if key.is_some() && items.is_empty() {
+                *value = Value::Null;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::StrategicPatch;
+    use k8s_openapi::api::{
+        apps::v1::{Deployment, DeploymentSpec},
+        core::v1::{Container, EnvVar, PodSpec, PodTemplateSpec},
+    };
+    use serde_json::json;
+
+    fn deployment(containers: Vec<Container>) -> Deployment {
+        Deployment {
+            spec: Some(DeploymentSpec {
+                template: PodTemplateSpec {
+                    spec: Some(PodSpec {
+                        containers,
+                        ..PodSpec::default()
+                    }),
+                    ..PodTemplateSpec::default()
+                },
+                ..DeploymentSpec::default()
+            }),
+            ..Deployment::default()
+        }
+    }
+
+    #[test]
+    fn partial_objects_only_keep_the_set_fields() {
+        let partial = deployment(vec![Container {
+            name: "app".into(),
+            image: Some("app:1.2".into()),
+            env: Some(vec![EnvVar {
+                name: "LOG".into(),
+                value: Some("debug".into()),
+                ..EnvVar::default()
+            }]),
+            ..Container::default()
+        }]);
+        let patch = StrategicPatch::from_partial(&partial)
+            .unwrap()
+            .delete_item("/spec/template/spec/containers", "sidecar")
+            .unwrap()
+            .remove_field("/metadata/labels/app.kubernetes.io~1version");
+        assert_eq!(
+            patch.as_value(),
+            &json!({
+                "apiVersion": "apps/v1",
+                "kind": "Deployment",
+                "metadata": { "labels": { "app.kubernetes.io/version": null } },
+                "spec": { "template": { "spec": { "containers": [
+                    { "name": "app", "image": "app:1.2", "env": [{ "name": "LOG", "value": "debug" }] },
+                    { "name": "sidecar", "$patch": "delete" },
+                ] } } }
+            })
+        );
+    }
+
+    #[test]
+    fn items_need_their_merge_key() {
+        let partial = deployment(vec![Container {
+            image: Some("app:1.2".into()),
+            ..Container::default()
+        }]);
+        // `name` is required, so it serializes as an empty string rather than being left out
+        assert!(StrategicPatch::from_partial(&partial).is_err());
+        assert!(StrategicPatch::from_partial(&json!({
+            "spec": { "containers": [{ "image": "app:1.2" }] }
+        }))
+        .is_err());
+        assert!(StrategicPatch::from_partial(&deployment(vec![]))
+            .unwrap()
+            .delete_item("/spec/template/spec/nodeSelector", "a")
+            .is_err());
+    }
+}
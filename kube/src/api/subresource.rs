@@ -10,8 +10,10 @@ use crate::{
     Error, Result,
 };
 
+use k8s_openapi::api::authentication::v1::TokenRequest;
 pub use k8s_openapi::api::autoscaling::v1::{Scale, ScaleSpec, ScaleStatus};
 
+#[cfg(feature = "ws")] use crate::api::portforward::PortforwardStream;
 #[cfg(feature = "ws")] use crate::api::remote_command::AttachedProcess;
 
 /// Methods for [scale subresource](https://kubernetes.io/docs/tasks/access-kubernetes-api/custom-resources/custom-resource-definitions/#scale-subresource).
@@ -34,20 +36,104 @@ where
         pp: &PatchParams,
         patch: &Patch<P>,
     ) -> Result<Scale> {
-        let req = self.request.patch_subresource("scale", name, &pp, patch)?;
+        let req =
+            self.request
+                .patch_subresource("scale", name, &self.client.patch_params(pp, patch), patch)?;
         self.client.request::<Scale>(req).await
     }
 
     /// Replace the scale subresource
     #[instrument(skip(self), level = "trace")]
     pub async fn replace_scale(&self, name: &str, pp: &PostParams, data: Vec<u8>) -> Result<Scale> {
-        let req = self.request.replace_subresource("scale", name, &pp, data)?;
+        let req = self
+            .request
+            .replace_subresource("scale", name, &self.client.post_params(pp), data)?;
         self.client.request::<Scale>(req).await
     }
 }
 
 // ----------------------------------------------------------------------------
 
+/// Methods for arbitrary subresources
+///
+/// These are the building blocks for the typed subresource helpers, and can be used to reach
+/// subresources that do not have a dedicated method, such as those of aggregated apiservers.
+impl<K> Api<K>
+where
+    K: DeserializeOwned,
+{
+    /// Fetch a named subresource as `T`
+    ///
+    /// ```no_run
+    /// use kube::{api::Api, Client};
+    /// use k8s_openapi::api::apps::v1::Deployment;
+    /// use k8s_openapi::api::autoscaling::v1::Scale;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let deploys: Api<Deployment> = Api::namespaced(client, "apps");
+    ///     let scale: Scale = deploys.get_subresource("scale", "blog").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn get_subresource<T: DeserializeOwned>(
+        &self,
+        subresource_name: &str,
+        name: &str,
+    ) -> Result<T> {
+        let req = self.request.get_subresource(subresource_name, name)?;
+        self.client.request::<T>(req).await
+    }
+
+    /// Create (POST) a named subresource, returning the response as `T`
+    #[instrument(skip(self, data), level = "trace")]
+    pub async fn create_subresource<T: DeserializeOwned>(
+        &self,
+        subresource_name: &str,
+        name: &str,
+        pp: &PostParams,
+        data: Vec<u8>,
+    ) -> Result<T> {
+        let req = self
+            .request
+            .create_subresource(subresource_name, name, pp, data)?;
+        self.client.request::<T>(req).await
+    }
+
+    /// Patch a named subresource, returning the response as `T`
+    #[instrument(skip(self), level = "trace")]
+    pub async fn patch_subresource<T: DeserializeOwned, P: serde::Serialize + Debug>(
+        &self,
+        subresource_name: &str,
+        name: &str,
+        pp: &PatchParams,
+        patch: &Patch<P>,
+    ) -> Result<T> {
+        let req = self
+            .request
+            .patch_subresource(subresource_name, name, pp, patch)?;
+        self.client.request::<T>(req).await
+    }
+
+    /// Replace (PUT) a named subresource, returning the response as `T`
+    #[instrument(skip(self, data), level = "trace")]
+    pub async fn replace_subresource<T: DeserializeOwned>(
+        &self,
+        subresource_name: &str,
+        name: &str,
+        pp: &PostParams,
+        data: Vec<u8>,
+    ) -> Result<T> {
+        let req = self
+            .request
+            .replace_subresource(subresource_name, name, pp, data)?;
+        self.client.request::<T>(req).await
+    }
+}
+
+// ----------------------------------------------------------------------------
+
 // TODO: Replace examples with owned custom resources. Bad practice to write to owned objects
 // These examples work, but the job controller will totally overwrite what we do.
 /// Methods for [status subresource](https://kubernetes.io/docs/tasks/access-kubernetes-api/custom-resources/custom-resource-definitions/#status-subresource).
@@ -94,7 +180,9 @@ where
         pp: &PatchParams,
         patch: &Patch<P>,
     ) -> Result<K> {
-        let req = self.request.patch_subresource("status", name, &pp, patch)?;
+        let req =
+            self.request
+                .patch_subresource("status", name, &self.client.patch_params(pp, patch), patch)?;
         self.client.request::<K>(req).await
     }
 
@@ -119,7 +207,9 @@ where
     /// ```
     #[instrument(skip(self), level = "trace")]
     pub async fn replace_status(&self, name: &str, pp: &PostParams, data: Vec<u8>) -> Result<K> {
-        let req = self.request.replace_subresource("status", name, &pp, data)?;
+        let req = self
+            .request
+            .replace_subresource("status", name, &self.client.post_params(pp), data)?;
         self.client.request::<K>(req).await
     }
 }
@@ -255,9 +345,7 @@ impl Request {
         let pp = &ep.post_options;
         pp.validate()?;
         let mut qp = url::form_urlencoded::Serializer::new(target);
-        if pp.dry_run {
-            qp.append_pair("dryRun", "All");
-        }
+        pp.populate_qp(&mut qp);
         let urlstr = qp.finish();
         // eviction body parameters are awkward, need metadata with name
         let data = serde_json::to_vec(&serde_json::json!({
@@ -295,6 +383,204 @@ where
     }
 }
 
+// ----------------------------------------------------------------------------
+// Token subresource
+// ----------------------------------------------------------------------------
+
+/// Marker trait for objects that can mint bound service account tokens
+pub trait TokenRequestable {}
+
+impl TokenRequestable for k8s_openapi::api::core::v1::ServiceAccount {}
+
+impl<K> Api<K>
+where
+    K: DeserializeOwned + TokenRequestable,
+{
+    /// Request a bound token for a service account
+    ///
+    /// The issued token is found in `.status.token` of the returned [`TokenRequest`].
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, PostParams}, Client};
+    /// use k8s_openapi::api::{authentication::v1::{TokenRequest, TokenRequestSpec}, core::v1::ServiceAccount};
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let sas: Api<ServiceAccount> = Api::namespaced(client, "apps");
+    ///     let tr = TokenRequest {
+    ///         spec: TokenRequestSpec {
+    ///             audiences: vec!["vault".into()],
+    ///             expiration_seconds: Some(600),
+    ///             ..Default::default()
+    ///         },
+    ///         ..Default::default()
+    ///     };
+    ///     let res = sas.create_token_request("builder", &PostParams::default(), &tr).await?;
+    ///     let token = res.status.map(|s| s.token);
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn create_token_request(
+        &self,
+        name: &str,
+        pp: &PostParams,
+        token_request: &TokenRequest,
+    ) -> Result<TokenRequest> {
+        let bytes = serde_json::to_vec(token_request)?;
+        let req = self.request.create_subresource("token", name, pp, bytes)?;
+        self.client.request::<TokenRequest>(req).await
+    }
+}
+
+#[test]
+fn token_request_path() {
+    use crate::api::{Request, Resource};
+    use k8s_openapi::api::core::v1 as corev1;
+    let url = corev1::ServiceAccount::url_path(&(), Some("ns"));
+    let req = Request::new(url)
+        .create_subresource("token", "foo", &PostParams::default(), vec![])
+        .unwrap();
+    assert_eq!(req.uri(), "/api/v1/namespaces/ns/serviceaccounts/foo/token?");
+}
+
+// ----------------------------------------------------------------------------
+// CertificateSigningRequest approval subresource
+// ----------------------------------------------------------------------------
+
+k8s_openapi::k8s_if_ge_1_19! {
+    use k8s_openapi::api::certificates::v1::{CertificateSigningRequest, CertificateSigningRequestCondition};
+
+    /// Methods for the `certificates.k8s.io/v1` [approval and status subresources](https://kubernetes.io/docs/reference/access-authn-authz/certificate-signing-requests/)
+    ///
+    /// Requires kubernetes >= 1.19.
+    impl Api<CertificateSigningRequest> {
+        /// Approve a certificate signing request
+        ///
+        /// The signer will issue the certificate asynchronously, see [`Api::certificate`].
+        /// Fails with [`Error::RequestValidation`] if the request was already denied, as the apiserver
+        /// does not allow changing the decision.
+        ///
+        /// ```no_run
+        /// use kube::{api::Api, Client};
+        /// use k8s_openapi::api::certificates::v1::CertificateSigningRequest;
+        /// #[tokio::main]
+        /// async fn main() -> Result<(), kube::Error> {
+        ///     let client = Client::try_default().await?;
+        ///     let csrs: Api<CertificateSigningRequest> = Api::all(client);
+        ///     csrs.approve("node-csr-x", "NodeVerified", "node identity verified").await?;
+        ///     Ok(())
+        /// }
+        /// ```
+        #[instrument(skip(self), level = "trace")]
+        pub async fn approve(&self, name: &str, reason: &str, message: &str) -> Result<CertificateSigningRequest> {
+            self.set_approval(name, "Approved", reason, message).await
+        }
+
+        /// Deny a certificate signing request
+        ///
+        /// Fails with [`Error::RequestValidation`] if the request was already approved.
+        #[instrument(skip(self), level = "trace")]
+        pub async fn deny(&self, name: &str, reason: &str, message: &str) -> Result<CertificateSigningRequest> {
+            self.set_approval(name, "Denied", reason, message).await
+        }
+
+        /// Fetch the PEM encoded certificate issued for a request, if it has been issued yet
+        #[instrument(skip(self), level = "trace")]
+        pub async fn certificate(&self, name: &str) -> Result<Option<Vec<u8>>> {
+            let csr: CertificateSigningRequest = self.get_subresource("status", name).await?;
+            Ok(csr.status.and_then(|s| s.certificate).map(|c| c.0))
+        }
+
+        async fn set_approval(
+            &self,
+            name: &str,
+            type_: &str,
+            reason: &str,
+            message: &str,
+        ) -> Result<CertificateSigningRequest> {
+            let mut csr: CertificateSigningRequest = self.get_subresource("approval", name).await?;
+            add_approval_condition(&mut csr, type_, reason, message)?;
+            let data = serde_json::to_vec(&csr)?;
+            self.replace_subresource("approval", name, &PostParams::default(), data)
+                .await
+        }
+    }
+
+    /// Add (or replace) an `Approved`/`Denied` condition on a certificate signing request
+    ///
+    /// The two are mutually exclusive, and the apiserver rejects removing either, so a request
+    /// that was decided the other way is an error.
+    fn add_approval_condition(
+        csr: &mut CertificateSigningRequest,
+        type_: &str,
+        reason: &str,
+        message: &str,
+    ) -> Result<()> {
+        use k8s_openapi::apimachinery::pkg::apis::meta::v1::Time;
+        let opposite = if type_ == "Approved" { "Denied" } else { "Approved" };
+        let now = Time(chrono::Utc::now());
+        let conditions = csr
+            .status
+            .get_or_insert_with(Default::default)
+            .conditions
+            .get_or_insert_with(Vec::new);
+        if conditions.iter().any(|c| c.type_ == opposite) {
+            return Err(Error::RequestValidation(format!(
+                "CertificateSigningRequest is already {}, and can not be {} as well",
+                opposite.to_lowercase(),
+                type_.to_lowercase()
+            )));
+        }
+        conditions.retain(|c| c.type_ != type_);
+        conditions.push(CertificateSigningRequestCondition {
+            type_: type_.into(),
+            status: "True".into(),
+            reason: Some(reason.into()),
+            message: Some(message.into()),
+            last_update_time: Some(now.clone()),
+            last_transition_time: Some(now),
+        });
+        Ok(())
+    }
+
+    #[test]
+    fn csr_approval_condition() {
+        let mut csr = CertificateSigningRequest::default();
+        add_approval_condition(&mut csr, "Approved", "Test", "first").unwrap();
+        add_approval_condition(&mut csr, "Approved", "Test", "second").unwrap();
+        let conditions = csr.status.as_ref().unwrap().conditions.as_ref().unwrap();
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].type_, "Approved");
+        assert_eq!(conditions[0].status, "True");
+        assert_eq!(conditions[0].message.as_deref(), Some("second"));
+    }
+
+    #[test]
+    fn csr_approval_conditions_are_exclusive() {
+        let mut csr = CertificateSigningRequest::default();
+        add_approval_condition(&mut csr, "Denied", "Test", "denied").unwrap();
+        let err = add_approval_condition(&mut csr, "Approved", "Test", "approved").unwrap_err();
+        assert!(matches!(err, Error::RequestValidation(msg) if msg.contains("already denied")));
+        let conditions = csr.status.unwrap().conditions.unwrap();
+        assert_eq!(conditions.len(), 1);
+        assert_eq!(conditions[0].type_, "Denied");
+    }
+
+    #[test]
+    fn csr_approval_path() {
+        use crate::api::{Request, Resource};
+        let url = CertificateSigningRequest::url_path(&(), None);
+        let req = Request::new(url)
+            .replace_subresource("approval", "foo", &PostParams::default(), vec![])
+            .unwrap();
+        assert_eq!(
+            req.uri(),
+            "/apis/certificates.k8s.io/v1/certificatesigningrequests/foo/approval?"
+        );
+    }
+}
+
 // ----------------------------------------------------------------------------
 // Attach subresource
 // ----------------------------------------------------------------------------
@@ -513,7 +799,7 @@ where
     pub async fn attach(&self, name: &str, ap: &AttachParams) -> Result<AttachedProcess> {
         let req = self.request.attach(name, ap)?;
         let stream = self.client.connect(req).await?;
-        Ok(AttachedProcess::new(stream, ap))
+        Ok(AttachedProcess::new(stream, ap, &*self.client.executor()))
     }
 }
 
@@ -592,6 +878,63 @@ where
     {
         let req = self.request.exec(name, command, ap)?;
         let stream = self.client.connect(req).await?;
-        Ok(AttachedProcess::new(stream, ap))
+        Ok(AttachedProcess::new(stream, ap, &*self.client.executor()))
+    }
+}
+
+// ----------------------------------------------------------------------------
+// Portforward subresource
+// ----------------------------------------------------------------------------
+#[cfg(feature = "ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+impl Request {
+    /// Forward a port of a pod
+    pub fn portforward(&self, name: &str, port: u16) -> Result<http::Request<Vec<u8>>> {
+        let target = format!("{}/{}/portforward?", self.url_path, name);
+        let mut qp = url::form_urlencoded::Serializer::new(target);
+        qp.append_pair("ports", &port.to_string());
+
+        let req = http::Request::get(qp.finish());
+        req.body(vec![]).map_err(Error::HttpError)
+    }
+}
+
+#[cfg(feature = "ws")]
+#[test]
+fn portforward_path() {
+    use crate::api::{Request, Resource};
+    use k8s_openapi::api::core::v1 as corev1;
+    let url = corev1::Pod::url_path(&(), Some("ns"));
+    let req = Request::new(url).portforward("foo", 8080).unwrap();
+    assert_eq!(
+        req.uri(),
+        "/api/v1/namespaces/ns/pods/foo/portforward?&ports=8080"
+    );
+}
+
+/// Marker trait for objects that has portforward
+#[cfg(feature = "ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+pub trait Portforwardable {}
+
+#[cfg(feature = "ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+impl Portforwardable for k8s_openapi::api::core::v1::Pod {}
+
+#[cfg(feature = "ws")]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Portforwardable,
+{
+    /// Open a connection to a port of a pod
+    ///
+    /// Every call opens a new connection, see [`ops::portforward_http`](crate::ops::portforward_http)
+    /// to make HTTP requests through them.
+    #[instrument(skip(self), level = "trace")]
+    pub async fn portforward(&self, name: &str, port: u16) -> Result<PortforwardStream> {
+        let req = self.request.portforward(name, port)?;
+        let stream = self.client.connect(req).await?;
+        Ok(PortforwardStream::new(stream, &*self.client.executor()))
     }
 }
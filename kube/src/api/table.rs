@@ -0,0 +1,203 @@
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::fmt::Debug;
+use tracing::instrument;
+
+use crate::{
+    api::{Api, ListMeta, ListParams, Request, TypeMeta},
+    Result,
+};
+
+/// Accept header asking the apiserver to render responses as a `meta.k8s.io/v1` `Table`
+///
+/// There is no fallback to plain json, which would not deserialize as a [`Table`]: (aggregated)
+/// apiservers that do not support tables answer with `406 Not Acceptable` instead.
+const ACCEPT_TABLE: &str = "application/json;as=Table;v=v1;g=meta.k8s.io";
+
+/// A server-side formatted list of objects, as used by `kubectl get`
+///
+/// See [Receiving resources as Tables](https://kubernetes.io/docs/reference/using-api/api-concepts/#receiving-resources-as-tables).
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Table {
+    /// apiVersion + kind
+    #[serde(flatten)]
+    pub types: TypeMeta,
+    /// Standard list metadata
+    #[serde(default)]
+    pub metadata: ListMeta,
+    /// Columns describing each cell in the rows
+    pub column_definitions: Vec<TableColumnDefinition>,
+    /// One row for each object in the response
+    #[serde(default)]
+    pub rows: Vec<TableRow>,
+}
+
+/// Describes a single column in a [`Table`]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TableColumnDefinition {
+    /// Human readable name of the column
+    pub name: String,
+    /// OpenAPI type of the column (`integer`, `number`, `string`, `boolean`, ...)
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Optional OpenAPI format modifier (e.g. `name` for object names)
+    #[serde(default)]
+    pub format: String,
+    /// Human readable description of the column
+    #[serde(default)]
+    pub description: String,
+    /// Relative importance of the column. `0` columns are shown by default in `kubectl get`,
+    /// higher numbers are only shown with `-o wide`.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+/// A single row in a [`Table`]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRow {
+    /// The cells of this row, matching the order of [`Table::column_definitions`]
+    pub cells: Vec<serde_json::Value>,
+    /// Optional conditions describing the row (e.g. `Completed`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conditions: Vec<TableRowCondition>,
+    /// The object this row represents, if requested
+    ///
+    /// By default this is a `PartialObjectMetadata` containing only `metadata`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub object: Option<serde_json::Value>,
+}
+
+/// A condition on a [`TableRow`]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TableRowCondition {
+    /// Type of the condition. Only `Completed` is currently defined by kubernetes
+    #[serde(rename = "type")]
+    pub type_: String,
+    /// Status of the condition: `True`, `False`, or `Unknown`
+    pub status: String,
+    /// Machine readable reason for the last transition
+    #[serde(default)]
+    pub reason: String,
+    /// Human readable message for the last transition
+    #[serde(default)]
+    pub message: String,
+}
+
+impl Table {
+    /// Names of the columns with a priority at or below `max_priority`
+    ///
+    /// Use `0` for the default `kubectl get` columns.
+    pub fn column_names(&self, max_priority: i32) -> Vec<&str> {
+        self.column_definitions
+            .iter()
+            .filter(|c| c.priority <= max_priority)
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+}
+
+impl Request {
+    /// List a collection of a resource as a [`Table`]
+    pub fn list_table(&self, lp: &ListParams) -> Result<http::Request<Vec<u8>>> {
+        let mut req = self.list(lp)?;
+        req.headers_mut()
+            .insert(http::header::ACCEPT, http::HeaderValue::from_static(ACCEPT_TABLE));
+        Ok(req)
+    }
+
+    /// Get a single instance as a [`Table`]
+    pub fn get_table(&self, name: &str) -> Result<http::Request<Vec<u8>>> {
+        let mut req = self.get(name)?;
+        req.headers_mut()
+            .insert(http::header::ACCEPT, http::HeaderValue::from_static(ACCEPT_TABLE));
+        Ok(req)
+    }
+}
+
+/// Methods for fetching resources as server-side formatted [`Table`]s
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Debug,
+{
+    /// Get a list of resources as a [`Table`]
+    ///
+    /// The columns are decided by the apiserver, just like in `kubectl get`. Apiservers that cannot render
+    /// tables, like some aggregated apiservers, fail this with an [`Error::Api`](crate::Error::Api) with code 406.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams}, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::namespaced(client, "apps");
+    ///     let table = pods.list_table(&ListParams::default()).await?;
+    ///     println!("{}", table.column_names(0).join("\t"));
+    ///     for row in table.rows {
+    ///         let cells: Vec<String> = row.cells.iter().map(|c| c.to_string()).collect();
+    ///         println!("{}", cells.join("\t"));
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn list_table(&self, lp: &ListParams) -> Result<Table> {
+        let req = self.request.list_table(lp)?;
+        self.client.request::<Table>(req).await
+    }
+
+    /// Get a named resource as a [`Table`] with a single row
+    #[instrument(skip(self), level = "trace")]
+    pub async fn get_table(&self, name: &str) -> Result<Table> {
+        let req = self.request.get_table(name)?;
+        self.client.request::<Table>(req).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Table, ACCEPT_TABLE};
+    use crate::api::{ListParams, Request, Resource};
+    use k8s_openapi::api::core::v1 as corev1;
+
+    #[test]
+    fn list_table_path() {
+        let url = corev1::Pod::url_path(&(), Some("ns"));
+        let req = Request::new(url).list_table(&ListParams::default()).unwrap();
+        assert_eq!(req.uri(), "/api/v1/namespaces/ns/pods");
+        assert_eq!(req.headers().get("Accept").unwrap(), ACCEPT_TABLE);
+    }
+
+    #[test]
+    fn get_table_path() {
+        let url = corev1::Pod::url_path(&(), Some("ns"));
+        let req = Request::new(url).get_table("foo").unwrap();
+        assert_eq!(req.uri(), "/api/v1/namespaces/ns/pods/foo");
+        assert_eq!(req.headers().get("Accept").unwrap(), ACCEPT_TABLE);
+    }
+
+    #[test]
+    fn table_deserialize() {
+        let data = r#"{
+            "kind": "Table",
+            "apiVersion": "meta.k8s.io/v1",
+            "metadata": {"resourceVersion": "123"},
+            "columnDefinitions": [
+                {"name": "Name", "type": "string", "format": "name", "description": "Name", "priority": 0},
+                {"name": "Ready", "type": "string", "format": "", "description": "Readiness", "priority": 0},
+                {"name": "IP", "type": "string", "format": "", "description": "Pod IP", "priority": 1}
+            ],
+            "rows": [
+                {"cells": ["blog", "1/1", "10.0.0.1"], "object": {"kind": "PartialObjectMetadata"}}
+            ]
+        }"#;
+        let table: Table = serde_json::from_str(data).unwrap();
+        assert_eq!(table.types.kind, "Table");
+        assert_eq!(table.column_names(0), vec!["Name", "Ready"]);
+        assert_eq!(table.rows[0].cells[0], "blog");
+        assert!(table.rows[0].conditions.is_empty());
+    }
+}
@@ -1,14 +1,16 @@
 use either::Either;
 use futures::Stream;
 use serde::{de::DeserializeOwned, Serialize};
-use std::{fmt::Debug, iter};
+use std::{fmt::Debug, iter, sync::Arc};
 use tracing::instrument;
 
 use crate::{
     api::{
-        DeleteParams, ListParams, ObjectList, Patch, PatchParams, PostParams, Request, Resource, WatchEvent,
+        DeleteParams, GetParams, ListParams, ObjectList, PartialObjectMetadata, Patch, PatchParams,
+        PostParams, Request, Resource, WatchEvent,
     },
     client::{Client, Status},
+    openapi::Validator,
     Result,
 };
 
@@ -27,6 +29,8 @@ pub struct Api<K> {
     /// `K` objects, so `Empty` better models our constraints (in particular, `Empty<K>`
     /// is `Send`, even if `K` may not be).
     pub(crate) phantom: iter::Empty<K>,
+    /// Checks objects before they are sent, see [`Api::with_validator`]
+    pub(crate) validator: Option<Arc<Validator>>,
 }
 
 /// Api constructors for Resource implementors with Default DynamicTypes
@@ -43,6 +47,7 @@ where
             client,
             request: Request::new(url),
             phantom: iter::empty(),
+            validator: None,
         }
     }
 
@@ -53,6 +58,7 @@ where
             client,
             request: Request::new(url),
             phantom: iter::empty(),
+            validator: None,
         }
     }
 }
@@ -70,6 +76,7 @@ impl<K: Resource> Api<K> {
             client,
             request: Request::new(url),
             phantom: iter::empty(),
+            validator: None,
         }
     }
 
@@ -82,9 +89,40 @@ impl<K: Resource> Api<K> {
             client,
             request: Request::new(url),
             phantom: iter::empty(),
+            validator: None,
         }
     }
 
+    /// Add a [`FlowHint`](crate::client::FlowHint) to the requests made through this `Api`
+    ///
+    /// Use this to deprioritize (or prioritize) some calls of a client, without affecting its other users.
+    pub fn with_flow_hint(mut self, hint: crate::client::FlowHint) -> Self {
+        self.client = self.client.with_flow_hint(hint);
+        self
+    }
+
+    /// Exchange bodies in the format of a [`Codec`](crate::client::Codec) through this `Api`
+    ///
+    /// Use this to pick a cheaper format for resources with large or many objects,
+    /// without affecting the other users of the client.
+    #[cfg(feature = "codec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "codec")))]
+    pub fn with_codec<C: crate::client::Codec + 'static>(mut self, codec: C) -> Self {
+        self.client = self.client.with_codec(codec);
+        self
+    }
+
+    /// Validate objects against a schema before sending them
+    ///
+    /// Objects passed to [`create`](Self::create) and [`replace`](Self::replace) are validated
+    /// in full, and `Apply` and `Merge` patches are validated without checking for required fields.
+    /// Violations are returned as [`Error::SchemaValidation`](crate::Error::SchemaValidation),
+    /// without contacting the apiserver.
+    pub fn with_validator(mut self, validator: Validator) -> Self {
+        self.validator = Some(Arc::new(validator));
+        self
+    }
+
     /// Consume self and return the [`Client`]
     pub fn into_client(self) -> Client {
         self.into()
@@ -120,6 +158,26 @@ where
         self.client.request::<K>(req).await
     }
 
+    /// Get a named resource, at a resource version
+    ///
+    /// [`Api::get`] always does a quorum read of the most recent version. Use [`GetParams::any`]
+    /// to allow a cheaper read from the cache of the apiserver, when a stale object is acceptable.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, GetParams}, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// # async fn scope(client: Client) -> Result<(), kube::Error> {
+    /// let pods: Api<Pod> = Api::namespaced(client, "apps");
+    /// let p: Pod = pods.get_with("blog", &GetParams::any()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn get_with(&self, name: &str, gp: &GetParams) -> Result<K> {
+        let req = self.request.get_with(name, gp)?;
+        self.client.request::<K>(req).await
+    }
+
     /// Get a list of resources
     ///
     /// You get use this to get everything, or a subset matching fields/labels, say:
@@ -144,6 +202,32 @@ where
         self.client.request::<ObjectList<K>>(req).await
     }
 
+    /// Get a list of resources, fetching only the metadata of each
+    ///
+    /// This is much cheaper than [`Api::list`] when you only need names, labels,
+    /// owner references and the like, as the apiserver drops `spec` and `status`.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams}, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::namespaced(client, "apps");
+    ///     for p in pods.list_metadata(&ListParams::default()).await? {
+    ///         println!("Found Pod: {:?}", p.metadata.name);
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn list_metadata(&self, lp: &ListParams) -> Result<ObjectList<PartialObjectMetadata>> {
+        let req = self.request.list_metadata(lp)?;
+        self.client
+            .request::<ObjectList<PartialObjectMetadata>>(req)
+            .await
+    }
+
     /// Create a resource
     ///
     /// This function requires a type that Serializes to `K`, which can be:
@@ -165,8 +249,11 @@ where
     where
         K: Serialize,
     {
+        if let Some(validator) = &self.validator {
+            validator.validate(data)?;
+        }
         let bytes = serde_json::to_vec(&data)?;
-        let req = self.request.create(&pp, bytes)?;
+        let req = self.request.create(&self.client.post_params(pp), bytes)?;
         self.client.request::<K>(req).await
     }
 
@@ -271,7 +358,15 @@ where
         pp: &PatchParams,
         patch: &Patch<P>,
     ) -> Result<K> {
-        let req = self.request.patch(name, &pp, patch)?;
+        if let Some(validator) = &self.validator {
+            match patch {
+                Patch::Apply(p) | Patch::Merge(p) => validator.validate_partial(p)?,
+                _ => {}
+            }
+        }
+        let req = self
+            .request
+            .patch(name, &self.client.patch_params(pp, patch), patch)?;
         self.client.request::<K>(req).await
     }
 
@@ -324,11 +419,53 @@ where
     where
         K: Serialize,
     {
+        if let Some(validator) = &self.validator {
+            validator.validate(data)?;
+        }
         let bytes = serde_json::to_vec(&data)?;
-        let req = self.request.replace(name, &pp, bytes)?;
+        let req = self.request.replace(name, &self.client.post_params(pp), bytes)?;
         self.client.request::<K>(req).await
     }
 
+    /// Replace a resource, filling in a missing `resourceVersion` from the current object
+    ///
+    /// [`replace`](Self::replace) requires `metadata.resourceVersion`, and forgetting to copy it
+    /// is a common cause of `409 Conflict` and `422 Invalid` errors. When `data` has no resource version,
+    /// this fetches the current object and uses its version, so the replace overwrites whatever is
+    /// stored at the time. When `data` has a resource version, it is kept and conflicts are reported as usual.
+    ///
+    /// Overwriting without a resource version gives up optimistic concurrency,
+    /// prefer mutating the result of `api.get` where concurrent writers matter.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, PostParams}, Client};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// # async fn scope(client: Client) -> Result<(), kube::Error> {
+    /// let cms: Api<ConfigMap> = Api::namespaced(client, "default");
+    /// let settings: ConfigMap = serde_json::from_value(serde_json::json!({
+    ///     "metadata": { "name": "settings" },
+    ///     "data": { "mode": "fast" }
+    /// }))?;
+    /// cms.replace_preserving_rv("settings", &PostParams::default(), &settings).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[instrument(skip(self), level = "trace")]
+    pub async fn replace_preserving_rv(&self, name: &str, pp: &PostParams, data: &K) -> Result<K>
+    where
+        K: Resource + Serialize,
+    {
+        if data.meta().resource_version.is_some() {
+            return self.replace(name, pp, data).await;
+        }
+        let current = self.get(name).await?;
+        let mut data = data.clone();
+        data.meta_mut()
+            .resource_version
+            .clone_from(&current.meta().resource_version);
+        self.replace(name, pp, &data).await
+    }
+
     /// Watch a list of resources
     ///
     /// This returns a future that awaits the initial response,
@@ -359,6 +496,8 @@ where
     ///             WatchEvent::Deleted(s) => println!("Deleted {}", s.name()),
     ///             WatchEvent::Bookmark(s) => {},
     ///             WatchEvent::Error(s) => println!("{}", s),
+    ///             WatchEvent::ErrorStatus(s) => println!("{:?}", s),
+    ///             _ => {},
     ///         }
     ///     }
     ///     Ok(())
@@ -375,6 +514,42 @@ where
         let req = self.request.watch(&lp, &version)?;
         self.client.request_events::<K>(req).await
     }
+
+    /// Watch a list of resources over a WebSocket connection
+    ///
+    /// This behaves like [`Api::watch`], but upgrades the connection to a WebSocket
+    /// instead of streaming a chunked HTTP response. Use this when the API server is
+    /// reached through middleboxes (HTTP/1-only gateways, buffering proxies) that hold
+    /// back chunked responses and thereby stall long-polling watches.
+    ///
+    /// ```no_run
+    /// use kube::{api::{Api, ListParams, ResourceExt, WatchEvent}, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// use futures::{StreamExt, TryStreamExt};
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::namespaced(client, "apps");
+    ///     let mut stream = pods.watch_ws(&ListParams::default(), "0").await?.boxed();
+    ///     while let Some(status) = stream.try_next().await? {
+    ///         if let WatchEvent::Added(p) = status {
+    ///             println!("Added {}", p.name());
+    ///         }
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[cfg(feature = "ws")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+    #[instrument(skip(self), level = "trace")]
+    pub async fn watch_ws(
+        &self,
+        lp: &ListParams,
+        version: &str,
+    ) -> Result<impl Stream<Item = Result<WatchEvent<K>>>> {
+        let req = self.request.watch(lp, version)?;
+        self.client.request_events_ws::<K>(req).await
+    }
 }
 
 impl<K> From<Api<K>> for Client {
@@ -382,3 +557,91 @@ impl<K> From<Api<K>> for Client {
         api.client
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        api::{Api, Patch, PatchParams, PostParams},
+        Client, Service,
+    };
+    use http::{Method, Request, Response};
+    use hyper::Body;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn replace_preserving_rv_fills_in_the_resource_version() {
+        let puts = Arc::new(Mutex::new(vec![]));
+        let sink = puts.clone();
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            let sink = sink.clone();
+            async move {
+                let body = if req.method() == Method::PUT {
+                    let body = hyper::body::to_bytes(req.into_body()).await?;
+                    sink.lock()
+                        .unwrap()
+                        .push(serde_json::from_slice::<serde_json::Value>(&body)?);
+                    body
+                } else {
+                    r#"{"metadata":{"name":"settings","resourceVersion":"42"}}"#.into()
+                };
+                Response::builder()
+                    .body(Body::from(body))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let cms: Api<ConfigMap> = Api::namespaced(Client::new(Service::new(svc)), "default");
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("settings".into());
+        cms.replace_preserving_rv("settings", &PostParams::default(), &cm)
+            .await
+            .unwrap();
+        cm.metadata.resource_version = Some("7".into());
+        cms.replace_preserving_rv("settings", &PostParams::default(), &cm)
+            .await
+            .unwrap();
+        let puts = puts.lock().unwrap();
+        assert_eq!(puts[0]["metadata"]["resourceVersion"], "42");
+        assert_eq!(puts[1]["metadata"]["resourceVersion"], "7");
+    }
+
+    #[tokio::test]
+    async fn writes_default_to_the_field_manager_of_the_client() {
+        let queries = Arc::new(Mutex::new(vec![]));
+        let sink = queries.clone();
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            sink.lock()
+                .unwrap()
+                .push(req.uri().query().unwrap_or_default().to_string());
+            async {
+                Response::builder()
+                    .body(Body::from(r#"{"metadata":{"name":"settings"}}"#))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let client = Client::new(Service::new(svc))
+            .with_field_manager("my-operator")
+            .with_forced_apply();
+        let cms: Api<ConfigMap> = Api::namespaced(client, "default");
+        let apply = Patch::Apply(serde_json::json!({ "data": { "a": "b" } }));
+        let merge = Patch::Merge(serde_json::json!({ "data": { "a": "b" } }));
+        cms.create(&PostParams::default(), &ConfigMap::default())
+            .await
+            .unwrap();
+        cms.patch("settings", &PatchParams::default(), &apply)
+            .await
+            .unwrap();
+        cms.patch("settings", &PatchParams::default(), &merge)
+            .await
+            .unwrap();
+        cms.patch("settings", &PatchParams::apply("someone-else"), &apply)
+            .await
+            .unwrap();
+        assert_eq!(*queries.lock().unwrap(), vec![
+            "&fieldManager=my-operator",
+            "&force=true&fieldManager=my-operator",
+            "&fieldManager=my-operator",
+            "&fieldManager=someone-else",
+        ]);
+    }
+}
@@ -0,0 +1,248 @@
+//! A synchronous facade over [`Client`](crate::Client) and [`Api`](crate::Api)
+//!
+//! For scripts, build tooling and tests where pulling in an async runtime is overkill.
+//! The blocking [`Client`] owns a single threaded Tokio runtime, and every call of the blocking
+//! [`Api`] drives its request to completion on it.
+//!
+//! These types must not be used from within an async runtime; calls will panic there.
+//! Use the async [`Api`](crate::Api) instead.
+//!
+//! ```no_run
+//! use kube::{api::ListParams, blocking::{Api, Client}};
+//! use k8s_openapi::api::core::v1::Pod;
+//! fn main() -> Result<(), kube::Error> {
+//!     let client = Client::try_default()?;
+//!     let pods: Api<Pod> = Api::namespaced(client, "apps");
+//!     for p in pods.list(&ListParams::default())? {
+//!         println!("found pod {:?}", p.metadata.name);
+//!     }
+//!     Ok(())
+//! }
+//! ```
+use either::Either;
+use futures::Future;
+use http::{Request, Response};
+use hyper::Body;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{convert::TryFrom, fmt::Debug, sync::Arc};
+use tokio::runtime::Runtime;
+use tower::BoxError;
+
+use crate::{
+    api::{DeleteParams, GetParams, ListParams, ObjectList, Patch, PatchParams, PostParams, Resource},
+    client::Status,
+    Config, Error, Result, Service,
+};
+
+/// A blocking client for connecting with a Kubernetes cluster
+///
+/// Clones share the underlying runtime and connection pool.
+#[derive(Clone)]
+pub struct Client {
+    client: crate::Client,
+    runtime: Arc<Runtime>,
+}
+
+impl Client {
+    /// Create a blocking client using the inferred configuration
+    ///
+    /// See [`Client::try_default`](crate::Client::try_default).
+    pub fn try_default() -> Result<Self> {
+        let runtime = runtime()?;
+        let client = runtime.block_on(crate::Client::try_default())?;
+        Ok(Self::from_parts(client, runtime))
+    }
+
+    /// Create a blocking client using a custom `service`
+    ///
+    /// See [`Service::new`](crate::Service::new).
+    pub fn with_service<S>(service: S) -> Result<Self>
+    where
+        S: tower::Service<Request<Body>, Response = Response<Body>, Error = BoxError> + Send + 'static,
+        S::Future: Send + 'static,
+    {
+        let runtime = runtime()?;
+        // The service spawns its buffer worker, which needs a runtime
+        let client = {
+            let _guard = runtime.enter();
+            crate::Client::new(Service::new(service))
+        };
+        Ok(Self::from_parts(client, runtime))
+    }
+
+    /// Run a future on the runtime of this client, blocking until it completes
+    ///
+    /// This is the escape hatch for async apis that have no blocking counterpart.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// The async client backing this client
+    ///
+    /// It can only be used within [`Client::block_on`].
+    pub fn as_async(&self) -> &crate::Client {
+        &self.client
+    }
+
+    fn from_parts(client: crate::Client, runtime: Runtime) -> Self {
+        Client {
+            client,
+            runtime: Arc::new(runtime),
+        }
+    }
+}
+
+impl TryFrom<Config> for Client {
+    type Error = Error;
+
+    /// Convert [`Config`] into a blocking [`Client`]
+    fn try_from(config: Config) -> Result<Self> {
+        let runtime = runtime()?;
+        let client = {
+            let _guard = runtime.enter();
+            crate::Client::try_from(config)?
+        };
+        Ok(Self::from_parts(client, runtime))
+    }
+}
+
+fn runtime() -> Result<Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(Error::Runtime)
+}
+
+/// A blocking version of [`Api`](crate::Api)
+#[derive(Clone)]
+pub struct Api<K> {
+    api: crate::Api<K>,
+    runtime: Arc<Runtime>,
+}
+
+/// Api constructors for Resource implementors with Default DynamicTypes
+impl<K: Resource> Api<K>
+where
+    <K as Resource>::DynamicType: Default,
+{
+    /// Cluster level resources, or resources viewed across all namespaces
+    pub fn all(client: Client) -> Self {
+        Self::all_with(client, &Default::default())
+    }
+
+    /// Namespaced resource within a given namespace
+    pub fn namespaced(client: Client, ns: &str) -> Self {
+        Self::namespaced_with(client, ns, &Default::default())
+    }
+}
+
+/// Api constructors for Resource implementors with custom DynamicTypes
+impl<K: Resource> Api<K> {
+    /// Cluster level resources, or resources viewed across all namespaces
+    pub fn all_with(client: Client, dyntype: &K::DynamicType) -> Self {
+        Api {
+            api: crate::Api::all_with(client.client, dyntype),
+            runtime: client.runtime,
+        }
+    }
+
+    /// Namespaced resource within a given namespace
+    pub fn namespaced_with(client: Client, ns: &str, dyntype: &K::DynamicType) -> Self {
+        Api {
+            api: crate::Api::namespaced_with(client.client, ns, dyntype),
+            runtime: client.runtime,
+        }
+    }
+
+    /// Return a reference to the current resource url path
+    pub fn resource_url(&self) -> &str {
+        self.api.resource_url()
+    }
+}
+
+/// PUSH/PUT/POST/GET abstractions, see the async [`Api`](crate::Api) for details
+impl<K> Api<K>
+where
+    K: Clone + DeserializeOwned + Debug,
+{
+    /// Get a named resource
+    pub fn get(&self, name: &str) -> Result<K> {
+        self.runtime.block_on(self.api.get(name))
+    }
+
+    /// Get a named resource, at a resource version
+    pub fn get_with(&self, name: &str, gp: &GetParams) -> Result<K> {
+        self.runtime.block_on(self.api.get_with(name, gp))
+    }
+
+    /// Get a list of resources
+    pub fn list(&self, lp: &ListParams) -> Result<ObjectList<K>> {
+        self.runtime.block_on(self.api.list(lp))
+    }
+
+    /// Create a resource
+    pub fn create(&self, pp: &PostParams, data: &K) -> Result<K>
+    where
+        K: Serialize,
+    {
+        self.runtime.block_on(self.api.create(pp, data))
+    }
+
+    /// Delete a named resource
+    pub fn delete(&self, name: &str, dp: &DeleteParams) -> Result<Either<K, Status>> {
+        self.runtime.block_on(self.api.delete(name, dp))
+    }
+
+    /// Delete a collection of resources
+    pub fn delete_collection(
+        &self,
+        dp: &DeleteParams,
+        lp: &ListParams,
+    ) -> Result<Either<ObjectList<K>, Status>> {
+        self.runtime.block_on(self.api.delete_collection(dp, lp))
+    }
+
+    /// Patch a subset of a resource's properties
+    pub fn patch<P: Serialize + Debug>(&self, name: &str, pp: &PatchParams, patch: &Patch<P>) -> Result<K> {
+        self.runtime.block_on(self.api.patch(name, pp, patch))
+    }
+
+    /// Replace a resource entirely with a new one
+    pub fn replace(&self, name: &str, pp: &PostParams, data: &K) -> Result<K>
+    where
+        K: Serialize,
+    {
+        self.runtime.block_on(self.api.replace(name, pp, data))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Api, Client};
+    use crate::api::PostParams;
+    use http::{Method, Request, Response};
+    use hyper::Body;
+    use k8s_openapi::api::core::v1::ConfigMap;
+
+    #[test]
+    fn blocking_api_runs_requests_to_completion() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            let body = match *req.method() {
+                Method::POST => hyper::body::to_bytes(req.into_body()).await?,
+                _ => r#"{"metadata":{"name":"settings","resourceVersion":"1"}}"#.into(),
+            };
+            Response::builder()
+                .body(Body::from(body))
+                .map_err(tower::BoxError::from)
+        });
+        let cms: Api<ConfigMap> = Api::namespaced(Client::with_service(svc).unwrap(), "default");
+        assert_eq!(cms.resource_url(), "/api/v1/namespaces/default/configmaps");
+
+        let cm = cms.get("settings").unwrap();
+        assert_eq!(cm.metadata.resource_version.as_deref(), Some("1"));
+        let mut new = ConfigMap::default();
+        new.metadata.name = Some("other".into());
+        let created = cms.create(&PostParams::default(), &new).unwrap();
+        assert_eq!(created.metadata.name.as_deref(), Some("other"));
+    }
+}
@@ -0,0 +1,677 @@
+use ciborium_ll::{simple, Decoder, Encoder, Header};
+use serde::de::{self, DeserializeSeed, IntoDeserializer, Visitor};
+use std::{borrow::Cow, convert::TryFrom, fmt};
+
+use super::{Codec, Framer, Visit};
+use crate::{Error, Result};
+
+/// The media type of CBOR bodies
+const CBOR: &str = "application/cbor";
+/// The media type of watches in CBOR, a sequence of items
+const CBOR_SEQ: &str = "application/cbor-seq";
+/// The self-described CBOR tag, which the apiserver puts in front of its items
+const SELF_DESCRIBED: u64 = 55799;
+/// Tags of byte strings that are expected to be converted to base64url, base64 and base16 text
+const BASE64URL: u64 = 21;
+const BASE64: u64 = 22;
+const BASE16: u64 = 23;
+/// Deepest nesting of arrays, maps and tags that is decoded
+const MAX_DEPTH: usize = 128;
+
+/// The [CBOR](https://www.rfc-editor.org/rfc/rfc8949) [`Codec`], for clusters serving CBOR
+///
+/// CBOR is an alpha serialization of Kubernetes 1.32+, behind the `CBORServingAndStorage`
+/// feature gate. Bodies are smaller and cheaper to parse than json, and watches are streamed
+/// as sequences of CBOR items. Resources that are not served as CBOR, like those of aggregated
+/// apis, keep falling back to json.
+///
+/// The apiserver encodes strings and field names as byte strings, these are deserialized as
+/// strings when they are utf-8. Byte strings tagged to be converted to base64, like the
+/// apiserver does for `[]byte` fields such as the data of secrets, are deserialized as base64
+/// strings, as they are represented in json.
+///
+/// ```no_run
+/// use kube::{api::Api, client::CborCodec, Client};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let cms: Api<ConfigMap> = Api::namespaced(client, "apps").with_codec(CborCodec);
+/// let cm = cms.get("settings").await?;
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "cbor")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CborCodec;
+
+impl Codec for CborCodec {
+    fn media_type(&self) -> &str {
+        CBOR
+    }
+
+    fn decodes(&self, media_type: &str) -> bool {
+        media_type == CBOR || media_type == CBOR_SEQ
+    }
+
+    fn encode_body(&self, json: &[u8]) -> Result<Vec<u8>> {
+        let value: serde_json::Value = serde_json::from_slice(json)?;
+        let mut out = Vec::with_capacity(json.len());
+        Encoder::from(&mut out)
+            .push(Header::Tag(SELF_DESCRIBED))
+            .map_err(|e| Error::Codec(Box::new(e)))?;
+        ciborium::ser::into_writer(&value, &mut out).map_err(|e| Error::Codec(Box::new(e)))?;
+        Ok(out)
+    }
+
+    fn decode_frame<'de>(&self, frame: &'de [u8], visit: Visit<'_, 'de>) -> Result<()> {
+        let mut de = Deserializer {
+            frame,
+            pos: 0,
+            depth: 0,
+        };
+        visit.deserialize(&mut de)?;
+        if de.pos != frame.len() {
+            return Err(CborError::Trailing.into());
+        }
+        Ok(())
+    }
+
+    fn framer(&self) -> Box<dyn Framer> {
+        Box::new(CborFramer::default())
+    }
+}
+
+/// A malformed or unsupported CBOR item
+#[derive(Debug)]
+enum CborError {
+    Incomplete,
+    Trailing,
+    Invalid(&'static str),
+    Deserialize(String),
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CborError::Incomplete => f.write_str("incomplete CBOR item"),
+            CborError::Trailing => f.write_str("trailing bytes after CBOR item"),
+            CborError::Invalid(what) => write!(f, "invalid CBOR: {}", what),
+            CborError::Deserialize(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for CborError {}
+
+impl de::Error for CborError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        CborError::Deserialize(msg.to_string())
+    }
+}
+
+impl From<CborError> for Error {
+    fn from(err: CborError) -> Self {
+        Error::Codec(Box::new(err))
+    }
+}
+
+/// Pull the head of the item at the start of `buf`, with its length
+fn pull(buf: &[u8]) -> Result<Option<(Header, usize)>, CborError> {
+    let mut decoder = Decoder::from(buf);
+    match decoder.pull() {
+        Ok(header) => Ok(Some((header, decoder.offset()))),
+        Err(ciborium_ll::Error::Io(_)) => Ok(None),
+        Err(ciborium_ll::Error::Syntax(_)) => Err(CborError::Invalid("malformed head")),
+    }
+}
+
+/// Frames a sequence of CBOR items
+///
+/// Items are skipped head by head. When the buffer ends in the middle of an item, the framer
+/// keeps how far it got and which arrays and maps are open, and carries on from there once more
+/// data arrives, so an item that spans many chunks is only scanned once.
+#[derive(Debug, Default)]
+struct CborFramer {
+    /// The end of the heads and strings that were skipped
+    pos: usize,
+    /// The items left in the open arrays and maps, `None` for indefinite lengths
+    open: Vec<Option<u64>>,
+}
+
+impl CborFramer {
+    /// The length of the first item in `buf`, or `None` if it is incomplete
+    fn item_len(&mut self, buf: &[u8]) -> Result<Option<usize>, CborError> {
+        loop {
+            let (header, head_len) = match pull(&buf[self.pos..])? {
+                Some(pulled) => pulled,
+                None => return Ok(None),
+            };
+            let mut end = self.pos + head_len;
+            let complete = match header {
+                Header::Bytes(Some(len)) | Header::Text(Some(len)) => match end.checked_add(len) {
+                    Some(string_end) if string_end <= buf.len() => {
+                        end = string_end;
+                        true
+                    }
+                    _ => return Ok(None),
+                },
+                Header::Array(Some(0)) | Header::Map(Some(0)) => true,
+                Header::Array(Some(len)) => self.open(Some(len as u64))?,
+                Header::Map(Some(len)) => match (len as u64).checked_mul(2) {
+                    Some(items) => self.open(Some(items))?,
+                    None => return Err(CborError::Invalid("map length out of range")),
+                },
+                Header::Bytes(None) | Header::Text(None) | Header::Array(None) | Header::Map(None) => {
+                    self.open(None)?
+                }
+                Header::Break => match self.open.pop() {
+                    Some(None) => true,
+                    _ => return Err(CborError::Invalid("unexpected break")),
+                },
+                // the item of a tag follows it
+                Header::Tag(_) => false,
+                Header::Positive(_) | Header::Negative(_) | Header::Float(_) | Header::Simple(_) => true,
+            };
+            self.pos = end;
+            if complete && self.close() {
+                return Ok(Some(std::mem::take(&mut self.pos)));
+            }
+        }
+    }
+
+    /// Open an array or map of `len` items, or of an indefinite length
+    fn open(&mut self, len: Option<u64>) -> Result<bool, CborError> {
+        if self.open.len() == MAX_DEPTH {
+            return Err(CborError::Invalid("nested too deeply"));
+        }
+        self.open.push(len);
+        Ok(false)
+    }
+
+    /// Count a complete item, returning whether it completes the frame
+    fn close(&mut self) -> bool {
+        loop {
+            match self.open.last_mut() {
+                None => return true,
+                Some(None) => return false,
+                Some(Some(left)) => {
+                    *left -= 1;
+                    if *left > 0 {
+                        return false;
+                    }
+                    self.open.pop();
+                }
+            }
+        }
+    }
+}
+
+impl Framer for CborFramer {
+    fn frame_len(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        Ok(self.item_len(buf)?)
+    }
+}
+
+/// Deserializes the item of a frame, borrowing its strings
+struct Deserializer<'de> {
+    frame: &'de [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'de> Deserializer<'de> {
+    fn header(&mut self) -> Result<Header, CborError> {
+        let (header, len) = pull(&self.frame[self.pos..])?.ok_or(CborError::Incomplete)?;
+        self.pos += len;
+        Ok(header)
+    }
+
+    fn peek(&mut self) -> Result<Header, CborError> {
+        pull(&self.frame[self.pos..])?
+            .map(|(header, _)| header)
+            .ok_or(CborError::Incomplete)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'de [u8], CborError> {
+        let end = match self.pos.checked_add(len) {
+            Some(end) if end <= self.frame.len() => end,
+            _ => return Err(CborError::Incomplete),
+        };
+        let bytes = &self.frame[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    /// The content of a byte or text string, joining the chunks of indefinite lengths
+    fn string(&mut self, len: Option<usize>, text: bool) -> Result<Cow<'de, [u8]>, CborError> {
+        if let Some(len) = len {
+            return self.take(len).map(Cow::Borrowed);
+        }
+        let mut joined = vec![];
+        loop {
+            match self.header()? {
+                Header::Break => return Ok(Cow::Owned(joined)),
+                Header::Bytes(Some(len)) if !text => joined.extend_from_slice(self.take(len)?),
+                Header::Text(Some(len)) if text => joined.extend_from_slice(self.take(len)?),
+                _ => return Err(CborError::Invalid("chunk of another type")),
+            }
+        }
+    }
+
+    /// A byte or text string as a str, skipping tags
+    fn str(&mut self, header: Header) -> Result<Cow<'de, str>, CborError> {
+        match header {
+            Header::Tag(_) => {
+                let header = self.header()?;
+                self.nested(|de| de.str(header))
+            }
+            Header::Bytes(len) | Header::Text(len) => {
+                let text = matches!(header, Header::Text(_));
+                match self.string(len, text)? {
+                    Cow::Borrowed(bytes) => std::str::from_utf8(bytes).map(Cow::Borrowed).ok(),
+                    Cow::Owned(bytes) => String::from_utf8(bytes).map(Cow::Owned).ok(),
+                }
+                .ok_or(CborError::Invalid("string is not utf-8"))
+            }
+            _ => Err(CborError::Invalid("expected a string")),
+        }
+    }
+
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T, CborError>) -> Result<T, CborError> {
+        if self.depth == MAX_DEPTH {
+            return Err(CborError::Invalid("nested too deeply"));
+        }
+        self.depth += 1;
+        let res = f(self);
+        self.depth -= 1;
+        res
+    }
+
+    fn visit<V: Visitor<'de>>(&mut self, header: Header, visitor: V) -> Result<V::Value, CborError> {
+        match header {
+            Header::Positive(n) => visitor.visit_u64(n),
+            Header::Negative(n) => match i64::try_from(n) {
+                Ok(n) => visitor.visit_i64(-1 - n),
+                Err(_) => visitor.visit_i128(-1 - i128::from(n)),
+            },
+            Header::Float(f) => visitor.visit_f64(f),
+            Header::Simple(simple::FALSE) => visitor.visit_bool(false),
+            Header::Simple(simple::TRUE) => visitor.visit_bool(true),
+            Header::Simple(simple::NULL) | Header::Simple(simple::UNDEFINED) => visitor.visit_unit(),
+            Header::Simple(_) => Err(CborError::Invalid("unsupported simple value")),
+            Header::Tag(tag) => self.nested(|de| de.tagged(tag, visitor)),
+            // the apiserver encodes strings as byte strings, so those that are utf-8 are strs
+            Header::Bytes(len) => match self.string(len, false)? {
+                Cow::Borrowed(bytes) => match std::str::from_utf8(bytes) {
+                    Ok(s) => visitor.visit_borrowed_str(s),
+                    Err(_) => visitor.visit_borrowed_bytes(bytes),
+                },
+                Cow::Owned(bytes) => match String::from_utf8(bytes) {
+                    Ok(s) => visitor.visit_string(s),
+                    Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+                },
+            },
+            Header::Text(_) => match self.str(header)? {
+                Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+                Cow::Owned(s) => visitor.visit_string(s),
+            },
+            Header::Array(len) => self.nested(|de| {
+                let mut seq = Seq { de, left: len };
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end().map(|()| value)
+            }),
+            Header::Map(len) => self.nested(|de| {
+                let mut map = Seq { de, left: len };
+                let value = visitor.visit_map(&mut map)?;
+                map.end().map(|()| value)
+            }),
+            Header::Break => Err(CborError::Invalid("unexpected break")),
+        }
+    }
+
+    /// Visit the item of a tag, converting byte strings that are expected to be text
+    fn tagged<V: Visitor<'de>>(&mut self, tag: u64, visitor: V) -> Result<V::Value, CborError> {
+        let header = self.header()?;
+        let len = match header {
+            Header::Bytes(len) if matches!(tag, BASE64URL | BASE64 | BASE16) => len,
+            // other tags, like the self-described tag, only annotate their item
+            _ => return self.visit(header, visitor),
+        };
+        let bytes = self.string(len, false)?;
+        visitor.visit_string(match tag {
+            BASE64URL => base64::encode_config(&bytes, base64::URL_SAFE_NO_PAD),
+            BASE64 => base64::encode(&bytes),
+            _ => {
+                const HEX: &[u8; 16] = b"0123456789abcdef";
+                let mut hex = String::with_capacity(bytes.len() * 2);
+                for b in bytes.iter() {
+                    hex.push(char::from(HEX[usize::from(b >> 4)]));
+                    hex.push(char::from(HEX[usize::from(b & 0xf)]));
+                }
+                hex
+            }
+        })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = CborError;
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CborError> {
+        let header = self.header()?;
+        self.visit(header, visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CborError> {
+        match self.peek()? {
+            Header::Simple(simple::NULL) | Header::Simple(simple::UNDEFINED) => {
+                self.header()?;
+                visitor.visit_none()
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CborError> {
+        match self.peek()? {
+            Header::Bytes(len) => {
+                self.header()?;
+                match self.string(len, false)? {
+                    Cow::Borrowed(bytes) => visitor.visit_borrowed_bytes(bytes),
+                    Cow::Owned(bytes) => visitor.visit_byte_buf(bytes),
+                }
+            }
+            _ => self.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CborError> {
+        self.deserialize_bytes(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, CborError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CborError> {
+        // unit variants are strings, others maps of the variant to its content
+        let header = self.header()?;
+        if let Header::Map(Some(1)) = header {
+            return self.nested(|de| visitor.visit_enum(Enum { de }));
+        }
+        match self.str(header)? {
+            Cow::Borrowed(variant) => visitor.visit_enum(variant.into_deserializer()),
+            Cow::Owned(variant) => visitor.visit_enum(variant.into_deserializer()),
+        }
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, CborError> {
+        let len = CborFramer::default()
+            .item_len(&self.frame[self.pos..])?
+            .ok_or(CborError::Incomplete)?;
+        self.pos += len;
+        visitor.visit_unit()
+    }
+}
+
+/// The items of an array, or the entries of a map
+struct Seq<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    /// `None` for indefinite lengths, until their break is read
+    left: Option<usize>,
+}
+
+impl<'a, 'de> Seq<'a, 'de> {
+    /// Whether there is another item, reading the break of indefinite lengths
+    fn next(&mut self) -> Result<bool, CborError> {
+        match &mut self.left {
+            Some(0) => Ok(false),
+            Some(left) => {
+                *left -= 1;
+                Ok(true)
+            }
+            None => match self.de.peek()? {
+                Header::Break => {
+                    self.de.header()?;
+                    self.left = Some(0);
+                    Ok(false)
+                }
+                _ => Ok(true),
+            },
+        }
+    }
+
+    /// Fail if the visitor did not read all items
+    fn end(&self) -> Result<(), CborError> {
+        match self.left {
+            Some(0) => Ok(()),
+            _ => Err(CborError::Invalid("items left unread")),
+        }
+    }
+}
+
+impl<'a, 'de> de::SeqAccess<'de> for Seq<'a, 'de> {
+    type Error = CborError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, CborError> {
+        if !self.next()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.left
+    }
+}
+
+impl<'a, 'de> de::MapAccess<'de> for Seq<'a, 'de> {
+    type Error = CborError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, CborError> {
+        if !self.next()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, CborError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        self.left
+    }
+}
+
+/// A variant with content, a map of one entry
+struct Enum<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for Enum<'a, 'de> {
+    type Error = CborError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), CborError> {
+        let variant = seed.deserialize(&mut *self.de)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'a, 'de> de::VariantAccess<'de> for Enum<'a, 'de> {
+    type Error = CborError;
+
+    fn unit_variant(self) -> Result<(), CborError> {
+        de::Deserialize::deserialize(&mut *self.de)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, CborError> {
+        seed.deserialize(&mut *self.de)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, CborError> {
+        de::Deserializer::deserialize_any(&mut *self.de, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, CborError> {
+        de::Deserializer::deserialize_any(&mut *self.de, visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CborCodec, CborFramer};
+    use crate::{
+        api::{Api, ListParams, WatchEvent},
+        client::{codec, Codec},
+        Client, Error, Service,
+    };
+    use futures::TryStreamExt;
+    use http::{Request, Response};
+    use hyper::Body;
+    use k8s_openapi::{api::core::v1::Secret, ByteString};
+    use serde_json::json;
+
+    // Items encoded like the apiserver does, with strings and field names as byte strings and
+    // the data of secrets as byte strings tagged as base64, see `testdata/cbor_fixtures.py`
+    const RESOURCES: &[u8] = include_bytes!("testdata/apiresourcelist.cbor");
+    const WATCH: &[u8] = include_bytes!("testdata/watch.cbor");
+    const STATUS: &[u8] = include_bytes!("testdata/status.cbor");
+
+    #[test]
+    fn json_roundtrips_through_cbor() {
+        let value = json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {"name": "a", "generation": 3, "labels": {}},
+            "data": {"big": "x".repeat(300)},
+            "numbers": [0, 23, 24, 255, 256, 65536, 4294967296u64, -1, -25, -4294967297i64, 1.5],
+            "flags": [true, false, null],
+        });
+        let json = serde_json::to_vec(&value).unwrap();
+        let cbor = CborCodec.encode_body(&json).unwrap();
+        assert_eq!(&cbor[..3], &[0xd9, 0xd9, 0xf7]);
+        assert!(cbor.len() < json.len());
+        assert_eq!(CborCodec.framer().frame_len(&cbor).unwrap(), Some(cbor.len()));
+        assert_eq!(
+            CborCodec.framer().frame_len(&cbor[..cbor.len() - 1]).unwrap(),
+            None
+        );
+        let decoded: serde_json::Value = codec::decode(&CborCodec, &cbor).unwrap();
+        assert_eq!(decoded, value);
+    }
+
+    #[test]
+    fn decodes_byte_strings_and_indefinite_lengths() {
+        // {_ "data": h'6869', "b64": 22(h'6869'), "list": [_ 1, 2], "half": 1.5 (f16), "text": (_ "a", "b")}
+        let cbor = [
+            0xbf, 0x64, b'd', b'a', b't', b'a', 0x42, b'h', b'i', 0x63, b'b', b'6', b'4', 0xd6, 0x42, b'h',
+            b'i', 0x64, b'l', b'i', b's', b't', 0x9f, 0x01, 0x02, 0xff, 0x64, b'h', b'a', b'l', b'f', 0xf9,
+            0x3e, 0x00, 0x64, b't', b'e', b'x', b't', 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0xff,
+        ];
+        assert_eq!(CborCodec.framer().frame_len(&cbor).unwrap(), Some(cbor.len()));
+        let decoded: serde_json::Value = codec::decode(&CborCodec, &cbor).unwrap();
+        assert_eq!(
+            decoded,
+            json!({"data": "hi", "b64": "aGk=", "list": [1, 2], "half": 1.5, "text": "ab"})
+        );
+        // byte strings that are not utf-8 are not strings
+        assert!(codec::decode::<String>(&CborCodec, &[0x42, 0x00, 0xff]).is_err());
+        assert!(codec::decode::<serde_json::Value>(&CborCodec, &[0xa1, 0x01, 0x02]).is_err());
+        assert!(codec::decode::<serde_json::Value>(&CborCodec, &[0x01, 0x02]).is_err());
+    }
+
+    #[test]
+    fn framer_resumes_items_that_span_chunks() {
+        let mut framer = CborFramer::default();
+        let mut frames = vec![];
+        let mut start = 0;
+        for end in 1..=WATCH.len() {
+            if let Some(len) = framer.item_len(&WATCH[start..end]).unwrap() {
+                frames.push(&WATCH[start..start + len]);
+                start += len;
+            } else if end - start > 16 {
+                // the heads that were complete are not scanned again
+                assert!(framer.pos > 0);
+            }
+        }
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames.concat(), WATCH);
+        assert!(framer.open.is_empty());
+    }
+
+    #[tokio::test]
+    async fn apiserver_items_are_decoded() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            let res = Response::builder();
+            let res = match req.uri().path() {
+                "/api/v1" => res
+                    .header("content-type", "application/cbor")
+                    .body(Body::from(RESOURCES)),
+                "/api/v1/namespaces/apps/secrets" => {
+                    // split items across chunks
+                    let chunks: Vec<Result<_, std::io::Error>> =
+                        WATCH.chunks(7).map(|chunk| Ok(chunk.to_vec())).collect();
+                    res.header("content-type", "application/cbor-seq")
+                        .body(Body::wrap_stream(futures::stream::iter(chunks)))
+                }
+                _ => res
+                    .status(404)
+                    .header("content-type", "application/cbor")
+                    .body(Body::from(STATUS)),
+            };
+            res.map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc)).with_codec(CborCodec);
+        let resources = client.list_core_api_resources("v1").await.unwrap();
+        assert_eq!(resources.group_version, "v1");
+        let names: Vec<_> = resources.resources.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["configmaps", "secrets"]);
+        assert_eq!(resources.resources[0].short_names, Some(vec!["cm".to_string()]));
+        assert!(resources.resources[1].namespaced);
+
+        let secrets: Api<Secret> = Api::namespaced(client, "apps");
+        let events = secrets.watch(&ListParams::default(), "0").await.unwrap();
+        let events: Vec<_> = events.try_collect().await.unwrap();
+        match events.as_slice() {
+            [WatchEvent::Added(added), WatchEvent::Modified(modified)] => {
+                let data = added.data.as_ref().unwrap();
+                assert_eq!(data["password"], ByteString(b"hunter2".to_vec()));
+                assert_eq!(data["key"], ByteString(vec![0x00, 0xff, 0x10]));
+                let meta = &added.metadata;
+                assert_eq!(meta.resource_version.as_deref(), Some("812"));
+                assert_eq!(meta.labels.as_ref().unwrap()["app"], "web");
+                assert_eq!(
+                    meta.creation_timestamp.as_ref().unwrap().0.to_rfc3339(),
+                    "2024-12-11T09:30:00+00:00"
+                );
+                let data = modified.data.as_ref().unwrap();
+                assert_eq!(data["password"], ByteString(b"correct horse".to_vec()));
+            }
+            events => panic!("unexpected events {:?}", events),
+        }
+
+        let gone = secrets.get("gone").await;
+        assert!(
+            matches!(gone, Err(Error::Api(ae)) if ae.code == 404 && ae.reason == "NotFound" && ae.message == "secrets \"gone\" not found")
+        );
+    }
+}
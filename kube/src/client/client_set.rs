@@ -0,0 +1,183 @@
+use crate::{
+    api::{Api, Resource},
+    config::{Config, KubeConfigOptions},
+    Client, Result,
+};
+use std::{collections::BTreeMap, convert::TryFrom};
+
+/// A client for one cluster of a [`ClientSet`]
+#[derive(Clone)]
+struct Cluster {
+    client: Client,
+    default_ns: String,
+}
+
+/// Clients for multiple clusters, keyed by cluster name
+///
+/// Fleet management controllers can hold the in-cluster client alongside clients for several
+/// kubeconfig contexts, and construct [`Api`]s for a cluster by its name.
+/// Every cluster has a default namespace, which is used by [`ClientSet::api`].
+///
+/// ```no_run
+/// use kube::{api::Api, client::ClientSet};
+/// use k8s_openapi::api::core::v1::Pod;
+/// # async fn scope() -> Result<(), kube::Error> {
+/// let mut clients = ClientSet::new();
+/// clients.add_in_cluster("local")?;
+/// clients.add_context("staging").await?;
+/// clients.add_context("production").await?;
+/// for cluster in clients.clusters() {
+///     let pods: Api<Pod> = clients.api(cluster).unwrap();
+///     println!("{}: {} pods", cluster, pods.list(&Default::default()).await?.items.len());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ClientSet {
+    clusters: BTreeMap<String, Cluster>,
+}
+
+impl ClientSet {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add (or replace) the client for a cluster
+    pub fn insert(&mut self, cluster: &str, client: Client, default_namespace: &str) {
+        self.clusters.insert(cluster.to_string(), Cluster {
+            client,
+            default_ns: default_namespace.to_string(),
+        });
+    }
+
+    /// Add a client for a cluster from a [`Config`], using its default namespace
+    pub fn insert_config(&mut self, cluster: &str, config: Config) -> Result<()> {
+        let default_ns = config.default_ns.clone();
+        self.insert(cluster, Client::try_from(config)?, &default_ns);
+        Ok(())
+    }
+
+    /// Add the cluster the program is running in
+    pub fn add_in_cluster(&mut self, cluster: &str) -> Result<()> {
+        self.insert_config(cluster, Config::from_cluster_env()?)
+    }
+
+    /// Add a context of the local kubeconfig, named after the context
+    pub async fn add_context(&mut self, context: &str) -> Result<()> {
+        let options = KubeConfigOptions {
+            context: Some(context.to_string()),
+            ..KubeConfigOptions::default()
+        };
+        self.insert_config(context, Config::from_kubeconfig(&options).await?)
+    }
+
+    /// Remove a cluster, returning its client
+    pub fn remove(&mut self, cluster: &str) -> Option<Client> {
+        self.clusters.remove(cluster).map(|c| c.client)
+    }
+
+    /// Names of the clusters in the set, in sorted order
+    pub fn clusters(&self) -> impl Iterator<Item = &str> {
+        self.clusters.keys().map(String::as_str)
+    }
+
+    /// The client for a cluster
+    pub fn client(&self, cluster: &str) -> Option<&Client> {
+        self.clusters.get(cluster).map(|c| &c.client)
+    }
+
+    /// The default namespace of a cluster
+    pub fn default_namespace(&self, cluster: &str) -> Option<&str> {
+        self.clusters.get(cluster).map(|c| c.default_ns.as_str())
+    }
+
+    /// An [`Api`] in the default namespace of a cluster
+    pub fn api<K>(&self, cluster: &str) -> Option<Api<K>>
+    where
+        K: Resource,
+        <K as Resource>::DynamicType: Default,
+    {
+        self.api_with(cluster, &Default::default())
+    }
+
+    /// An [`Api`] in the default namespace of a cluster, for a resource with a dynamic type
+    pub fn api_with<K: Resource>(&self, cluster: &str, dyntype: &K::DynamicType) -> Option<Api<K>> {
+        let c = self.clusters.get(cluster)?;
+        Some(Api::namespaced_with(c.client.clone(), &c.default_ns, dyntype))
+    }
+
+    /// An [`Api`] in a namespace of a cluster
+    pub fn namespaced_api<K>(&self, cluster: &str, namespace: &str) -> Option<Api<K>>
+    where
+        K: Resource,
+        <K as Resource>::DynamicType: Default,
+    {
+        let client = self.client(cluster)?.clone();
+        Some(Api::namespaced(client, namespace))
+    }
+
+    /// An [`Api`] across all namespaces (or for cluster scoped resources) of a cluster
+    pub fn all_api<K>(&self, cluster: &str) -> Option<Api<K>>
+    where
+        K: Resource,
+        <K as Resource>::DynamicType: Default,
+    {
+        let client = self.client(cluster)?.clone();
+        Some(Api::all(client))
+    }
+}
+
+impl std::fmt::Debug for ClientSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(self.clusters.iter().map(|(name, c)| (name, &c.default_ns)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ClientSet;
+    use crate::{api::Api, Client, Service};
+    use http::{Request, Response};
+    use hyper::Body;
+    use k8s_openapi::api::core::v1::ConfigMap;
+    use std::sync::{Arc, Mutex};
+
+    fn recording_client(cluster: &'static str, seen: Arc<Mutex<Vec<String>>>) -> Client {
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            seen.lock().unwrap().push(format!("{} {}", cluster, req.uri()));
+            async {
+                Response::builder()
+                    .body(Body::from(r#"{"metadata":{},"items":[]}"#))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        Client::new(Service::new(svc))
+    }
+
+    #[tokio::test]
+    async fn apis_are_keyed_by_cluster() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let mut clients = ClientSet::new();
+        clients.insert("east", recording_client("east", seen.clone()), "apps");
+        clients.insert("west", recording_client("west", seen.clone()), "default");
+        assert_eq!(clients.clusters().collect::<Vec<_>>(), vec!["east", "west"]);
+        assert_eq!(clients.default_namespace("east"), Some("apps"));
+        assert!(clients.api::<ConfigMap>("north").is_none());
+
+        let east: Api<ConfigMap> = clients.api("east").unwrap();
+        east.list(&Default::default()).await.unwrap();
+        let west: Api<ConfigMap> = clients.namespaced_api("west", "kube-system").unwrap();
+        west.list(&Default::default()).await.unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec![
+            "east /api/v1/namespaces/apps/configmaps?",
+            "west /api/v1/namespaces/kube-system/configmaps?",
+        ]);
+
+        assert!(clients.remove("east").is_some());
+        assert_eq!(clients.clusters().collect::<Vec<_>>(), vec!["west"]);
+    }
+}
@@ -0,0 +1,252 @@
+use http::{header, HeaderValue, Request};
+use serde::de::{self, DeserializeOwned};
+
+use crate::{Error, Result};
+
+/// The media type of json bodies
+const JSON: &str = "application/json";
+
+/// Deserializes the expected type from the deserializer of a [`Codec`] format
+pub struct Visit<'a, 'de> {
+    visit: &'a mut ErasedVisit<'a, 'de>,
+}
+
+type ErasedVisit<'a, 'de> =
+    dyn FnMut(&mut dyn erased_serde::Deserializer<'de>) -> Result<(), erased_serde::Error> + 'a;
+
+impl<'a, 'de> Visit<'a, 'de> {
+    /// Deserialize the expected type from `de`
+    pub fn deserialize<D: de::Deserializer<'de>>(self, de: D) -> Result<(), D::Error> {
+        let mut de = <dyn erased_serde::Deserializer>::erase(de);
+        (self.visit)(&mut de).map_err(de::Error::custom)
+    }
+}
+
+/// Serialization of bodies on the wire, selected with [`Client::with_codec`](crate::Client::with_codec)
+/// or [`Api::with_codec`](crate::Api::with_codec)
+///
+/// Objects are serialized as json by the typed apis, a codec translates json request bodies into
+/// the format that is sent over the wire. Responses are deserialized straight from that format,
+/// with the [`serde::Deserializer`] passed to a [`Visit`], so types need not implement anything
+/// for it. This lets a format with smaller or cheaper to parse bodies be chosen per resource.
+///
+/// Requests ask for the codec's media type, with json as a fallback, since a server need not
+/// support it for every resource. Responses are only decoded when they are of that media type.
+/// Requests that ask for another media type, like patches and tables, are left alone.
+pub trait Codec: Send + Sync {
+    /// The media type of the format, like `application/json`
+    fn media_type(&self) -> &str;
+
+    /// Whether responses of `media_type` are in this format
+    ///
+    /// Defaults to the [`media_type`](Self::media_type), formats with another media type for
+    /// streams can add it.
+    fn decodes(&self, media_type: &str) -> bool {
+        media_type == self.media_type()
+    }
+
+    /// Encode a json request body
+    fn encode_body(&self, json: &[u8]) -> Result<Vec<u8>>;
+
+    /// Decode a response body, or a frame of a watch
+    ///
+    /// `visit` is given a deserializer of the whole frame. Data after the value it deserializes
+    /// is an error.
+    fn decode_frame<'de>(&self, frame: &'de [u8], visit: Visit<'_, 'de>) -> Result<()>;
+
+    /// Splits the frames of a watch
+    ///
+    /// Defaults to newline delimited frames.
+    fn framer(&self) -> Box<dyn Framer> {
+        Box::new(LineFramer::default())
+    }
+}
+
+/// Splits a watch response into frames, see [`Codec::framer`]
+///
+/// A framer is created for every watch, so it can keep how much of the next frame it has seen.
+pub trait Framer: Send {
+    /// The length of the first frame in `buf`, or `None` if it is incomplete
+    ///
+    /// Until a frame is found, every call passes the same buffer with more data appended, so only
+    /// the new data needs to be scanned.
+    fn frame_len(&mut self, buf: &[u8]) -> Result<Option<usize>>;
+}
+
+/// A [`Framer`] of newline delimited frames, which include the newline
+#[derive(Clone, Debug, Default)]
+pub struct LineFramer {
+    /// How much of the buffer is known to have no newline
+    scanned: usize,
+}
+
+impl Framer for LineFramer {
+    fn frame_len(&mut self, buf: &[u8]) -> Result<Option<usize>> {
+        match buf[self.scanned..].iter().position(|b| *b == b'\n') {
+            Some(offset) => {
+                let len = self.scanned + offset + 1;
+                self.scanned = 0;
+                Ok(Some(len))
+            }
+            None => {
+                self.scanned = buf.len();
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// The json [`Codec`], which the apiserver supports for all resources
+///
+/// Bodies are sent as they are, so this is the same as using no codec.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    fn media_type(&self) -> &str {
+        JSON
+    }
+
+    fn encode_body(&self, json: &[u8]) -> Result<Vec<u8>> {
+        Ok(json.to_vec())
+    }
+
+    fn decode_frame<'de>(&self, frame: &'de [u8], visit: Visit<'_, 'de>) -> Result<()> {
+        let mut de = serde_json::Deserializer::from_slice(frame);
+        visit.deserialize(&mut de)?;
+        Ok(de.end()?)
+    }
+}
+
+/// Ask for the codec's format, and encode a json body with it
+pub(crate) fn encode_request(codec: &dyn Codec, request: Request<Vec<u8>>) -> Result<Request<Vec<u8>>> {
+    let (mut parts, mut body) = request.into_parts();
+    let media_type = codec.media_type();
+    if media_type == JSON || parts.headers.contains_key(header::ACCEPT) {
+        return Ok(Request::from_parts(parts, body));
+    }
+    let (accept, content_type) = header_values(media_type)?;
+    parts.headers.insert(header::ACCEPT, accept);
+    let is_json = match parts.headers.get(header::CONTENT_TYPE) {
+        Some(content_type) => content_type == JSON,
+        None => true,
+    };
+    if !body.is_empty() && is_json {
+        body = codec.encode_body(&body)?;
+        parts.headers.insert(header::CONTENT_TYPE, content_type);
+    }
+    Ok(Request::from_parts(parts, body))
+}
+
+/// Whether a response is in the codec's format, rather than the json fallback
+pub(crate) fn is_encoded(codec: &dyn Codec, content_type: Option<&HeaderValue>) -> bool {
+    let media_type = content_type
+        .and_then(|ct| ct.to_str().ok())
+        .and_then(|ct| ct.split(';').next())
+        .map(str::trim);
+    matches!(media_type, Some(mt) if mt != JSON && codec.decodes(mt))
+}
+
+/// Deserialize a `T` from a body or frame in the codec's format
+pub(crate) fn decode<T: DeserializeOwned>(codec: &dyn Codec, frame: &[u8]) -> Result<T> {
+    let mut value = None;
+    let mut visit = |de: &mut dyn erased_serde::Deserializer<'_>| {
+        value = Some(erased_serde::deserialize(de)?);
+        Ok(())
+    };
+    codec.decode_frame(frame, Visit { visit: &mut visit })?;
+    value.ok_or_else(|| Error::Codec("the codec did not deserialize the frame".into()))
+}
+
+fn header_values(media_type: &str) -> Result<(HeaderValue, HeaderValue)> {
+    let accept = HeaderValue::from_str(&format!("{}, {}", media_type, JSON));
+    let content_type = HeaderValue::from_str(media_type);
+    match (accept, content_type) {
+        (Ok(accept), Ok(content_type)) => Ok((accept, content_type)),
+        (Err(e), _) | (_, Err(e)) => Err(http::Error::from(e).into()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Codec, Framer, JsonCodec, LineFramer, Visit};
+    use crate::{
+        api::{Api, ListParams, PostParams, WatchEvent},
+        Client, Error, Result, Service,
+    };
+    use futures::{StreamExt, TryStreamExt};
+    use http::{Request, Response};
+    use hyper::Body;
+    use k8s_openapi::api::core::v1::ConfigMap;
+
+    /// Json prefixed with a `~`
+    struct TildeCodec;
+
+    impl Codec for TildeCodec {
+        fn media_type(&self) -> &str {
+            "application/x-tilde"
+        }
+
+        fn encode_body(&self, json: &[u8]) -> Result<Vec<u8>> {
+            Ok([b"~", json].concat())
+        }
+
+        fn decode_frame<'de>(&self, frame: &'de [u8], visit: Visit<'_, 'de>) -> Result<()> {
+            match frame.strip_prefix(b"~") {
+                Some(json) => JsonCodec.decode_frame(json, visit),
+                None => Err(Error::Codec("missing tilde".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn bodies_are_exchanged_with_the_codec() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            assert_eq!(req.headers()["accept"], "application/x-tilde, application/json");
+            let watch = req.uri().query().unwrap_or_default().contains("watch=true");
+            if req.uri().path().ends_with("/missing") {
+                let status = r#"~{"status":"Failure","message":"gone","reason":"NotFound","code":404}"#;
+                return Response::builder()
+                    .status(404)
+                    .header("content-type", "application/x-tilde")
+                    .body(Body::from(status))
+                    .map_err(tower::BoxError::from);
+            }
+            let body = if watch {
+                "~{\"type\":\"ADDED\",\"object\":{\"metadata\":{\"name\":\"a\"}}}\n".to_string()
+            } else {
+                assert_eq!(req.headers()["content-type"], "application/x-tilde");
+                let body = hyper::body::to_bytes(req.into_body()).await?;
+                assert_eq!(body[0], b'~');
+                String::from_utf8(body.to_vec())?
+            };
+            Response::builder()
+                .header("content-type", "application/x-tilde")
+                .body(Body::from(body))
+                .map_err(tower::BoxError::from)
+        });
+        let api: Api<ConfigMap> =
+            Api::namespaced(Client::new(Service::new(svc)), "ns").with_codec(TildeCodec);
+        let mut cm = ConfigMap::default();
+        cm.metadata.name = Some("a".into());
+        let created = api.create(&PostParams::default(), &cm).await.unwrap();
+        assert_eq!(created.metadata.name.as_deref(), Some("a"));
+        let missing = api.get("missing").await;
+        assert!(matches!(missing, Err(Error::Api(ae)) if ae.code == 404 && ae.message == "gone"));
+
+        let events = api.watch(&ListParams::default(), "0").await.unwrap().boxed();
+        let events: Vec<WatchEvent<ConfigMap>> = events.try_collect().await.unwrap();
+        assert!(
+            matches!(events.as_slice(), [WatchEvent::Added(cm)] if cm.metadata.name.as_deref() == Some("a"))
+        );
+    }
+
+    #[test]
+    fn line_frames_resume_where_they_stopped() {
+        let mut framer = LineFramer::default();
+        assert_eq!(framer.frame_len(b"ab").unwrap(), None);
+        // the newline of data that was already scanned is not looked for again
+        assert_eq!(framer.frame_len(b"a\nb\n").unwrap(), Some(4));
+        assert_eq!(framer.frame_len(b"\n").unwrap(), Some(1));
+    }
+}
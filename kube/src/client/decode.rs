@@ -0,0 +1,224 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{error::DeserializeError, Error, Result};
+
+/// Characters of the raw json shown on either side of an error
+const SNIPPET_CONTEXT: usize = 40;
+
+/// Deserialize a response, with context about the failing object and field on errors
+pub(crate) fn from_slice<T: DeserializeOwned>(data: &[u8]) -> Result<T> {
+    serde_json::from_slice(data).map_err(|e| {
+        let err = with_context(data, e);
+        tracing::warn!("{}", err);
+        err
+    })
+}
+
+/// Add context from the raw json to a deserialization error
+pub(crate) fn with_context(data: &[u8], source: serde_json::Error) -> Error {
+    let text = String::from_utf8_lossy(data);
+    let offset = byte_offset(&text, source.line(), source.column());
+    let path = json_path(&text, offset);
+    let mut err = DeserializeError {
+        api_version: None,
+        kind: None,
+        name: None,
+        namespace: None,
+        path: render_path(&path),
+        snippet: snippet(&text, offset),
+        source,
+    };
+    // syntactically valid json failing to match the type, describe the failing object
+    if let Ok(value) = serde_json::from_str::<Value>(&text) {
+        describe(&mut err, &value, &path);
+    }
+    Error::Deserialize(Box::new(err))
+}
+
+/// A step of a json path
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+/// Fill in the kind and identity of the innermost object along `path` that has them
+fn describe(err: &mut DeserializeError, mut value: &Value, path: &[Segment]) {
+    let str_at = |v: &Value, pointer: &str| v.pointer(pointer).and_then(Value::as_str).map(String::from);
+    let mut visit = |v: &Value| {
+        if let Some(name) = str_at(v, "/metadata/name") {
+            err.name = Some(name);
+            err.namespace = str_at(v, "/metadata/namespace");
+        }
+        if let Some(kind) = str_at(v, "/kind") {
+            err.kind = Some(kind);
+            err.api_version = str_at(v, "/apiVersion");
+        }
+    };
+    visit(value);
+    for segment in path {
+        let next = match segment {
+            Segment::Key(key) => value.get(key),
+            Segment::Index(i) => value.get(i),
+        };
+        value = match next {
+            Some(next) => next,
+            None => break,
+        };
+        visit(value);
+    }
+}
+
+/// Byte offset of a 1-based line and column, as reported by serde_json
+fn byte_offset(text: &str, line: usize, column: usize) -> usize {
+    let line_start: usize = text
+        .split_inclusive('\n')
+        .take(line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    (line_start + column).min(text.len())
+}
+
+/// The path of containers enclosing `offset` in `text`
+fn json_path(text: &str, offset: usize) -> Vec<Segment> {
+    // containers, with the current key of objects and whether a key is expected
+    let mut stack: Vec<(Segment, bool)> = vec![];
+    let bytes = &text.as_bytes()[..offset];
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => {
+                let start = i + 1;
+                i += 1;
+                while i < bytes.len() && bytes[i] != b'"' {
+                    if bytes[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                if let Some((Segment::Key(key), expect_key)) = stack.last_mut() {
+                    if *expect_key {
+                        *key = text[start..i.min(bytes.len())].to_string();
+                        *expect_key = false;
+                    }
+                }
+            }
+            b'{' => stack.push((Segment::Key(String::new()), true)),
+            b'[' => stack.push((Segment::Index(0), false)),
+            b'}' | b']' => {
+                stack.pop();
+            }
+            b',' => match stack.last_mut() {
+                Some((Segment::Index(index), _)) => *index += 1,
+                Some((Segment::Key(_), expect_key)) => *expect_key = true,
+                None => {}
+            },
+            _ => {}
+        }
+        i += 1;
+    }
+    stack
+        .into_iter()
+        .map(|(segment, _)| segment)
+        .filter(|segment| segment != &Segment::Key(String::new()))
+        .collect()
+}
+
+/// Render a path like `.items[3].spec.replicas`
+fn render_path(path: &[Segment]) -> String {
+    let mut rendered = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                rendered.push('.');
+                rendered.push_str(key);
+            }
+            Segment::Index(i) => rendered.push_str(&format!("[{}]", i)),
+        }
+    }
+    if rendered.is_empty() {
+        rendered.push('.');
+    }
+    rendered
+}
+
+/// The raw json around `offset`
+fn snippet(text: &str, offset: usize) -> String {
+    let mut start = offset.saturating_sub(SNIPPET_CONTEXT);
+    while !text.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (offset + SNIPPET_CONTEXT).min(text.len());
+    while !text.is_char_boundary(end) {
+        end += 1;
+    }
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    snippet.push_str(&text[start..end]);
+    if end < text.len() {
+        snippet.push_str("...");
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod test {
+    use super::from_slice;
+    use crate::{api::ObjectList, Error};
+    use k8s_openapi::api::apps::v1::Deployment;
+
+    #[test]
+    fn errors_describe_the_failing_object() {
+        let list = serde_json::json!({
+            "apiVersion": "apps/v1",
+            "kind": "DeploymentList",
+            "metadata": {},
+            "items": [
+                { "metadata": { "name": "ok", "namespace": "default" }, "spec": { "selector": {}, "template": {} } },
+                { "metadata": { "name": "blog", "namespace": "prod" }, "spec": { "replicas": "three", "selector": {}, "template": {} } }
+            ]
+        });
+        let data = serde_json::to_vec(&list).unwrap();
+        let err = match from_slice::<ObjectList<Deployment>>(&data) {
+            Err(Error::Deserialize(err)) => err,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(err.path, ".items[1].spec.replicas");
+        assert_eq!(err.name.as_deref(), Some("blog"));
+        assert_eq!(err.namespace.as_deref(), Some("prod"));
+        assert_eq!(err.kind.as_deref(), Some("DeploymentList"));
+        assert!(err.snippet.contains(r#""replicas":"three""#));
+        let text = err.to_string();
+        assert!(
+            text.contains("DeploymentList prod/blog at .items[1].spec.replicas"),
+            "{}",
+            text
+        );
+    }
+
+    #[test]
+    fn missing_fields_point_at_their_object() {
+        let data =
+            br#"{"apiVersion":"v1","kind":"Status","metadata":{},"items":[{"metadata":{"name":"a"}}]}"#;
+        #[derive(serde::Deserialize, Debug)]
+        struct Item {
+            #[allow(dead_code)]
+            spec: serde_json::Value,
+        }
+        #[derive(serde::Deserialize, Debug)]
+        struct List {
+            #[allow(dead_code)]
+            items: Vec<Item>,
+        }
+        match from_slice::<List>(data) {
+            Err(Error::Deserialize(err)) => {
+                assert_eq!(err.path, ".items[0]");
+                assert_eq!(err.name.as_deref(), Some("a"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use http::{
+    header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT},
+    Request,
+};
+
+use crate::{error::ConfigError, Result};
+
+/// Header naming the `FlowSchema` the apiserver matched a request to
+pub const FLOW_SCHEMA_HEADER: &str = "x-kubernetes-pf-flowschema-uid";
+/// Header naming the `PriorityLevelConfiguration` the apiserver assigned a request to
+pub const PRIORITY_LEVEL_HEADER: &str = "x-kubernetes-pf-prioritylevel-uid";
+
+/// Hints for how the apiserver classifies requests for [API Priority and Fairness]
+///
+/// The apiserver assigns every request to a priority level through the first matching `FlowSchema`.
+/// FlowSchemas match on the user, groups or service account making the request, so impersonating a
+/// dedicated user or group is how a client picks a priority level, e.g. to run a low priority cache
+/// warmup next to high priority writes. The component is appended to the `User-Agent`, which does
+/// not affect the classification, but identifies the traffic in audit logs and apiserver metrics.
+///
+/// Impersonation requires the `impersonate` verb on the users and groups in RBAC.
+///
+/// ```no_run
+/// use kube::{api::{Api, ListParams}, client::FlowHint, Client};
+/// use k8s_openapi::api::core::v1::Pod;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let warmup = FlowHint::new("cache-warmup")?.impersonate_group("my-operator:low-priority")?;
+/// let pods: Api<Pod> = Api::all(client.with_flow_hint(warmup));
+/// let all = pods.list(&ListParams::default()).await?;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [API Priority and Fairness]: https://kubernetes.io/docs/concepts/cluster-administration/flow-control/
+#[derive(Clone, Debug, Default)]
+pub struct FlowHint {
+    component: Option<String>,
+    headers: HeaderMap,
+}
+
+impl FlowHint {
+    /// Create a hint for traffic of a named component, e.g. `cache-warmup`
+    pub fn new(component: &str) -> Result<Self> {
+        // validate the component early, rather than on every request
+        HeaderValue::from_str(component).map_err(ConfigError::InvalidFlowHint)?;
+        Ok(Self {
+            component: Some(component.to_string()),
+            headers: HeaderMap::new(),
+        })
+    }
+
+    /// Make requests as `user`, to be matched by FlowSchemas for that user
+    pub fn impersonate_user(self, user: &str) -> Result<Self> {
+        self.header(HeaderName::from_static("impersonate-user"), user)
+    }
+
+    /// Make requests with the added `group`, to be matched by FlowSchemas for that group
+    ///
+    /// The apiserver requires a user to impersonate groups, so combine this with [`impersonate_user`](Self::impersonate_user).
+    pub fn impersonate_group(mut self, group: &str) -> Result<Self> {
+        let value = HeaderValue::from_str(group).map_err(ConfigError::InvalidFlowHint)?;
+        self.headers
+            .append(HeaderName::from_static("impersonate-group"), value);
+        Ok(self)
+    }
+
+    /// Set an arbitrary header on requests
+    pub fn header(mut self, name: HeaderName, value: &str) -> Result<Self> {
+        let value = HeaderValue::from_str(value).map_err(ConfigError::InvalidFlowHint)?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    /// Add the hint to a request, headers already set on the request are kept
+    pub(crate) fn apply<B>(&self, req: &mut Request<B>) {
+        let headers = req.headers_mut();
+        for name in self.headers.keys() {
+            if !headers.contains_key(name) {
+                for value in self.headers.get_all(name) {
+                    headers.append(name.clone(), value.clone());
+                }
+            }
+        }
+        if let Some(component) = &self.component {
+            let agent = match headers.get(USER_AGENT).and_then(|v| v.to_str().ok()) {
+                Some(agent) => format!("{} ({})", agent, component),
+                None => format!("kube-rs/{} ({})", env!("CARGO_PKG_VERSION"), component),
+            };
+            if let Ok(agent) = HeaderValue::from_str(&agent) {
+                headers.insert(USER_AGENT, agent);
+            }
+        }
+    }
+}
+
+/// Log how the apiserver classified a request, when it says so
+pub(crate) fn log_classification(headers: &HeaderMap) {
+    let flow_schema = headers.get(FLOW_SCHEMA_HEADER).and_then(|v| v.to_str().ok());
+    let priority_level = headers.get(PRIORITY_LEVEL_HEADER).and_then(|v| v.to_str().ok());
+    if flow_schema.is_some() || priority_level.is_some() {
+        tracing::trace!(?flow_schema, ?priority_level, "request classified");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FlowHint;
+    use crate::{Client, Service};
+    use http::{Request, Response};
+    use hyper::Body;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn hints_are_added_to_requests() {
+        let seen = Arc::new(Mutex::new(vec![]));
+        let sink = seen.clone();
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            sink.lock().unwrap().push(req.headers().clone());
+            async {
+                Response::builder()
+                    .body(Body::from("{}"))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let client = Client::new(Service::new(svc));
+        let hint = FlowHint::new("cache-warmup")
+            .unwrap()
+            .impersonate_user("warmup")
+            .unwrap()
+            .impersonate_group("low")
+            .unwrap()
+            .impersonate_group("batch")
+            .unwrap();
+        let hinted = client.clone().with_flow_hint(hint);
+
+        let req = || Request::builder().uri("/api").body(vec![]).unwrap();
+        hinted.request_text(req()).await.unwrap();
+        client.request_text(req()).await.unwrap();
+        let mut custom = req();
+        custom
+            .headers_mut()
+            .insert("user-agent", "my-tool/1.0".parse().unwrap());
+        hinted.request_text(custom).await.unwrap();
+
+        let seen = seen.lock().unwrap();
+        let agent = seen[0]["user-agent"].to_str().unwrap();
+        assert!(agent.starts_with("kube-rs/") && agent.ends_with(" (cache-warmup)"));
+        assert_eq!(seen[0]["impersonate-user"], "warmup");
+        let groups: Vec<_> = seen[0].get_all("impersonate-group").iter().collect();
+        assert_eq!(groups, vec!["low", "batch"]);
+        assert!(!seen[1].contains_key("impersonate-user"));
+        assert_eq!(seen[2]["user-agent"], "my-tool/1.0 (cache-warmup)");
+    }
+}
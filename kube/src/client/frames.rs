@@ -0,0 +1,363 @@
+use bytes::{Buf, Bytes, BytesMut};
+use serde::de::DeserializeOwned;
+use std::{io, marker::PhantomData};
+use tokio_util::codec::{Decoder, LinesCodecError};
+
+#[cfg(feature = "codec")] use super::Framer;
+
+/// Decodes a stream of newline delimited json values, for [`Client::request_stream`](crate::Client::request_stream)
+///
+/// This is the framing of kubernetes watches, which many aggregated apis reuse for their streams.
+/// Empty lines are skipped.
+#[derive(Debug)]
+pub struct JsonLinesDecoder<T> {
+    phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> JsonLinesDecoder<T> {
+    /// Create a decoder of newline delimited `T`s
+    pub fn new() -> Self {
+        Self { phantom: PhantomData }
+    }
+}
+
+impl<T> Default for JsonLinesDecoder<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: DeserializeOwned> Decoder for JsonLinesDecoder<T> {
+    type Error = io::Error;
+    type Item = T;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<T>, io::Error> {
+        while let Some(newline) = src.iter().position(|b| *b == b'\n') {
+            let line = src.split_to(newline + 1);
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            return Ok(Some(serde_json::from_slice(&line)?));
+        }
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<T>, io::Error> {
+        if let Some(value) = self.decode(src)? {
+            return Ok(Some(value));
+        }
+        // a final value without a trailing newline
+        if src.iter().all(u8::is_ascii_whitespace) {
+            src.clear();
+            return Ok(None);
+        }
+        let rest = src.split();
+        Ok(Some(serde_json::from_slice(&rest)?))
+    }
+}
+
+/// Splits a watch response into its newline delimited frames
+///
+/// Unlike a `LinesCodec`, frames are not copied into strings, and a frame that spans many chunks
+/// is scanned for its newline only once. The buffer grows by doubling while a frame is incomplete,
+/// so megabyte-scale objects are not copied once per chunk.
+pub(crate) struct WatchFrameDecoder {
+    max_frame_size: Option<usize>,
+    /// How much of the buffer is known to have no newline
+    scanned: usize,
+    /// Splits a response in another format than json instead, see [`Codec::framer`](super::Codec::framer)
+    #[cfg(feature = "codec")]
+    framer: Option<Box<dyn Framer>>,
+}
+
+impl WatchFrameDecoder {
+    pub(crate) fn new(max_frame_size: Option<usize>) -> Self {
+        Self {
+            max_frame_size,
+            scanned: 0,
+            #[cfg(feature = "codec")]
+            framer: None,
+        }
+    }
+
+    /// Split frames with the framer of a codec, which are left to it to decode
+    #[cfg(feature = "codec")]
+    pub(crate) fn with_framer(self, framer: Box<dyn Framer>) -> Self {
+        Self {
+            framer: Some(framer),
+            ..self
+        }
+    }
+
+    fn exceeds(&self, len: usize) -> bool {
+        matches!(self.max_frame_size, Some(max) if len > max)
+    }
+
+    /// The length of the first frame, and of its content without a delimiter
+    fn frame_len(&mut self, src: &[u8]) -> Result<Option<(usize, usize)>, LinesCodecError> {
+        #[cfg(feature = "codec")]
+        if let Some(framer) = &mut self.framer {
+            let len = framer.frame_len(src).map_err(invalid_data)?;
+            return Ok(len.map(|len| (len, len)));
+        }
+        match src[self.scanned..].iter().position(|b| *b == b'\n') {
+            Some(offset) => {
+                let newline = self.scanned + offset;
+                self.scanned = 0;
+                Ok(Some((newline + 1, newline)))
+            }
+            None => {
+                self.scanned = src.len();
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "codec")]
+fn invalid_data(err: crate::Error) -> LinesCodecError {
+    LinesCodecError::Io(io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+impl Decoder for WatchFrameDecoder {
+    type Error = LinesCodecError;
+    type Item = Bytes;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>, LinesCodecError> {
+        if let Some((len, content_len)) = self.frame_len(src)? {
+            if self.exceeds(content_len) {
+                return Err(LinesCodecError::MaxLineLengthExceeded);
+            }
+            let mut frame = src.split_to(len);
+            frame.truncate(content_len);
+            return Ok(Some(frame.freeze()));
+        }
+        if self.exceeds(src.len()) {
+            return Err(LinesCodecError::MaxLineLengthExceeded);
+        }
+        // `FramedRead` only reserves room for the next chunk, double instead
+        let mut additional = src.len();
+        if let Some(max) = self.max_frame_size {
+            additional = additional.min(max + 1 - src.len());
+        }
+        src.reserve(additional);
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, src: &mut BytesMut) -> Result<Option<Bytes>, LinesCodecError> {
+        if let Some(frame) = self.decode(src)? {
+            return Ok(Some(frame));
+        }
+        // a final frame without a trailing newline
+        self.scanned = 0;
+        if src.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(src.split().freeze()))
+    }
+}
+
+/// An event of a `text/event-stream`, see [`SseDecoder`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The `event` field, the type of the event
+    pub event: Option<String>,
+    /// The `data` fields, joined by newlines
+    pub data: String,
+    /// The `id` field
+    pub id: Option<String>,
+    /// The `retry` field, the reconnection time in milliseconds
+    pub retry: Option<u64>,
+}
+
+/// Decodes [server-sent events](https://html.spec.whatwg.org/multipage/server-sent-events.html),
+/// for [`Client::request_stream`](crate::Client::request_stream)
+///
+/// Comments and events without any fields are skipped.
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    event: SseEvent,
+    has_fields: bool,
+    has_data: bool,
+}
+
+impl SseDecoder {
+    /// Create a decoder of server-sent events
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a line of the stream, returning an event when the line ends it
+    fn line(&mut self, line: &str) -> Option<SseEvent> {
+        if line.is_empty() {
+            if !self.has_fields {
+                return None;
+            }
+            self.has_fields = false;
+            self.has_data = false;
+            return Some(std::mem::take(&mut self.event));
+        }
+        if line.starts_with(':') {
+            return None;
+        }
+        let (field, value) = match line.find(':') {
+            Some(colon) => {
+                let value = &line[colon + 1..];
+                (&line[..colon], value.strip_prefix(' ').unwrap_or(value))
+            }
+            None => (line, ""),
+        };
+        match field {
+            "event" => self.event.event = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.event.data.push('\n');
+                }
+                self.event.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" => self.event.id = Some(value.to_string()),
+            "retry" => self.event.retry = value.parse().ok(),
+            // unknown fields are ignored
+            _ => return None,
+        }
+        self.has_fields = true;
+        None
+    }
+}
+
+impl Decoder for SseDecoder {
+    type Error = io::Error;
+    type Item = SseEvent;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<SseEvent>, io::Error> {
+        while let Some(newline) = src.iter().position(|b| *b == b'\n') {
+            let mut line = src.split_to(newline + 1);
+            line.truncate(newline);
+            if line.last() == Some(&b'\r') {
+                line.truncate(newline - 1);
+            }
+            let line = std::str::from_utf8(line.chunk())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            if let Some(event) = self.line(line) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{JsonLinesDecoder, SseDecoder, SseEvent, WatchFrameDecoder};
+    use crate::{Client, Error, Service};
+    use bytes::BytesMut;
+    use futures::TryStreamExt;
+    use http::{Request, Response, StatusCode};
+    use hyper::Body;
+    use tokio_util::codec::{Decoder, LinesCodecError};
+
+    #[test]
+    fn watch_frames_span_chunks_and_are_limited() {
+        let mut decoder = WatchFrameDecoder::new(Some(8));
+        let mut buf = BytesMut::from(&b"{\"a\""[..]);
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        // the buffer doubles for incomplete frames
+        assert!(buf.capacity() >= 8);
+        buf.extend_from_slice(b":1}\n{}");
+        assert_eq!(&decoder.decode(&mut buf).unwrap().unwrap()[..], b"{\"a\":1}");
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        assert_eq!(&decoder.decode_eof(&mut buf).unwrap().unwrap()[..], b"{}");
+        assert_eq!(decoder.decode_eof(&mut buf).unwrap(), None);
+
+        let mut buf = BytesMut::from(&b"{\"b\":\"xxxx"[..]);
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(LinesCodecError::MaxLineLengthExceeded)
+        ));
+        let mut decoder = WatchFrameDecoder::new(Some(8));
+        let mut buf = BytesMut::from(&b"{\"b\":\"xxxx\"}\n"[..]);
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(LinesCodecError::MaxLineLengthExceeded)
+        ));
+    }
+
+    #[test]
+    fn json_lines_are_decoded() {
+        let mut decoder = JsonLinesDecoder::<serde_json::Value>::new();
+        let mut buf = BytesMut::from(&b"{\"a\":1}\n\n{\"b\":"[..]);
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some(serde_json::json!({"a": 1}))
+        );
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"2}");
+        assert_eq!(
+            decoder.decode_eof(&mut buf).unwrap(),
+            Some(serde_json::json!({"b": 2}))
+        );
+        assert_eq!(decoder.decode_eof(&mut buf).unwrap(), None);
+        let mut bad = BytesMut::from(&b"nope\n"[..]);
+        assert!(decoder.decode(&mut bad).is_err());
+    }
+
+    #[test]
+    fn server_sent_events_are_decoded() {
+        let mut decoder = SseDecoder::new();
+        let mut buf = BytesMut::from(&b": keepalive\n\nevent: usage\r\ndata: {\"cpu\":\n"[..]);
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"data: 1}\nid: 7\n\ndata:plain\nretry: 3000\n\n");
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some(SseEvent {
+                event: Some("usage".into()),
+                data: "{\"cpu\":\n1}".into(),
+                id: Some("7".into()),
+                retry: None,
+            })
+        );
+        assert_eq!(
+            decoder.decode(&mut buf).unwrap(),
+            Some(SseEvent {
+                data: "plain".into(),
+                retry: Some(3000),
+                ..SseEvent::default()
+            })
+        );
+        assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn request_stream_decodes_chunked_bodies() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            if req.uri().path() == "/missing" {
+                let status = r#"{"status":"Failure","message":"not found","reason":"NotFound","code":404}"#;
+                return Response::builder()
+                    .status(StatusCode::NOT_FOUND)
+                    .body(Body::from(status))
+                    .map_err(tower::BoxError::from);
+            }
+            let chunks: Vec<Result<_, std::io::Error>> = vec![Ok("{\"n\":1}\n{\"n\""), Ok(":2}\n{\"n\":3}")];
+            Response::builder()
+                .body(Body::wrap_stream(futures::stream::iter(chunks)))
+                .map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc));
+        let req = |path: &str| Request::get(path).body(vec![]).unwrap();
+
+        let stream = client
+            .request_stream(req("/stream"), JsonLinesDecoder::<serde_json::Value>::new())
+            .await
+            .unwrap();
+        let values: Vec<_> = stream.try_collect().await.unwrap();
+        assert_eq!(values, vec![
+            serde_json::json!({"n": 1}),
+            serde_json::json!({"n": 2}),
+            serde_json::json!({"n": 3})
+        ]);
+
+        let missing = client.request_stream(req("/missing"), SseDecoder::new()).await;
+        assert!(matches!(missing, Err(Error::Api(ae)) if ae.code == 404));
+    }
+}
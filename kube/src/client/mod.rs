@@ -6,7 +6,15 @@
 //! the [`Api`][crate::api::Api] type for more structured
 //! interaction with the kuberneres API.
 
-use crate::{api::WatchEvent, config::Config, error::ErrorResponse, service::Service, Error, Result};
+use crate::{
+    api::{Patch, PatchParams, PostParams, WatchEvent},
+    config::Config,
+    error::ErrorResponse,
+    executor::{default_executor, Executor},
+    openapi::{OpenApiDocument, OpenApiPaths},
+    service::{timeout_of, RequestId, Service},
+    Error, Result,
+};
 
 #[cfg(feature = "ws")]
 use tokio_tungstenite::{tungstenite as ws, WebSocketStream};
@@ -17,15 +25,39 @@ use futures::{self, Stream, StreamExt, TryStream, TryStreamExt};
 use http::{self, Request, Response, StatusCode};
 use hyper::Body;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1 as k8s_meta_v1;
-use serde::{de::DeserializeOwned, Deserialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::{self, Value};
 use tokio_util::{
-    codec::{FramedRead, LinesCodec, LinesCodecError},
+    codec::{Decoder, FramedRead, LinesCodecError},
     io::StreamReader,
 };
 use tower::{Service as _, ServiceExt};
 
-use std::convert::{TryFrom, TryInto};
+use std::{
+    borrow::Cow,
+    convert::{TryFrom, TryInto},
+    sync::Arc,
+};
+
+mod client_set;
+pub use client_set::ClientSet;
+
+#[cfg(feature = "codec")] mod codec;
+#[cfg(feature = "codec")]
+pub use codec::{Codec, Framer, JsonCodec, LineFramer, Visit};
+#[cfg(feature = "cbor")] mod cbor;
+#[cfg(feature = "cbor")] pub use cbor::CborCodec;
+
+pub(crate) mod decode;
+mod frames;
+use frames::WatchFrameDecoder;
+pub use frames::{JsonLinesDecoder, SseDecoder, SseEvent};
+
+mod flow_control;
+mod shutdown;
+use shutdown::{Lifecycle, Settings};
+
+pub use flow_control::{FlowHint, FLOW_SCHEMA_HEADER, PRIORITY_LEVEL_HEADER};
 
 // Binary subprotocol v4. See `Client::connect`.
 #[cfg(feature = "ws")]
@@ -39,7 +71,16 @@ const WS_PROTOCOL: &str = "v4.channel.k8s.io";
 /// using [`Client::try_from`].
 #[derive(Clone)]
 pub struct Client {
-    inner: Service,
+    lifecycle: Arc<Lifecycle>,
+    warning_handler: Arc<dyn Fn(&str) + Send + Sync>,
+    flow_hint: Option<Arc<FlowHint>>,
+    max_response_body_size: Option<usize>,
+    max_watch_frame_size: Option<usize>,
+    field_manager: Option<Arc<str>>,
+    force_apply: bool,
+    #[cfg(feature = "codec")]
+    codec: Option<Arc<dyn Codec>>,
+    executor: Arc<dyn Executor>,
 }
 
 impl Client {
@@ -47,7 +88,208 @@ impl Client {
     ///
     /// Use [`Client::try_from`](Self::try_from) to create with a [`Config`].
     pub fn new(service: Service) -> Self {
-        Self { inner: service }
+        Self::with_lifecycle(Lifecycle::new(service, Settings::default()))
+    }
+
+    fn with_lifecycle(lifecycle: Arc<Lifecycle>) -> Self {
+        Self {
+            lifecycle,
+            warning_handler: Arc::new(log_warning),
+            flow_hint: None,
+            max_response_body_size: None,
+            max_watch_frame_size: None,
+            field_manager: None,
+            force_apply: false,
+            #[cfg(feature = "codec")]
+            codec: None,
+            executor: default_executor(),
+        }
+    }
+
+    /// Set a handler for warnings returned by the apiserver
+    ///
+    /// The apiserver returns `Warning` headers for deprecated apis and from admission webhooks.
+    /// The handler is called with the text of every warning.
+    /// By default, warnings are logged at `warn` level, like `kubectl` does.
+    ///
+    /// ```no_run
+    /// # async fn scope() -> Result<(), kube::Error> {
+    /// let client = kube::Client::try_default()
+    ///     .await?
+    ///     .with_warning_handler(|warning| eprintln!("Warning: {}", warning));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_warning_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&str) + Send + Sync + 'static,
+    {
+        self.warning_handler = Arc::new(handler);
+        self
+    }
+
+    /// Spawn the background tasks of this client with `executor`
+    ///
+    /// This covers the drivers of exec, attach and port forward connections, and is the default
+    /// executor of the controllers and shared watches of `kube-runtime` that use this client.
+    /// By default tasks are spawned onto the current Tokio runtime, see [`executor`](crate::executor).
+    pub fn with_executor(mut self, executor: Arc<dyn Executor>) -> Self {
+        self.executor = executor;
+        self
+    }
+
+    /// The executor of the background tasks of this client, see [`Client::with_executor`]
+    pub fn executor(&self) -> Arc<dyn Executor> {
+        self.executor.clone()
+    }
+
+    /// Add a [`FlowHint`] to every request made by this client
+    ///
+    /// Clones of the client share the hint, the client it was created from is unaffected.
+    pub fn with_flow_hint(mut self, hint: FlowHint) -> Self {
+        self.flow_hint = Some(Arc::new(hint));
+        self
+    }
+
+    /// Limit the size of response bodies, see [`Config::max_response_body_size`]
+    ///
+    /// This takes precedence over the limit of the config, also after [`reload_config`](Self::reload_config).
+    pub fn with_max_response_body_size(mut self, limit: usize) -> Self {
+        self.max_response_body_size = Some(limit);
+        self
+    }
+
+    /// Limit the size of single events of watches, see [`Config::max_watch_frame_size`]
+    ///
+    /// This takes precedence over the limit of the config, also after [`reload_config`](Self::reload_config).
+    pub fn with_max_watch_frame_size(mut self, limit: usize) -> Self {
+        self.max_watch_frame_size = Some(limit);
+        self
+    }
+
+    /// Use `manager` as the field manager of writes whose params do not name one
+    ///
+    /// This covers creates, replaces and patches through [`Api`](crate::Api), including server-side applies
+    /// with `PatchParams::default()`, so an operator states its manager once instead of at every call site,
+    /// and every write is attributed to the same manager.
+    ///
+    /// ```no_run
+    /// use kube::api::{Api, Patch, PatchParams};
+    /// use k8s_openapi::api::core::v1::ConfigMap;
+    /// # async fn scope(client: kube::Client) -> Result<(), kube::Error> {
+    /// let client = client.with_field_manager("my-operator").with_forced_apply();
+    /// let cms: Api<ConfigMap> = Api::namespaced(client, "apps");
+    /// let patch = serde_json::json!({ "apiVersion": "v1", "kind": "ConfigMap", "data": { "a": "b" } });
+    /// cms.patch("settings", &PatchParams::default(), &Patch::Apply(&patch)).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_field_manager(mut self, manager: &str) -> Self {
+        self.field_manager = Some(manager.into());
+        self
+    }
+
+    /// Force server-side applies by the [default field manager](Self::with_field_manager) through conflicts
+    ///
+    /// Use this for controllers that own the objects they apply, so that edits by other managers
+    /// are overwritten rather than failing the apply. Applies that name their own field manager
+    /// keep their `force` setting.
+    pub fn with_forced_apply(mut self) -> Self {
+        self.force_apply = true;
+        self
+    }
+
+    /// The default field manager of writes, see [`Client::with_field_manager`]
+    ///
+    /// This is the field manager of the client, or else the one of its config.
+    pub fn field_manager(&self) -> Option<Arc<str>> {
+        self.field_manager
+            .clone()
+            .or_else(|| self.lifecycle.settings().field_manager.clone())
+    }
+
+    /// `pp`, with the default field manager if it does not name one
+    pub(crate) fn post_params<'a>(&self, pp: &'a PostParams) -> Cow<'a, PostParams> {
+        match &self.field_manager() {
+            Some(manager) if pp.field_manager.is_none() => Cow::Owned(PostParams {
+                field_manager: Some(manager.to_string()),
+                ..pp.clone()
+            }),
+            _ => Cow::Borrowed(pp),
+        }
+    }
+
+    /// `pp`, with the default field manager and forced applies if it does not name a manager
+    pub(crate) fn patch_params<'a, P: Serialize>(
+        &self,
+        pp: &'a PatchParams,
+        patch: &Patch<P>,
+    ) -> Cow<'a, PatchParams> {
+        match &self.field_manager() {
+            Some(manager) if pp.field_manager.is_none() => Cow::Owned(PatchParams {
+                field_manager: Some(manager.to_string()),
+                force: pp.force || (self.force_apply && patch.is_apply()),
+                ..pp.clone()
+            }),
+            _ => Cow::Borrowed(pp),
+        }
+    }
+
+    /// Exchange bodies in the format of `codec` instead of json, see [`Codec`]
+    ///
+    /// Clones of the client share the codec, the client it was created from is unaffected.
+    #[cfg(feature = "codec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "codec")))]
+    pub fn with_codec<C: Codec + 'static>(mut self, codec: C) -> Self {
+        self.codec = Some(Arc::new(codec));
+        self
+    }
+
+    /// Switch the client and all of its clones to a new configuration
+    ///
+    /// This rebuilds the connection, authentication and TLS layers from `config`, e.g. after choosing
+    /// another kubeconfig context, and existing [`Api`](crate::Api) handles send their next requests with it.
+    /// Requests in flight complete on the old connection. Every setting of the config is reloaded,
+    /// including its response size limits, field manager and `User-Agent`. Settings made on the client
+    /// itself, like its warning handler, or a [response size limit](Self::with_max_response_body_size) or
+    /// [field manager](Self::with_field_manager) that override the config, are kept, and
+    /// [`Api`](crate::Api) handles keep their namespaces.
+    ///
+    /// This also reopens a client that was [shut down](Self::shutdown).
+    ///
+    /// ```no_run
+    /// use kube::config::{Config, KubeConfigOptions};
+    /// # async fn scope(client: kube::Client) -> Result<(), kube::Error> {
+    /// let options = KubeConfigOptions {
+    ///     context: Some("staging".into()),
+    ///     ..KubeConfigOptions::default()
+    /// };
+    /// client.reload_config(Config::from_kubeconfig(&options).await?)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn reload_config(&self, config: Config) -> Result<()> {
+        let settings = Settings::from(&config);
+        self.lifecycle.replace(Service::try_from(config)?, settings);
+        Ok(())
+    }
+
+    /// Stop the client and all of its clones
+    ///
+    /// New requests fail with [`Error::ClientShutdown`] right away. Requests that were already
+    /// sent are given up to `timeout` to receive their response, and the connection pool is closed
+    /// once they are done. Bodies that are still streaming, like watches, are not waited for.
+    /// Returns whether all in-flight requests completed in time.
+    ///
+    /// ```no_run
+    /// # async fn scope(client: kube::Client) {
+    /// if !client.shutdown(std::time::Duration::from_secs(10)).await {
+    ///     eprintln!("gave up waiting for requests to complete");
+    /// }
+    /// # }
+    /// ```
+    pub async fn shutdown(&self, timeout: std::time::Duration) -> bool {
+        self.lifecycle.shutdown(timeout).await
     }
 
     /// Create and initialize a [`Client`] using the inferred
@@ -65,8 +307,19 @@ impl Client {
         Self::try_from(client_config)
     }
 
-    async fn send(&self, request: Request<Body>) -> Result<Response<Body>> {
-        let mut svc = self.inner.clone();
+    async fn send(&self, mut request: Request<Body>) -> Result<Response<Body>> {
+        if let Some(hint) = &self.flow_hint {
+            // Default headers are only added by the service, after the hint
+            if let Some(agent) = &self.lifecycle.settings().user_agent {
+                if !request.headers().contains_key(http::header::USER_AGENT) {
+                    request
+                        .headers_mut()
+                        .insert(http::header::USER_AGENT, agent.clone());
+                }
+            }
+            hint.apply(&mut request);
+        }
+        let (mut svc, _in_flight) = self.lifecycle.start()?;
         let res = svc
             .ready()
             .await
@@ -85,6 +338,12 @@ impl Client {
                     Error::Service(err)
                 }
             })?;
+        flow_control::log_classification(res.headers());
+        for warning in res.headers().get_all(http::header::WARNING) {
+            if let Ok(warning) = warning.to_str() {
+                (self.warning_handler)(&warning_text(warning));
+            }
+        }
         Ok(res)
     }
 
@@ -94,6 +353,21 @@ impl Client {
     pub async fn connect(
         &self,
         request: Request<Vec<u8>>,
+    ) -> Result<WebSocketStream<hyper::upgrade::Upgraded>> {
+        // Use the binary subprotocol v4, to get JSON `Status` object in `error` channel (3).
+        // There's no official documentation about this protocol, but it's described in
+        // [`k8s.io/apiserver/pkg/util/wsstream/conn.go`](https://git.io/JLQED).
+        // There's a comment about v4 and `Status` object in
+        // [`kublet/cri/streaming/remotecommand/httpstream.go`](https://git.io/JLQEh).
+        self.connect_with_protocol(request, Some(WS_PROTOCOL)).await
+    }
+
+    /// Make WebSocket connection, optionally negotiating a subprotocol.
+    #[cfg(feature = "ws")]
+    async fn connect_with_protocol(
+        &self,
+        request: Request<Vec<u8>>,
+        protocol: Option<&'static str>,
     ) -> Result<WebSocketStream<hyper::upgrade::Upgraded>> {
         use http::header::HeaderValue;
         let (mut parts, body) = request.into_parts();
@@ -112,18 +386,15 @@ impl Client {
             http::header::SEC_WEBSOCKET_KEY,
             key.parse().expect("valid header value"),
         );
-        // Use the binary subprotocol v4, to get JSON `Status` object in `error` channel (3).
-        // There's no official documentation about this protocol, but it's described in
-        // [`k8s.io/apiserver/pkg/util/wsstream/conn.go`](https://git.io/JLQED).
-        // There's a comment about v4 and `Status` object in
-        // [`kublet/cri/streaming/remotecommand/httpstream.go`](https://git.io/JLQEh).
-        parts.headers.insert(
-            http::header::SEC_WEBSOCKET_PROTOCOL,
-            HeaderValue::from_static(WS_PROTOCOL),
-        );
+        if let Some(protocol) = protocol {
+            parts.headers.insert(
+                http::header::SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(protocol),
+            );
+        }
 
         let res = self.send(Request::from_parts(parts, Body::from(body))).await?;
-        verify_upgrade_response(&res, &key)?;
+        verify_upgrade_response(&res, &key, protocol)?;
         match hyper::upgrade::on(res).await {
             Ok(upgraded) => {
                 Ok(WebSocketStream::from_raw_socket(upgraded, ws::protocol::Role::Client, None).await)
@@ -139,25 +410,103 @@ impl Client {
     where
         T: DeserializeOwned,
     {
-        let text = self.request_text(request).await?;
+        let (body, format) = self.request_body(request).await?;
+        format.decode(&body)
+    }
 
-        serde_json::from_str(&text).map_err(|e| {
-            tracing::warn!("{}, {:?}", text, e);
-            Error::SerdeError(e)
-        })
+    /// Ask for the format of the codec, if any
+    fn encode_request(&self, request: Request<Vec<u8>>) -> Result<Request<Vec<u8>>> {
+        #[cfg(feature = "codec")]
+        if let Some(codec) = &self.codec {
+            return codec::encode_request(codec.as_ref(), request);
+        }
+        Ok(request)
+    }
+
+    /// The response size limit of the client, or else the one of its config
+    fn max_response_body_size(&self) -> Option<usize> {
+        self.max_response_body_size
+            .or_else(|| self.lifecycle.settings().max_response_body_size)
+    }
+
+    /// Buffer a response body, up to the `max_response_body_size`
+    async fn read_body(&self, res: Response<Body>) -> Result<Bytes> {
+        let limit = match self.max_response_body_size() {
+            Some(limit) => limit,
+            None => return hyper::body::to_bytes(res.into_body()).await.map_err(body_error),
+        };
+        let content_length = res
+            .headers()
+            .get(http::header::CONTENT_LENGTH)
+            .and_then(|len| len.to_str().ok())
+            .and_then(|len| len.parse::<usize>().ok());
+        if matches!(content_length, Some(len) if len > limit) {
+            return Err(Error::ResponseTooLarge(limit));
+        }
+        let mut body = res.into_body();
+        let mut buf = bytes::BytesMut::with_capacity(content_length.unwrap_or(0));
+        while let Some(chunk) = body.next().await {
+            let chunk = chunk.map_err(body_error)?;
+            if buf.len() + chunk.len() > limit {
+                return Err(Error::ResponseTooLarge(limit));
+            }
+            buf.extend_from_slice(&chunk);
+        }
+        Ok(buf.freeze())
+    }
+
+    /// The format of a response, which is the codec's if it isn't the json fallback
+    #[cfg_attr(not(feature = "codec"), allow(unused_variables))]
+    fn response_format(&self, res: &Response<Body>) -> BodyFormat {
+        #[cfg(feature = "codec")]
+        if let Some(codec) = &self.codec {
+            if codec::is_encoded(codec.as_ref(), res.headers().get(http::header::CONTENT_TYPE)) {
+                return BodyFormat::Codec(codec.clone());
+            }
+        }
+        BodyFormat::Json
+    }
+
+    /// Send a request and buffer the response body, with the format it is in
+    async fn request_body(&self, request: Request<Vec<u8>>) -> Result<(Bytes, BodyFormat)> {
+        let res = self.send(self.encode_request(request)?.map(Body::from)).await?;
+        let format = self.response_format(&res);
+        // trace!("Status = {:?} for {}", status, res.url());
+        let res = self.check_status(res, &format).await?;
+        let body = self.read_body(res).await?;
+        Ok((body, format))
+    }
+
+    /// Fail with the error in the body of responses with an error status
+    ///
+    /// Errors from the API carry the id the request was sent with, see [`ErrorResponse::request_id`].
+    async fn check_status(&self, res: Response<Body>, format: &BodyFormat) -> Result<Response<Body>> {
+        let status = res.status();
+        if !status.is_client_error() && !status.is_server_error() {
+            return Ok(res);
+        }
+        let request_id = res.extensions().get::<RequestId>().map(|id| id.0.clone());
+        let body = self.read_body(res).await?;
+        let ae = match format {
+            #[cfg(feature = "codec")]
+            BodyFormat::Codec(codec) => codec::decode::<ErrorResponse>(codec.as_ref(), &body).ok(),
+            BodyFormat::Json => None,
+        };
+        let ae = ae.unwrap_or_else(|| api_error(&String::from_utf8_lossy(&body), status));
+        Err(ErrorResponse { request_id, ..ae }.into_error())
     }
 
     /// Perform a raw HTTP request against the API and get back the response
     /// as a string
+    ///
+    /// Responses in the format of a codec are returned as json.
     pub async fn request_text(&self, request: Request<Vec<u8>>) -> Result<String> {
-        let res = self.send(request.map(Body::from)).await?;
-        let status = res.status();
-        // trace!("Status = {:?} for {}", status, res.url());
-        let body_bytes = hyper::body::to_bytes(res.into_body()).await?;
-        let text = String::from_utf8(body_bytes.to_vec())?;
-        handle_api_errors(&text, status)?;
-
-        Ok(text)
+        let (body, format) = self.request_body(request).await?;
+        match format {
+            #[cfg(feature = "codec")]
+            BodyFormat::Codec(codec) => Ok(codec::decode::<Value>(codec.as_ref(), &body)?.to_string()),
+            BodyFormat::Json => Ok(String::from_utf8(body.to_vec())?),
+        }
     }
 
     /// Perform a raw HTTP request against the API and get back the response
@@ -177,20 +526,13 @@ impl Client {
     where
         T: DeserializeOwned,
     {
-        let text = self.request_text(request).await?;
-        // It needs to be JSON:
-        let v: Value = serde_json::from_str(&text)?;
-        if v["kind"] == "Status" {
-            trace!("Status from {}", text);
-            Ok(Right(serde_json::from_str::<Status>(&text).map_err(|e| {
-                tracing::warn!("{}, {:?}", text, e);
-                Error::SerdeError(e)
-            })?))
+        let (body, format) = self.request_body(request).await?;
+        let value: Value = format.decode(&body)?;
+        if value.get("kind").and_then(Value::as_str) == Some("Status") {
+            trace!("Status from {}", String::from_utf8_lossy(&body));
+            Ok(Right(format.decode_value(value, &body)?))
         } else {
-            Ok(Left(serde_json::from_str::<T>(&text).map_err(|e| {
-                tracing::warn!("{}, {:?}", text, e);
-                Error::SerdeError(e)
-            })?))
+            Ok(Left(format.decode_value(value, &body)?))
         }
     }
 
@@ -202,67 +544,143 @@ impl Client {
     where
         T: Clone + DeserializeOwned,
     {
-        let res = self.send(request.map(Body::from)).await?;
+        let res = self.send(self.encode_request(request)?.map(Body::from)).await?;
         // trace!("Streaming from {} -> {}", res.url(), res.status().as_str());
         trace!("headers: {:?}", res.headers());
+        let request_id = res.extensions().get::<RequestId>().map(|id| id.0.clone());
 
-        let frames = FramedRead::new(
-            StreamReader::new(res.into_body().map_err(|e| {
-                // Client timeout. This will be ignored.
-                if e.is_timeout() {
-                    return std::io::Error::new(std::io::ErrorKind::TimedOut, e);
-                }
-                // Unexpected EOF from chunked decoder.
-                // Tends to happen when watching for 300+s. This will be ignored.
-                if e.to_string().contains("unexpected EOF during chunk") {
-                    return std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e);
+        let settings = self.lifecycle.settings();
+        let limit = self
+            .max_watch_frame_size
+            .or(settings.max_watch_frame_size)
+            .or(self.max_response_body_size)
+            .or(settings.max_response_body_size);
+        let format = self.response_format(&res);
+        let decoder = format.watch_decoder(limit);
+        let frames = FramedRead::new(body_reader(res.into_body()), decoder);
+
+        Ok(frames.filter_map(move |res| {
+            let format = format.clone();
+            async move {
+                match res {
+                    Ok(frame) => format.decode_event(&frame),
+
+                    Err(LinesCodecError::Io(e)) => match e.kind() {
+                        std::io::ErrorKind::TimedOut => match e.get_ref().and_then(|e| timeout_of(e)) {
+                            // Read timeout of the `Config`, like when waiting for the response
+                            Some(what) => Some(Err(Error::Timeout(what))),
+                            // Client timeout
+                            None => {
+                                tracing::warn!("timeout in poll: {}", e); // our client timeout
+                                None
+                            }
+                        },
+                        // Unexpected EOF from chunked decoder.
+                        // Tends to happen after 300+s of watching.
+                        std::io::ErrorKind::UnexpectedEof => {
+                            tracing::warn!("eof in poll: {}", e);
+                            None
+                        }
+                        _ => Some(Err(Error::ReadEvents(e))),
+                    },
+
+                    // Reached the maximum frame size without finding a newline.
+                    // Without a limit the decoder never returns this.
+                    Err(LinesCodecError::MaxLineLengthExceeded) => Some(Err(match limit {
+                        Some(limit) => Error::ResponseTooLarge(limit),
+                        None => Error::LinesCodecMaxLineLengthExceeded,
+                    })),
                 }
-                std::io::Error::new(std::io::ErrorKind::Other, e)
-            })),
-            LinesCodec::new(),
-        );
+            }
+        })
+        .map_err(move |err| match err {
+            // Errors sent as watch events, such as expired resource versions
+            Error::Api(ae) => Error::Api(ErrorResponse {
+                request_id: request_id.clone(),
+                ..ae
+            }),
+            err => err,
+        }))
+    }
 
-        Ok(frames.filter_map(|res| async {
-            match res {
-                Ok(line) => match serde_json::from_str::<WatchEvent<T>>(&line) {
+    /// Perform a raw request and get back a stream of frames decoded from the response body
+    ///
+    /// This is for streaming endpoints of aggregated apis, which don't necessarily frame their
+    /// responses like watches. Decoders for newline delimited json ([`JsonLinesDecoder`]) and
+    /// server-sent events ([`SseDecoder`]) are included, and any [`Decoder`] can be supplied.
+    ///
+    /// Error statuses are returned as errors before streaming, like with [`Client::request`].
+    /// Errors of the decoder end the stream, and are returned as [`Error::FrameDecode`].
+    ///
+    /// ```no_run
+    /// use kube::{client::SseDecoder, Client};
+    /// use futures::TryStreamExt;
+    /// # async fn scope(client: Client) -> Result<(), kube::Error> {
+    /// let req = http::Request::get("/apis/usage.example.com/v1/streams/cpu").body(vec![])?;
+    /// let mut events = Box::pin(client.request_stream(req, SseDecoder::new()).await?);
+    /// while let Some(event) = events.try_next().await? {
+    ///     println!("{}: {}", event.event.unwrap_or_default(), event.data);
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn request_stream<D>(
+        &self,
+        request: Request<Vec<u8>>,
+        decoder: D,
+    ) -> Result<impl Stream<Item = Result<D::Item>>>
+    where
+        D: Decoder,
+        D::Error: std::error::Error + Send + Sync + 'static,
+    {
+        let res = self.send(request.map(Body::from)).await?;
+        let res = self.check_status(res, &BodyFormat::Json).await?;
+        let frames = FramedRead::new(body_reader(res.into_body()), decoder);
+        Ok(frames.map_err(|e| Error::FrameDecode(Box::new(e))))
+    }
+
+    /// Perform a raw watch request over a WebSocket and get back a stream of [`WatchEvent`] objects
+    ///
+    /// This is an alternative to [`Client::request_events`] for environments where proxies or
+    /// gateways buffer chunked HTTP/1 responses, which breaks long-polling watches.
+    /// The API server sends every watch event as a separate WebSocket message.
+    #[cfg(feature = "ws")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+    pub async fn request_events_ws<T>(
+        &self,
+        request: Request<Vec<u8>>,
+    ) -> Result<impl TryStream<Item = Result<WatchEvent<T>>>>
+    where
+        T: Clone + DeserializeOwned,
+    {
+        let stream = self.connect_with_protocol(request, None).await?;
+        Ok(stream
+            .take_while(|msg| {
+                // A close frame (or a failure to read it) ends the watch, just like an EOF would
+                let closed = matches!(msg, Ok(ws::Message::Close(_)));
+                async move { !closed }
+            })
+            .filter_map(|msg| async {
+                let data = match msg {
+                    Ok(ws::Message::Text(text)) => text.into_bytes(),
+                    Ok(ws::Message::Binary(bin)) => bin,
+                    // Control frames are handled by tungstenite
+                    Ok(_) => return None,
+                    Err(ws::Error::Io(e)) => return Some(Err(Error::ReadEvents(e))),
+                    Err(e) => return Some(Err(Error::ReadEvents(std::io::Error::other(e)))),
+                };
+                match serde_json::from_slice::<WatchEvent<T>>(&data) {
                     Ok(event) => Some(Ok(event)),
                     Err(e) => {
-                        // Ignore EOF error that can happen for incomplete line from `decode_eof`.
-                        if e.is_eof() {
-                            return None;
-                        }
-
                         // Got general error response
-                        if let Ok(e_resp) = serde_json::from_str::<ErrorResponse>(&line) {
+                        if let Ok(e_resp) = serde_json::from_slice::<ErrorResponse>(&data) {
                             return Some(Err(Error::Api(e_resp)));
                         }
                         // Parsing error
-                        Some(Err(Error::SerdeError(e)))
-                    }
-                },
-
-                Err(LinesCodecError::Io(e)) => match e.kind() {
-                    // Client timeout
-                    std::io::ErrorKind::TimedOut => {
-                        tracing::warn!("timeout in poll: {}", e); // our client timeout
-                        None
-                    }
-                    // Unexpected EOF from chunked decoder.
-                    // Tends to happen after 300+s of watching.
-                    std::io::ErrorKind::UnexpectedEof => {
-                        tracing::warn!("eof in poll: {}", e);
-                        None
+                        Some(Err(decode::with_context(&data, e)))
                     }
-                    _ => Some(Err(Error::ReadEvents(e))),
-                },
-
-                // Reached the maximum line length without finding a newline.
-                // This should never happen because we're using the default `usize::MAX`.
-                Err(LinesCodecError::MaxLineLengthExceeded) => {
-                    Some(Err(Error::LinesCodecMaxLineLengthExceeded))
                 }
-            }
-        }))
+            }))
     }
 
     /// Returns apiserver version.
@@ -309,6 +727,136 @@ impl Client {
         let url = format!("/api/{}", version);
         self.request(Request::builder().uri(url).body(vec![])?).await
     }
+
+    /// Lists the OpenAPI v3 documents the apiserver serves.
+    ///
+    /// Requires kubernetes >= 1.24 (or the `OpenAPIV3` feature gate on earlier versions).
+    pub async fn list_openapi_v3_paths(&self) -> Result<OpenApiPaths> {
+        self.request(Request::builder().uri("/openapi/v3").body(vec![])?)
+            .await
+    }
+
+    /// Fetches the OpenAPI v3 document of a group version, e.g. `apps/v1` or `v1`.
+    ///
+    /// ### Example usage:
+    /// ```rust
+    /// # async fn scope(client: kube::Client) -> Result<(), Box<dyn std::error::Error>> {
+    /// let doc = client.openapi_v3_schema("apps/v1").await?;
+    /// if let Some(deploy) = doc.schema_for_kind("apps", "v1", "Deployment") {
+    ///     let replicas = doc.explain(deploy, "spec.replicas");
+    ///     if let Some(description) = replicas.and_then(|s| s.description.as_ref()) {
+    ///         println!("spec.replicas: {}", description);
+    ///     }
+    /// }
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn openapi_v3_schema(&self, group_version: &str) -> Result<OpenApiDocument> {
+        let url = if group_version.contains('/') {
+            format!("/openapi/v3/apis/{}", group_version)
+        } else {
+            format!("/openapi/v3/api/{}", group_version)
+        };
+        self.request(Request::builder().uri(url).body(vec![])?).await
+    }
+}
+
+/// Error reading a response body, with the read timeouts of the `Config` as [`Error::Timeout`]
+fn body_error(err: hyper::Error) -> Error {
+    match timeout_of(&err) {
+        Some(what) => Error::Timeout(what),
+        None => Error::HyperError(err),
+    }
+}
+
+/// Read a response body as an `AsyncRead`, for decoding frames
+fn body_reader(body: Body) -> impl tokio::io::AsyncRead {
+    StreamReader::new(body.map_err(|e| {
+        // Read timeout of the `Config`, reported as `Error::Timeout`
+        if let Some(what) = timeout_of(&e) {
+            return std::io::Error::new(std::io::ErrorKind::TimedOut, Error::Timeout(what));
+        }
+        // Client timeout. This will be ignored.
+        if e.is_timeout() {
+            return std::io::Error::new(std::io::ErrorKind::TimedOut, e);
+        }
+        // Unexpected EOF from chunked decoder.
+        // Tends to happen when watching for 300+s. This will be ignored.
+        if e.to_string().contains("unexpected EOF during chunk") {
+            return std::io::Error::new(std::io::ErrorKind::UnexpectedEof, e);
+        }
+        std::io::Error::other(e)
+    }))
+}
+
+/// The format of a response body
+#[derive(Clone)]
+enum BodyFormat {
+    Json,
+    /// The format of the client's codec, rather than the json fallback
+    #[cfg(feature = "codec")]
+    Codec(Arc<dyn Codec>),
+}
+
+impl BodyFormat {
+    /// Deserialize a body
+    fn decode<T: DeserializeOwned>(&self, body: &[u8]) -> Result<T> {
+        match self {
+            BodyFormat::Json => decode::from_slice(body),
+            #[cfg(feature = "codec")]
+            BodyFormat::Codec(codec) => codec::decode(codec.as_ref(), body),
+        }
+    }
+
+    /// Deserialize a body that was decoded as a `Value`
+    ///
+    /// A mismatch is decoded again from the body, for the context of the error.
+    fn decode_value<T: DeserializeOwned>(&self, value: Value, body: &[u8]) -> Result<T> {
+        serde_json::from_value(value).or_else(|_| self.decode(body))
+    }
+
+    /// Split a watch into the frames of the format
+    fn watch_decoder(&self, limit: Option<usize>) -> WatchFrameDecoder {
+        let decoder = WatchFrameDecoder::new(limit);
+        match self {
+            BodyFormat::Json => decoder,
+            #[cfg(feature = "codec")]
+            BodyFormat::Codec(codec) => decoder.with_framer(codec.framer()),
+        }
+    }
+
+    /// Deserialize a frame of a watch, or the error sent in place of an event
+    fn decode_event<T: DeserializeOwned>(&self, frame: &[u8]) -> Option<Result<WatchEvent<T>>> {
+        match self {
+            BodyFormat::Json => match serde_json::from_slice::<WatchEvent<T>>(frame) {
+                Ok(event) => Some(Ok(event)),
+                Err(e) => {
+                    // Ignore EOF error that can happen for incomplete line from `decode_eof`.
+                    if e.is_eof() {
+                        return None;
+                    }
+
+                    // Got general error response
+                    if let Ok(e_resp) = serde_json::from_slice::<ErrorResponse>(frame) {
+                        return Some(Err(Error::Api(e_resp)));
+                    }
+                    // Parsing error
+                    Some(Err(decode::with_context(frame, e)))
+                }
+            },
+            #[cfg(feature = "codec")]
+            BodyFormat::Codec(codec) => {
+                let codec = codec.as_ref();
+                Some(codec::decode::<WatchEvent<T>>(codec, frame).map_err(|err| {
+                    // Got general error response
+                    match codec::decode::<ErrorResponse>(codec, frame) {
+                        Ok(e_resp) => Error::Api(e_resp),
+                        Err(_) => err,
+                    }
+                }))
+            }
+        }
+    }
 }
 
 /// Kubernetes returned error handling
@@ -318,36 +866,70 @@ impl Client {
 ///
 /// In either case, present an ApiError upstream.
 /// The latter is probably a bug if encountered.
-fn handle_api_errors(text: &str, s: StatusCode) -> Result<()> {
-    if s.is_client_error() || s.is_server_error() {
-        // Print better debug when things do fail
-        // trace!("Parsing error: {}", text);
-        if let Ok(errdata) = serde_json::from_str::<ErrorResponse>(text) {
-            tracing::debug!("Unsuccessful: {:?}", errdata);
-            Err(Error::Api(errdata))
-        } else {
-            tracing::warn!("Unsuccessful data error parse: {}", text);
-            // Propagate errors properly via reqwest
-            let ae = ErrorResponse {
-                status: s.to_string(),
-                code: s.as_u16(),
-                message: format!("{:?}", text),
-                reason: "Failed to parse error data".into(),
-            };
-            tracing::debug!("Unsuccessful: {:?} (reconstruct)", ae);
-            Err(Error::Api(ae))
-        }
+fn api_error(text: &str, s: StatusCode) -> ErrorResponse {
+    // Print better debug when things do fail
+    // trace!("Parsing error: {}", text);
+    if let Ok(errdata) = serde_json::from_str::<ErrorResponse>(text) {
+        tracing::debug!("Unsuccessful: {:?}", errdata);
+        errdata
     } else {
-        Ok(())
+        tracing::warn!("Unsuccessful data error parse: {}", text);
+        // Propagate errors properly via reqwest
+        let ae = ErrorResponse {
+            status: s.to_string(),
+            code: s.as_u16(),
+            message: format!("{:?}", text),
+            reason: "Failed to parse error data".into(),
+            details: None,
+            request_id: None,
+        };
+        tracing::debug!("Unsuccessful: {:?} (reconstruct)", ae);
+        ae
     }
 }
 
+/// Default warning handler
+fn log_warning(warning: &str) {
+    tracing::warn!("apiserver warning: {}", warning);
+}
+
+/// Extract the text from a `Warning` header value
+///
+/// The apiserver sends warnings as `299 - "text"` as per
+/// [RFC7234](https://tools.ietf.org/html/rfc7234#section-5.5).
+/// The text is a quoted-string, which ends at the first unescaped quote and may be followed by a date.
+fn warning_text(header: &str) -> Cow<'_, str> {
+    let mut parts = header.splitn(3, ' ');
+    let text = match (parts.next(), parts.next(), parts.next()) {
+        (Some(code), Some(_agent), Some(text)) if code.len() == 3 && code.bytes().all(|b| b.is_ascii_digit()) => {
+            text
+        }
+        _ => return Cow::Borrowed(header),
+    };
+    let quoted = match text.strip_prefix('"') {
+        Some(quoted) => quoted,
+        None => return Cow::Borrowed(text),
+    };
+    let mut unescaped = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Cow::Owned(unescaped),
+            '\\' => unescaped.extend(chars.next()),
+            c => unescaped.push(c),
+        }
+    }
+    // Unterminated quoted-string, keep what we have
+    Cow::Owned(unescaped)
+}
+
 impl TryFrom<Config> for Client {
     type Error = Error;
 
     /// Convert [`Config`] into a [`Client`]
     fn try_from(config: Config) -> Result<Self> {
-        Ok(Self::new(config.try_into()?))
+        let settings = Settings::from(&config);
+        Ok(Self::with_lifecycle(Lifecycle::new(config.try_into()?, settings)))
     }
 }
 
@@ -355,7 +937,7 @@ impl TryFrom<Config> for Client {
 
 /// A Kubernetes status object
 #[allow(missing_docs)]
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct Status {
     // TODO: typemeta
     // TODO: metadata that can be completely empty (listmeta...)
@@ -367,12 +949,29 @@ pub struct Status {
     pub reason: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub details: Option<StatusDetails>,
-    #[serde(default, skip_serializing_if = "num::Zero::is_zero")]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub code: u16,
 }
 
+impl From<Status> for ErrorResponse {
+    fn from(status: Status) -> Self {
+        ErrorResponse {
+            status: status.status,
+            message: status.message,
+            reason: status.reason,
+            code: status.code,
+            details: status.details.map(Box::new),
+            request_id: None,
+        }
+    }
+}
+
+fn is_zero<T: Default + PartialEq>(n: &T) -> bool {
+    *n == T::default()
+}
+
 /// Status details object on the [`Status`] object
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 #[allow(missing_docs)]
 pub struct StatusDetails {
@@ -386,12 +985,12 @@ pub struct StatusDetails {
     pub uid: String,
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub causes: Vec<StatusCause>,
-    #[serde(default, skip_serializing_if = "num::Zero::is_zero")]
+    #[serde(default, skip_serializing_if = "is_zero")]
     pub retry_after_seconds: u32,
 }
 
 /// Status cause object on the [`StatusDetails`] object
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
 #[allow(missing_docs)]
 pub struct StatusCause {
     #[serde(default, skip_serializing_if = "String::is_empty")]
@@ -402,27 +1001,10 @@ pub struct StatusCause {
     pub field: String,
 }
 
-#[cfg(test)]
-mod test {
-    use super::Status;
-
-    // ensure our status schema is sensible
-    #[test]
-    fn delete_deserialize_test() {
-        let statusresp = r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Success","details":{"name":"some-app","group":"clux.dev","kind":"foos","uid":"1234-some-uid"}}"#;
-        let s: Status = serde_json::from_str::<Status>(statusresp).unwrap();
-        assert_eq!(s.details.unwrap().name, "some-app");
-
-        let statusnoname = r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Success","details":{"group":"clux.dev","kind":"foos","uid":"1234-some-uid"}}"#;
-        let s2: Status = serde_json::from_str::<Status>(statusnoname).unwrap();
-        assert_eq!(s2.details.unwrap().name, ""); // optional probably better..
-    }
-}
-
 #[cfg(feature = "ws")]
 // Verify upgrade response according to RFC6455.
 // Based on `tungstenite` and added subprotocol verification.
-fn verify_upgrade_response(res: &Response<Body>, key: &str) -> Result<()> {
+fn verify_upgrade_response(res: &Response<Body>, key: &str, protocol: Option<&str>) -> Result<()> {
     if res.status() != StatusCode::SWITCHING_PROTOCOLS {
         return Err(Error::ProtocolSwitch(res.status()));
     }
@@ -456,12 +1038,14 @@ fn verify_upgrade_response(res: &Response<Body>, key: &str) -> Result<()> {
     }
 
     // Make sure that the server returned the correct subprotocol.
-    if !headers
-        .get(http::header::SEC_WEBSOCKET_PROTOCOL)
-        .map(|h| h == WS_PROTOCOL)
-        .unwrap_or(false)
-    {
-        return Err(Error::SecWebSocketProtocolMismatch);
+    if let Some(protocol) = protocol {
+        if !headers
+            .get(http::header::SEC_WEBSOCKET_PROTOCOL)
+            .map(|h| h == protocol)
+            .unwrap_or(false)
+        {
+            return Err(Error::SecWebSocketProtocolMismatch);
+        }
     }
 
     Ok(())
@@ -472,5 +1056,312 @@ fn verify_upgrade_response(res: &Response<Body>, key: &str) -> Result<()> {
 #[cfg(feature = "ws")]
 fn sec_websocket_key() -> String {
     let r: [u8; 16] = rand::random();
-    base64::encode(&r)
+    base64::encode(r)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{warning_text, Status};
+    use crate::{Client, Service};
+    use http::{Request, Response};
+    use hyper::Body;
+    use std::sync::{Arc, Mutex};
+
+    #[tokio::test]
+    async fn warning_handler_receives_warnings() {
+        let svc = tower::service_fn(|_req: Request<Body>| async {
+            Response::builder()
+                .header("Warning", r#"299 - "first""#)
+                .header("Warning", r#"299 - "second""#)
+                .body(Body::from("{}"))
+                .map_err(tower::BoxError::from)
+        });
+        let seen = Arc::new(Mutex::new(vec![]));
+        let sink = seen.clone();
+        let client = Client::new(Service::new(svc))
+            .with_warning_handler(move |w| sink.lock().unwrap().push(w.to_string()));
+        let req = Request::builder().uri("/api").body(vec![]).unwrap();
+        client.request_text(req).await.unwrap();
+        assert_eq!(*seen.lock().unwrap(), vec!["first", "second"]);
+    }
+
+    #[tokio::test]
+    async fn response_bodies_are_limited() {
+        use crate::{api::WatchEvent, Error};
+        use futures::StreamExt;
+        use k8s_openapi::api::core::v1::ConfigMap;
+
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            let body = match req.uri().path() {
+                "/small" => Body::from("{}"),
+                // chunked, without a content length
+                "/large" => Body::wrap_stream(futures::stream::iter(vec![
+                    Ok::<_, std::io::Error>("[\"".to_string()),
+                    Ok("x".repeat(100)),
+                    Ok("\"]".to_string()),
+                ])),
+                _ => Body::from(format!(
+                    "{{\"type\":\"ADDED\",\"object\":{{\"metadata\":{{\"name\":\"{}\"}}}}}}\n",
+                    "x".repeat(100)
+                )),
+            };
+            Response::builder().body(body).map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc)).with_max_response_body_size(64);
+        let req = |path: &str| Request::builder().uri(path).body(vec![]).unwrap();
+        assert_eq!(client.request_text(req("/small")).await.unwrap(), "{}");
+        assert!(matches!(
+            client.request_text(req("/large")).await,
+            Err(Error::ResponseTooLarge(64))
+        ));
+        let events = client.request_events::<ConfigMap>(req("/watch")).await.unwrap();
+        let events: Vec<Result<WatchEvent<ConfigMap>, Error>> = events.collect().await;
+        assert!(matches!(events.as_slice(), [Err(Error::ResponseTooLarge(64))]));
+    }
+
+    #[tokio::test]
+    async fn api_errors_carry_the_request_id() {
+        use crate::{error::ErrorResponse, service::RequestIdLayer, Error};
+        use tokio_util::codec::LinesCodec;
+        use tower::Layer;
+
+        let svc = tower::service_fn(|_req: Request<Body>| async {
+            Response::builder()
+                .status(http::StatusCode::NOT_FOUND)
+                .body(Body::from(
+                    r#"{"status":"Failure","message":"not found","reason":"NotFound","code":404}"#,
+                ))
+                .map_err(tower::BoxError::from)
+        });
+        let svc = RequestIdLayer::new(http::header::HeaderName::from_static("x-request-id")).layer(svc);
+        let client = Client::new(Service::new(svc));
+        let req = || {
+            Request::builder()
+                .uri("/")
+                .header("x-request-id", "abc")
+                .body(vec![])
+                .unwrap()
+        };
+        match client.request_text(req()).await {
+            Err(Error::Api(ae)) => {
+                assert_eq!(ae.request_id(), Some("abc"));
+                // the request id does not take part in comparisons
+                assert_eq!(ae, ErrorResponse::new(404, "NotFound", "not found"));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match client.request_stream(req(), LinesCodec::new()).await {
+            Err(Error::Api(ae)) => assert_eq!(ae.request_id(), Some("abc")),
+            Err(err) => panic!("unexpected {:?}", err),
+            Ok(_) => panic!("unexpected stream"),
+        }
+    }
+
+    #[tokio::test]
+    async fn request_status_tells_objects_from_statuses() {
+        use either::Either::{Left, Right};
+        use k8s_openapi::api::core::v1::ConfigMap;
+
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            let body = match req.uri().path() {
+                "/gone" => r#"{"kind":"Status","status":"Success","details":{"name":"gone"}}"#,
+                _ => r#"{"kind":"ConfigMap","metadata":{"name":"deleting"}}"#,
+            };
+            Response::builder()
+                .body(Body::from(body))
+                .map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc));
+        let req = |path| Request::builder().uri(path).body(vec![]).unwrap();
+        match client.request_status::<ConfigMap>(req("/gone")).await.unwrap() {
+            Right(status) => assert_eq!(status.status, "Success"),
+            Left(cm) => panic!("unexpected {:?}", cm),
+        }
+        match client.request_status::<ConfigMap>(req("/deleting")).await.unwrap() {
+            Left(cm) => assert_eq!(cm.metadata.name.as_deref(), Some("deleting")),
+            Right(status) => panic!("unexpected {:?}", status),
+        }
+    }
+
+    #[tokio::test]
+    async fn body_timeouts_are_reported_as_timeouts() {
+        use crate::{api::WatchEvent, Error};
+        use k8s_openapi::api::core::v1::Pod;
+
+        // what the `TimeoutLayer` of the service sends when the next chunk is late
+        let svc = tower::service_fn(|_req: Request<Body>| async {
+            let chunks: Vec<Result<&'static str, tower::BoxError>> = vec![
+                Ok("{"),
+                Err(Error::Timeout("reading the response body").into()),
+            ];
+            Ok::<_, tower::BoxError>(Response::new(Body::wrap_stream(futures::stream::iter(chunks))))
+        });
+        let client = Client::new(Service::new(svc));
+        let req = || Request::builder().uri("/").body(vec![]).unwrap();
+        assert!(matches!(
+            client.request_text(req()).await,
+            Err(Error::Timeout("reading the response body"))
+        ));
+        let events = client.request_events::<Pod>(req()).await.unwrap();
+        let events: Vec<Result<WatchEvent<Pod>, Error>> = futures::StreamExt::collect(events).await;
+        assert!(matches!(
+            events.as_slice(),
+            [Err(Error::Timeout("reading the response body"))]
+        ));
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn watch_ws_request() {
+        use crate::{
+            api::{Api, ListParams},
+            Error,
+        };
+        use k8s_openapi::api::core::v1::Pod;
+
+        let sent = Arc::new(Mutex::new(None));
+        let record = sent.clone();
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            *record.lock().unwrap() = Some((req.uri().clone(), req.headers().clone()));
+            async {
+                Response::builder()
+                    .status(http::StatusCode::OK)
+                    .body(Body::empty())
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let pods: Api<Pod> = Api::namespaced(Client::new(Service::new(svc)), "ns");
+        let lp = ListParams::default().timeout(30);
+        // the server never switched protocols, so the watch itself fails
+        assert!(matches!(
+            pods.watch_ws(&lp, "0").await.err(),
+            Some(Error::ProtocolSwitch(http::StatusCode::OK))
+        ));
+
+        let (uri, headers) = sent.lock().unwrap().take().unwrap();
+        assert_eq!(
+            uri.to_string(),
+            "/api/v1/namespaces/ns/pods?&watch=true&resourceVersion=0&timeoutSeconds=30&allowWatchBookmarks=true"
+        );
+        assert_eq!(headers["connection"], "Upgrade");
+        assert_eq!(headers["upgrade"], "websocket");
+        assert_eq!(headers["sec-websocket-version"], "13");
+        assert!(headers.contains_key("sec-websocket-key"));
+        assert!(!headers.contains_key("sec-websocket-protocol"));
+    }
+
+    #[tokio::test]
+    async fn shutdown_drains_in_flight_requests() {
+        use crate::Error;
+        use std::time::Duration;
+        use tokio::sync::oneshot;
+
+        let (release, released) = oneshot::channel::<()>();
+        let released = Arc::new(Mutex::new(Some(released)));
+        let svc = tower::service_fn(move |_req: Request<Body>| {
+            let released = released.lock().unwrap().take();
+            async move {
+                if let Some(released) = released {
+                    released.await.ok();
+                }
+                Response::builder()
+                    .body(Body::from("{}"))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let client = Client::new(Service::new(svc));
+        let req = || Request::builder().uri("/").body(vec![]).unwrap();
+        let slow = tokio::spawn({
+            let client = client.clone();
+            async move { client.request_text(req()).await }
+        });
+        tokio::task::yield_now().await;
+
+        assert!(!client.shutdown(Duration::from_millis(10)).await);
+        assert!(matches!(
+            client.request_text(req()).await,
+            Err(Error::ClientShutdown)
+        ));
+        release.send(()).unwrap();
+        assert!(client.shutdown(Duration::from_secs(5)).await);
+        assert_eq!(slow.await.unwrap().unwrap(), "{}");
+    }
+
+    #[tokio::test]
+    async fn reload_config_switches_clones() {
+        use crate::{api::Api, Config};
+        use k8s_openapi::api::core::v1::ConfigMap;
+
+        let svc = tower::service_fn(|_req: Request<Body>| async {
+            Response::builder()
+                .body(Body::from(r#"{"metadata":{"name":"old"}}"#))
+                .map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc));
+        let cms: Api<ConfigMap> = Api::namespaced(client.clone(), "default");
+        assert_eq!(
+            cms.get("old").await.unwrap().metadata.name.as_deref(),
+            Some("old")
+        );
+
+        // nothing listens on port 1, so requests now fail to connect
+        client
+            .reload_config(Config::new("http://127.0.0.1:1".parse().unwrap()))
+            .unwrap();
+        assert!(cms.get("old").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reload_config_reloads_its_settings() {
+        use crate::Config;
+        use std::convert::TryFrom;
+
+        let config = |manager: &str| Config {
+            field_manager: Some(manager.into()),
+            max_response_body_size: Some(1),
+            ..Config::new("http://127.0.0.1:1".parse().unwrap())
+        };
+        let client = Client::try_from(config("old")).unwrap();
+        assert_eq!(client.field_manager().as_deref(), Some("old"));
+        let overridden = client.clone().with_field_manager("mine");
+
+        client.reload_config(config("new")).unwrap();
+        assert_eq!(client.field_manager().as_deref(), Some("new"));
+        assert_eq!(client.max_response_body_size(), Some(1));
+        // settings of the client itself take precedence over the config
+        assert_eq!(overridden.field_manager().as_deref(), Some("mine"));
+    }
+
+    #[test]
+    fn warning_header_text() {
+        assert_eq!(
+            warning_text(r#"299 - "batch/v1beta1 CronJob is deprecated in v1.21+""#),
+            "batch/v1beta1 CronJob is deprecated in v1.21+"
+        );
+        assert_eq!(
+            warning_text(r#"299 - "unknown field \"spec.foo\"""#),
+            r#"unknown field "spec.foo""#
+        );
+        assert_eq!(
+            warning_text(r#"299 - "path \\ with \"quotes\" inside" "Wed, 21 Oct 2015 07:28:00 GMT""#),
+            r#"path \ with "quotes" inside"#
+        );
+        assert_eq!(
+            warning_text(r#"299 - "deprecated" "Wed, 21 Oct 2015 07:28:00 GMT""#),
+            "deprecated"
+        );
+        assert_eq!(warning_text("not a warning"), "not a warning");
+    }
+
+    // ensure our status schema is sensible
+    #[test]
+    fn delete_deserialize_test() {
+        let statusresp = r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Success","details":{"name":"some-app","group":"clux.dev","kind":"foos","uid":"1234-some-uid"}}"#;
+        let s: Status = serde_json::from_str::<Status>(statusresp).unwrap();
+        assert_eq!(s.details.unwrap().name, "some-app");
+
+        let statusnoname = r#"{"kind":"Status","apiVersion":"v1","metadata":{},"status":"Success","details":{"group":"clux.dev","kind":"foos","uid":"1234-some-uid"}}"#;
+        let s2: Status = serde_json::from_str::<Status>(statusnoname).unwrap();
+        assert_eq!(s2.details.unwrap().name, ""); // optional probably better..
+    }
 }
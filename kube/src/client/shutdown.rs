@@ -0,0 +1,109 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+use tokio::sync::Notify;
+
+use crate::{service::Service, Config, Error, Result};
+
+/// The settings of a [`Config`] that the [`Client`](super::Client) applies itself, rather than its service
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Settings {
+    pub(crate) max_response_body_size: Option<usize>,
+    pub(crate) max_watch_frame_size: Option<usize>,
+    pub(crate) field_manager: Option<Arc<str>>,
+    /// The default `User-Agent` of the config, which a flow hint extends
+    pub(crate) user_agent: Option<http::HeaderValue>,
+}
+
+impl From<&Config> for Settings {
+    fn from(config: &Config) -> Self {
+        Settings {
+            max_response_body_size: config.max_response_body_size,
+            max_watch_frame_size: config.max_watch_frame_size,
+            field_manager: config.field_manager.as_deref().map(Arc::from),
+            user_agent: config.headers.get(http::header::USER_AGENT).cloned(),
+        }
+    }
+}
+
+struct Current {
+    service: Option<Service>,
+    settings: Arc<Settings>,
+}
+
+/// The service and settings shared by a [`Client`](super::Client) and its clones, until it is shut down
+pub(crate) struct Lifecycle {
+    current: Mutex<Current>,
+    in_flight: AtomicUsize,
+    drained: Notify,
+}
+
+impl Lifecycle {
+    pub(crate) fn new(service: Service, settings: Settings) -> Arc<Self> {
+        Arc::new(Lifecycle {
+            current: Mutex::new(Current {
+                service: Some(service),
+                settings: Arc::new(settings),
+            }),
+            in_flight: AtomicUsize::new(0),
+            drained: Notify::new(),
+        })
+    }
+
+    /// The settings of the current config
+    pub(crate) fn settings(&self) -> Arc<Settings> {
+        self.current.lock().unwrap().settings.clone()
+    }
+
+    /// Start a request, unless the client has been shut down
+    pub(crate) fn start(self: &Arc<Self>) -> Result<(Service, InFlight)> {
+        let current = self.current.lock().unwrap();
+        let service = current.service.as_ref().ok_or(Error::ClientShutdown)?.clone();
+        // Counted while locked, so `shutdown` never misses a request that got the service
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        Ok((service, InFlight(self.clone())))
+    }
+
+    /// Send new requests with `service` and `settings`, also if the client had been shut down
+    pub(crate) fn replace(&self, service: Service, settings: Settings) {
+        let old = {
+            let mut current = self.current.lock().unwrap();
+            current.settings = Arc::new(settings);
+            current.service.replace(service)
+        };
+        // The old service closes its connections once the in-flight requests are done with it
+        drop(old);
+    }
+
+    /// Refuse new requests, and wait up to `timeout` for the started ones to complete
+    pub(crate) async fn shutdown(&self, timeout: Duration) -> bool {
+        // Dropping our handle of the service lets it close its connections once the in-flight
+        // requests are done with their clones
+        drop(self.current.lock().unwrap().service.take());
+        let drained = async {
+            loop {
+                let notified = self.drained.notified();
+                if self.in_flight.load(Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, drained).await.is_ok()
+    }
+}
+
+/// A started request, counted until it is dropped
+pub(crate) struct InFlight(Arc<Lifecycle>);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.drained.notify_waiters();
+        }
+    }
+}
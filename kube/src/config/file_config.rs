@@ -295,6 +295,180 @@ impl Kubeconfig {
     }
 }
 
+/// A context of a [`Kubeconfig`], with the cluster and user it refers to
+#[derive(Clone, Debug)]
+pub struct ContextEntry<'a> {
+    /// The name of the context
+    pub name: &'a str,
+    /// The context
+    pub context: &'a Context,
+    /// Whether this is the `current-context`
+    pub is_current: bool,
+    /// The cluster of the context, `None` if it is missing from the kubeconfig
+    pub cluster: Option<&'a Cluster>,
+    /// The user of the context, `None` if it is missing from the kubeconfig
+    pub user: Option<&'a AuthInfo>,
+}
+
+/// An inconsistency found by [`Kubeconfig::validate`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KubeconfigProblem {
+    /// The `current-context` does not exist
+    MissingCurrentContext { context: String },
+    /// A context refers to a cluster that does not exist
+    MissingCluster { context: String, cluster: String },
+    /// A context refers to a user that does not exist
+    MissingUser { context: String, user: String },
+    /// Several entries of the same kind share a name, only the first one is used
+    DuplicateName { kind: &'static str, name: String },
+}
+
+impl std::fmt::Display for KubeconfigProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingCurrentContext { context } => {
+                write!(f, "current context {} does not exist", context)
+            }
+            Self::MissingCluster { context, cluster } => {
+                write!(f, "context {} refers to missing cluster {}", context, cluster)
+            }
+            Self::MissingUser { context, user } => {
+                write!(f, "context {} refers to missing user {}", context, user)
+            }
+            Self::DuplicateName { kind, name } => write!(f, "{} {} is defined more than once", kind, name),
+        }
+    }
+}
+
+/// Queries and edits for building context pickers, like `kubectx` and `kubens`
+impl Kubeconfig {
+    /// The named context
+    pub fn context(&self, name: &str) -> Option<&Context> {
+        self.contexts.iter().find(|c| c.name == name).map(|c| &c.context)
+    }
+
+    /// The named cluster
+    pub fn cluster(&self, name: &str) -> Option<&Cluster> {
+        self.clusters.iter().find(|c| c.name == name).map(|c| &c.cluster)
+    }
+
+    /// The named user
+    pub fn auth_info(&self, name: &str) -> Option<&AuthInfo> {
+        self.auth_infos
+            .iter()
+            .find(|a| a.name == name)
+            .map(|a| &a.auth_info)
+    }
+
+    /// All contexts, resolved to the clusters and users they refer to
+    pub fn context_entries(&self) -> Vec<ContextEntry<'_>> {
+        self.contexts
+            .iter()
+            .map(|named| ContextEntry {
+                name: &named.name,
+                context: &named.context,
+                is_current: self.current_context.as_deref() == Some(named.name.as_str()),
+                cluster: self.cluster(&named.context.cluster),
+                user: self.auth_info(&named.context.user),
+            })
+            .collect()
+    }
+
+    /// The names of all clusters
+    pub fn cluster_names(&self) -> Vec<&str> {
+        self.clusters.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    /// The names of all users
+    pub fn user_names(&self) -> Vec<&str> {
+        self.auth_infos.iter().map(|a| a.name.as_str()).collect()
+    }
+
+    /// The namespaces used by the contexts, sorted and without duplicates
+    pub fn namespaces(&self) -> Vec<&str> {
+        let mut namespaces = self
+            .contexts
+            .iter()
+            .filter_map(|c| c.context.namespace.as_deref())
+            .collect::<Vec<_>>();
+        namespaces.sort_unstable();
+        namespaces.dedup();
+        namespaces
+    }
+
+    /// Find references to missing entries, and entries that shadow each other
+    pub fn validate(&self) -> Vec<KubeconfigProblem> {
+        let mut problems = vec![];
+        if let Some(context) = &self.current_context {
+            if self.context(context).is_none() {
+                problems.push(KubeconfigProblem::MissingCurrentContext {
+                    context: context.clone(),
+                });
+            }
+        }
+        for entry in self.context_entries() {
+            if entry.cluster.is_none() {
+                problems.push(KubeconfigProblem::MissingCluster {
+                    context: entry.name.to_string(),
+                    cluster: entry.context.cluster.clone(),
+                });
+            }
+            if entry.user.is_none() {
+                problems.push(KubeconfigProblem::MissingUser {
+                    context: entry.name.to_string(),
+                    user: entry.context.user.clone(),
+                });
+            }
+        }
+        let named = [
+            (
+                "context",
+                self.contexts.iter().map(|c| &c.name).collect::<Vec<_>>(),
+            ),
+            ("cluster", self.clusters.iter().map(|c| &c.name).collect()),
+            ("user", self.auth_infos.iter().map(|a| &a.name).collect()),
+        ];
+        for (kind, names) in &named {
+            for (i, name) in names.iter().enumerate() {
+                // Reported once, at the first duplicate
+                if names[..i].iter().filter(|n| *n == name).count() == 1 {
+                    problems.push(KubeconfigProblem::DuplicateName {
+                        kind,
+                        name: name.to_string(),
+                    });
+                }
+            }
+        }
+        problems
+    }
+
+    /// Switch the `current-context`
+    ///
+    /// Fails if the context does not exist.
+    pub fn set_current_context(&mut self, name: &str) -> Result<()> {
+        self.context(name).ok_or_else(|| ConfigError::LoadContext {
+            context_name: name.to_string(),
+        })?;
+        self.current_context = Some(name.to_string());
+        Ok(())
+    }
+
+    /// Set the default namespace of a context, or clear it with `None`
+    ///
+    /// Fails if the context does not exist.
+    pub fn set_namespace(&mut self, context: &str, namespace: Option<&str>) -> Result<()> {
+        let named = self
+            .contexts
+            .iter_mut()
+            .find(|c| c.name == context)
+            .ok_or_else(|| ConfigError::LoadContext {
+                context_name: context.to_string(),
+            })?;
+        named.context.namespace = namespace.map(String::from);
+        Ok(())
+    }
+}
+
 fn append_new_named<T, F>(base: &mut Vec<T>, next: Vec<T>, f: F)
 where
     F: Fn(&T) -> &String,
@@ -343,6 +517,59 @@ mod tests {
     use super::*;
     use serde_json::Value;
 
+    #[test]
+    fn kubeconfig_queries() {
+        let config = r#"
+current-context: dev
+clusters:
+- name: dev-cluster
+  cluster:
+    server: https://dev:6443
+users:
+- name: admin
+  user:
+    token: secret
+- name: admin
+  user: {}
+contexts:
+- name: dev
+  context:
+    cluster: dev-cluster
+    user: admin
+    namespace: blog
+- name: prod
+  context:
+    cluster: prod-cluster
+    user: deployer
+    namespace: blog
+"#;
+        let mut config: Kubeconfig = serde_yaml::from_str(config).unwrap();
+        let entries = config.context_entries();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_current && entries[0].cluster.is_some());
+        assert!(!entries[1].is_current && entries[1].user.is_none());
+        assert_eq!(config.namespaces(), vec!["blog"]);
+        assert_eq!(config.validate(), vec![
+            KubeconfigProblem::MissingCluster {
+                context: "prod".into(),
+                cluster: "prod-cluster".into()
+            },
+            KubeconfigProblem::MissingUser {
+                context: "prod".into(),
+                user: "deployer".into()
+            },
+            KubeconfigProblem::DuplicateName {
+                kind: "user",
+                name: "admin".into()
+            },
+        ]);
+
+        assert!(config.set_current_context("staging").is_err());
+        config.set_current_context("prod").unwrap();
+        config.set_namespace("prod", Some("shop")).unwrap();
+        assert_eq!(config.context("prod").unwrap().namespace.as_deref(), Some("shop"));
+    }
+
     #[test]
     fn kubeconfig_merge() {
         let kubeconfig1 = Kubeconfig {
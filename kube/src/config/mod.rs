@@ -14,9 +14,9 @@ use file_loader::ConfigLoader;
 pub use file_loader::KubeConfigOptions;
 pub(crate) use utils::read_file_to_string;
 
-use http::header::HeaderMap;
+use http::header::{HeaderMap, HeaderName, HeaderValue, IntoHeaderName, USER_AGENT};
 
-use std::time::Duration;
+use std::{convert::TryFrom, time::Duration};
 
 /// Configuration object detailing things like cluster URL, default namespace, root certificates, and timeouts.
 #[derive(Debug, Clone)]
@@ -29,12 +29,57 @@ pub struct Config {
     pub root_cert: Option<Vec<Vec<u8>>>,
     /// Default headers to be used to communicate with the Kubernetes API
     pub headers: HeaderMap,
-    /// Timeout for calls to the Kubernetes API.
+    /// Timeout for establishing a connection to the Kubernetes API, including the TLS handshake
     ///
     /// A value of `None` means no timeout
-    pub timeout: Option<std::time::Duration>,
+    pub connect_timeout: Option<Duration>,
+    /// Timeout for reading from the Kubernetes API
+    ///
+    /// Applies separately to waiting for the response headers, and to every chunk of the body,
+    /// so large responses don't time out while data keeps arriving.
+    /// A value of `None` means no timeout
+    pub read_timeout: Option<Duration>,
+    /// Timeout for a whole call, from sending the request until the response body has been read
+    ///
+    /// Does not apply to long running calls.
+    /// A value of `None` means no timeout
+    pub total_timeout: Option<Duration>,
+    /// Read timeout for long running calls, in place of [`read_timeout`](Self::read_timeout)
+    ///
+    /// Long running calls are watches, followed logs, and exec, attach and port forward connections.
+    /// Defaults to 295s, just over the longest `timeoutSeconds` of a watch, so a stalled connection is
+    /// noticed. A followed log or an exec session can be quiet for longer, set this to `None` for those.
+    /// A value of `None` means no timeout
+    pub long_running_read_timeout: Option<Duration>,
+    /// Maximum size in bytes of a response body, and by default of a single event of a watch
+    ///
+    /// Larger responses fail with [`Error::ResponseTooLarge`](crate::Error::ResponseTooLarge) before
+    /// they are buffered in full, protecting against colossal lists or misbehaving aggregated apis.
+    /// Streams from [`Client::request_stream`](crate::Client::request_stream) are bounded by their decoder instead.
+    /// A value of `None` means no limit
+    pub max_response_body_size: Option<usize>,
+    /// Maximum size in bytes of a single event of a watch
+    ///
+    /// Larger events end the watch with [`Error::ResponseTooLarge`](crate::Error::ResponseTooLarge)
+    /// before they are buffered in full.
+    /// A value of `None` falls back to [`max_response_body_size`](Self::max_response_body_size)
+    pub max_watch_frame_size: Option<usize>,
+    /// Field manager of writes that do not name one in their params, see [`Client::with_field_manager`](crate::Client::with_field_manager)
+    ///
+    /// A value of `None` leaves the field manager to the apiserver, except for server-side applies, which require one.
+    pub field_manager: Option<String>,
+    /// Report API calls whose response takes longer than this, see [`SlowRequestLayer`](crate::service::SlowRequestLayer)
+    ///
+    /// A value of `None` disables the reports
+    pub slow_request_threshold: Option<Duration>,
     /// Whether to accept invalid ceritifacts
     pub accept_invalid_certs: bool,
+    /// Header used to send a unique id with every request
+    ///
+    /// Defaults to `Audit-ID`, which the apiserver uses as the id of the request in its audit log.
+    /// The id is recorded in the `request` tracing span and in [`ErrorResponse`](crate::error::ErrorResponse)s.
+    /// A value of `None` disables request ids.
+    pub request_id_header: Option<HeaderName>,
     /// Client certs and key in PEM format and a password for a client to create `Identity` with.
     /// Password is only used with `native_tls` to create a PKCS12 archive.
     pub(crate) identity: Option<(Vec<u8>, String)>,
@@ -54,13 +99,46 @@ impl Config {
             default_ns: String::from("default"),
             root_cert: None,
             headers: HeaderMap::new(),
-            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            total_timeout: None,
+            long_running_read_timeout: Some(DEFAULT_LONG_RUNNING_READ_TIMEOUT),
+            max_response_body_size: None,
+            max_watch_frame_size: None,
+            field_manager: None,
+            slow_request_threshold: None,
             accept_invalid_certs: false,
+            request_id_header: Some(HeaderName::from_static(DEFAULT_REQUEST_ID_HEADER)),
             identity: None,
             auth_info: AuthInfo::default(),
         }
     }
 
+    /// Identify the client with a `User-Agent` of `component/version`
+    ///
+    /// The apiserver records the user agent in its audit log.
+    ///
+    /// ```
+    /// # fn scope() -> Result<(), kube::Error> {
+    /// let config = kube::Config::new("https://localhost:6443".parse().unwrap())
+    ///     .with_user_agent("shop-operator", env!("CARGO_PKG_VERSION"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_user_agent(self, component: &str, version: &str) -> Result<Self> {
+        let agent = HeaderValue::try_from(format!("{}/{}", component, version))
+            .map_err(ConfigError::InvalidUserAgent)?;
+        Ok(self.with_header(USER_AGENT, agent))
+    }
+
+    /// Send a header with every request, replacing any earlier default header of the same name
+    ///
+    /// Headers set on a request take precedence over default headers.
+    pub fn with_header<K: IntoHeaderName>(mut self, name: K, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
     /// Infer the configuration from the environment
     ///
     /// Done by attempting to load in-cluster environment variables first, and
@@ -112,8 +190,16 @@ impl Config {
             default_ns,
             root_cert: Some(root_cert),
             headers: HeaderMap::new(),
-            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            total_timeout: None,
+            long_running_read_timeout: Some(DEFAULT_LONG_RUNNING_READ_TIMEOUT),
+            max_response_body_size: None,
+            max_watch_frame_size: None,
+            field_manager: None,
+            slow_request_threshold: None,
             accept_invalid_certs: false,
+            request_id_header: Some(HeaderName::from_static(DEFAULT_REQUEST_ID_HEADER)),
             identity: None,
             auth_info: AuthInfo {
                 token: Some(token),
@@ -178,17 +264,30 @@ impl Config {
             default_ns,
             root_cert,
             headers: HeaderMap::new(),
-            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: Some(DEFAULT_READ_TIMEOUT),
+            total_timeout: None,
+            long_running_read_timeout: Some(DEFAULT_LONG_RUNNING_READ_TIMEOUT),
+            max_response_body_size: None,
+            max_watch_frame_size: None,
+            field_manager: None,
+            slow_request_threshold: None,
             accept_invalid_certs,
+            request_id_header: Some(HeaderName::from_static(DEFAULT_REQUEST_ID_HEADER)),
             identity: identity_pem.map(|i| (i, String::from(IDENTITY_PASSWORD))),
             auth_info: loader.user,
         })
     }
 }
 
+/// Default header for request ids
+const DEFAULT_REQUEST_ID_HEADER: &str = "audit-id";
+
 // https://github.com/clux/kube-rs/issues/146#issuecomment-590924397
 /// Default Timeout
-const DEFAULT_TIMEOUT: Duration = Duration::from_secs(295);
+const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_READ_TIMEOUT: Duration = Duration::from_secs(295);
+const DEFAULT_LONG_RUNNING_READ_TIMEOUT: Duration = Duration::from_secs(295);
 const IDENTITY_PASSWORD: &str = " ";
 
 // temporary catalina hack for openssl only
@@ -209,6 +308,23 @@ fn hacky_cert_lifetime_for_macos(_: &[u8]) -> bool {
 
 // Expose raw config structs
 pub use file_config::{
-    AuthInfo, AuthProviderConfig, Cluster, Context, ExecConfig, Kubeconfig, NamedAuthInfo, NamedCluster,
-    NamedContext, NamedExtension, Preferences,
+    AuthInfo, AuthProviderConfig, Cluster, Context, ContextEntry, ExecConfig, Kubeconfig, KubeconfigProblem,
+    NamedAuthInfo, NamedCluster, NamedContext, NamedExtension, Preferences,
 };
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+    use http::header::USER_AGENT;
+
+    #[test]
+    fn default_headers() {
+        let config = Config::new("https://localhost:6443".parse().unwrap())
+            .with_header("x-team", "payments".parse().unwrap())
+            .with_user_agent("shop-operator", "1.2.3")
+            .unwrap();
+        assert_eq!(config.headers[USER_AGENT], "shop-operator/1.2.3");
+        assert_eq!(config.headers["x-team"], "payments");
+        assert!(config.with_user_agent("bad\n", "1").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::Discovery;
+use crate::{Client, Result};
+
+/// A [`Discovery`] with its fetch time
+struct Snapshot {
+    /// Seconds since the unix epoch
+    fetched_at: u64,
+    discovery: Arc<Discovery>,
+}
+
+/// On-disk format of a [`Snapshot`]
+#[derive(Serialize, Deserialize)]
+struct DiskSnapshot<D> {
+    fetched_at: u64,
+    discovery: D,
+}
+
+impl Snapshot {
+    fn new(discovery: Discovery) -> Self {
+        let fetched_at = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Self {
+            fetched_at,
+            discovery: Arc::new(discovery),
+        }
+    }
+
+    fn is_fresh(&self, ttl: Duration) -> bool {
+        let fetched_at = SystemTime::UNIX_EPOCH + Duration::from_secs(self.fetched_at);
+        match SystemTime::now().duration_since(fetched_at) {
+            Ok(age) => age < ttl,
+            // fetched in the future, clock skew between processes sharing a disk cache
+            Err(_) => false,
+        }
+    }
+}
+
+/// A [`Discovery`] that is only re-run when its cache expires
+///
+/// Running discovery takes one request per api group version, which adds noticeable latency
+/// when done for every dynamic operation. `CachedDiscovery` keeps the result in memory, and
+/// optionally on disk to share it between invocations of a CLI, similar to `kubectl`'s
+/// `~/.kube/cache/discovery`.
+///
+/// ```no_run
+/// use kube::{discovery::CachedDiscovery, Client};
+/// use std::time::Duration;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let cache = CachedDiscovery::new(client, Duration::from_secs(600))
+///     .disk_cache("/home/me/.cache/myctl/discovery.json")
+///     .override_kind("Certificate", "cert-manager.io/v1");
+/// let discovery = cache.get().await?;
+/// let deploy = discovery.resolve_kind("deployment.apps");
+/// // after installing a crd:
+/// cache.invalidate();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CachedDiscovery {
+    client: Client,
+    ttl: Duration,
+    path: Option<PathBuf>,
+    overrides: Vec<(String, String)>,
+    snapshot: Arc<Mutex<Option<Snapshot>>>,
+}
+
+impl CachedDiscovery {
+    /// Create an in-memory cache whose entries expire after `ttl`
+    pub fn new(client: Client, ttl: Duration) -> Self {
+        Self {
+            client,
+            ttl,
+            path: None,
+            overrides: Vec::new(),
+            snapshot: Arc::new(Mutex::new(None)),
+        }
+    }
+
+    /// Also persist the cache to a file at `path`
+    ///
+    /// The file is read when the in-memory cache is empty, and written after every refresh.
+    /// Failing to read or write the file is not an error, but a cache miss.
+    pub fn disk_cache<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Pin the `apiVersion` used when resolving `kind`, see [`Discovery::override_kind`]
+    ///
+    /// Overrides apply to every [`Discovery`] handed out by the cache, also ones read from disk.
+    pub fn override_kind(mut self, kind: &str, api_version: &str) -> Self {
+        self.overrides.push((kind.to_string(), api_version.to_string()));
+        self
+    }
+
+    /// Get the cached [`Discovery`], running discovery if the cache is empty or expired
+    pub async fn get(&self) -> Result<Arc<Discovery>> {
+        if let Some(discovery) = self.cached() {
+            return Ok(discovery);
+        }
+        self.refresh().await
+    }
+
+    /// Run discovery and replace the cached result
+    pub async fn refresh(&self) -> Result<Arc<Discovery>> {
+        let snapshot = Snapshot::new(self.with_overrides(Discovery::run(&self.client).await?));
+        let discovery = snapshot.discovery.clone();
+        if let Some(path) = &self.path {
+            if let Err(err) = store(path, &snapshot) {
+                warn!("Failed to write discovery cache {}: {}", path.display(), err);
+            }
+        }
+        *self.snapshot.lock().unwrap() = Some(snapshot);
+        Ok(discovery)
+    }
+
+    /// Drop the cached result, so that the next [`CachedDiscovery::get`] runs discovery
+    pub fn invalidate(&self) {
+        self.snapshot.lock().unwrap().take();
+        if let Some(path) = &self.path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    fn cached(&self) -> Option<Arc<Discovery>> {
+        let mut snapshot = self.snapshot.lock().unwrap();
+        if snapshot.is_none() {
+            *snapshot = self.path.as_deref().and_then(load).map(|mut s| {
+                if let Some(discovery) = Arc::get_mut(&mut s.discovery) {
+                    *discovery = self.with_overrides(std::mem::take(discovery));
+                }
+                s
+            });
+        }
+        match &*snapshot {
+            Some(s) if s.is_fresh(self.ttl) => Some(s.discovery.clone()),
+            _ => None,
+        }
+    }
+
+    fn with_overrides(&self, discovery: Discovery) -> Discovery {
+        self.overrides
+            .iter()
+            .fold(discovery, |discovery, (kind, api_version)| {
+                discovery.override_kind(kind, api_version)
+            })
+    }
+}
+
+fn load(path: &Path) -> Option<Snapshot> {
+    let data = std::fs::read(path).ok()?;
+    let disk: DiskSnapshot<Discovery> = serde_json::from_slice(&data).ok()?;
+    Some(Snapshot {
+        fetched_at: disk.fetched_at,
+        discovery: Arc::new(disk.discovery),
+    })
+}
+
+fn store(path: &Path, snapshot: &Snapshot) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let disk = DiskSnapshot {
+        fetched_at: snapshot.fetched_at,
+        discovery: &*snapshot.discovery,
+    };
+    let data = serde_json::to_vec(&disk).map_err(std::io::Error::from)?;
+    std::fs::write(path, data)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{load, store, CachedDiscovery, Snapshot};
+    use crate::{discovery::Discovery, Client, Service};
+    use std::time::Duration;
+
+    #[test]
+    fn disk_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache").join("discovery.json");
+        assert!(load(&path).is_none());
+
+        let mut discovery = Discovery::default();
+        discovery.add_group("apps", "v1");
+        store(&path, &Snapshot::new(discovery)).unwrap();
+
+        let snapshot = load(&path).unwrap();
+        assert!(snapshot.is_fresh(Duration::from_secs(60)));
+        assert_eq!(snapshot.discovery.groups, vec![("apps".into(), "v1".into())]);
+    }
+
+    #[test]
+    fn expiry() {
+        let mut snapshot = Snapshot::new(Discovery::default());
+        assert!(snapshot.is_fresh(Duration::from_secs(60)));
+        snapshot.fetched_at -= 120;
+        assert!(!snapshot.is_fresh(Duration::from_secs(60)));
+    }
+
+    #[tokio::test]
+    async fn overrides_apply_to_cached_discovery() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("discovery.json");
+        let mut discovery = Discovery::default();
+        for gv in &["clux.dev/v1", "clux.dev/v2"] {
+            discovery.add_resource_list(
+                &serde_json::from_value(serde_json::json!({
+                    "groupVersion": gv,
+                    "resources": [{"name": "foos", "singularName": "", "namespaced": true, "kind": "Foo", "verbs": ["get"]}]
+                }))
+                .unwrap(),
+            );
+        }
+        discovery.add_group("clux.dev", "v2");
+        store(&path, &Snapshot::new(discovery)).unwrap();
+
+        // the cache is fresh, so the client is never called
+        let svc = tower::service_fn(|_req: http::Request<hyper::Body>| async {
+            Err::<http::Response<hyper::Body>, tower::BoxError>("unexpected request".into())
+        });
+        let cache = CachedDiscovery::new(Client::new(Service::new(svc)), Duration::from_secs(60))
+            .disk_cache(&path)
+            .override_kind("Foo", "clux.dev/v1");
+        let discovery = cache.get().await.unwrap();
+        assert_eq!(discovery.resolve_kind("foo").unwrap().api_version, "clux.dev/v1");
+    }
+}
@@ -0,0 +1,353 @@
+//! High-level utilities for runtime API discovery
+//!
+//! Discovery resolves the resources the apiserver serves, so that dynamic
+//! consumers (like [`ops::apply_manifest`](crate::ops::apply_manifest)) can map
+//! an `apiVersion` + `kind` pair to the information needed to build queries.
+use crate::{api::GroupVersionKind, Client, Result};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::{APIResource, APIResourceList};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+mod cache;
+pub use cache::CachedDiscovery;
+
+/// Information about a served resource, as found through discovery
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ApiResource {
+    /// Resource group, empty for core group
+    pub group: String,
+    /// Group version
+    pub version: String,
+    /// apiVersion of the resource (group/version or just version for the core group)
+    pub api_version: String,
+    /// Singular PascalCase name of the resource
+    pub kind: String,
+    /// Plural name of the resource (and the last url path component)
+    pub plural: String,
+    /// Whether the resource is namespaced or cluster scoped
+    pub namespaced: bool,
+    /// Verbs supported by the resource (`get`, `list`, `patch`, ...)
+    pub verbs: Vec<String>,
+}
+
+impl ApiResource {
+    /// Creates an `ApiResource` from an [`APIResource`] and the `groupVersion` of its [`APIResourceList`]
+    ///
+    /// If it does not specify version and/or group, they will be taken from `group_version`.
+    pub fn from_apiresource(ar: &APIResource, group_version: &str) -> Self {
+        let gvsplit = group_version.splitn(2, '/').collect::<Vec<_>>();
+        let (default_group, default_version) = match *gvsplit.as_slice() {
+            [g, v] => (g, v), // standard case
+            [v] => ("", v),   // core v1 case
+            _ => unreachable!(),
+        };
+        let group = ar.group.clone().unwrap_or_else(|| default_group.into());
+        let version = ar.version.clone().unwrap_or_else(|| default_version.into());
+        let api_version = if group.is_empty() {
+            version.clone()
+        } else {
+            format!("{}/{}", group, version)
+        };
+        Self {
+            group,
+            version,
+            api_version,
+            kind: ar.kind.clone(),
+            plural: ar.name.clone(),
+            namespaced: ar.namespaced,
+            verbs: ar.verbs.clone(),
+        }
+    }
+
+    /// The [`GroupVersionKind`] to use with [`DynamicObject`](crate::api::DynamicObject) for this resource
+    pub fn to_gvk(&self) -> GroupVersionKind {
+        GroupVersionKind::gvk(&self.group, &self.version, &self.kind)
+            .expect("discovered resources have a version and kind")
+            .plural(&self.plural)
+    }
+
+    /// Whether the resource supports the given verb
+    pub fn supports(&self, verb: &str) -> bool {
+        self.verbs.iter().any(|v| v == verb)
+    }
+}
+
+/// A snapshot of the resources served by an apiserver
+///
+/// ```no_run
+/// use kube::{discovery::Discovery, Client};
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let discovery = Discovery::run(&client).await?;
+/// if let Some(ar) = discovery.resolve("apps/v1", "Deployment") {
+///     println!("Deployments are served at {}", ar.plural);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Discovery {
+    resources: Vec<ApiResource>,
+    /// Groups in priority order, with their preferred version
+    groups: Vec<(String, String)>,
+    /// Pinned apiVersion for lowercased kinds
+    #[serde(skip)]
+    overrides: HashMap<String, String>,
+}
+
+impl Discovery {
+    /// Discover all resources in all served versions of all api groups
+    ///
+    /// Groups whose discovery endpoint fails (typically unavailable aggregated apiservers)
+    /// are skipped with a warning, rather than failing the whole discovery.
+    pub async fn run(client: &Client) -> Result<Self> {
+        let mut discovery = Self::default();
+        let core = client.list_core_api_versions().await?;
+        if let Some(preferred) = core.versions.first() {
+            discovery.add_group("", preferred);
+        }
+        for version in core.versions {
+            let list = client.list_core_api_resources(&version).await?;
+            discovery.add_resource_list(&list);
+        }
+        let groups = client.list_api_groups().await?;
+        // the apiserver returns groups sorted by priority
+        for group in groups.groups {
+            let preferred = group
+                .preferred_version
+                .as_ref()
+                .or_else(|| group.versions.first())
+                .map(|v| v.version.clone());
+            if let Some(preferred) = preferred {
+                discovery.add_group(&group.name, &preferred);
+            }
+            for version in group.versions {
+                match client.list_api_group_resources(&version.group_version).await {
+                    Ok(list) => discovery.add_resource_list(&list),
+                    Err(err) => warn!("Skipping discovery of {}: {}", version.group_version, err),
+                }
+            }
+        }
+        Ok(discovery)
+    }
+
+    /// Add the resources from an [`APIResourceList`] to this discovery
+    ///
+    /// Subresources (like `pods/log`) are ignored.
+    pub fn add_resource_list(&mut self, list: &APIResourceList) {
+        for ar in &list.resources {
+            if ar.name.contains('/') {
+                continue;
+            }
+            self.resources
+                .push(ApiResource::from_apiresource(ar, &list.group_version));
+        }
+    }
+
+    /// Register an api group and its preferred version
+    ///
+    /// Groups added first take priority when resolving kinds with [`Discovery::resolve_kind`].
+    pub fn add_group(&mut self, group: &str, preferred_version: &str) {
+        if !self.groups.iter().any(|(g, _)| g == group) {
+            self.groups
+                .push((group.to_string(), preferred_version.to_string()));
+        }
+    }
+
+    /// Pin the `apiVersion` used when resolving `kind` with [`Discovery::resolve_kind`]
+    ///
+    /// Use this to disambiguate kinds that are served by several groups.
+    pub fn override_kind(mut self, kind: &str, api_version: &str) -> Self {
+        self.overrides
+            .insert(kind.to_ascii_lowercase(), api_version.to_string());
+        self
+    }
+
+    /// Resolve a kind (or plural resource name) to its preferred served resource, like `kubectl` does
+    ///
+    /// The name is matched case-insensitively, and can be qualified with a group as
+    /// `kind.group` (e.g. `deployment.apps`). When several groups serve the kind, the
+    /// group with the highest priority wins, and the preferred version of that group is
+    /// chosen where available. Overrides from [`Discovery::override_kind`] take precedence.
+    pub fn resolve_kind(&self, name: &str) -> Option<&ApiResource> {
+        let name = name.to_ascii_lowercase();
+        let (kind, group) = match name.find('.') {
+            Some(idx) => (&name[..idx], Some(&name[idx + 1..])),
+            None => (name.as_str(), None),
+        };
+        let matches_kind = |ar: &&ApiResource| ar.kind.to_ascii_lowercase() == kind || ar.plural == kind;
+        if let Some(api_version) = self.overrides.get(kind) {
+            return self
+                .resources
+                .iter()
+                .filter(matches_kind)
+                .find(|ar| &ar.api_version == api_version);
+        }
+        self.resources
+            .iter()
+            .filter(matches_kind)
+            .filter(|ar| match group {
+                Some(g) => ar.group == g,
+                None => true,
+            })
+            .min_by_key(|ar| {
+                let rank = self.groups.iter().position(|(g, _)| g == &ar.group);
+                let preferred = self
+                    .groups
+                    .iter()
+                    .any(|(g, v)| g == &ar.group && v == &ar.version);
+                (rank.unwrap_or(usize::MAX), !preferred)
+            })
+    }
+
+    /// Find the resource served for an `apiVersion` and `kind`
+    pub fn resolve(&self, api_version: &str, kind: &str) -> Option<&ApiResource> {
+        self.resources
+            .iter()
+            .find(|ar| ar.api_version == api_version && ar.kind == kind)
+    }
+
+    /// Iterate over all discovered resources
+    pub fn resources(&self) -> impl Iterator<Item = &ApiResource> {
+        self.resources.iter()
+    }
+
+    /// Iterate over the discovered resources of the preferred version of each group
+    ///
+    /// Resources that are not served in the preferred version of their group (or whose group has no
+    /// registered preferred version) are returned in the first version they were discovered in,
+    /// so every resource is returned once.
+    pub fn preferred_resources(&self) -> impl Iterator<Item = &ApiResource> {
+        let preferred: HashMap<&str, &str> = self
+            .groups
+            .iter()
+            .map(|(group, version)| (group.as_str(), version.as_str()))
+            .collect();
+        let mut versions = HashMap::new();
+        for ar in &self.resources {
+            let is_preferred = preferred.get(ar.group.as_str()) == Some(&ar.version.as_str());
+            let version = versions
+                .entry((ar.group.as_str(), ar.plural.as_str()))
+                .or_insert(ar.version.as_str());
+            if is_preferred {
+                *version = ar.version.as_str();
+            }
+        }
+        self.resources.iter().filter(move |ar| {
+            versions.get(&(ar.group.as_str(), ar.plural.as_str())) == Some(&ar.version.as_str())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Discovery;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::APIResourceList;
+
+    fn apps_v1() -> APIResourceList {
+        serde_json::from_value(serde_json::json!({
+            "groupVersion": "apps/v1",
+            "resources": [
+                {"name": "deployments", "singularName": "", "namespaced": true, "kind": "Deployment",
+                 "verbs": ["create", "delete", "get", "list", "patch", "update", "watch"]},
+                {"name": "deployments/scale", "singularName": "", "namespaced": true, "kind": "Scale",
+                 "group": "autoscaling", "version": "v1", "verbs": ["get", "patch", "update"]},
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_skips_subresources() {
+        let mut discovery = Discovery::default();
+        discovery.add_resource_list(&apps_v1());
+        assert_eq!(discovery.resources().count(), 1);
+        let ar = discovery.resolve("apps/v1", "Deployment").unwrap();
+        assert_eq!(ar.group, "apps");
+        assert_eq!(ar.plural, "deployments");
+        assert!(ar.namespaced);
+        assert!(ar.supports("patch"));
+        assert!(discovery.resolve("autoscaling/v1", "Scale").is_none());
+    }
+
+    fn resource_list(group_version: &str, kind: &str, plural: &str) -> APIResourceList {
+        serde_json::from_value(serde_json::json!({
+            "groupVersion": group_version,
+            "resources": [
+                {"name": plural, "singularName": "", "namespaced": true, "kind": kind, "verbs": ["get"]},
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_kind_by_priority() {
+        let mut discovery = Discovery::default();
+        discovery.add_group("", "v1");
+        discovery.add_group("events.k8s.io", "v1");
+        discovery.add_group("clux.dev", "v2");
+        discovery.add_resource_list(&resource_list("events.k8s.io/v1beta1", "Event", "events"));
+        discovery.add_resource_list(&resource_list("events.k8s.io/v1", "Event", "events"));
+        discovery.add_resource_list(&resource_list("v1", "Event", "events"));
+        discovery.add_resource_list(&resource_list("clux.dev/v1", "Foo", "foos"));
+        discovery.add_resource_list(&resource_list("clux.dev/v2", "Foo", "foos"));
+
+        // core group has the highest priority
+        assert_eq!(discovery.resolve_kind("Event").unwrap().api_version, "v1");
+        // preferred version within a group
+        assert_eq!(
+            discovery.resolve_kind("event.events.k8s.io").unwrap().api_version,
+            "events.k8s.io/v1"
+        );
+        assert_eq!(discovery.resolve_kind("foos").unwrap().api_version, "clux.dev/v2");
+        assert!(discovery.resolve_kind("bar").is_none());
+
+        let discovery = discovery.override_kind("Foo", "clux.dev/v1");
+        assert_eq!(discovery.resolve_kind("foo").unwrap().api_version, "clux.dev/v1");
+    }
+
+    #[test]
+    fn preferred_resources_are_listed_once() {
+        let mut discovery = Discovery::default();
+        discovery.add_group("events.k8s.io", "v1");
+        discovery.add_resource_list(&resource_list("events.k8s.io/v1beta1", "Event", "events"));
+        discovery.add_resource_list(&resource_list("events.k8s.io/v1", "Event", "events"));
+        discovery.add_resource_list(&resource_list("clux.dev/v1", "Foo", "foos"));
+        discovery.add_resource_list(&resource_list("clux.dev/v2", "Foo", "foos"));
+        let preferred: Vec<_> = discovery
+            .preferred_resources()
+            .map(|ar| ar.api_version.as_str())
+            .collect();
+        assert_eq!(preferred, vec!["events.k8s.io/v1", "clux.dev/v1"]);
+    }
+
+    #[test]
+    fn preferred_resources_include_resources_missing_from_the_preferred_version() {
+        let mut discovery = Discovery::default();
+        discovery.add_group("batch", "v1");
+        discovery.add_resource_list(&resource_list("batch/v1", "Job", "jobs"));
+        discovery.add_resource_list(&resource_list("batch/v1beta1", "CronJob", "cronjobs"));
+        discovery.add_resource_list(&resource_list("batch/v2alpha1", "CronJob", "cronjobs"));
+        let preferred: Vec<_> = discovery
+            .preferred_resources()
+            .map(|ar| ar.api_version.as_str())
+            .collect();
+        assert_eq!(preferred, vec!["batch/v1", "batch/v1beta1"]);
+    }
+
+    #[test]
+    fn core_group_version() {
+        let list: APIResourceList = serde_json::from_value(serde_json::json!({
+            "groupVersion": "v1",
+            "resources": [
+                {"name": "namespaces", "singularName": "", "namespaced": false, "kind": "Namespace", "verbs": ["get"]},
+            ]
+        }))
+        .unwrap();
+        let mut discovery = Discovery::default();
+        discovery.add_resource_list(&list);
+        let ar = discovery.resolve("v1", "Namespace").unwrap();
+        assert_eq!(ar.group, "");
+        assert_eq!(ar.api_version, "v1");
+        assert!(!ar.namespaced);
+    }
+}
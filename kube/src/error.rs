@@ -1,5 +1,6 @@
 //! Error handling in [`kube`][crate]
 
+use crate::client::StatusDetails;
 use http::header::InvalidHeaderValue;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
@@ -17,6 +18,13 @@ pub enum Error {
     #[error("ApiError: {0} ({0:?})")]
     Api(#[source] ErrorResponse),
 
+    /// An admission webhook or admission policy rejected the request
+    ///
+    /// These are returned in place of [`Error::Api`], so denials can be told apart from
+    /// other client errors. The original response is kept in [`AdmissionError::response`].
+    #[error("AdmissionError: {0}")]
+    Admission(#[source] Box<AdmissionError>),
+
     /// ConnectionError for when TcpStream fails to connect.
     #[error("ConnectionError: {0}")]
     Connection(std::io::Error),
@@ -28,6 +36,18 @@ pub enum Error {
     #[error("ServiceError: {0}")]
     Service(tower::BoxError),
 
+    /// A request timed out waiting for the response, or while reading its body
+    ///
+    /// See the timeouts of [`Config`](crate::Config).
+    #[error("Request timed out {0}")]
+    Timeout(&'static str),
+
+    /// A response body, or an event of a watch, exceeded the configured size limit
+    ///
+    /// See [`Config::max_response_body_size`](crate::Config::max_response_body_size).
+    #[error("Response exceeds the size limit of {0} bytes")]
+    ResponseTooLarge(usize),
+
     /// UTF-8 Error
     #[error("UTF-8 Error: {0}")]
     FromUtf8(#[from] std::string::FromUtf8Error),
@@ -38,6 +58,16 @@ pub enum Error {
     #[error("Error finding newline character")]
     LinesCodecMaxLineLengthExceeded,
 
+    /// Returned by the decoder of `Client::request_stream`, including errors reading the stream
+    #[error("Error decoding frames: {0}")]
+    FrameDecode(#[source] Box<dyn std::error::Error + Send + Sync>),
+
+    /// Returned by a [`Codec`](crate::client::Codec) that failed to encode or decode a body
+    #[cfg(feature = "codec")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "codec")))]
+    #[error("Codec error: {0}")]
+    Codec(#[source] Box<dyn std::error::Error + Send + Sync>),
+
     /// Returned on `std::io::Error` when reading event stream.
     #[error("Error reading events stream: {0}")]
     ReadEvents(std::io::Error),
@@ -51,9 +81,22 @@ pub enum Error {
     InternalUrlError(#[from] url::ParseError),
 
     /// Common error case when requesting parsing into own structs
+    ///
+    /// Responses and watch events that fail to deserialize are reported as [`Error::Deserialize`]
+    /// instead, which keeps this error as its `source`.
     #[error("Error deserializing response")]
     SerdeError(#[from] serde_json::Error),
 
+    /// Error deserializing a response, with context about the failing object
+    ///
+    /// Before this variant was added, these errors were reported as [`Error::SerdeError`].
+    #[error("Error deserializing response: {0}")]
+    Deserialize(#[source] Box<DeserializeError>),
+
+    /// Error deserializing a yaml manifest
+    #[error("Error deserializing yaml: {0}")]
+    YamlError(#[from] serde_yaml::Error),
+
     /// Error building a request
     #[error("Error building request")]
     RequestBuild,
@@ -70,14 +113,38 @@ pub enum Error {
     #[error("Request validation failed with {0}")]
     RequestValidation(String),
 
+    /// An object did not match its schema, see [`Validator`](crate::openapi::Validator)
+    #[error("Object does not match its schema: {}", crate::openapi::display_violations(.0))]
+    SchemaValidation(Vec<crate::openapi::SchemaViolation>),
+
+    /// The client lacks permissions, see [`verify_rbac`](crate::ops::verify_rbac)
+    #[error("Missing permissions: {}", crate::ops::display_missing(.0))]
+    MissingPermissions(Vec<crate::ops::MissingPermission>),
+
     /// A dynamic type conversion failure
     #[error("Dynamic type conversion failed {0}")]
     DynamicType(String),
 
+    /// The client was stopped with [`Client::shutdown`](crate::Client::shutdown)
+    #[error("The client has been shut down")]
+    ClientShutdown,
+
     /// Configuration error
     #[error("Error loading kubeconfig: {0}")]
     Kubeconfig(#[from] ConfigError),
 
+    /// The runtime of a [blocking client](crate::blocking::Client) could not be started
+    #[cfg(feature = "blocking")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+    #[error("Failed to start the blocking runtime: {0}")]
+    Runtime(#[source] std::io::Error),
+
+    /// Copying files to or from a container failed
+    #[cfg(feature = "cp")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "cp")))]
+    #[error("Failed to copy files: {0}")]
+    Copy(#[source] std::io::Error),
+
     /// An error with configuring SSL occured
     #[error("SslError: {0}")]
     SslError(String),
@@ -129,9 +196,15 @@ pub enum ConfigError {
     #[error("Invalid basic auth: {0}")]
     InvalidBasicAuth(#[source] InvalidHeaderValue),
 
+    #[error("Invalid user agent: {0}")]
+    InvalidUserAgent(#[source] InvalidHeaderValue),
+
     #[error("Invalid bearer token: {0}")]
     InvalidBearerToken(#[source] InvalidHeaderValue),
 
+    #[error("Invalid flow hint: {0}")]
+    InvalidFlowHint(#[source] InvalidHeaderValue),
+
     #[error("Tried to refresh a token and got a non-refreshable token response")]
     /// Tried to refresh a token and got a non-refreshable token response
     UnrefreshableTokenResponse,
@@ -263,7 +336,9 @@ impl From<OAuthError> for Error {
 }
 
 /// An error response from the API.
-#[derive(Error, Deserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+///
+/// Responses are equal when their fields are, whatever [request](ErrorResponse::request_id) they answered.
+#[derive(Error, Deserialize, Serialize, Debug, Clone, Eq)]
 #[error("{message}: {reason}")]
 pub struct ErrorResponse {
     /// The status
@@ -276,4 +351,203 @@ pub struct ErrorResponse {
     pub reason: String,
     /// The error code
     pub code: u16,
+    /// Details of the error, like the causes of an invalid request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<Box<StatusDetails>>,
+    #[serde(skip)]
+    pub(crate) request_id: Option<String>,
+}
+
+/// Context for a response that failed to deserialize, see [`Error::Deserialize`]
+#[derive(Error, Debug)]
+pub struct DeserializeError {
+    /// apiVersion of the innermost object around the failing field, if it was parseable
+    pub api_version: Option<String>,
+    /// Kind of the innermost object around the failing field, if it was parseable
+    pub kind: Option<String>,
+    /// Name of the innermost object around the failing field, if it was parseable
+    pub name: Option<String>,
+    /// Namespace of the innermost object around the failing field, if it was parseable
+    pub namespace: Option<String>,
+    /// Json path of the failing field, e.g. `.items[3].spec.replicas`
+    pub path: String,
+    /// A truncated snippet of the raw json around the failing field
+    pub snippet: String,
+    /// The error from serde
+    #[source]
+    pub source: serde_json::Error,
+}
+
+impl std::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(kind) = &self.kind {
+            write!(f, "{} ", kind)?;
+        }
+        match (&self.namespace, &self.name) {
+            (Some(ns), Some(name)) => write!(f, "{}/{} ", ns, name)?,
+            (None, Some(name)) => write!(f, "{} ", name)?,
+            _ => {}
+        }
+        write!(f, "at {}: {} (near `{}`)", self.path, self.source, self.snippet)
+    }
+}
+
+impl PartialEq for ErrorResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.status == other.status
+            && self.message == other.message
+            && self.reason == other.reason
+            && self.code == other.code
+            && self.details == other.details
+    }
+}
+
+impl ErrorResponse {
+    /// A `Failure` response with `code`, `reason` and `message`, like for mocking errors in tests
+    pub fn new(code: u16, reason: &str, message: &str) -> Self {
+        ErrorResponse {
+            status: "Failure".into(),
+            message: message.into(),
+            reason: reason.into(),
+            code,
+            details: None,
+            request_id: None,
+        }
+    }
+
+    /// The id the failed request was sent with, for correlation with apiserver audit logs
+    ///
+    /// See [`Config::request_id_header`](crate::Config::request_id_header).
+    pub fn request_id(&self) -> Option<&str> {
+        self.request_id.as_deref()
+    }
+
+    /// Turn the response into an [`Error`], telling admission rejections apart from other errors
+    pub(crate) fn into_error(self) -> Error {
+        match AdmissionError::parse(&self.message) {
+            Some((kind, webhook, message)) => Error::Admission(Box::new(AdmissionError {
+                kind,
+                webhook,
+                message,
+                response: self,
+            })),
+            None => Error::Api(self),
+        }
+    }
+}
+
+/// How an admission webhook or policy rejected a request
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AdmissionErrorKind {
+    /// The webhook (or `ValidatingAdmissionPolicy`) answered, and denied the request
+    Denied,
+    /// The apiserver could not call the webhook, and its `failurePolicy` is `Fail`
+    CallFailed,
+}
+
+/// A request rejected during admission, see [`Error::Admission`]
+#[derive(Error, Debug, Clone, Eq, PartialEq)]
+#[error("{webhook} rejected the request: {message}")]
+pub struct AdmissionError {
+    /// Whether the request was denied, or the webhook could not be called
+    pub kind: AdmissionErrorKind,
+    /// Name of the webhook, or of the `ValidatingAdmissionPolicy`
+    pub webhook: String,
+    /// The message of the webhook, or why calling it failed, empty if there is none
+    pub message: String,
+    /// The response from the apiserver
+    pub response: ErrorResponse,
+}
+
+impl AdmissionError {
+    /// Parse the messages of the apiserver for rejected admission
+    ///
+    /// These are built by `k8s.io/apiserver/pkg/admission/plugin/webhook/errors`
+    /// and the `ValidatingAdmissionPolicy` plugin.
+    fn parse(text: &str) -> Option<(AdmissionErrorKind, String, String)> {
+        let patterns = [
+            (
+                "admission webhook \"",
+                "\" denied the request",
+                AdmissionErrorKind::Denied,
+            ),
+            ("failed calling webhook \"", "\"", AdmissionErrorKind::CallFailed),
+            (
+                "ValidatingAdmissionPolicy '",
+                "' with binding",
+                AdmissionErrorKind::Denied,
+            ),
+        ];
+        for (prefix, suffix, kind) in &patterns {
+            let start = match text.find(prefix) {
+                Some(start) => start + prefix.len(),
+                None => continue,
+            };
+            let end = match text[start..].find(suffix) {
+                Some(end) => start + end,
+                None => continue,
+            };
+            let rest = &text[end + suffix.len()..];
+            let message = match rest.find(": ") {
+                Some(colon) => rest[colon + 2..].to_string(),
+                None => String::new(),
+            };
+            return Some((*kind, text[start..end].to_string(), message));
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AdmissionErrorKind, Error, ErrorResponse};
+
+    #[test]
+    fn admission_rejections_are_recognized() {
+        let denied = ErrorResponse::new(
+            403,
+            "",
+            r#"admission webhook "validate.example.com" denied the request: replicas must be odd"#,
+        );
+        match denied.clone().into_error() {
+            Error::Admission(ae) => {
+                assert_eq!(ae.kind, AdmissionErrorKind::Denied);
+                assert_eq!(ae.webhook, "validate.example.com");
+                assert_eq!(ae.message, "replicas must be odd");
+                assert_eq!(ae.response, denied);
+            }
+            err => panic!("unexpected {:?}", err),
+        }
+
+        let silent = ErrorResponse::new(
+            400,
+            "",
+            r#"admission webhook "validate.example.com" denied the request"#,
+        );
+        assert!(matches!(silent.into_error(), Error::Admission(ae) if ae.message.is_empty()));
+
+        let failed = ErrorResponse::new(
+            500,
+            "InternalError",
+            r#"Internal error occurred: failed calling webhook "mutate.example.com": Post "https://hook.svc:443/mutate": dial tcp: connection refused"#,
+        );
+        match failed.into_error() {
+            Error::Admission(ae) => {
+                assert_eq!(ae.kind, AdmissionErrorKind::CallFailed);
+                assert_eq!(ae.webhook, "mutate.example.com");
+                assert!(ae.message.ends_with("connection refused"));
+            }
+            err => panic!("unexpected {:?}", err),
+        }
+
+        let policy = ErrorResponse::new(
+            422,
+            "Invalid",
+            "deployments.apps \"blog\" is forbidden: ValidatingAdmissionPolicy 'replicas' with binding 'replicas-prod' denied request: failed expression: object.spec.replicas <= 5",
+        );
+        assert!(matches!(policy.into_error(), Error::Admission(ae) if ae.webhook == "replicas"));
+
+        let conflict = ErrorResponse::new(409, "AlreadyExists", r#"configmaps "settings" already exists"#);
+        assert!(matches!(conflict.into_error(), Error::Api(ae) if ae.code == 409));
+    }
 }
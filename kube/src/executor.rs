@@ -0,0 +1,106 @@
+//! Pluggable task spawning
+//!
+//! The [`Client`](crate::Client) and the controllers of `kube-runtime` spawn their background tasks
+//! (like the drivers of exec and port forward connections, shared watches and reconciliations)
+//! through an [`Executor`]. The default [`TokioExecutor`] spawns onto the current Tokio runtime.
+//!
+//! Implement [`Executor`] to spawn the tasks elsewhere, such as onto the executor of `async-std` or `smol`.
+//! Only spawning is pluggable. The requests of the default [`Service`](crate::Service) are sent with
+//! hyper, and timeouts and the waits of [`ops`](crate::ops) use `tokio::time`, so the tasks still
+//! need a Tokio runtime to make progress, which can be entered from any thread:
+//!
+//! ```
+//! use futures::future::BoxFuture;
+//! use kube::executor::Executor;
+//! use tokio::runtime::Handle;
+//!
+//! #[derive(Debug)]
+//! struct ThreadPerTask(Handle);
+//!
+//! impl Executor for ThreadPerTask {
+//!     fn spawn(&self, future: BoxFuture<'static, ()>) {
+//!         let runtime = self.0.clone();
+//!         std::thread::spawn(move || {
+//!             let _guard = runtime.enter();
+//!             futures::executor::block_on(future)
+//!         });
+//!     }
+//! }
+//! ```
+use futures::future::BoxFuture;
+use std::{fmt::Debug, sync::Arc};
+use tokio::runtime::Handle;
+
+/// Runs spawned futures to completion in the background
+pub trait Executor: Debug + Send + Sync + 'static {
+    /// Spawn `future` as a background task
+    fn spawn(&self, future: BoxFuture<'static, ()>);
+}
+
+/// Spawns tasks onto a Tokio runtime
+#[derive(Clone, Debug, Default)]
+pub struct TokioExecutor {
+    handle: Option<Handle>,
+}
+
+impl TokioExecutor {
+    /// Spawn onto the runtime that is current when each task is spawned
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn onto the runtime of `handle`
+    pub fn with_handle(handle: Handle) -> Self {
+        TokioExecutor { handle: Some(handle) }
+    }
+}
+
+impl Executor for TokioExecutor {
+    /// # Panics
+    ///
+    /// Panics if no runtime handle was given, and this is called outside of a Tokio runtime.
+    fn spawn(&self, future: BoxFuture<'static, ()>) {
+        match &self.handle {
+            Some(handle) => handle.spawn(future),
+            None => tokio::spawn(future),
+        };
+    }
+}
+
+/// The default executor, a [`TokioExecutor`] for the current runtime
+pub fn default_executor() -> Arc<dyn Executor> {
+    Arc::new(TokioExecutor::new())
+}
+
+#[cfg(test)]
+mod test {
+    use super::Executor;
+    use futures::{future::BoxFuture, FutureExt};
+    use tokio::runtime::Handle;
+
+    #[derive(Debug)]
+    struct ThreadPerTask(Handle);
+
+    impl Executor for ThreadPerTask {
+        fn spawn(&self, future: BoxFuture<'static, ()>) {
+            let runtime = self.0.clone();
+            std::thread::spawn(move || {
+                let _guard = runtime.enter();
+                futures::executor::block_on(future)
+            });
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn tasks_on_other_threads_can_use_tokio_timers() {
+        let (tx, rx) = futures::channel::oneshot::channel();
+        ThreadPerTask(Handle::current()).spawn(
+            async move {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+                tx.send(()).unwrap();
+            }
+            .boxed(),
+        );
+        rx.await.unwrap();
+    }
+}
@@ -86,8 +86,15 @@ assert_cfg!(
 #[macro_use] extern crate log;
 
 pub mod api;
+#[cfg(feature = "blocking")]
+#[cfg_attr(docsrs, doc(cfg(feature = "blocking")))]
+pub mod blocking;
 pub mod client;
 pub mod config;
+pub mod discovery;
+pub mod executor;
+pub mod openapi;
+pub mod ops;
 pub mod service;
 
 pub mod error;
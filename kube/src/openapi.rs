@@ -0,0 +1,445 @@
+//! Types for walking the OpenAPI v3 schemas published by the apiserver
+//!
+//! Fetch documents with [`Client::openapi_v3_schema`](crate::Client::openapi_v3_schema).
+//! This only models the parts of OpenAPI needed to explain and validate kubernetes objects.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fmt};
+
+use crate::{Error, Result};
+
+/// The index of OpenAPI v3 documents served at `/openapi/v3`
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct OpenApiPaths {
+    /// Documents keyed by path, e.g. `apis/apps/v1` or `api/v1`
+    #[serde(default)]
+    pub paths: BTreeMap<String, OpenApiPath>,
+}
+
+/// Location of an OpenAPI v3 document
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct OpenApiPath {
+    /// Url of the document, including a content hash for caching
+    #[serde(rename = "serverRelativeURL")]
+    pub server_relative_url: String,
+}
+
+/// An OpenAPI v3 document for a single group version
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct OpenApiDocument {
+    /// Reusable parts of the document
+    #[serde(default)]
+    pub components: Components,
+}
+
+/// Reusable schemas of an [`OpenApiDocument`]
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct Components {
+    /// Schemas keyed by fully qualified name, e.g. `io.k8s.api.apps.v1.Deployment`
+    #[serde(default)]
+    pub schemas: BTreeMap<String, Schema>,
+}
+
+/// A (subset of an) OpenAPI v3 schema
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Schema {
+    /// Reference to a schema in [`Components::schemas`]
+    #[serde(rename = "$ref", default, skip_serializing_if = "Option::is_none")]
+    pub ref_path: Option<String>,
+    /// Human readable description
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Json type of the value (`object`, `array`, `string`, ...)
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub type_: Option<String>,
+    /// Format modifier of the type (`int32`, `date-time`, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Properties of an object
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, Schema>,
+    /// Required properties of an object
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required: Vec<String>,
+    /// Schema of the items of an array
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub items: Option<Box<Schema>>,
+    /// Schema of the values of a map, or a boolean
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub additional_properties: Option<serde_json::Value>,
+    /// Schemas that all must match, kubernetes uses this to wrap a single `$ref`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub all_of: Vec<Schema>,
+    /// Allowed values
+    #[serde(rename = "enum", default, skip_serializing_if = "Vec::is_empty")]
+    pub enum_: Vec<serde_json::Value>,
+    /// The kinds this schema is the root object of
+    #[serde(
+        rename = "x-kubernetes-group-version-kind",
+        default,
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub group_version_kinds: Vec<SchemaGroupVersionKind>,
+}
+
+/// An entry of `x-kubernetes-group-version-kind`
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SchemaGroupVersionKind {
+    /// API group
+    #[serde(default)]
+    pub group: String,
+    /// Version
+    pub version: String,
+    /// Kind
+    pub kind: String,
+}
+
+impl OpenApiDocument {
+    /// Find the schema of the root object of a kind
+    pub fn schema_for_kind(&self, group: &str, version: &str, kind: &str) -> Option<&Schema> {
+        self.components.schemas.values().find(|s| {
+            s.group_version_kinds
+                .iter()
+                .any(|gvk| gvk.group == group && gvk.version == version && gvk.kind == kind)
+        })
+    }
+
+    /// Follow `$ref`s (including ones wrapped in a single `allOf`) to the referenced schema
+    pub fn resolve<'a>(&'a self, mut schema: &'a Schema) -> &'a Schema {
+        // bounded, in case of reference cycles
+        for _ in 0..32 {
+            let reference = match (&schema.ref_path, schema.all_of.as_slice()) {
+                (Some(r), _) => r,
+                (None, [single]) if single.ref_path.is_some() => single.ref_path.as_ref().unwrap(),
+                _ => break,
+            };
+            let name = reference.trim_start_matches("#/components/schemas/");
+            match self.components.schemas.get(name) {
+                Some(target) => schema = target,
+                None => break,
+            }
+        }
+        schema
+    }
+
+    /// Walk a dotted field path (like `spec.template.spec.containers`) below a schema
+    ///
+    /// Arrays are stepped through transparently, so `spec.containers.image`
+    /// returns the schema of the image of a container, like `kubectl explain`.
+    pub fn explain<'a>(&'a self, schema: &'a Schema, path: &str) -> Option<&'a Schema> {
+        let mut current = self.resolve(schema);
+        for field in path.split('.').filter(|f| !f.is_empty()) {
+            current = self.resolve(self.element(current));
+            current = self.resolve(current.properties.get(field)?);
+        }
+        Some(current)
+    }
+
+    /// Check `value` against a schema
+    ///
+    /// This checks types, required properties and enum values, recursing into properties,
+    /// array items and map values. Formats, patterns and bounds are left to the apiserver.
+    /// `null` is accepted for any type, like an omitted field.
+    pub fn validate(&self, schema: &Schema, value: &Value) -> Vec<SchemaViolation> {
+        let mut violations = vec![];
+        self.validate_at(schema, value, "", true, &mut violations);
+        violations
+    }
+
+    /// Like [`validate`](Self::validate), but without checking for required properties
+    ///
+    /// This is for partial objects, such as merge patches.
+    pub fn validate_partial(&self, schema: &Schema, value: &Value) -> Vec<SchemaViolation> {
+        let mut violations = vec![];
+        self.validate_at(schema, value, "", false, &mut violations);
+        violations
+    }
+
+    fn validate_at(
+        &self,
+        schema: &Schema,
+        value: &Value,
+        path: &str,
+        required: bool,
+        violations: &mut Vec<SchemaViolation>,
+    ) {
+        let schema = self.resolve(schema);
+        let mut violation = |message: String| {
+            violations.push(SchemaViolation {
+                path: path.to_string(),
+                message,
+            })
+        };
+        if value.is_null() {
+            return;
+        }
+        if !schema.enum_.is_empty() && !schema.enum_.contains(value) {
+            let allowed = schema.enum_.iter().map(Value::to_string).collect::<Vec<_>>();
+            violation(format!(
+                "unsupported value {}, expected one of: {}",
+                value,
+                allowed.join(", ")
+            ));
+            return;
+        }
+        let matches_type = match schema.type_.as_deref() {
+            Some("object") => value.is_object(),
+            Some("array") => value.is_array(),
+            Some("string") => value.is_string(),
+            Some("integer") => value.is_i64() || value.is_u64(),
+            Some("number") => value.is_number(),
+            Some("boolean") => value.is_boolean(),
+            _ => true,
+        };
+        if !matches_type {
+            violation(format!(
+                "expected {}, got {}",
+                schema.type_.as_deref().unwrap_or_default(),
+                value
+            ));
+            return;
+        }
+        match value {
+            Value::Object(fields) => {
+                if required {
+                    for field in schema.required.iter().filter(|f| !fields.contains_key(*f)) {
+                        violation(format!("missing required field {}", field));
+                    }
+                }
+                // Maps have a schema for their values, rather than properties
+                let values = match &schema.additional_properties {
+                    Some(values @ Value::Object(_)) => Schema::deserialize(values).ok(),
+                    _ => None,
+                };
+                for (field, value) in fields {
+                    let child = join_path(path, field);
+                    if let Some(property) = schema.properties.get(field) {
+                        self.validate_at(property, value, &child, required, violations);
+                    } else if let Some(values) = &values {
+                        self.validate_at(values, value, &child, required, violations);
+                    }
+                }
+            }
+            Value::Array(items) => {
+                if let Some(item_schema) = &schema.items {
+                    for (i, item) in items.iter().enumerate() {
+                        let child = format!("{}[{}]", path, i);
+                        self.validate_at(item_schema, item, &child, required, violations);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// The schema of elements of an array, or the schema itself
+    fn element<'a>(&'a self, schema: &'a Schema) -> &'a Schema {
+        if let Some(items) = &schema.items {
+            return self.resolve(items);
+        }
+        schema
+    }
+}
+
+fn join_path(path: &str, field: &str) -> String {
+    if path.is_empty() {
+        field.to_string()
+    } else {
+        format!("{}.{}", path, field)
+    }
+}
+
+/// A part of an object that does not match its schema
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SchemaViolation {
+    /// Dotted path to the offending field, like `spec.containers[0].image`, or empty for the object itself
+    pub path: String,
+    /// What is wrong with the field
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+pub(crate) fn display_violations(violations: &[SchemaViolation]) -> String {
+    violations
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Validates objects of a kind against its schema, before they are sent to the apiserver
+///
+/// Attach a validator to an [`Api`](crate::Api) with [`Api::with_validator`](crate::Api::with_validator),
+/// to catch mistakes like bad enum values or missing required fields without a round trip.
+///
+/// ```no_run
+/// use kube::{openapi::Validator, Api, Client};
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let document = client.openapi_v3_schema("apps/v1").await?;
+/// let validator = Validator::for_kind(document, "apps", "v1", "Deployment").expect("known kind");
+/// let deploys: Api<Deployment> = Api::namespaced(client, "default").with_validator(validator);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// The schema of a custom resource can be taken from its `CustomResourceDefinition`:
+///
+/// ```no_run
+/// use kube::openapi::{Schema, Validator};
+/// # fn scope(crd_schema: serde_json::Value) -> Result<(), serde_json::Error> {
+/// // crd_schema is `spec.versions[].schema.openAPIV3Schema` of the CRD
+/// let validator = Validator::from_schema(serde_json::from_value::<Schema>(crd_schema)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Validator {
+    document: OpenApiDocument,
+    schema: Schema,
+}
+
+impl Validator {
+    /// Validate against the schema of a kind in an OpenAPI document
+    ///
+    /// Returns `None` if the document has no schema for the kind.
+    pub fn for_kind(document: OpenApiDocument, group: &str, version: &str, kind: &str) -> Option<Self> {
+        let schema = document.schema_for_kind(group, version, kind)?.clone();
+        Some(Validator { document, schema })
+    }
+
+    /// Validate against a standalone schema, without references to other schemas
+    pub fn from_schema(schema: Schema) -> Self {
+        Validator {
+            document: OpenApiDocument::default(),
+            schema,
+        }
+    }
+
+    /// Validate a complete object
+    ///
+    /// Returns [`Error::SchemaValidation`] with all violations if the object does not match the schema.
+    pub fn validate<T: Serialize>(&self, obj: &T) -> Result<()> {
+        let value = serde_json::to_value(obj)?;
+        Self::check(self.document.validate(&self.schema, &value))
+    }
+
+    /// Validate a partial object, such as a merge patch, ignoring missing required fields
+    pub fn validate_partial<T: Serialize>(&self, obj: &T) -> Result<()> {
+        let value = serde_json::to_value(obj)?;
+        Self::check(self.document.validate_partial(&self.schema, &value))
+    }
+
+    fn check(violations: Vec<SchemaViolation>) -> Result<()> {
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::SchemaValidation(violations))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OpenApiDocument, SchemaViolation};
+
+    fn document() -> OpenApiDocument {
+        serde_json::from_value(serde_json::json!({
+            "openapi": "3.0.0",
+            "components": { "schemas": {
+                "io.k8s.api.core.v1.Pod": {
+                    "description": "Pod is a collection of containers",
+                    "type": "object",
+                    "properties": {
+                        "spec": { "allOf": [{ "$ref": "#/components/schemas/io.k8s.api.core.v1.PodSpec" }] }
+                    },
+                    "x-kubernetes-group-version-kind": [{ "group": "", "kind": "Pod", "version": "v1" }]
+                },
+                "io.k8s.api.core.v1.PodSpec": {
+                    "type": "object",
+                    "required": ["containers"],
+                    "properties": {
+                        "restartPolicy": { "type": "string", "enum": ["Always", "Never"] },
+                        "containers": {
+                            "type": "array",
+                            "items": { "allOf": [{ "$ref": "#/components/schemas/io.k8s.api.core.v1.Container" }] }
+                        }
+                    }
+                },
+                "io.k8s.api.core.v1.Container": {
+                    "type": "object",
+                    "properties": {
+                        "image": { "type": "string", "description": "Container image name" },
+                        "env": { "type": "object", "additionalProperties": { "type": "string" } }
+                    }
+                }
+            }}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn explain_walks_refs_and_arrays() {
+        let doc = document();
+        let pod = doc.schema_for_kind("", "v1", "Pod").unwrap();
+        assert_eq!(
+            pod.description.as_deref(),
+            Some("Pod is a collection of containers")
+        );
+
+        let spec = doc.explain(pod, "spec").unwrap();
+        assert_eq!(spec.required, vec!["containers"]);
+        let image = doc.explain(pod, "spec.containers.image").unwrap();
+        assert_eq!(image.type_.as_deref(), Some("string"));
+        assert_eq!(image.description.as_deref(), Some("Container image name"));
+
+        assert!(doc.explain(pod, "spec.foo").is_none());
+        assert!(doc.schema_for_kind("apps", "v1", "Deployment").is_none());
+    }
+
+    #[test]
+    fn validate_reports_every_violation() {
+        let doc = document();
+        let pod = doc.schema_for_kind("", "v1", "Pod").unwrap();
+        let violation = |path: &str, message: &str| SchemaViolation {
+            path: path.to_string(),
+            message: message.to_string(),
+        };
+
+        let valid =
+            serde_json::json!({ "spec": { "containers": [{ "image": "nginx", "env": { "a": "b" } }] } });
+        assert!(doc.validate(pod, &valid).is_empty());
+
+        let invalid = serde_json::json!({
+            "spec": { "restartPolicy": "Sometimes", "containers": [{ "image": 5, "env": { "a": true } }] }
+        });
+        // object key order depends on the features of serde_json
+        let mut violations = doc.validate(pod, &invalid);
+        violations.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(violations, vec![
+            violation("spec.containers[0].env.a", "expected string, got true"),
+            violation("spec.containers[0].image", "expected string, got 5"),
+            violation(
+                "spec.restartPolicy",
+                r#"unsupported value "Sometimes", expected one of: "Always", "Never""#
+            ),
+        ]);
+
+        let partial = serde_json::json!({ "spec": { "restartPolicy": "Never" } });
+        assert_eq!(doc.validate(pod, &partial), vec![violation(
+            "spec",
+            "missing required field containers"
+        )]);
+        assert!(doc.validate_partial(pod, &partial).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use std::time::Duration;
+
+use k8s_openapi::{
+    kube_aggregator::pkg::apis::apiregistration::v1::{APIService, APIServiceSpec, ServiceReference},
+    ByteString,
+};
+
+use crate::{
+    api::{Api, ObjectMeta, Patch, PatchParams},
+    Client, Error, Result,
+};
+
+/// Registration of an extension apiserver as an `APIService`
+///
+/// The apiserver proxies requests for the group version to the `Service` of the extension
+/// apiserver, verifying its serving certificate against the CA bundle.
+///
+/// ```no_run
+/// use kube::{ops::ApiServiceRegistration, Client};
+/// use std::time::Duration;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let ca = std::fs::read("/certs/ca.crt").unwrap();
+/// let registration = ApiServiceRegistration::new("usage.example.com", "v1", "usage-system", "usage-apiserver")
+///     .ca_bundle(ca)
+///     .priority(1000, 15);
+/// registration.apply(&client, "usage-apiserver").await?;
+/// registration.wait_until_available(&client, Duration::from_secs(60)).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct ApiServiceRegistration {
+    group: String,
+    version: String,
+    service: ServiceReference,
+    ca_bundle: Option<Vec<u8>>,
+    insecure_skip_tls_verify: bool,
+    group_priority_minimum: i32,
+    version_priority: i32,
+}
+
+impl ApiServiceRegistration {
+    /// Register `group`/`version` to be served by the `Service` `service_name` in `service_namespace`
+    pub fn new(group: &str, version: &str, service_namespace: &str, service_name: &str) -> Self {
+        Self {
+            group: group.to_string(),
+            version: version.to_string(),
+            service: ServiceReference {
+                name: Some(service_name.to_string()),
+                namespace: Some(service_namespace.to_string()),
+                port: None,
+            },
+            ca_bundle: None,
+            insecure_skip_tls_verify: false,
+            group_priority_minimum: 1000,
+            version_priority: 15,
+        }
+    }
+
+    /// Name of the `APIService`, `<version>.<group>`
+    pub fn name(&self) -> String {
+        format!("{}.{}", self.version, self.group)
+    }
+
+    /// Port of the `Service`, defaults to 443
+    pub fn port(mut self, port: i32) -> Self {
+        self.service.port = Some(port);
+        self
+    }
+
+    /// PEM encoded CA bundle to verify the serving certificate of the extension apiserver
+    pub fn ca_bundle(mut self, pem: Vec<u8>) -> Self {
+        self.ca_bundle = Some(pem);
+        self
+    }
+
+    /// Skip verifying the serving certificate, strongly discouraged outside of development
+    pub fn insecure_skip_tls_verify(mut self) -> Self {
+        self.insecure_skip_tls_verify = true;
+        self
+    }
+
+    /// Priorities of the group and the version, see the `APIServiceSpec` documentation
+    ///
+    /// Defaults to a `group_priority_minimum` of 1000 and a `version_priority` of 15.
+    pub fn priority(mut self, group_priority_minimum: i32, version_priority: i32) -> Self {
+        self.group_priority_minimum = group_priority_minimum;
+        self.version_priority = version_priority;
+        self
+    }
+
+    /// The `APIService` object for this registration
+    pub fn to_api_service(&self) -> APIService {
+        APIService {
+            metadata: ObjectMeta {
+                name: Some(self.name()),
+                ..ObjectMeta::default()
+            },
+            spec: Some(APIServiceSpec {
+                ca_bundle: self.ca_bundle.clone().map(ByteString),
+                group: Some(self.group.clone()),
+                group_priority_minimum: self.group_priority_minimum,
+                insecure_skip_tls_verify: Some(self.insecure_skip_tls_verify).filter(|skip| *skip),
+                service: Some(self.service.clone()),
+                version: Some(self.version.clone()),
+                version_priority: self.version_priority,
+            }),
+            status: None,
+        }
+    }
+
+    /// Create or update the `APIService` with server-side apply
+    pub async fn apply(&self, client: &Client, field_manager: &str) -> Result<APIService> {
+        let api: Api<APIService> = Api::all(client.clone());
+        let pp = PatchParams::apply(field_manager).force();
+        // apply patches need type information
+        let mut patch = serde_json::to_value(self.to_api_service())?;
+        patch["apiVersion"] = "apiregistration.k8s.io/v1".into();
+        patch["kind"] = "APIService".into();
+        api.patch(&self.name(), &pp, &Patch::Apply(&patch)).await
+    }
+
+    /// Poll the `APIService` until its `Available` condition is true
+    ///
+    /// Fails with [`Error::Timeout`] when it is not available within `timeout`.
+    pub async fn wait_until_available(&self, client: &Client, timeout: Duration) -> Result<APIService> {
+        wait_for_api_service(client, &self.name(), timeout).await
+    }
+}
+
+/// Update the CA bundle of an existing `APIService`, e.g. after rotating the serving certificate
+pub async fn inject_ca_bundle(client: &Client, name: &str, pem: &[u8]) -> Result<APIService> {
+    let api: Api<APIService> = Api::all(client.clone());
+    let patch = serde_json::json!({
+        "spec": { "caBundle": ByteString(pem.to_vec()) }
+    });
+    api.patch(name, &PatchParams::default(), &Patch::Merge(&patch))
+        .await
+}
+
+/// Poll an `APIService` until its `Available` condition is true
+///
+/// This is how the aggregator reports whether it can reach the extension apiserver.
+/// Fails with [`Error::Timeout`] when it is not available within `timeout`.
+pub async fn wait_for_api_service(client: &Client, name: &str, timeout: Duration) -> Result<APIService> {
+    let api: Api<APIService> = Api::all(client.clone());
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let service = api.get(name).await?;
+        if is_api_service_available(&service) {
+            return Ok(service);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Err(Error::Timeout("waiting for the APIService to become available"));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Whether the `Available` condition of an `APIService` is true
+pub fn is_api_service_available(service: &APIService) -> bool {
+    let conditions = service
+        .status
+        .as_ref()
+        .and_then(|status| status.conditions.as_ref());
+    conditions
+        .into_iter()
+        .flatten()
+        .any(|c| c.type_ == "Available" && c.status == "True")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{is_api_service_available, ApiServiceRegistration};
+    use k8s_openapi::kube_aggregator::pkg::apis::apiregistration::v1::APIService;
+
+    #[test]
+    fn registration_builds_api_service() {
+        let service = ApiServiceRegistration::new("usage.example.com", "v1", "usage-system", "usage")
+            .port(8443)
+            .ca_bundle(b"-----BEGIN CERTIFICATE-----".to_vec())
+            .to_api_service();
+        let json = serde_json::to_value(&service).unwrap();
+        assert_eq!(json["metadata"]["name"], "v1.usage.example.com");
+        assert_eq!(json["spec"]["service"]["port"], 8443);
+        assert_eq!(json["spec"]["caBundle"], "LS0tLS1CRUdJTiBDRVJUSUZJQ0FURS0tLS0t");
+        assert_eq!(json["spec"]["groupPriorityMinimum"], 1000);
+        assert!(json["spec"].get("insecureSkipTLSVerify").is_none());
+        assert!(!is_api_service_available(&service));
+    }
+
+    #[test]
+    fn availability_is_read_from_conditions() {
+        let service: APIService = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "v1.usage.example.com" },
+            "status": { "conditions": [{ "type": "Available", "status": "True" }] }
+        }))
+        .unwrap();
+        assert!(is_api_service_available(&service));
+    }
+}
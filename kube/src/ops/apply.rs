@@ -0,0 +1,248 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+use crate::{
+    api::{Api, DynamicObject, Patch, PatchParams},
+    discovery::Discovery,
+    Client, Error, Result,
+};
+
+/// The outcome of applying a single document of a manifest
+#[derive(Debug)]
+pub struct ApplyOutcome {
+    /// apiVersion of the document
+    pub api_version: String,
+    /// Kind of the document
+    pub kind: String,
+    /// Name of the object
+    pub name: String,
+    /// Namespace the object was applied to, `None` for cluster scoped resources
+    pub namespace: Option<String>,
+    /// The object returned by the apiserver, or why applying it failed
+    pub result: Result<DynamicObject>,
+}
+
+/// Server-side apply every document in a multi-document yaml manifest
+///
+/// Each document is resolved through [`Discovery`], so any served kind can be applied.
+/// Namespaces are applied first, then `CustomResourceDefinition`s, then everything else
+/// in document order. Every applied CRD is waited on until it is `Established` (for up to
+/// 30 seconds), and if a kind cannot be resolved after a CRD was applied, discovery is re-run
+/// once, so that custom resources can be installed alongside their definitions.
+///
+/// Namespaced objects without a `metadata.namespace` are applied to `default_namespace`.
+///
+/// The outer `Result` fails when the manifest cannot be parsed or discovery fails.
+/// Failures to apply individual objects are reported in the corresponding [`ApplyOutcome`],
+/// which are returned in the order they were applied.
+///
+/// ```no_run
+/// use kube::{api::PatchParams, ops::apply_manifest, Client};
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let manifest = std::fs::read_to_string("deploy.yaml").unwrap();
+/// let pp = PatchParams::apply("my-deployer").force();
+/// for outcome in apply_manifest(&client, &manifest, &pp, "default").await? {
+///     match outcome.result {
+///         Ok(_) => println!("applied {} {}", outcome.kind, outcome.name),
+///         Err(e) => println!("failed to apply {} {}: {}", outcome.kind, outcome.name, e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn apply_manifest(
+    client: &Client,
+    manifest: &str,
+    pp: &PatchParams,
+    default_namespace: &str,
+) -> Result<Vec<ApplyOutcome>> {
+    if pp.field_manager.is_none() {
+        return Err(Error::RequestValidation(
+            "apply_manifest requires PatchParams::field_manager to be set".into(),
+        ));
+    }
+    let mut docs = parse_manifest(manifest)?;
+    docs.sort_by_key(|(_, kind, _)| apply_priority(kind));
+
+    let mut discovery = Discovery::run(client).await?;
+    let mut stale_discovery = false;
+    let mut outcomes = Vec::with_capacity(docs.len());
+    for (api_version, kind, obj) in docs {
+        let name = obj.metadata.name.clone().unwrap_or_default();
+        if stale_discovery && discovery.resolve(&api_version, &kind).is_none() {
+            discovery = Discovery::run(client).await?;
+            stale_discovery = false;
+        }
+        let ar = match discovery.resolve(&api_version, &kind) {
+            Some(ar) => ar,
+            None => {
+                outcomes.push(ApplyOutcome {
+                    result: Err(Error::DynamicType(format!(
+                        "{} {} is not served by the apiserver",
+                        api_version, kind
+                    ))),
+                    namespace: obj.metadata.namespace,
+                    api_version,
+                    kind,
+                    name,
+                });
+                continue;
+            }
+        };
+        let gvk = ar.to_gvk();
+        let (api, namespace): (Api<DynamicObject>, _) = if ar.namespaced {
+            let ns = obj
+                .metadata
+                .namespace
+                .clone()
+                .unwrap_or_else(|| default_namespace.to_string());
+            (Api::namespaced_with(client.clone(), &ns, &gvk), Some(ns))
+        } else {
+            (Api::all_with(client.clone(), &gvk), None)
+        };
+        let mut result = api.patch(&name, pp, &Patch::Apply(&obj)).await;
+        if kind == "CustomResourceDefinition" {
+            if let Ok(crd) = result {
+                result = wait_for_established(&api, crd).await;
+                stale_discovery = true;
+            }
+        }
+        outcomes.push(ApplyOutcome {
+            api_version,
+            kind,
+            name,
+            namespace,
+            result,
+        });
+    }
+    Ok(outcomes)
+}
+
+/// How long [`apply_manifest`] waits for an applied CRD to be established
+const CRD_ESTABLISHED_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Poll an applied `CustomResourceDefinition` until its `Established` condition is true
+///
+/// Its custom resources are not served before then.
+async fn wait_for_established(api: &Api<DynamicObject>, mut crd: DynamicObject) -> Result<DynamicObject> {
+    let deadline = tokio::time::Instant::now() + CRD_ESTABLISHED_TIMEOUT;
+    let name = crd.metadata.name.clone().unwrap_or_default();
+    while !is_established(&crd) {
+        if tokio::time::Instant::now() >= deadline {
+            return Err(Error::Timeout(
+                "waiting for the CustomResourceDefinition to be established",
+            ));
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+        crd = api.get(&name).await?;
+    }
+    Ok(crd)
+}
+
+/// Whether the `Established` condition of a `CustomResourceDefinition` is true
+fn is_established(crd: &DynamicObject) -> bool {
+    crd.data["status"]["conditions"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .any(|c| c["type"] == "Established" && c["status"] == "True")
+}
+
+/// Order in which kinds are applied, lower first
+fn apply_priority(kind: &str) -> u8 {
+    match kind {
+        "Namespace" => 0,
+        "CustomResourceDefinition" => 1,
+        _ => 2,
+    }
+}
+
+/// Split a multi-document manifest into `(apiVersion, kind, object)` triples, skipping empty documents
+pub(crate) fn parse_manifest(manifest: &str) -> Result<Vec<(String, String, DynamicObject)>> {
+    let mut docs = vec![];
+    for (i, de) in serde_yaml::Deserializer::from_str(manifest).enumerate() {
+        let value = serde_yaml::Value::deserialize(de)?;
+        if value.is_null() {
+            continue;
+        }
+        let obj: DynamicObject = serde_yaml::from_value(value)?;
+        let types = obj.types.clone().unwrap_or_default();
+        if types.api_version.is_empty() || types.kind.is_empty() {
+            return Err(Error::RequestValidation(format!(
+                "manifest document {} is missing apiVersion or kind",
+                i
+            )));
+        }
+        if obj.metadata.name.is_none() {
+            return Err(Error::RequestValidation(format!(
+                "manifest document {} is missing metadata.name",
+                i
+            )));
+        }
+        docs.push((types.api_version, types.kind, obj));
+    }
+    Ok(docs)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{apply_priority, is_established, parse_manifest};
+    use crate::api::DynamicObject;
+
+    #[test]
+    fn parse_multi_document_manifest() {
+        let manifest = r#"
+---
+apiVersion: v1
+kind: ConfigMap
+metadata:
+  name: cfg
+  namespace: apps
+data:
+  key: value
+---
+# empty documents are skipped
+---
+apiVersion: v1
+kind: Namespace
+metadata:
+  name: apps
+"#;
+        let mut docs = parse_manifest(manifest).unwrap();
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].1, "ConfigMap");
+        assert_eq!(docs[0].2.data["data"]["key"], "value");
+
+        docs.sort_by_key(|(_, kind, _)| apply_priority(kind));
+        assert_eq!(docs[0].1, "Namespace");
+        assert_eq!(docs[0].2.metadata.name.as_deref(), Some("apps"));
+    }
+
+    #[test]
+    fn crds_are_established_once_the_condition_is_true() {
+        let crd = |conditions: serde_json::Value| -> DynamicObject {
+            serde_json::from_value(serde_json::json!({
+                "apiVersion": "apiextensions.k8s.io/v1",
+                "kind": "CustomResourceDefinition",
+                "metadata": { "name": "foos.clux.dev" },
+                "status": { "conditions": conditions },
+            }))
+            .unwrap()
+        };
+        assert!(!is_established(&crd(serde_json::Value::Null)));
+        assert!(!is_established(&crd(serde_json::json!([
+            { "type": "NamesAccepted", "status": "True" },
+            { "type": "Established", "status": "False" },
+        ]))));
+        assert!(is_established(&crd(serde_json::json!([
+            { "type": "NamesAccepted", "status": "True" },
+            { "type": "Established", "status": "True" },
+        ]))));
+    }
+
+    #[test]
+    fn parse_manifest_requires_types() {
+        let manifest = "metadata:\n  name: foo\n";
+        assert!(parse_manifest(manifest).is_err());
+    }
+}
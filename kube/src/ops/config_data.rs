@@ -0,0 +1,184 @@
+use k8s_openapi::{
+    api::core::v1::{ConfigMap, Secret},
+    ByteString,
+};
+use std::collections::BTreeMap;
+
+use crate::{
+    api::{Api, Patch, PatchParams},
+    Result,
+};
+
+/// Convenience accessors for the data of a [`Secret`]
+///
+/// The api transfers secret values base64 encoded; `k8s_openapi` decodes them into raw bytes.
+/// These helpers take care of the conversions to and from strings.
+pub trait SecretExt {
+    /// Build a secret named `name` from string values
+    ///
+    /// The values are stored in `data`, so they can be read back without a round trip to the apiserver.
+    fn from_string_data<I, K, V>(name: &str, data: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: AsRef<str>;
+
+    /// The raw bytes of the `key` entry
+    fn data_bytes(&self, key: &str) -> Option<&[u8]>;
+
+    /// The `key` entry as a string
+    ///
+    /// Returns `None` if the entry is missing or is not valid UTF-8, use [`SecretExt::data_bytes`]
+    /// for binary values.
+    fn data_string(&self, key: &str) -> Option<&str>;
+
+    /// Set the `key` entry to `value`
+    fn set_data_string(&mut self, key: &str, value: &str);
+}
+
+impl SecretExt for Secret {
+    fn from_string_data<I, K, V>(name: &str, data: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: AsRef<str>,
+    {
+        let data = data
+            .into_iter()
+            .map(|(k, v)| (k.into(), ByteString(v.as_ref().as_bytes().to_vec())))
+            .collect::<BTreeMap<_, _>>();
+        let mut secret = Secret::default();
+        secret.metadata.name = Some(name.to_string());
+        secret.data = Some(data);
+        secret
+    }
+
+    fn data_bytes(&self, key: &str) -> Option<&[u8]> {
+        self.data.as_ref()?.get(key).map(|value| value.0.as_slice())
+    }
+
+    fn data_string(&self, key: &str) -> Option<&str> {
+        std::str::from_utf8(self.data_bytes(key)?).ok()
+    }
+
+    fn set_data_string(&mut self, key: &str, value: &str) {
+        self.data
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key.to_string(), ByteString(value.as_bytes().to_vec()));
+    }
+}
+
+/// Convenience accessors for the data of a [`ConfigMap`]
+pub trait ConfigMapExt {
+    /// The `key` entry of `data`
+    fn data_value(&self, key: &str) -> Option<&str>;
+
+    /// The `key` entry of `binaryData`
+    fn binary_data_value(&self, key: &str) -> Option<&[u8]>;
+
+    /// Set the `key` entry of `binaryData` to `value`
+    fn set_binary_data(&mut self, key: &str, value: Vec<u8>);
+}
+
+impl ConfigMapExt for ConfigMap {
+    fn data_value(&self, key: &str) -> Option<&str> {
+        self.data.as_ref()?.get(key).map(String::as_str)
+    }
+
+    fn binary_data_value(&self, key: &str) -> Option<&[u8]> {
+        self.binary_data
+            .as_ref()?
+            .get(key)
+            .map(|value| value.0.as_slice())
+    }
+
+    fn set_binary_data(&mut self, key: &str, value: Vec<u8>) {
+        self.binary_data
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key.to_string(), ByteString(value));
+    }
+}
+
+/// A merge patch setting a single entry of `data`
+///
+/// Merge patches merge objects member by member, so the other entries are left alone,
+/// and `data` is created if the object does not have one yet.
+fn data_key_patch(key: &str, value: serde_json::Value) -> Patch<serde_json::Value> {
+    Patch::Merge(serde_json::json!({ "data": { key: value } }))
+}
+
+/// Methods for single entries of secrets
+impl Api<Secret> {
+    /// Set the `key` entry of a secret, without touching its other entries
+    ///
+    /// This is a single merge patch, so concurrent writers of other keys are not overwritten.
+    pub async fn patch_data_key(&self, name: &str, key: &str, value: &[u8]) -> Result<Secret> {
+        let value = serde_json::to_value(ByteString(value.to_vec()))?;
+        self.patch(name, &PatchParams::default(), &data_key_patch(key, value))
+            .await
+    }
+}
+
+/// Methods for single entries of config maps
+impl Api<ConfigMap> {
+    /// Set the `key` entry of a config map, without touching its other entries
+    ///
+    /// This is a single merge patch, so concurrent writers of other keys are not overwritten.
+    pub async fn patch_data_key(&self, name: &str, key: &str, value: &str) -> Result<ConfigMap> {
+        self.patch(name, &PatchParams::default(), &data_key_patch(key, value.into()))
+            .await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ConfigMapExt, SecretExt};
+    use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+
+    #[test]
+    fn secret_strings_roundtrip() {
+        let mut secret = Secret::from_string_data("creds", vec![("user", "admin")]);
+        secret.set_data_string("password", "hunter2");
+        let json = serde_json::to_value(&secret).unwrap();
+        assert_eq!(json["data"]["user"], "YWRtaW4=");
+
+        let secret: Secret = serde_json::from_value(json).unwrap();
+        assert_eq!(secret.data_string("user"), Some("admin"));
+        assert_eq!(secret.data_string("password"), Some("hunter2"));
+        assert_eq!(secret.data_string("token"), None);
+    }
+
+    #[test]
+    fn config_map_binary_data() {
+        let mut cm = ConfigMap::default();
+        assert_eq!(cm.binary_data_value("blob"), None);
+        cm.set_binary_data("blob", vec![0, 159, 146, 150]);
+        assert_eq!(cm.binary_data_value("blob"), Some(&[0, 159, 146, 150][..]));
+        assert_eq!(cm.data_value("blob"), None);
+    }
+
+    #[tokio::test]
+    async fn patch_data_key_sends_a_merge_patch() {
+        use crate::{api::Api, Client, Service};
+        use http::{Request, Response};
+        use hyper::Body;
+
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            assert_eq!(
+                req.headers()[http::header::CONTENT_TYPE],
+                "application/merge-patch+json"
+            );
+            let body = hyper::body::to_bytes(req.into_body()).await?;
+            let patch: serde_json::Value = serde_json::from_slice(&body)?;
+            assert_eq!(
+                patch,
+                serde_json::json!({ "data": { "a/b": "c2VjcmV0" } })
+            );
+            Response::builder()
+                .body(Body::from(r#"{"metadata":{"name":"creds"}}"#))
+                .map_err(tower::BoxError::from)
+        });
+        let secrets: Api<Secret> = Api::namespaced(Client::new(Service::new(svc)), "default");
+        secrets.patch_data_key("creds", "a/b", b"secret").await.unwrap();
+    }
+}
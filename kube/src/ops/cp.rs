@@ -0,0 +1,446 @@
+//! Copy files to and from containers, like `kubectl cp`
+//!
+//! Files are sent as tar archives through [`exec`](crate::Api::exec), so the container needs
+//! `tar` (and `sh` and `head` for uploads), as it does for `kubectl cp`.
+//!
+//! ```no_run
+//! use kube::{api::Api, ops::cp::{self, CpParams}, Client};
+//! use k8s_openapi::api::core::v1::Pod;
+//! use std::path::Path;
+//! # async fn scope(client: Client) -> Result<(), kube::Error> {
+//! let pods: Api<Pod> = Api::namespaced(client, "apps");
+//! let cp = CpParams::default()
+//!     .container("app")
+//!     .on_progress(|progress| println!("sent {} bytes", progress.transferred));
+//! cp::upload(&pods, "blog", Path::new("./fixtures"), "/srv", &cp).await?;
+//! cp::download(&pods, "blog", "/var/log/app", Path::new("./logs"), &cp).await?;
+//! # Ok(())
+//! # }
+//! ```
+use futures::future;
+use k8s_openapi::api::core::v1::Pod;
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWriteExt},
+    sync::mpsc,
+};
+
+use crate::{
+    api::{Api, AttachParams, AttachedProcess},
+    Error, Result,
+};
+
+const CHUNK_SIZE: usize = 16 * 1024;
+/// Chunks buffered between the archive and the connection
+const PIPE_CHUNKS: usize = 4;
+
+/// How symbolic links are copied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SymlinkPolicy {
+    /// Copy links as links, this is the default
+    #[default]
+    Preserve,
+    /// Copy the files and directories that links point to
+    ///
+    /// Trees must not contain cycles of links.
+    Follow,
+    /// Leave links out
+    Skip,
+}
+
+/// Progress of a copy, reported to [`CpParams::on_progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpProgress {
+    /// The number of archive bytes sent or received so far
+    pub transferred: u64,
+    /// The size of the archive, if known
+    ///
+    /// This is known for uploads, but not for downloads.
+    pub total: Option<u64>,
+}
+
+/// Parameters for [`upload`] and [`download`]
+#[derive(Clone, Default)]
+pub struct CpParams {
+    /// The container to copy to or from
+    ///
+    /// Defaults to the only container if there is only one container in the pod.
+    pub container: Option<String>,
+    /// How symbolic links are copied
+    pub symlinks: SymlinkPolicy,
+    progress: Option<Arc<dyn Fn(CpProgress) + Send + Sync>>,
+}
+
+impl fmt::Debug for CpParams {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CpParams")
+            .field("container", &self.container)
+            .field("symlinks", &self.symlinks)
+            .finish()
+    }
+}
+
+impl CpParams {
+    /// Specify the container to copy to or from
+    pub fn container<T: Into<String>>(mut self, container: T) -> Self {
+        self.container = Some(container.into());
+        self
+    }
+
+    /// Set the [`SymlinkPolicy`]
+    pub fn symlinks(mut self, symlinks: SymlinkPolicy) -> Self {
+        self.symlinks = symlinks;
+        self
+    }
+
+    /// Call `progress` whenever a chunk of the archive has been transferred
+    pub fn on_progress(mut self, progress: impl Fn(CpProgress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(Arc::new(progress));
+        self
+    }
+
+    fn attach_params(&self) -> AttachParams {
+        let ap = AttachParams::default();
+        match &self.container {
+            Some(container) => ap.container(container.as_str()),
+            None => ap,
+        }
+    }
+
+    fn report(&self, transferred: u64, total: Option<u64>) {
+        if let Some(progress) = &self.progress {
+            progress(CpProgress { transferred, total });
+        }
+    }
+}
+
+/// Copy the local file or directory `local` into the directory `remote_dir` of a container
+///
+/// The copy keeps the name of `local`, and `remote_dir` must exist.
+/// The archive is streamed to the container as it is packed, after a first pass that only measures
+/// its size. Returns the size of the transferred archive.
+pub async fn upload(
+    pods: &Api<Pod>,
+    name: &str,
+    local: &Path,
+    remote_dir: &str,
+    cp: &CpParams,
+) -> Result<u64> {
+    let (local, symlinks) = (local.to_path_buf(), cp.symlinks);
+    let total = {
+        let local = local.clone();
+        blocking(move || pack(&local, symlinks, Counter::default()))
+            .await?
+            .0
+    };
+
+    // `head` ends the input of `tar` once the archive is received, the protocol can't close stdin
+    let script = format!("head -c {} | tar xmf - -C \"$0\"", total);
+    let ap = cp.attach_params().stdin(true).stdout(false);
+    let mut process = pods
+        .exec(name, vec!["sh", "-c", &script, remote_dir], &ap)
+        .await?;
+    let mut stdin = process.stdin().expect("stdin is attached");
+    let stderr = process.stderr().expect("stderr is attached");
+
+    let (tx, mut rx) = mpsc::channel(PIPE_CHUNKS);
+    let packing = blocking(move || {
+        let writer = pack(
+            &local,
+            symlinks,
+            io::BufWriter::with_capacity(CHUNK_SIZE, ChunkWriter(tx)),
+        )?;
+        writer
+            .into_inner()
+            .map(drop)
+            .map_err(io::IntoInnerError::into_error)
+    });
+    let send = async move {
+        let mut transferred = 0;
+        while let Some(chunk) = rx.recv().await {
+            stdin.write_all(&chunk).await?;
+            transferred += chunk.len() as u64;
+            cp.report(transferred, Some(total));
+        }
+        // let `head` finish if the files shrank, the remote `tar` ignores trailing zeros
+        let mut padding = total.saturating_sub(transferred);
+        let zeros = [0; CHUNK_SIZE];
+        while padding > 0 {
+            let n = padding.min(CHUNK_SIZE as u64);
+            stdin.write_all(&zeros[..n as usize]).await?;
+            padding -= n;
+        }
+        Ok(transferred)
+    };
+    let (packed, sent, errors) = future::join3(packing, send, read_to_end(stderr)).await;
+    let sent = sent.map_err(Error::Copy)?;
+    packed?;
+    finish(process, errors).await?;
+    if sent != total {
+        return Err(Error::Copy(io::Error::other(
+            "the files changed while they were uploaded",
+        )));
+    }
+    Ok(total)
+}
+
+/// Copy the file or directory `remote` of a container into the local directory `local_dir`
+///
+/// The copy keeps the name of `remote`, and `local_dir` is created if needed.
+/// The archive is unpacked as it is received. Returns the size of the transferred archive.
+pub async fn download(
+    pods: &Api<Pod>,
+    name: &str,
+    remote: &str,
+    local_dir: &Path,
+    cp: &CpParams,
+) -> Result<u64> {
+    let remote = remote.trim_end_matches('/');
+    let (dir, base) = match remote.rfind('/') {
+        Some(0) => ("/", &remote[1..]),
+        Some(i) => (&remote[..i], &remote[i + 1..]),
+        None => (".", remote),
+    };
+    let mut command = vec!["tar", "cf", "-"];
+    if cp.symlinks == SymlinkPolicy::Follow {
+        command.push("-h");
+    }
+    command.extend_from_slice(&["-C", dir, base]);
+
+    let mut process = pods.exec(name, command, &cp.attach_params()).await?;
+    let mut stdout = process.stdout().expect("stdout is attached");
+    let stderr = process.stderr().expect("stderr is attached");
+
+    let (tx, rx) = mpsc::channel(PIPE_CHUNKS);
+    let (local_dir, symlinks) = (local_dir.to_path_buf(), cp.symlinks);
+    let unpacking = blocking(move || unpack(ChunkReader::new(rx), &local_dir, symlinks));
+    let receive = async move {
+        let mut transferred = 0;
+        let mut chunk = vec![0; CHUNK_SIZE];
+        let mut unpacker = Some(tx);
+        loop {
+            let n = stdout.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok::<_, io::Error>(transferred);
+            }
+            transferred += n as u64;
+            cp.report(transferred, None);
+            if let Some(tx) = &unpacker {
+                // once unpacking stops, the rest is drained so the remote `tar` can exit
+                if tx.send(chunk[..n].to_vec()).await.is_err() {
+                    unpacker = None;
+                }
+            }
+        }
+    };
+    let (unpacked, received, errors) = future::join3(unpacking, receive, read_to_end(stderr)).await;
+    let total = received.map_err(Error::Copy)?;
+    finish(process, errors).await?;
+    unpacked?;
+    Ok(total)
+}
+
+async fn read_to_end(mut reader: impl AsyncRead + Unpin) -> io::Result<Vec<u8>> {
+    let mut data = vec![];
+    reader.read_to_end(&mut data).await?;
+    Ok(data)
+}
+
+/// Wait for the remote `tar` to exit, and turn a failure into an error with its output
+async fn finish(process: AttachedProcess, errors: io::Result<Vec<u8>>) -> Result<()> {
+    let status = process.await;
+    if matches!(&status, Some(s) if s.status.as_deref() == Some("Success")) {
+        return Ok(());
+    }
+    let message = status
+        .and_then(|s| s.message)
+        .unwrap_or_else(|| "no exit status".into());
+    let output = errors
+        .map(|e| String::from_utf8_lossy(&e).into_owned())
+        .unwrap_or_default();
+    Err(Error::Copy(io::Error::other(format!(
+        "{}: {}",
+        message,
+        output.trim()
+    ))))
+}
+
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> io::Result<T> + Send + 'static) -> Result<T> {
+    match tokio::task::spawn_blocking(f).await {
+        Ok(res) => res.map_err(Error::Copy),
+        Err(err) => Err(Error::Copy(io::Error::other(err))),
+    }
+}
+
+/// Counts the bytes of an archive, without keeping them
+#[derive(Default)]
+struct Counter(u64);
+
+impl io::Write for Counter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0 += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Sends the bytes of an archive that is packed on a blocking thread to an async task
+struct ChunkWriter(mpsc::Sender<Vec<u8>>);
+
+impl io::Write for ChunkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .blocking_send(buf.to_vec())
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "the upload stopped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Reads the bytes of an archive that is received by an async task, on a blocking thread
+struct ChunkReader {
+    rx: mpsc::Receiver<Vec<u8>>,
+    chunk: io::Cursor<Vec<u8>>,
+}
+
+impl ChunkReader {
+    fn new(rx: mpsc::Receiver<Vec<u8>>) -> Self {
+        ChunkReader {
+            rx,
+            chunk: io::Cursor::new(vec![]),
+        }
+    }
+}
+
+impl io::Read for ChunkReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let n = io::Read::read(&mut self.chunk, buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            match self.rx.blocking_recv() {
+                Some(chunk) => self.chunk = io::Cursor::new(chunk),
+                None => return Ok(0),
+            }
+        }
+    }
+}
+
+fn pack<W: io::Write>(local: &Path, symlinks: SymlinkPolicy, out: W) -> io::Result<W> {
+    let name = local
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let mut builder = tar::Builder::new(out);
+    builder.follow_symlinks(symlinks == SymlinkPolicy::Follow);
+    append(&mut builder, local, &PathBuf::from(name), symlinks)?;
+    builder.into_inner()
+}
+
+fn append<W: io::Write>(
+    builder: &mut tar::Builder<W>,
+    path: &Path,
+    name: &Path,
+    symlinks: SymlinkPolicy,
+) -> io::Result<()> {
+    if fs::symlink_metadata(path)?.file_type().is_symlink() {
+        match symlinks {
+            SymlinkPolicy::Skip => return Ok(()),
+            SymlinkPolicy::Preserve => return builder.append_path_with_name(path, name),
+            SymlinkPolicy::Follow => {}
+        }
+    }
+    if !fs::metadata(path)?.is_dir() {
+        return builder.append_path_with_name(path, name);
+    }
+    builder.append_dir(name, path)?;
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        append(builder, &entry.path(), &name.join(entry.file_name()), symlinks)?;
+    }
+    Ok(())
+}
+
+fn unpack(archive: impl io::Read, local_dir: &Path, symlinks: SymlinkPolicy) -> io::Result<()> {
+    fs::create_dir_all(local_dir)?;
+    let mut archive = tar::Archive::new(archive);
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if symlinks == SymlinkPolicy::Skip && entry.header().entry_type().is_symlink() {
+            continue;
+        }
+        // `unpack_in` refuses entries that would end up outside of `local_dir`
+        entry.unpack_in(local_dir)?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::{pack, unpack, ChunkReader, ChunkWriter, Counter, SymlinkPolicy};
+    use std::{fs, io::Write, os::unix::fs::symlink};
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn archives_roundtrip_with_symlink_policies() {
+        let src = tempfile::tempdir().unwrap();
+        let tree = src.path().join("tree");
+        fs::create_dir_all(tree.join("nested")).unwrap();
+        fs::write(tree.join("nested/file.txt"), "hello").unwrap();
+        symlink("nested/file.txt", tree.join("link")).unwrap();
+
+        for (policy, link_is_symlink) in &[
+            (SymlinkPolicy::Preserve, Some(true)),
+            (SymlinkPolicy::Follow, Some(false)),
+            (SymlinkPolicy::Skip, None),
+        ] {
+            let dst = tempfile::tempdir().unwrap();
+            let archive = pack(&tree, *policy, Vec::new()).unwrap();
+            assert_eq!(
+                pack(&tree, *policy, Counter::default()).unwrap().0,
+                archive.len() as u64
+            );
+            unpack(&archive[..], dst.path(), *policy).unwrap();
+            let copy = dst.path().join("tree");
+            assert_eq!(fs::read_to_string(copy.join("nested/file.txt")).unwrap(), "hello");
+            let link = fs::symlink_metadata(copy.join("link")).ok();
+            assert_eq!(
+                link.map(|m| m.file_type().is_symlink()),
+                *link_is_symlink,
+                "{:?}",
+                policy
+            );
+        }
+    }
+
+    #[test]
+    fn archives_stream_through_chunks() {
+        let src = tempfile::tempdir().unwrap();
+        let tree = src.path().join("tree");
+        fs::create_dir_all(&tree).unwrap();
+        fs::write(tree.join("big.bin"), vec![7; 100_000]).unwrap();
+
+        let (tx, rx) = mpsc::channel(1);
+        let packer = std::thread::spawn(move || {
+            let mut writer = pack(&tree, SymlinkPolicy::Preserve, ChunkWriter(tx)).unwrap();
+            writer.flush().unwrap();
+        });
+        let dst = tempfile::tempdir().unwrap();
+        unpack(ChunkReader::new(rx), dst.path(), SymlinkPolicy::Preserve).unwrap();
+        packer.join().unwrap();
+        assert_eq!(fs::read(dst.path().join("tree/big.bin")).unwrap(), vec![
+            7;
+            100_000
+        ]);
+    }
+}
@@ -0,0 +1,373 @@
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::Value;
+use std::fmt::Debug;
+
+#[cfg(feature = "jsonpatch")] use crate::api::Resource;
+use crate::{
+    api::{Api, Patch, PatchParams},
+    Error, Result,
+};
+
+/// Fields that the apiserver manages, which are usually left out of diffs and patches
+///
+/// Patches of these fields either fail or are ignored, and `status` belongs to its own subresource.
+/// [`dry_run_diff`] leaves out the metadata fields, which change on every write.
+pub const SERVER_MANAGED_FIELDS: &[&str] = &[
+    "/metadata/creationTimestamp",
+    "/metadata/generation",
+    "/metadata/managedFields",
+    "/metadata/resourceVersion",
+    "/metadata/selfLink",
+    "/metadata/uid",
+    "/status",
+];
+
+/// A single field level difference between two objects
+///
+/// Paths are [JSON pointers](https://tools.ietf.org/html/rfc6901) into the object.
+/// Arrays are compared as a whole, and are reported as a single change on the array path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldChange {
+    /// A field that only exists in the new object
+    Added {
+        /// Path to the field
+        path: String,
+        /// New value
+        value: Value,
+    },
+    /// A field that only exists in the old object
+    Removed {
+        /// Path to the field
+        path: String,
+        /// Old value
+        value: Value,
+    },
+    /// A field whose value differs between the objects
+    Changed {
+        /// Path to the field
+        path: String,
+        /// Old value
+        old: Value,
+        /// New value
+        new: Value,
+    },
+}
+
+impl FieldChange {
+    /// The path of the changed field
+    pub fn path(&self) -> &str {
+        match self {
+            Self::Added { path, .. } | Self::Removed { path, .. } | Self::Changed { path, .. } => path,
+        }
+    }
+}
+
+/// Show what would change if `patch` was server-side applied to the object `name`
+///
+/// The patch is applied with `dryRun=All`, so mutating admission and defaulting is taken
+/// into account, but nothing is persisted. The result is compared against the live object,
+/// ignoring fields that change on every write (`resourceVersion`, `managedFields`, ...).
+/// If the object does not exist yet, every field of the result is reported as added.
+///
+/// ```no_run
+/// use kube::{api::{Api, PatchParams}, ops::dry_run_diff, Client};
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let deploys: Api<Deployment> = Api::namespaced(client, "apps");
+/// let desired = serde_json::json!({
+///     "apiVersion": "apps/v1",
+///     "kind": "Deployment",
+///     "metadata": { "name": "blog" },
+///     "spec": { "replicas": 3 }
+/// });
+/// for change in dry_run_diff(&deploys, "blog", &PatchParams::apply("deployer"), &desired).await? {
+///     println!("{:?}", change);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn dry_run_diff<K, P>(
+    api: &Api<K>,
+    name: &str,
+    pp: &PatchParams,
+    patch: &P,
+) -> Result<Vec<FieldChange>>
+where
+    K: Clone + DeserializeOwned + Serialize + Debug,
+    P: Serialize + Debug,
+{
+    let live = match api.get(name).await {
+        Ok(live) => serde_json::to_value(live)?,
+        Err(Error::Api(ae)) if ae.code == 404 => Value::Null,
+        Err(e) => return Err(e),
+    };
+    let pp = pp.clone().dry_run();
+    let applied = serde_json::to_value(api.patch(name, &pp, &Patch::Apply(patch)).await?)?;
+    Ok(diff(&strip_volatile(live), &strip_volatile(applied)))
+}
+
+/// Compute the field level differences between two json values
+///
+/// Changes are sorted by path.
+pub fn diff(old: &Value, new: &Value) -> Vec<FieldChange> {
+    let mut changes = vec![];
+    diff_at("", old, new, &mut changes);
+    changes.sort_by(|a, b| a.path().cmp(b.path()));
+    changes
+}
+
+fn diff_at(path: &str, old: &Value, new: &Value, changes: &mut Vec<FieldChange>) {
+    match (old, new) {
+        (Value::Object(o), Value::Object(n)) => {
+            for (key, ov) in o {
+                let p = format!("{}/{}", path, escape(key));
+                match n.get(key) {
+                    Some(nv) => diff_at(&p, ov, nv, changes),
+                    None => changes.push(FieldChange::Removed {
+                        path: p,
+                        value: ov.clone(),
+                    }),
+                }
+            }
+            for (key, nv) in n {
+                if !o.contains_key(key) {
+                    changes.push(FieldChange::Added {
+                        path: format!("{}/{}", path, escape(key)),
+                        value: nv.clone(),
+                    });
+                }
+            }
+        }
+        (Value::Null, Value::Object(_)) => diff_at(path, &Value::Object(Default::default()), new, changes),
+        (Value::Object(_), Value::Null) => diff_at(path, old, &Value::Object(Default::default()), changes),
+        _ if old != new => changes.push(FieldChange::Changed {
+            path: path.to_string(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+/// A JSON patch turning `old` into `new`, leaving out the `ignored` fields
+///
+/// Ignored fields are JSON pointers, like those of [`SERVER_MANAGED_FIELDS`], and are left out
+/// of both objects before they are compared with [`diff`]. Every [`FieldChange`] becomes an
+/// operation of the patch, so lists that differ are replaced as a whole. Apply the patch with a
+/// precondition on the resource version, like [`Api::patch_to_desired`] does.
+///
+/// ```
+/// use kube::ops::{json_patch_diff, SERVER_MANAGED_FIELDS};
+/// use k8s_openapi::api::core::v1::ConfigMap;
+/// let mut old = ConfigMap::default();
+/// old.metadata.resource_version = Some("42".into());
+/// let mut new = old.clone();
+/// new.data = Some(std::iter::once(("a".to_string(), "b".to_string())).collect());
+/// new.metadata.resource_version = None;
+/// let patch = json_patch_diff(&old, &new, SERVER_MANAGED_FIELDS)?;
+/// assert_eq!(patch.0.len(), 1);
+/// # Ok::<(), kube::Error>(())
+/// ```
+#[cfg(feature = "jsonpatch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jsonpatch")))]
+pub fn json_patch_diff<K: Serialize>(old: &K, new: &K, ignored: &[&str]) -> Result<json_patch::Patch> {
+    let (mut old, mut new) = (serde_json::to_value(old)?, serde_json::to_value(new)?);
+    for pointer in ignored {
+        remove_pointer(&mut old, pointer);
+        remove_pointer(&mut new, pointer);
+    }
+    let operations = diff(&old, &new)
+        .into_iter()
+        .map(|change| match change {
+            FieldChange::Added { path, value } => serde_json::json!({ "op": "add", "path": path, "value": value }),
+            FieldChange::Removed { path, .. } => serde_json::json!({ "op": "remove", "path": path }),
+            FieldChange::Changed { path, new, .. } => {
+                serde_json::json!({ "op": "replace", "path": path, "value": new })
+            }
+        })
+        .collect();
+    Ok(serde_json::from_value(Value::Array(operations))?)
+}
+
+fn remove_pointer(value: &mut Value, pointer: &str) {
+    if let Some(split) = pointer.rfind('/') {
+        let key = pointer[split + 1..].replace("~1", "/").replace("~0", "~");
+        if let Some(parent) = value
+            .pointer_mut(&pointer[..split])
+            .and_then(Value::as_object_mut)
+        {
+            parent.remove(&key);
+        }
+    }
+}
+
+/// Methods for patching objects to a desired state
+#[cfg(feature = "jsonpatch")]
+#[cfg_attr(docsrs, doc(cfg(feature = "jsonpatch")))]
+impl<K> Api<K>
+where
+    K: Resource + Clone + DeserializeOwned + Serialize + Debug,
+{
+    /// Patch the object `current` into `desired`, sending only the fields that differ
+    ///
+    /// The patch is computed with [`json_patch_diff`], leaving out the [`SERVER_MANAGED_FIELDS`],
+    /// and is guarded by a `test` of the resource version of `current`. If the object changed since
+    /// `current` was read, the apiserver rejects the patch with a `422`, and nothing is changed.
+    /// Returns `current` without a request if there are no differences.
+    ///
+    /// ```no_run
+    /// use kube::{api::Api, Client};
+    /// use k8s_openapi::api::apps::v1::Deployment;
+    /// # async fn scope(client: Client) -> Result<(), kube::Error> {
+    /// let deploys: Api<Deployment> = Api::namespaced(client, "apps");
+    /// let current = deploys.get("blog").await?;
+    /// let mut desired = current.clone();
+    /// desired.spec.as_mut().unwrap().replicas = Some(3);
+    /// deploys.patch_to_desired(&current, &desired).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn patch_to_desired(&self, current: &K, desired: &K) -> Result<K> {
+        let meta = current.meta();
+        let name = meta
+            .name
+            .as_deref()
+            .ok_or_else(|| Error::RequestValidation("patch_to_desired requires a named object".into()))?;
+        let mut patch = json_patch_diff(current, desired, SERVER_MANAGED_FIELDS)?;
+        if patch.0.is_empty() {
+            return Ok(current.clone());
+        }
+        if let Some(rv) = &meta.resource_version {
+            let test = serde_json::json!({ "op": "test", "path": "/metadata/resourceVersion", "value": rv });
+            patch.0.insert(0, serde_json::from_value(test)?);
+        }
+        self.patch(name, &PatchParams::default(), &Patch::Json::<()>(patch))
+            .await
+    }
+}
+
+/// Escape `key` for use as a segment of a [JSON pointer](https://tools.ietf.org/html/rfc6901)
+pub(super) fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Leave out the server managed metadata, which changes on every write
+fn strip_volatile(mut obj: Value) -> Value {
+    for pointer in SERVER_MANAGED_FIELDS.iter().filter(|p| p.starts_with("/metadata/")) {
+        remove_pointer(&mut obj, pointer);
+    }
+    obj
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff, strip_volatile, FieldChange};
+    use serde_json::json;
+
+    #[test]
+    fn diff_nested_fields() {
+        let old = json!({
+            "metadata": { "name": "blog", "labels": { "app.kubernetes.io/name": "blog" } },
+            "spec": { "replicas": 1, "paused": false }
+        });
+        let new = json!({
+            "metadata": { "name": "blog", "labels": { "app.kubernetes.io/name": "blog", "tier": "web" } },
+            "spec": { "replicas": 3 }
+        });
+        assert_eq!(diff(&old, &new), vec![
+            FieldChange::Added {
+                path: "/metadata/labels/tier".into(),
+                value: json!("web"),
+            },
+            FieldChange::Removed {
+                path: "/spec/paused".into(),
+                value: json!(false),
+            },
+            FieldChange::Changed {
+                path: "/spec/replicas".into(),
+                old: json!(1),
+                new: json!(3),
+            },
+        ]);
+    }
+
+    #[test]
+    fn diff_escapes_keys_and_creation() {
+        let new = json!({ "metadata": { "annotations": { "a/b": "c" } } });
+        let changes = diff(&serde_json::Value::Null, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path(), "/metadata");
+
+        let old = json!({ "metadata": { "annotations": {} } });
+        assert_eq!(diff(&old, &new)[0].path(), "/metadata/annotations/a~1b");
+    }
+
+    #[test]
+    fn volatile_fields_ignored() {
+        let old = json!({ "metadata": { "name": "a", "resourceVersion": "1", "managedFields": [] } });
+        let new = json!({ "metadata": { "name": "a", "resourceVersion": "2", "generation": 2 } });
+        assert!(diff(&strip_volatile(old), &strip_volatile(new)).is_empty());
+    }
+
+    #[cfg(feature = "jsonpatch")]
+    #[test]
+    fn json_patches_follow_the_diff() {
+        use super::{json_patch_diff, SERVER_MANAGED_FIELDS};
+
+        let old = json!({
+            "metadata": { "name": "blog", "resourceVersion": "1", "labels": { "a": "1", "b": "2" } },
+            "spec": { "ports": [80, 443] },
+            "status": { "ready": true }
+        });
+        let new = json!({
+            "metadata": { "name": "blog", "resourceVersion": "2", "labels": { "a": "3", "c/d": "4" } },
+            "spec": { "ports": [80] }
+        });
+        let patch = json_patch_diff(&old, &new, SERVER_MANAGED_FIELDS).unwrap();
+        assert_eq!(serde_json::to_value(&patch).unwrap(), json!([
+            { "op": "replace", "path": "/metadata/labels/a", "value": "3" },
+            { "op": "remove", "path": "/metadata/labels/b" },
+            { "op": "add", "path": "/metadata/labels/c~1d", "value": "4" },
+            { "op": "replace", "path": "/spec/ports", "value": [80] },
+        ]));
+        let mut patched = old.clone();
+        json_patch::patch(&mut patched, &patch).unwrap();
+        assert_eq!(patched["metadata"]["labels"], new["metadata"]["labels"]);
+    }
+
+    #[cfg(feature = "jsonpatch")]
+    #[tokio::test]
+    async fn patch_to_desired_sends_the_differences() {
+        use crate::{api::Api, Client, Service};
+        use http::{Request, Response};
+        use hyper::Body;
+        use k8s_openapi::api::core::v1::ConfigMap;
+
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            let body = hyper::body::to_bytes(req.into_body()).await?;
+            let patch: serde_json::Value = serde_json::from_slice(&body)?;
+            assert_eq!(
+                patch,
+                json!([
+                    { "op": "test", "path": "/metadata/resourceVersion", "value": "42" },
+                    { "op": "add", "path": "/data/b", "value": "2" },
+                ])
+            );
+            Response::builder()
+                .body(Body::from(r#"{"metadata":{"name":"settings"}}"#))
+                .map_err(tower::BoxError::from)
+        });
+        let cms: Api<ConfigMap> = Api::namespaced(Client::new(Service::new(svc)), "default");
+        let current: ConfigMap = serde_json::from_value(json!({
+            "metadata": { "name": "settings", "resourceVersion": "42", "uid": "1234" },
+            "data": { "a": "1" }
+        }))
+        .unwrap();
+        let mut desired = current.clone();
+        desired.metadata.uid = None;
+        desired.data.as_mut().unwrap().insert("b".into(), "2".into());
+        cms.patch_to_desired(&current, &desired).await.unwrap();
+        // without differences, nothing is sent
+        cms.patch_to_desired(&current, &current.clone()).await.unwrap();
+    }
+}
@@ -0,0 +1,75 @@
+use crate::Result;
+use std::{fs, io, path::Path};
+
+/// Render objects to a multi-document yaml manifest
+///
+/// Keys are sorted, so the output is deterministic and can be committed and diffed,
+/// e.g. for the `crd_json()` of a `#[derive(CustomResource)]` type.
+///
+/// ```
+/// use kube::ops::to_yaml_manifest;
+/// let cm = serde_json::json!({ "kind": "ConfigMap", "apiVersion": "v1", "metadata": { "name": "a" } });
+/// let yaml = to_yaml_manifest(&[cm]).unwrap();
+/// assert_eq!(yaml, "---\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: a\n");
+/// ```
+pub fn to_yaml_manifest(objects: &[serde_json::Value]) -> Result<String> {
+    let sorted: Vec<_> = objects.iter().cloned().map(sort_keys).collect();
+    super::serde_multi_doc::to_string(&sorted)
+}
+
+/// Recursively sort the keys of objects, `serde_json` may be preserving insertion order
+fn sort_keys(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<_> = map.into_iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            Value::Object(entries.into_iter().map(|(k, v)| (k, sort_keys(v))).collect())
+        }
+        Value::Array(items) => Value::Array(items.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+/// Write objects to a yaml manifest file, leaving it untouched if the contents are unchanged
+///
+/// Intended for `build.rs` scripts that keep committed CRD manifests up to date:
+///
+/// ```no_run
+/// # let crd = serde_json::json!({});
+/// // e.g. with `let crd = Foo::crd_json();` for a `#[derive(CustomResource)]` type
+/// kube::ops::write_yaml_manifest("deploy/crds.yaml", &[crd]).unwrap();
+/// ```
+///
+/// Returns whether the file was written.
+pub fn write_yaml_manifest(path: impl AsRef<Path>, objects: &[serde_json::Value]) -> io::Result<bool> {
+    let manifest = to_yaml_manifest(objects).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let path = path.as_ref();
+    match fs::read_to_string(path) {
+        Ok(existing) if existing == manifest => return Ok(false),
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, manifest)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod test {
+    use super::to_yaml_manifest;
+
+    #[test]
+    fn manifests_are_sorted_and_separated() {
+        let objects = vec![
+            serde_json::json!({ "b": 1, "a": { "d": 2, "c": 3 } }),
+            serde_json::json!({ "z": [1, 2] }),
+        ];
+        let yaml = to_yaml_manifest(&objects).unwrap();
+        assert_eq!(yaml, "---\na:\n  c: 3\n  d: 2\nb: 1\n---\nz:\n  - 1\n  - 2\n");
+        assert_eq!(yaml, to_yaml_manifest(&objects).unwrap());
+    }
+}
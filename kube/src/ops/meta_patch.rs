@@ -0,0 +1,395 @@
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{collections::BTreeMap, fmt::Debug, time::Duration};
+
+use super::diff::escape;
+use crate::{
+    api::{Api, Patch, PatchParams, Resource},
+    error::ErrorResponse,
+    Error, Result,
+};
+
+/// How many times [`Api::remove_finalizer`] tries the removal, when it races with other writers
+const FINALIZER_ATTEMPTS: u32 = 5;
+/// How long [`Api::remove_finalizer`] waits before its first retry, doubled for every further one
+const FINALIZER_BACKOFF: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, PartialEq)]
+enum MetaOp {
+    AddLabel(String, String),
+    RemoveLabel(String),
+    AddAnnotation(String, String),
+    RemoveAnnotation(String),
+    AddFinalizer(String),
+    RemoveFinalizer(String),
+}
+
+/// A set of changes to the labels, annotations and finalizers of an object
+///
+/// Applied with [`Api::patch_metadata`] as a single JSON patch of the individual entries, so
+/// concurrent writers of other entries are not overwritten. Keys containing `/` or `~`, like
+/// `app.kubernetes.io/name`, are escaped.
+///
+/// ```
+/// use kube::ops::MetaPatch;
+/// let patch = MetaPatch::new()
+///     .add_label("app.kubernetes.io/managed-by", "my-operator")
+///     .remove_annotation("example.com/paused")
+///     .add_finalizer("example.com/cleanup");
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "jsonpatch")))]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetaPatch {
+    ops: Vec<MetaOp>,
+}
+
+impl MetaPatch {
+    /// Create an empty set of changes
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the label `key` to `value`
+    pub fn add_label(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.ops.push(MetaOp::AddLabel(key.into(), value.into()));
+        self
+    }
+
+    /// Remove the label `key`, if it is set
+    pub fn remove_label(mut self, key: impl Into<String>) -> Self {
+        self.ops.push(MetaOp::RemoveLabel(key.into()));
+        self
+    }
+
+    /// Set the annotation `key` to `value`
+    pub fn add_annotation(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.ops.push(MetaOp::AddAnnotation(key.into(), value.into()));
+        self
+    }
+
+    /// Remove the annotation `key`, if it is set
+    pub fn remove_annotation(mut self, key: impl Into<String>) -> Self {
+        self.ops.push(MetaOp::RemoveAnnotation(key.into()));
+        self
+    }
+
+    /// Add the finalizer `name`, unless it is already present
+    pub fn add_finalizer(mut self, name: impl Into<String>) -> Self {
+        self.ops.push(MetaOp::AddFinalizer(name.into()));
+        self
+    }
+
+    /// Remove the finalizer `name`, if it is present
+    pub fn remove_finalizer(mut self, name: impl Into<String>) -> Self {
+        self.ops.push(MetaOp::RemoveFinalizer(name.into()));
+        self
+    }
+
+    /// Whether there are no changes
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// The JSON patch operations applying the changes to an object with metadata `meta`
+    ///
+    /// Entries can only be added to maps and arrays that exist, so missing ones are created
+    /// first, guarded by a `test` of the resource version so a concurrently created map is not
+    /// replaced. Finalizers are removed by index, guarded by a `test` of the value at that index.
+    fn operations(&self, meta: &ObjectMeta) -> Vec<Value> {
+        let mut labels = meta.labels.clone();
+        let mut annotations = meta.annotations.clone();
+        let mut finalizers = meta.finalizers.clone();
+        let mut ops = vec![];
+        let mut guarded = false;
+        let mut guard = |ops: &mut Vec<Value>| {
+            if let (false, Some(rv)) = (guarded, &meta.resource_version) {
+                ops.push(json!({ "op": "test", "path": "/metadata/resourceVersion", "value": rv }));
+                guarded = true;
+            }
+        };
+
+        for op in &self.ops {
+            match op {
+                MetaOp::AddLabel(key, value) => {
+                    add_entry(&mut ops, &mut guard, &mut labels, "labels", key, value)
+                }
+                MetaOp::AddAnnotation(key, value) => {
+                    add_entry(&mut ops, &mut guard, &mut annotations, "annotations", key, value)
+                }
+                MetaOp::RemoveLabel(key) => remove_entry(&mut ops, &mut labels, "labels", key),
+                MetaOp::RemoveAnnotation(key) => remove_entry(&mut ops, &mut annotations, "annotations", key),
+                MetaOp::AddFinalizer(name) => match &mut finalizers {
+                    Some(present) if present.contains(name) => {}
+                    Some(present) => {
+                        ops.push(json!({ "op": "add", "path": "/metadata/finalizers/-", "value": name }));
+                        present.push(name.clone());
+                    }
+                    None => {
+                        guard(&mut ops);
+                        ops.push(json!({ "op": "add", "path": "/metadata/finalizers", "value": [name] }));
+                        finalizers = Some(vec![name.clone()]);
+                    }
+                },
+                MetaOp::RemoveFinalizer(name) => {
+                    let present = finalizers.iter_mut().find_map(|present| {
+                        let i = present.iter().position(|f| f == name)?;
+                        Some((present, i))
+                    });
+                    if let Some((present, i)) = present {
+                        let path = format!("/metadata/finalizers/{}", i);
+                        ops.push(json!({ "op": "test", "path": path, "value": name }));
+                        ops.push(json!({ "op": "remove", "path": path }));
+                        present.remove(i);
+                    }
+                }
+            }
+        }
+        ops
+    }
+}
+
+fn add_entry(
+    ops: &mut Vec<Value>,
+    guard: &mut impl FnMut(&mut Vec<Value>),
+    map: &mut Option<BTreeMap<String, String>>,
+    field: &str,
+    key: &str,
+    value: &str,
+) {
+    if map.as_ref().and_then(|map| map.get(key)).map(String::as_str) == Some(value) {
+        return;
+    }
+    if map.is_none() {
+        guard(ops);
+        ops.push(json!({ "op": "add", "path": format!("/metadata/{}", field), "value": {} }));
+    }
+    let path = format!("/metadata/{}/{}", field, escape(key));
+    ops.push(json!({ "op": "add", "path": path, "value": value }));
+    map.get_or_insert_with(BTreeMap::new)
+        .insert(key.to_string(), value.to_string());
+}
+
+fn remove_entry(ops: &mut Vec<Value>, map: &mut Option<BTreeMap<String, String>>, field: &str, key: &str) {
+    if map.as_mut().and_then(|map| map.remove(key)).is_some() {
+        let path = format!("/metadata/{}/{}", field, escape(key));
+        ops.push(json!({ "op": "remove", "path": path }));
+    }
+}
+
+/// Methods for the metadata of any resource
+#[cfg_attr(docsrs, doc(cfg(feature = "jsonpatch")))]
+impl<K> Api<K>
+where
+    K: Resource + Clone + DeserializeOwned + Debug,
+{
+    /// Add and remove individual labels, annotations and finalizers of an object
+    ///
+    /// The object is fetched first to find which entries exist, then the changes are sent as a
+    /// single JSON patch. If the object changed in between so the patch no longer applies, the
+    /// apiserver rejects it with a `422` and nothing is changed.
+    /// Returns the object as it is after the patch, or as fetched if nothing needed to change.
+    ///
+    /// ```no_run
+    /// use kube::{api::Api, ops::MetaPatch, Client};
+    /// use k8s_openapi::api::core::v1::Pod;
+    /// #[tokio::main]
+    /// async fn main() -> Result<(), kube::Error> {
+    ///     let client = Client::try_default().await?;
+    ///     let pods: Api<Pod> = Api::namespaced(client, "apps");
+    ///     let patch = MetaPatch::new().add_label("example.com/owner", "web").remove_finalizer("example.com/cleanup");
+    ///     pods.patch_metadata("blog", &patch).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn patch_metadata(&self, name: &str, patch: &MetaPatch) -> Result<K> {
+        let obj = self.get(name).await?;
+        let ops = patch.operations(obj.meta());
+        if ops.is_empty() {
+            return Ok(obj);
+        }
+        let patch = Patch::Json::<()>(serde_json::from_value(Value::Array(ops))?);
+        self.patch(name, &PatchParams::default(), &patch).await
+    }
+
+    /// Remove the finalizer `finalizer` from an object, without touching its other finalizers
+    ///
+    /// The entry is removed by its index, with a `test` precondition on the value at that index,
+    /// so a concurrent edit of the finalizers can never make this remove another entry.
+    /// If the precondition fails, the object is fetched again and the removal retried with a
+    /// backoff, up to five times. Other rejections of the patch are returned as they are.
+    /// The backoff sleeps on the Tokio timer, so this has to run within a Tokio runtime,
+    /// whatever [`Executor`](crate::executor::Executor) the client spawns its tasks with.
+    /// Returns the object as it is after the removal, or as fetched if the finalizer was not present.
+    pub async fn remove_finalizer(&self, name: &str, finalizer: &str) -> Result<K> {
+        let patch = MetaPatch::new().remove_finalizer(finalizer);
+        let mut attempt = 1;
+        loop {
+            match self.patch_metadata(name, &patch).await {
+                Err(Error::Api(ae)) if is_conflict(&ae) && attempt < FINALIZER_ATTEMPTS => {
+                    tokio::time::sleep(FINALIZER_BACKOFF * 2u32.pow(attempt - 1)).await;
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+/// Whether a patch was rejected because the object changed since it was fetched
+///
+/// The apiserver rejects JSON patches with a failed `test` operation as `422 Invalid`, with the
+/// failure as a cause. Other `422`s, like invalid finalizer names, fail the same way every time.
+fn is_conflict(ae: &ErrorResponse) -> bool {
+    let causes = ae.details.iter().flat_map(|details| &details.causes);
+    match ae.code {
+        409 => true,
+        422 => std::iter::once(&ae.message)
+            .chain(causes.map(|cause| &cause.message))
+            .any(|message| message.contains("test failed")),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MetaPatch;
+    use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+    use serde_json::json;
+
+    /// How the apiserver rejects a JSON patch with a failed `test` operation
+    const TEST_FAILED: &str = r#"{"status":"Failure","message":"the server rejected our request due to an error in our request","reason":"Invalid","details":{"causes":[{"reason":"UnexpectedServerResponse","message":"testing value /metadata/finalizers/0 failed: test failed"}]},"code":422}"#;
+
+    #[test]
+    fn operations_escape_keys_and_create_missing_fields() {
+        let meta = ObjectMeta {
+            resource_version: Some("42".into()),
+            annotations: Some(std::iter::once(("a~b/c".to_string(), "x".to_string())).collect()),
+            finalizers: Some(vec!["example.com/keep".into(), "example.com/cleanup".into()]),
+            ..ObjectMeta::default()
+        };
+        let patch = MetaPatch::new()
+            .add_label("app.kubernetes.io/name", "blog")
+            .add_label("tier", "web")
+            .remove_label("missing")
+            .remove_annotation("a~b/c")
+            .add_finalizer("example.com/keep")
+            .remove_finalizer("example.com/cleanup");
+        assert_eq!(patch.operations(&meta), vec![
+            json!({ "op": "test", "path": "/metadata/resourceVersion", "value": "42" }),
+            json!({ "op": "add", "path": "/metadata/labels", "value": {} }),
+            json!({ "op": "add", "path": "/metadata/labels/app.kubernetes.io~1name", "value": "blog" }),
+            json!({ "op": "add", "path": "/metadata/labels/tier", "value": "web" }),
+            json!({ "op": "remove", "path": "/metadata/annotations/a~0b~1c" }),
+            json!({ "op": "test", "path": "/metadata/finalizers/1", "value": "example.com/cleanup" }),
+            json!({ "op": "remove", "path": "/metadata/finalizers/1" }),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn patch_metadata_skips_the_patch_without_changes() {
+        use crate::{api::Api, Client, Service};
+        use http::{Method, Request, Response};
+        use hyper::Body;
+        use k8s_openapi::api::core::v1::Pod;
+
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            assert_eq!(req.method(), Method::GET);
+            Response::builder()
+                .body(Body::from(
+                    r#"{"metadata":{"name":"blog","labels":{"tier":"web"}}}"#,
+                ))
+                .map_err(tower::BoxError::from)
+        });
+        let pods: Api<Pod> = Api::namespaced(Client::new(Service::new(svc)), "default");
+        let patch = MetaPatch::new()
+            .remove_finalizer("example.com/cleanup")
+            .remove_label("app");
+        pods.patch_metadata("blog", &patch).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn remove_finalizer_retries_when_the_index_moved() {
+        use crate::{api::Api, Client, Service};
+        use http::{Method, Request, Response};
+        use hyper::Body;
+        use k8s_openapi::api::core::v1::Pod;
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = calls.clone();
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            let call = seen.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let (status, body) = match call {
+                    // another writer removes `a` between the first fetch and the patch
+                    0 => (200, r#"{"metadata":{"name":"blog","finalizers":["a","f"]}}"#),
+                    1 => {
+                        assert_eq!(req.method(), Method::PATCH);
+                        (422, TEST_FAILED)
+                    }
+                    2 => (200, r#"{"metadata":{"name":"blog","finalizers":["f"]}}"#),
+                    _ => {
+                        assert_eq!(req.method(), Method::PATCH);
+                        let body = hyper::body::to_bytes(req.into_body()).await?;
+                        let patch: serde_json::Value = serde_json::from_slice(&body)?;
+                        assert_eq!(
+                            patch,
+                            json!([
+                                { "op": "test", "path": "/metadata/finalizers/0", "value": "f" },
+                                { "op": "remove", "path": "/metadata/finalizers/0" },
+                            ])
+                        );
+                        (200, r#"{"metadata":{"name":"blog"}}"#)
+                    }
+                };
+                Response::builder()
+                    .status(status)
+                    .body(Body::from(body))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let pods: Api<Pod> = Api::namespaced(Client::new(Service::new(svc)), "default");
+        let pod = pods.remove_finalizer("blog", "f").await.unwrap();
+        assert_eq!(pod.metadata.finalizers, None);
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn remove_finalizer_returns_invalid_patches() {
+        use crate::{api::Api, Client, Error, Service};
+        use http::{Request, Response};
+        use hyper::Body;
+        use k8s_openapi::api::core::v1::Pod;
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+
+        let calls = Arc::new(AtomicUsize::new(0));
+        let seen = calls.clone();
+        let svc = tower::service_fn(move |_req: Request<Body>| {
+            let call = seen.fetch_add(1, Ordering::SeqCst);
+            async move {
+                let (status, body) = match call {
+                    0 => (200, r#"{"metadata":{"name":"blog","finalizers":["f"]}}"#),
+                    _ => (
+                        422,
+                        r#"{"status":"Failure","message":"Pod \"blog\" is invalid: metadata.finalizers[0]: Forbidden","reason":"Invalid","code":422}"#,
+                    ),
+                };
+                Response::builder()
+                    .status(status)
+                    .body(Body::from(body))
+                    .map_err(tower::BoxError::from)
+            }
+        });
+        let pods: Api<Pod> = Api::namespaced(Client::new(Service::new(svc)), "default");
+        let err = pods.remove_finalizer("blog", "f").await.unwrap_err();
+        assert!(matches!(err, Error::Api(ae) if ae.code == 422));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
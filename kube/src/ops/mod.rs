@@ -0,0 +1,53 @@
+//! Higher level operations composed from [`Api`](crate::Api) calls
+//!
+//! These mirror common `kubectl` workflows for library consumers.
+
+mod api_service;
+pub use api_service::{
+    inject_ca_bundle, is_api_service_available, wait_for_api_service, ApiServiceRegistration,
+};
+
+mod apply;
+pub use apply::{apply_manifest, ApplyOutcome};
+
+mod config_data;
+pub use config_data::{ConfigMapExt, SecretExt};
+
+#[cfg(feature = "cp")]
+#[cfg_attr(docsrs, doc(cfg(feature = "cp")))]
+pub mod cp;
+
+mod diff;
+#[cfg(feature = "jsonpatch")] pub use diff::json_patch_diff;
+pub use diff::{diff, dry_run_diff, FieldChange, SERVER_MANAGED_FIELDS};
+
+#[cfg(feature = "jsonpatch")] mod meta_patch;
+#[cfg(feature = "jsonpatch")] pub use meta_patch::MetaPatch;
+
+mod manifest;
+pub use manifest::{to_yaml_manifest, write_yaml_manifest};
+
+mod namespace;
+pub use namespace::{delete_namespace_and_wait, ensure_namespace};
+
+#[cfg(feature = "ws")] mod portforward;
+#[cfg(feature = "ws")]
+pub use portforward::{portforward_http, PortforwardConnector};
+
+mod output;
+pub use output::{Column, OutputFormat, Printer};
+
+mod rbac;
+pub(crate) use rbac::display_missing;
+pub use rbac::{verify_rbac, MissingPermission, RbacRequirement};
+
+pub mod serde_multi_doc;
+
+mod status;
+pub use status::{DeploymentConditionType, DeploymentExt, PodConditionType, PodExt};
+
+mod ownership;
+pub use ownership::{
+    is_managed_by, list_managed, managed_selector, set_managed_by, ManagedObject, INSTANCE_LABEL,
+    MANAGED_BY_LABEL,
+};
@@ -0,0 +1,198 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use k8s_openapi::api::core::v1::{Namespace, NamespaceSpec};
+
+use crate::{
+    api::{Api, DeleteParams, ObjectMeta, Patch, PatchParams, PostParams},
+    Client, Error, Result,
+};
+
+/// Make sure the namespace `name` exists with `labels`, creating it if it is absent
+///
+/// Uses server-side apply, so labels set by other field managers are kept and calling this again
+/// is a no-op. A namespace that is being deleted is returned as is, with a `Terminating` phase.
+///
+/// ```no_run
+/// use kube::{ops::ensure_namespace, Client};
+/// use std::collections::BTreeMap;
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let mut labels = BTreeMap::new();
+/// labels.insert("e2e.example.com/run".to_string(), "42".to_string());
+/// ensure_namespace(&client, "e2e-42", &labels, "e2e-harness").await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn ensure_namespace(
+    client: &Client,
+    name: &str,
+    labels: &BTreeMap<String, String>,
+    field_manager: &str,
+) -> Result<Namespace> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    let patch = serde_json::json!({
+        "apiVersion": "v1",
+        "kind": "Namespace",
+        "metadata": { "name": name, "labels": labels },
+    });
+    api.patch(name, &PatchParams::apply(field_manager), &Patch::Apply(&patch))
+        .await
+}
+
+/// Delete the namespace `name` and poll until it is gone
+///
+/// Deleting a namespace only starts its termination: the namespace controller removes every
+/// object in it before dropping the `kubernetes` finalizer. Objects whose own finalizers are never
+/// removed, or an unavailable aggregated api, keep it `Terminating` forever. With
+/// `clear_finalizers_after`, the finalizers of the namespace are cleared once it was terminating
+/// for that long, which orphans whatever is left in etcd and is only meant for throwaway test
+/// namespaces.
+///
+/// Succeeds right away if the namespace does not exist, and fails with [`Error::Timeout`]
+/// when it is not gone within `timeout`.
+pub async fn delete_namespace_and_wait(
+    client: &Client,
+    name: &str,
+    timeout: Duration,
+    clear_finalizers_after: Option<Duration>,
+) -> Result<()> {
+    let api: Api<Namespace> = Api::all(client.clone());
+    let start = tokio::time::Instant::now();
+    let deadline = start + timeout;
+    match api.delete(name, &DeleteParams::default()).await {
+        Err(Error::Api(ae)) if ae.code == 404 => return Ok(()),
+        res => res?,
+    };
+    let mut cleared = false;
+    loop {
+        let namespace = match api.get(name).await {
+            Err(Error::Api(ae)) if ae.code == 404 => return Ok(()),
+            res => res?,
+        };
+        let now = tokio::time::Instant::now();
+        if let Some(after) = clear_finalizers_after {
+            if !cleared && now >= start + after {
+                clear_finalizers(&api, namespace).await?;
+                cleared = true;
+                continue;
+            }
+        }
+        if now >= deadline {
+            return Err(Error::Timeout("waiting for the namespace to be deleted"));
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+}
+
+/// Drop the finalizers of the object, and those of the spec through the `finalize` subresource
+async fn clear_finalizers(api: &Api<Namespace>, namespace: Namespace) -> Result<()> {
+    let name = namespace.metadata.name.clone().unwrap_or_default();
+    if matches!(&namespace.metadata.finalizers, Some(f) if !f.is_empty()) {
+        let patch = serde_json::json!({ "metadata": { "finalizers": null } });
+        api.patch(&name, &PatchParams::default(), &Patch::Merge(&patch))
+            .await?;
+    }
+    let finalized = Namespace {
+        metadata: ObjectMeta {
+            name: Some(name.clone()),
+            ..ObjectMeta::default()
+        },
+        spec: Some(NamespaceSpec { finalizers: None }),
+        status: None,
+    };
+    let data = serde_json::to_vec(&finalized)?;
+    api.replace_subresource::<Namespace>("finalize", &name, &PostParams::default(), data)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{delete_namespace_and_wait, ensure_namespace};
+    use crate::{Client, Service};
+    use http::{Request, Response};
+    use hyper::Body;
+    use std::{
+        collections::BTreeMap,
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    fn respond(status: u16, body: serde_json::Value) -> Result<Response<Body>, tower::BoxError> {
+        Response::builder()
+            .status(status)
+            .body(Body::from(serde_json::to_vec(&body)?))
+            .map_err(tower::BoxError::from)
+    }
+
+    #[tokio::test]
+    async fn ensure_applies_the_labels() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            assert_eq!(req.method(), "PATCH");
+            assert_eq!(
+                req.uri().to_string(),
+                "/api/v1/namespaces/e2e?&fieldManager=harness"
+            );
+            let body = hyper::body::to_bytes(req.into_body()).await?;
+            let patch: serde_json::Value = serde_json::from_slice(&body)?;
+            assert_eq!(patch["metadata"]["labels"]["run"], "42");
+            respond(200, patch)
+        });
+        let client = Client::new(Service::new(svc));
+        let mut labels = BTreeMap::new();
+        labels.insert("run".to_string(), "42".to_string());
+        let ns = ensure_namespace(&client, "e2e", &labels, "harness")
+            .await
+            .unwrap();
+        assert_eq!(ns.metadata.name.as_deref(), Some("e2e"));
+    }
+
+    #[tokio::test]
+    async fn delete_clears_stuck_finalizers() {
+        let finalized = Arc::new(AtomicBool::new(false));
+        let seen = finalized.clone();
+        let svc = tower::service_fn(move |req: Request<Body>| {
+            let finalized = finalized.clone();
+            async move {
+                let terminating = serde_json::json!({
+                    "metadata": { "name": "e2e", "finalizers": ["example.com/stuck"] },
+                    "spec": { "finalizers": ["kubernetes"] },
+                    "status": { "phase": "Terminating" },
+                });
+                match (req.method().as_str(), req.uri().path()) {
+                    ("DELETE", "/api/v1/namespaces/e2e") | ("PATCH", "/api/v1/namespaces/e2e") => {
+                        respond(200, terminating)
+                    }
+                    ("PUT", "/api/v1/namespaces/e2e/finalize") => {
+                        let body = hyper::body::to_bytes(req.into_body()).await?;
+                        let ns: serde_json::Value = serde_json::from_slice(&body)?;
+                        assert!(ns["spec"].get("finalizers").is_none());
+                        finalized.store(true, Ordering::SeqCst);
+                        respond(200, ns)
+                    }
+                    ("GET", "/api/v1/namespaces/e2e") if finalized.load(Ordering::SeqCst) => respond(
+                        404,
+                        serde_json::json!({
+                            "kind": "Status", "apiVersion": "v1", "metadata": {}, "status": "Failure",
+                            "message": "not found", "reason": "NotFound", "code": 404,
+                        }),
+                    ),
+                    ("GET", "/api/v1/namespaces/e2e") => respond(200, terminating),
+                    (method, path) => panic!("unexpected {} {}", method, path),
+                }
+            }
+        });
+        let client = Client::new(Service::new(svc));
+        delete_namespace_and_wait(
+            &client,
+            "e2e",
+            Duration::from_secs(10),
+            Some(Duration::from_secs(0)),
+        )
+        .await
+        .unwrap();
+        assert!(seen.load(Ordering::SeqCst));
+    }
+}
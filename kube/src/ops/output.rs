@@ -0,0 +1,331 @@
+use crate::{
+    api::{DynamicObject, ObjectList, Table},
+    Result,
+};
+use jsonpath_lib::select as jsonpath_select;
+use k8s_openapi::chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+/// How [`Printer`] renders objects, like `kubectl get -o`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Aligned columns with a header, the default of `kubectl get`
+    Table,
+    /// Like `Table`, with the additional columns of `-o wide`
+    Wide,
+    /// A json `List`, or the `Table` itself
+    Json,
+    /// A yaml `List`, or the `Table` itself
+    Yaml,
+    /// `kind/name` of every object, like `-o name`
+    Name,
+}
+
+/// A column of [`Printer::columns`] for plain objects, like `kubectl get -o custom-columns`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Column {
+    /// Header of the column
+    pub header: String,
+    /// Dotted path to the value, e.g. `.spec.replicas` (jsonpath is also accepted)
+    pub path: String,
+}
+
+impl Column {
+    /// Create a column from a header and a path
+    pub fn new(header: &str, path: &str) -> Self {
+        Self {
+            header: header.to_string(),
+            path: path.to_string(),
+        }
+    }
+}
+
+/// Renders lists of objects and server-side [`Table`]s as text, json or yaml
+///
+/// ```
+/// use kube::{api::{DynamicObject, GroupVersionKind, ObjectList}, ops::{Column, OutputFormat, Printer}};
+/// let gvk = GroupVersionKind::gvk("", "v1", "ConfigMap").unwrap();
+/// let list = ObjectList {
+///     metadata: Default::default(),
+///     items: vec![DynamicObject::new("settings", &gvk).namespace("prod")],
+/// };
+/// let printer = Printer::new(OutputFormat::Table)
+///     .columns(vec![Column::new("NAME", ".metadata.name"), Column::new("NAMESPACE", ".metadata.namespace")]);
+/// assert_eq!(printer.print_objects(&list).unwrap(), "NAME       NAMESPACE\nsettings   prod\n");
+/// assert_eq!(Printer::new(OutputFormat::Name).print_objects(&list).unwrap(), "configmap/settings\n");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Printer {
+    format: OutputFormat,
+    columns: Vec<Column>,
+    headers: bool,
+}
+
+impl Printer {
+    /// Create a printer for a format
+    pub fn new(format: OutputFormat) -> Self {
+        Self {
+            format,
+            columns: vec![],
+            headers: true,
+        }
+    }
+
+    /// Select the columns to print in the text formats
+    ///
+    /// For [`Table`]s, columns are selected by their header (case insensitively), and the path is ignored.
+    /// For plain objects, the value at the path is printed.
+    pub fn columns(mut self, columns: Vec<Column>) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Omit the header line in the text formats, like `--no-headers`
+    pub fn no_headers(mut self) -> Self {
+        self.headers = false;
+        self
+    }
+
+    /// Render a server-side formatted [`Table`]
+    pub fn print_table(&self, table: &Table) -> Result<String> {
+        match self.format {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(table)? + "\n"),
+            OutputFormat::Yaml => Ok(serde_yaml::to_string(table)?),
+            OutputFormat::Name => Ok(table
+                .rows
+                .iter()
+                .filter_map(|row| row.cells.first())
+                .map(|name| cell_text(name) + "\n")
+                .collect()),
+            OutputFormat::Table | OutputFormat::Wide => {
+                let selected: Vec<usize> = if self.columns.is_empty() {
+                    let max_priority = if self.format == OutputFormat::Wide {
+                        i32::MAX
+                    } else {
+                        0
+                    };
+                    (0..table.column_definitions.len())
+                        .filter(|i| table.column_definitions[*i].priority <= max_priority)
+                        .collect()
+                } else {
+                    self.columns
+                        .iter()
+                        .filter_map(|c| {
+                            table
+                                .column_definitions
+                                .iter()
+                                .position(|d| d.name.eq_ignore_ascii_case(&c.header))
+                        })
+                        .collect()
+                };
+                let headers = selected
+                    .iter()
+                    .map(|i| table.column_definitions[*i].name.to_ascii_uppercase())
+                    .collect();
+                let rows = table
+                    .rows
+                    .iter()
+                    .map(|row| {
+                        selected
+                            .iter()
+                            .map(|i| row.cells.get(*i).map_or_else(String::new, cell_text))
+                            .collect()
+                    })
+                    .collect();
+                Ok(self.align(headers, rows))
+            }
+        }
+    }
+
+    /// Render a list of objects
+    ///
+    /// Without [`columns`](Self::columns), the text formats print the name, namespace (if any) and age of every object.
+    pub fn print_objects(&self, list: &ObjectList<DynamicObject>) -> Result<String> {
+        match self.format {
+            OutputFormat::Json => Ok(serde_json::to_string_pretty(&as_list(list)?)? + "\n"),
+            OutputFormat::Yaml => Ok(serde_yaml::to_string(&as_list(list)?)?),
+            OutputFormat::Name => Ok(list
+                .items
+                .iter()
+                .map(|obj| {
+                    let kind = obj.types.as_ref().map(|t| t.kind.to_ascii_lowercase());
+                    let name = obj.metadata.name.clone().unwrap_or_default();
+                    match kind {
+                        Some(kind) => format!("{}/{}\n", kind, name),
+                        None => format!("{}\n", name),
+                    }
+                })
+                .collect()),
+            OutputFormat::Table | OutputFormat::Wide => {
+                let columns = if self.columns.is_empty() {
+                    default_columns(list)
+                } else {
+                    self.columns.clone()
+                };
+                let headers = columns.iter().map(|c| c.header.clone()).collect();
+                let mut rows = vec![];
+                for obj in &list.items {
+                    let value = serde_json::to_value(obj)?;
+                    rows.push(columns.iter().map(|c| column_text(&value, c)).collect());
+                }
+                Ok(self.align(headers, rows))
+            }
+        }
+    }
+
+    /// Align cells into columns separated by three spaces, like `kubectl`
+    fn align(&self, headers: Vec<String>, rows: Vec<Vec<String>>) -> String {
+        let mut lines = if self.headers { vec![headers] } else { vec![] };
+        lines.extend(rows);
+        let columns = lines.iter().map(Vec::len).max().unwrap_or(0);
+        let widths: Vec<usize> = (0..columns)
+            .map(|i| {
+                lines
+                    .iter()
+                    .filter_map(|l| l.get(i))
+                    .map(|c| c.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        let mut out = String::new();
+        for line in lines {
+            let mut text = String::new();
+            for (i, cell) in line.iter().enumerate() {
+                text.push_str(cell);
+                if i + 1 < line.len() {
+                    let pad = widths[i] - cell.chars().count() + 3;
+                    text.push_str(&" ".repeat(pad));
+                }
+            }
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// The columns printed for objects by default
+fn default_columns(list: &ObjectList<DynamicObject>) -> Vec<Column> {
+    let mut columns = vec![];
+    if list.items.iter().any(|o| o.metadata.namespace.is_some()) {
+        columns.push(Column::new("NAMESPACE", ".metadata.namespace"));
+    }
+    columns.push(Column::new("NAME", ".metadata.name"));
+    columns.push(Column::new("AGE", ".metadata.creationTimestamp"));
+    columns
+}
+
+/// Wrap a list of objects in a `List`, like kubectl
+fn as_list(list: &ObjectList<DynamicObject>) -> Result<Value> {
+    Ok(json!({
+        "apiVersion": "v1",
+        "kind": "List",
+        "metadata": { "resourceVersion": "" },
+        "items": serde_json::to_value(&list.items)?,
+    }))
+}
+
+/// The text of a column for an object
+fn column_text(value: &Value, column: &Column) -> String {
+    let path = if column.path.starts_with('$') {
+        column.path.clone()
+    } else {
+        format!("${}", column.path)
+    };
+    let found = match jsonpath_select(value, &path) {
+        Ok(found) => found,
+        Err(_) => return "<invalid>".to_string(),
+    };
+    let text = found.into_iter().map(cell_text).collect::<Vec<_>>().join(",");
+    if text.is_empty() {
+        return "<none>".to_string();
+    }
+    if column.path == ".metadata.creationTimestamp" {
+        if let Ok(created) = DateTime::parse_from_rfc3339(&text) {
+            let age = Utc::now().signed_duration_since(created);
+            return human_age(age.num_seconds());
+        }
+    }
+    text
+}
+
+/// The text of a table cell or json value, strings are printed without quotes
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => "<none>".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// A short human readable age, like `45s`, `12m`, `5h` or `3d`
+pub(crate) fn human_age(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    match seconds {
+        s if s < 120 => format!("{}s", s),
+        s if s < 120 * 60 => format!("{}m", s / 60),
+        s if s < 48 * 3600 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{human_age, Column, OutputFormat, Printer};
+    use crate::api::Table;
+
+    fn table() -> Table {
+        serde_json::from_value(serde_json::json!({
+            "kind": "Table",
+            "apiVersion": "meta.k8s.io/v1",
+            "metadata": {},
+            "columnDefinitions": [
+                { "name": "Name", "type": "string", "format": "name", "priority": 0 },
+                { "name": "Ready", "type": "string", "priority": 0 },
+                { "name": "IP", "type": "string", "priority": 1 }
+            ],
+            "rows": [
+                { "cells": ["blog-6d4cf56db6-xj5rw", "1/1", "10.0.0.12"] },
+                { "cells": ["db-0", "0/1", null] }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn tables_are_aligned() {
+        let table = table();
+        assert_eq!(
+            Printer::new(OutputFormat::Table).print_table(&table).unwrap(),
+            "NAME                    READY\nblog-6d4cf56db6-xj5rw   1/1\ndb-0                    0/1\n"
+        );
+        assert_eq!(
+            Printer::new(OutputFormat::Wide)
+                .no_headers()
+                .print_table(&table)
+                .unwrap(),
+            "blog-6d4cf56db6-xj5rw   1/1   10.0.0.12\ndb-0                    0/1   <none>\n"
+        );
+        assert_eq!(
+            Printer::new(OutputFormat::Table)
+                .columns(vec![Column::new("ip", ""), Column::new("name", "")])
+                .print_table(&table)
+                .unwrap(),
+            "IP          NAME\n10.0.0.12   blog-6d4cf56db6-xj5rw\n<none>      db-0\n"
+        );
+        assert_eq!(
+            Printer::new(OutputFormat::Name).print_table(&table).unwrap(),
+            "blog-6d4cf56db6-xj5rw\ndb-0\n"
+        );
+    }
+
+    #[test]
+    fn ages_are_human_readable() {
+        assert_eq!(human_age(-3), "0s");
+        assert_eq!(human_age(45), "45s");
+        assert_eq!(human_age(600), "10m");
+        assert_eq!(human_age(5 * 3600), "5h");
+        assert_eq!(human_age(3 * 86400), "3d");
+    }
+}
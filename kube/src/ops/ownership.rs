@@ -0,0 +1,179 @@
+use crate::{
+    api::{Api, DynamicObject, ListParams, PartialObjectMetadata, ResourceExt},
+    discovery::{ApiResource, Discovery},
+    Client, Error, Result,
+};
+use tracing::warn;
+
+/// Label recording the tool that manages an object
+///
+/// See the [recommended labels](https://kubernetes.io/docs/concepts/overview/working-with-objects/common-labels/).
+pub const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+
+/// Label recording which instance (release) of the manager owns an object
+pub const INSTANCE_LABEL: &str = "app.kubernetes.io/instance";
+
+/// An object found by [`list_managed`]
+#[derive(Debug, Clone)]
+pub struct ManagedObject {
+    /// The resource the object belongs to
+    pub resource: ApiResource,
+    /// Metadata of the object
+    pub object: PartialObjectMetadata,
+}
+
+/// Mark an object as managed by `manager`, and optionally a named instance of it
+///
+/// Use this on objects before applying them, so they can later be found with [`list_managed`]
+/// for pruning or garbage collection.
+pub fn set_managed_by<K: ResourceExt>(obj: &mut K, manager: &str, instance: Option<&str>) {
+    let labels = obj.labels_mut();
+    labels.insert(MANAGED_BY_LABEL.to_string(), manager.to_string());
+    if let Some(instance) = instance {
+        labels.insert(INSTANCE_LABEL.to_string(), instance.to_string());
+    }
+}
+
+/// Whether an object is managed by `manager`
+pub fn is_managed_by<K: ResourceExt>(obj: &K, manager: &str) -> bool {
+    obj.labels().get(MANAGED_BY_LABEL).map(String::as_str) == Some(manager)
+}
+
+/// List the metadata of all objects managed by `manager`, across all namespaces
+///
+/// Every listable resource in `discovery` is queried with a label selector for the manager,
+/// in the preferred version of its group only, so each object is returned once.
+/// When `instance` is set, only objects labelled with that instance are returned.
+/// Resources the client is not allowed to list are skipped with a warning.
+///
+/// ```no_run
+/// use kube::{discovery::Discovery, ops::list_managed, Client};
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// let discovery = Discovery::run(&client).await?;
+/// for managed in list_managed(&client, &discovery, "my-deployer", Some("blog")).await? {
+///     println!("{} {:?}", managed.resource.kind, managed.object.metadata.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub async fn list_managed(
+    client: &Client,
+    discovery: &Discovery,
+    manager: &str,
+    instance: Option<&str>,
+) -> Result<Vec<ManagedObject>> {
+    let lp = ListParams::default().labels(&managed_selector(manager, instance));
+    let mut managed = vec![];
+    for ar in discovery.preferred_resources().filter(|ar| ar.supports("list")) {
+        let api: Api<DynamicObject> = Api::all_with(client.clone(), &ar.to_gvk());
+        let objects = match api.list_metadata(&lp).await {
+            Ok(objects) => objects,
+            Err(Error::Api(ae)) if ae.code == 403 => {
+                warn!("Skipping managed {}: {}", ar.api_version, ae.message);
+                continue;
+            }
+            Err(err) => return Err(err),
+        };
+        for object in objects {
+            managed.push(ManagedObject {
+                resource: ar.clone(),
+                object,
+            });
+        }
+    }
+    Ok(managed)
+}
+
+/// Label selector matching objects set up with [`set_managed_by`]
+pub fn managed_selector(manager: &str, instance: Option<&str>) -> String {
+    match instance {
+        Some(instance) => format!("{}={},{}={}", MANAGED_BY_LABEL, manager, INSTANCE_LABEL, instance),
+        None => format!("{}={}", MANAGED_BY_LABEL, manager),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        is_managed_by, list_managed, managed_selector, set_managed_by, INSTANCE_LABEL, MANAGED_BY_LABEL,
+    };
+    use crate::{api::ResourceExt, discovery::Discovery, Client, Service};
+    use http::{Request, Response};
+    use hyper::Body;
+    use k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::APIResourceList};
+
+    #[test]
+    fn managed_by_roundtrip() {
+        let mut cm = ConfigMap::default();
+        assert!(!is_managed_by(&cm, "deployer"));
+        set_managed_by(&mut cm, "deployer", Some("blog"));
+        assert!(is_managed_by(&cm, "deployer"));
+        assert_eq!(cm.labels()[MANAGED_BY_LABEL], "deployer");
+        assert_eq!(cm.labels()[INSTANCE_LABEL], "blog");
+    }
+
+    #[test]
+    fn selectors() {
+        assert_eq!(
+            managed_selector("deployer", None),
+            "app.kubernetes.io/managed-by=deployer"
+        );
+        assert_eq!(
+            managed_selector("deployer", Some("blog")),
+            "app.kubernetes.io/managed-by=deployer,app.kubernetes.io/instance=blog"
+        );
+    }
+
+    fn resource_list(group_version: &str, kind: &str, plural: &str) -> APIResourceList {
+        serde_json::from_value(serde_json::json!({
+            "groupVersion": group_version,
+            "resources": [
+                {"name": plural, "singularName": "", "namespaced": true, "kind": kind, "verbs": ["list"]},
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn list_managed_uses_preferred_versions_and_skips_forbidden() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            let (status, body) = match req.uri().path() {
+                "/apis/clux.dev/v2/foos" => (
+                    200,
+                    serde_json::json!({
+                        "apiVersion": "meta.k8s.io/v1",
+                        "kind": "PartialObjectMetadataList",
+                        "metadata": {},
+                        "items": [{
+                            "apiVersion": "clux.dev/v2",
+                            "kind": "Foo",
+                            "metadata": { "name": "blog", "namespace": "default" },
+                        }],
+                    }),
+                ),
+                "/apis/clux.dev/v2/secrets" => (
+                    403,
+                    serde_json::json!({
+                        "kind": "Status", "apiVersion": "v1", "metadata": {}, "status": "Failure",
+                        "message": "secrets is forbidden", "reason": "Forbidden", "code": 403,
+                    }),
+                ),
+                path => panic!("unexpected list of {}", path),
+            };
+            Response::builder()
+                .status(status)
+                .body(Body::from(serde_json::to_vec(&body)?))
+                .map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc));
+        let mut discovery = Discovery::default();
+        discovery.add_group("clux.dev", "v2");
+        discovery.add_resource_list(&resource_list("clux.dev/v1", "Foo", "foos"));
+        discovery.add_resource_list(&resource_list("clux.dev/v2", "Foo", "foos"));
+        discovery.add_resource_list(&resource_list("clux.dev/v2", "Secret", "secrets"));
+        let managed = list_managed(&client, &discovery, "deployer", None).await.unwrap();
+        assert_eq!(managed.len(), 1);
+        assert_eq!(managed[0].resource.api_version, "clux.dev/v2");
+        assert_eq!(managed[0].object.metadata.name.as_deref(), Some("blog"));
+    }
+}
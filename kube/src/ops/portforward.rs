@@ -0,0 +1,62 @@
+use futures::future::BoxFuture;
+use http::Uri;
+use k8s_openapi::api::core::v1::Pod;
+use std::task::{Context, Poll};
+
+use crate::{
+    api::{Api, PortforwardStream},
+    Error, Result,
+};
+
+/// A hyper connector that reaches a port of a pod through the apiserver
+///
+/// Created with [`portforward_http`].
+#[derive(Clone)]
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+pub struct PortforwardConnector {
+    pods: Api<Pod>,
+    name: String,
+    port: u16,
+}
+
+/// Make HTTP requests to a port of a pod, through a forwarded tunnel
+///
+/// The returned connector ignores the host of the requested uri, and opens a new
+/// [port forward](Api::portforward) for every connection hyper makes.
+/// This lets tests and tools talk to in-cluster services with ordinary client code.
+///
+/// ```no_run
+/// use hyper::Body;
+/// use kube::{api::Api, ops::portforward_http, Client};
+/// use k8s_openapi::api::core::v1::Pod;
+/// # async fn scope(client: Client) -> Result<(), Box<dyn std::error::Error>> {
+/// let pods: Api<Pod> = Api::namespaced(client, "apps");
+/// let http = hyper::Client::builder().build::<_, Body>(portforward_http(pods, "blog", 8080));
+/// let res = http.get("http://blog/healthz".parse()?).await?;
+/// assert!(res.status().is_success());
+/// # Ok(())
+/// # }
+/// ```
+#[cfg_attr(docsrs, doc(cfg(feature = "ws")))]
+pub fn portforward_http(pods: Api<Pod>, name: &str, port: u16) -> PortforwardConnector {
+    PortforwardConnector {
+        pods,
+        name: name.to_string(),
+        port,
+    }
+}
+
+impl tower::Service<Uri> for PortforwardConnector {
+    type Error = Error;
+    type Future = BoxFuture<'static, Result<PortforwardStream>>;
+    type Response = PortforwardStream;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _uri: Uri) -> Self::Future {
+        let connector = self.clone();
+        Box::pin(async move { connector.pods.portforward(&connector.name, connector.port).await })
+    }
+}
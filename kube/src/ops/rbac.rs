@@ -0,0 +1,253 @@
+use std::fmt;
+
+use futures::future::try_join_all;
+use k8s_openapi::api::authorization::v1::{
+    ResourceAttributes, SelfSubjectAccessReview, SelfSubjectAccessReviewSpec,
+};
+
+use crate::{
+    api::{Api, PostParams, Resource},
+    discovery::ApiResource,
+    Client, Error, Result,
+};
+
+/// Permissions a controller needs on a resource, checked by [`verify_rbac`]
+///
+/// ```
+/// use kube::ops::RbacRequirement;
+/// use k8s_openapi::api::apps::v1::Deployment;
+/// let requirement = RbacRequirement::of::<Deployment>(&())
+///     .verbs(&["get", "list", "watch", "patch"])
+///     .in_namespace("apps");
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct RbacRequirement {
+    group: String,
+    resource: String,
+    subresource: Option<String>,
+    namespace: Option<String>,
+    verbs: Vec<String>,
+}
+
+impl RbacRequirement {
+    /// Require permissions on `resource`, the plural name of a kind, in `group`
+    ///
+    /// The group is empty for the core group.
+    pub fn new(group: &str, resource: &str) -> Self {
+        Self {
+            group: group.to_string(),
+            resource: resource.to_string(),
+            subresource: None,
+            namespace: None,
+            verbs: vec![],
+        }
+    }
+
+    /// Require permissions on the resource of `K`
+    pub fn of<K: Resource>(dyntype: &K::DynamicType) -> Self {
+        Self::new(&K::group(dyntype), &K::plural(dyntype))
+    }
+
+    /// Require permissions on a discovered resource
+    pub fn from_api_resource(ar: &ApiResource) -> Self {
+        Self::new(&ar.group, &ar.plural)
+    }
+
+    /// Require the permissions on a subresource, like `status`
+    pub fn subresource(mut self, subresource: &str) -> Self {
+        self.subresource = Some(subresource.to_string());
+        self
+    }
+
+    /// Require the permissions in `namespace` only, instead of across all namespaces
+    pub fn in_namespace(mut self, namespace: &str) -> Self {
+        self.namespace = Some(namespace.to_string());
+        self
+    }
+
+    /// Add the verbs that are required, like `list` or `patch`
+    ///
+    /// A requirement without verbs is never satisfied, so a forgotten call is reported
+    /// rather than passing silently.
+    pub fn verbs(mut self, verbs: &[&str]) -> Self {
+        self.verbs.extend(verbs.iter().map(ToString::to_string));
+        self
+    }
+
+    fn missing(&self, verb: &str, reason: Option<String>) -> MissingPermission {
+        MissingPermission {
+            group: self.group.clone(),
+            resource: self.resource.clone(),
+            subresource: self.subresource.clone(),
+            namespace: self.namespace.clone(),
+            verb: verb.to_string(),
+            reason,
+        }
+    }
+}
+
+/// A permission that [`verify_rbac`] found missing
+#[derive(Clone, Debug, PartialEq)]
+pub struct MissingPermission {
+    /// Group of the resource, empty for the core group
+    pub group: String,
+    /// Plural name of the resource
+    pub resource: String,
+    /// Subresource, if the permission was required on one
+    pub subresource: Option<String>,
+    /// Namespace, `None` for all namespaces
+    pub namespace: Option<String>,
+    /// The verb that is not allowed, empty if the requirement had no verbs
+    pub verb: String,
+    /// Why the authorizer did not allow it, if it said so
+    pub reason: Option<String>,
+}
+
+impl fmt::Display for MissingPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if !self.verb.is_empty() {
+            write!(f, "{} ", self.verb)?;
+        }
+        if !self.group.is_empty() {
+            write!(f, "{}/", self.group)?;
+        }
+        write!(f, "{}", self.resource)?;
+        if let Some(subresource) = &self.subresource {
+            write!(f, "/{}", subresource)?;
+        }
+        match &self.namespace {
+            Some(ns) => write!(f, " in namespace {}", ns)?,
+            None => write!(f, " in all namespaces")?,
+        }
+        if let Some(reason) = self.reason.as_deref().filter(|r| !r.is_empty()) {
+            write!(f, " ({})", reason)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn display_missing(missing: &[MissingPermission]) -> String {
+    missing
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Check that the client is allowed everything a controller needs
+///
+/// Issues a `SelfSubjectAccessReview` for every verb of every requirement, concurrently, and
+/// fails with [`Error::MissingPermissions`] listing all that are not allowed. Calling this at
+/// startup turns a misconfigured `Role` into one clear error, instead of `403`s from watches
+/// that keep retrying in the background.
+///
+/// ```no_run
+/// use kube::{ops::{verify_rbac, RbacRequirement}, Client};
+/// use k8s_openapi::api::{apps::v1::Deployment, core::v1::Event};
+/// # async fn scope(client: Client) -> Result<(), kube::Error> {
+/// verify_rbac(&client, &[
+///     RbacRequirement::of::<Deployment>(&()).verbs(&["get", "list", "watch", "patch"]),
+///     RbacRequirement::of::<Deployment>(&()).subresource("status").verbs(&["patch"]),
+///     RbacRequirement::of::<Event>(&()).verbs(&["create"]),
+/// ])
+/// .await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn verify_rbac(client: &Client, requirements: &[RbacRequirement]) -> Result<()> {
+    let reviews: Api<SelfSubjectAccessReview> = Api::all(client.clone());
+    let unchecked = requirements
+        .iter()
+        .filter(|requirement| requirement.verbs.is_empty())
+        .map(|requirement| requirement.missing("", Some("no verbs were required".into())));
+    let checks = requirements.iter().flat_map(|requirement| {
+        let reviews = &reviews;
+        requirement.verbs.iter().map(move |verb| async move {
+            let review = SelfSubjectAccessReview {
+                spec: SelfSubjectAccessReviewSpec {
+                    resource_attributes: Some(ResourceAttributes {
+                        group: Some(requirement.group.clone()),
+                        resource: Some(requirement.resource.clone()),
+                        subresource: requirement.subresource.clone(),
+                        namespace: requirement.namespace.clone(),
+                        verb: Some(verb.clone()),
+                        ..ResourceAttributes::default()
+                    }),
+                    ..SelfSubjectAccessReviewSpec::default()
+                },
+                ..SelfSubjectAccessReview::default()
+            };
+            let status = reviews.create(&PostParams::default(), &review).await?.status;
+            Ok::<_, Error>(match status {
+                Some(status) if status.allowed => None,
+                Some(status) => Some(requirement.missing(verb, status.reason.or(status.evaluation_error))),
+                None => Some(requirement.missing(verb, None)),
+            })
+        })
+    });
+    let missing = unchecked
+        .chain(try_join_all(checks).await?.into_iter().flatten())
+        .collect::<Vec<_>>();
+    if missing.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::MissingPermissions(missing))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{verify_rbac, RbacRequirement};
+    use crate::{Client, Error, Service};
+    use http::{Request, Response};
+    use hyper::Body;
+    use k8s_openapi::api::{apps::v1::Deployment, core::v1::Pod};
+
+    #[tokio::test]
+    async fn reports_every_denied_verb() {
+        let svc = tower::service_fn(|req: Request<Body>| async move {
+            assert_eq!(
+                req.uri().path(),
+                "/apis/authorization.k8s.io/v1/selfsubjectaccessreviews"
+            );
+            let body = hyper::body::to_bytes(req.into_body()).await?;
+            let mut review: serde_json::Value = serde_json::from_slice(&body)?;
+            let verb = review["spec"]["resourceAttributes"]["verb"].clone();
+            review["status"] = serde_json::json!({ "allowed": verb == "get", "reason": "" });
+            Response::builder()
+                .status(201)
+                .body(Body::from(serde_json::to_vec(&review)?))
+                .map_err(tower::BoxError::from)
+        });
+        let client = Client::new(Service::new(svc));
+        let err = verify_rbac(&client, &[
+            RbacRequirement::of::<Pod>(&()).verbs(&["get", "list"]),
+            RbacRequirement::of::<Deployment>(&())
+                .subresource("status")
+                .in_namespace("apps")
+                .verbs(&["get", "patch"]),
+        ])
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Missing permissions: list pods in all namespaces; patch apps/deployments/status in namespace apps"
+        );
+        assert!(matches!(err, Error::MissingPermissions(missing) if missing.len() == 2));
+    }
+
+    #[tokio::test]
+    async fn requirements_without_verbs_are_missing() {
+        let svc = tower::service_fn(|_req: Request<Body>| async move {
+            Err::<Response<Body>, _>(tower::BoxError::from("no review is needed"))
+        });
+        let client = Client::new(Service::new(svc));
+        let err = verify_rbac(&client, &[RbacRequirement::of::<Pod>(&())])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Missing permissions: pods in all namespaces (no verbs were required)"
+        );
+    }
+}
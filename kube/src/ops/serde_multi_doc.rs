@@ -0,0 +1,98 @@
+//! Parse and render multi-document yaml, as used by manifests
+//!
+//! Documents that are empty or only contain comments are skipped:
+//!
+//! ```
+//! use kube::{api::DynamicObject, ops::serde_multi_doc};
+//! let manifest = "
+//! apiVersion: v1
+//! kind: Namespace
+//! metadata:
+//!   name: apps
+//! ---
+//! ## comments only
+//! ---
+//! apiVersion: v1
+//! kind: ConfigMap
+//! metadata:
+//!   name: cfg
+//!   namespace: apps
+//! ";
+//! let objects: Vec<DynamicObject> = serde_multi_doc::from_str(manifest).unwrap();
+//! assert_eq!(objects.len(), 2);
+//! let yaml = serde_multi_doc::to_string(&objects).unwrap();
+//! assert_eq!(serde_multi_doc::from_str::<DynamicObject>(&yaml).unwrap().len(), 2);
+//! ```
+//!
+//! Documents can also be parsed into typed enums, dispatching on `kind`:
+//!
+//! ```
+//! use kube::ops::serde_multi_doc;
+//! use k8s_openapi::api::core::v1::{ConfigMap, Namespace};
+//! #[derive(serde::Deserialize)]
+//! #[serde(tag = "kind")]
+//! enum Manifest {
+//!     ConfigMap(ConfigMap),
+//!     Namespace(Namespace),
+//! }
+//! let objects: Vec<Manifest> = serde_multi_doc::from_str("
+//! apiVersion: v1
+//! kind: ConfigMap
+//! metadata:
+//!   name: cfg
+//! ").unwrap();
+//! assert!(matches!(objects[0], Manifest::ConfigMap(_)));
+//! ```
+use crate::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+/// Parse every non-empty document of a multi-document yaml string
+pub fn from_str<T: DeserializeOwned>(yaml: &str) -> Result<Vec<T>> {
+    let mut docs = vec![];
+    for de in serde_yaml::Deserializer::from_str(yaml) {
+        let value = serde_yaml::Value::deserialize(de)?;
+        if value.is_null() {
+            continue;
+        }
+        docs.push(serde_yaml::from_value(value)?);
+    }
+    Ok(docs)
+}
+
+/// Render objects as a multi-document yaml string
+///
+/// Every document starts with `---`. Fields are rendered in the order they were serialized,
+/// use [`to_yaml_manifest`](super::to_yaml_manifest) for deterministic output.
+pub fn to_string<T: Serialize>(objects: &[T]) -> Result<String> {
+    let mut yaml = String::new();
+    for object in objects {
+        let doc = serde_yaml::to_string(object)?;
+        if !doc.starts_with("---") {
+            yaml.push_str("---\n");
+        }
+        yaml.push_str(&doc);
+        if !yaml.ends_with('\n') {
+            yaml.push('\n');
+        }
+    }
+    Ok(yaml)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{from_str, to_string};
+    use k8s_openapi::api::core::v1::ConfigMap;
+
+    #[test]
+    fn roundtrips_documents() {
+        let yaml = "---\n# comment only\n---\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: a\n---\n---\napiVersion: v1\nkind: ConfigMap\nmetadata:\n  name: b\n";
+        let cms: Vec<ConfigMap> = from_str(yaml).unwrap();
+        let names: Vec<_> = cms.iter().map(|cm| cm.metadata.name.clone().unwrap()).collect();
+        assert_eq!(names, vec!["a", "b"]);
+
+        let rendered = to_string(&cms).unwrap();
+        assert_eq!(rendered.matches("---").count(), 2);
+        assert_eq!(from_str::<ConfigMap>(&rendered).unwrap(), cms);
+        assert_eq!(to_string::<ConfigMap>(&[]).unwrap(), "");
+    }
+}
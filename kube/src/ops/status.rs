@@ -0,0 +1,205 @@
+use k8s_openapi::api::{
+    apps::v1::{Deployment, DeploymentCondition},
+    core::v1::{ContainerStatus, Pod, PodCondition},
+};
+use std::collections::BTreeMap;
+
+/// The standard types of [`PodCondition`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PodConditionType {
+    /// The pod has been scheduled to a node
+    PodScheduled,
+    /// All init containers have completed
+    Initialized,
+    /// All containers are ready
+    ContainersReady,
+    /// The pod can serve requests, and is added to the endpoints of matching services
+    Ready,
+}
+
+impl PodConditionType {
+    /// The value of the condition's `type` field
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PodConditionType::PodScheduled => "PodScheduled",
+            PodConditionType::Initialized => "Initialized",
+            PodConditionType::ContainersReady => "ContainersReady",
+            PodConditionType::Ready => "Ready",
+        }
+    }
+}
+
+/// Interprets the status of a [`Pod`]
+pub trait PodExt {
+    /// The condition of type `type_`, if any
+    fn condition(&self, type_: PodConditionType) -> Option<&PodCondition>;
+
+    /// Whether the pod is `Ready`
+    fn is_ready(&self) -> bool;
+
+    /// The restart count of every container, including init containers, by container name
+    fn restart_counts(&self) -> BTreeMap<&str, i32>;
+
+    /// Why containers terminated, by container name
+    ///
+    /// Uses the current state of each container, or its last state if it has since been restarted,
+    /// e.g. `OOMKilled` or `Error` for a container in a crash loop.
+    /// Containers that never terminated are left out.
+    fn terminated_reasons(&self) -> BTreeMap<&str, &str>;
+}
+
+impl PodExt for Pod {
+    fn condition(&self, type_: PodConditionType) -> Option<&PodCondition> {
+        self.status
+            .as_ref()?
+            .conditions
+            .as_ref()?
+            .iter()
+            .find(|c| c.type_ == type_.as_str())
+    }
+
+    fn is_ready(&self) -> bool {
+        matches!(self.condition(PodConditionType::Ready), Some(c) if c.status == "True")
+    }
+
+    fn restart_counts(&self) -> BTreeMap<&str, i32> {
+        container_statuses(self)
+            .map(|cs| (cs.name.as_str(), cs.restart_count))
+            .collect()
+    }
+
+    fn terminated_reasons(&self) -> BTreeMap<&str, &str> {
+        container_statuses(self)
+            .filter_map(|cs| {
+                let terminated = [cs.state.as_ref(), cs.last_state.as_ref()]
+                    .iter()
+                    .flatten()
+                    .find_map(|state| state.terminated.as_ref())?;
+                Some((cs.name.as_str(), terminated.reason.as_deref()?))
+            })
+            .collect()
+    }
+}
+
+fn container_statuses(pod: &Pod) -> impl Iterator<Item = &ContainerStatus> {
+    pod.status.iter().flat_map(|status| {
+        let init = status.init_container_statuses.iter().flatten();
+        init.chain(status.container_statuses.iter().flatten())
+    })
+}
+
+/// The standard types of [`DeploymentCondition`]s
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeploymentConditionType {
+    /// At least the minimum number of replicas is available
+    Available,
+    /// The rollout is making progress, or has completed
+    Progressing,
+    /// A replica set failed to create or delete pods
+    ReplicaFailure,
+}
+
+impl DeploymentConditionType {
+    /// The value of the condition's `type` field
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DeploymentConditionType::Available => "Available",
+            DeploymentConditionType::Progressing => "Progressing",
+            DeploymentConditionType::ReplicaFailure => "ReplicaFailure",
+        }
+    }
+}
+
+/// Interprets the status of a [`Deployment`]
+pub trait DeploymentExt {
+    /// The condition of type `type_`, if any
+    fn condition(&self, type_: DeploymentConditionType) -> Option<&DeploymentCondition>;
+
+    /// Whether the deployment has at least its minimum number of available replicas
+    fn is_available(&self) -> bool;
+
+    /// Whether the latest spec has been fully rolled out
+    ///
+    /// This matches `kubectl rollout status`: the controller has observed the current generation,
+    /// and all desired replicas are updated and available, with no old replicas left.
+    fn is_rolled_out(&self) -> bool;
+}
+
+impl DeploymentExt for Deployment {
+    fn condition(&self, type_: DeploymentConditionType) -> Option<&DeploymentCondition> {
+        self.status
+            .as_ref()?
+            .conditions
+            .as_ref()?
+            .iter()
+            .find(|c| c.type_ == type_.as_str())
+    }
+
+    fn is_available(&self) -> bool {
+        matches!(self.condition(DeploymentConditionType::Available), Some(c) if c.status == "True")
+    }
+
+    fn is_rolled_out(&self) -> bool {
+        let status = match &self.status {
+            Some(status) => status,
+            None => return false,
+        };
+        let desired = self.spec.as_ref().and_then(|spec| spec.replicas).unwrap_or(1);
+        status.observed_generation >= self.metadata.generation
+            && status.updated_replicas.unwrap_or(0) == desired
+            && status.replicas.unwrap_or(0) == desired
+            && status.available_replicas.unwrap_or(0) == desired
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{DeploymentExt, PodConditionType, PodExt};
+    use k8s_openapi::api::{apps::v1::Deployment, core::v1::Pod};
+
+    #[test]
+    fn pod_status() {
+        let pod: Pod = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "blog" },
+            "status": {
+                "conditions": [
+                    { "type": "PodScheduled", "status": "True" },
+                    { "type": "Ready", "status": "False" },
+                ],
+                "initContainerStatuses": [{
+                    "name": "migrate", "image": "", "imageID": "", "ready": true, "restartCount": 0,
+                    "state": { "terminated": { "exitCode": 0, "reason": "Completed" } },
+                }],
+                "containerStatuses": [{
+                    "name": "app", "image": "", "imageID": "", "ready": false, "restartCount": 3,
+                    "state": { "waiting": { "reason": "CrashLoopBackOff" } },
+                    "lastState": { "terminated": { "exitCode": 137, "reason": "OOMKilled" } },
+                }],
+            },
+        }))
+        .unwrap();
+        assert!(!pod.is_ready());
+        assert!(pod.condition(PodConditionType::PodScheduled).is_some());
+        assert!(pod.condition(PodConditionType::Initialized).is_none());
+        assert_eq!(pod.restart_counts()["app"], 3);
+        assert_eq!(pod.terminated_reasons()["app"], "OOMKilled");
+        assert_eq!(pod.terminated_reasons()["migrate"], "Completed");
+    }
+
+    #[test]
+    fn deployment_rollout() {
+        let mut deploy: Deployment = serde_json::from_value(serde_json::json!({
+            "metadata": { "name": "blog", "generation": 2 },
+            "spec": { "replicas": 2, "selector": {}, "template": {} },
+            "status": {
+                "observedGeneration": 2, "replicas": 3, "updatedReplicas": 2, "availableReplicas": 2,
+                "conditions": [{ "type": "Available", "status": "True" }],
+            },
+        }))
+        .unwrap();
+        assert!(deploy.is_available());
+        assert!(!deploy.is_rolled_out());
+        deploy.status.as_mut().unwrap().replicas = Some(2);
+        assert!(deploy.is_rolled_out());
+    }
+}
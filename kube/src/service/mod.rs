@@ -4,6 +4,9 @@ mod auth;
 #[cfg(feature = "gzip")] mod compression;
 mod headers;
 mod log;
+mod request_id;
+mod slow_request;
+mod timeout;
 mod tls;
 mod url;
 
@@ -11,6 +14,11 @@ use self::{log::LogRequest, url::set_cluster_url};
 use auth::AuthLayer;
 #[cfg(feature = "gzip")] use compression::{accept_compressed, maybe_decompress};
 use headers::set_default_headers;
+pub(crate) use request_id::RequestId;
+pub use request_id::{RequestIdLayer, RequestIdService};
+pub use slow_request::{SlowRequest, SlowRequestLayer, SlowRequestService};
+pub(crate) use timeout::timeout_of;
+use timeout::TimeoutLayer;
 use tls::HttpsConnector;
 
 use std::convert::{TryFrom, TryInto};
@@ -70,7 +78,9 @@ impl TryFrom<Config> for Service {
     fn try_from(config: Config) -> Result<Self> {
         let cluster_url = config.cluster_url.clone();
         let mut default_headers = config.headers.clone();
-        let timeout = config.timeout;
+        let timeouts = TimeoutLayer::new(&config);
+        let request_id = config.request_id_header.clone().map(RequestIdLayer::new);
+        let slow_requests = config.slow_request_threshold.map(SlowRequestLayer::new);
 
         // AuthLayer is not necessary unless `RefreshableToken`
         let maybe_auth = match Authentication::try_from(&config.auth_info)? {
@@ -104,22 +114,20 @@ impl TryFrom<Config> for Service {
             .map_response(maybe_decompress)
             .into_inner();
 
+        let connect_timeout = config.connect_timeout;
         let https: HttpsConnector<_> = config.try_into()?;
         let mut connector = TimeoutConnector::new(https);
-        if let Some(timeout) = timeout {
-            // reqwest's timeout is applied from when the request stars connecting until
-            // the response body has finished.
-            // Setting both connect and read timeout should be close enough.
-            connector.set_connect_timeout(Some(timeout));
-            // Timeout for reading the response.
-            connector.set_read_timeout(Some(timeout));
-        }
+        // Read timeouts are applied per request by `TimeoutLayer`, so long running calls can opt out
+        connector.set_connect_timeout(connect_timeout);
         let client: HyperClient<_, Body> = HyperClient::builder().build(connector);
 
         let inner = ServiceBuilder::new()
             .layer(common)
+            .option_layer(request_id)
+            .option_layer(slow_requests)
             .option_layer(maybe_auth)
             .layer(tower::layer::layer_fn(LogRequest::new))
+            .layer(timeouts)
             .service(client);
         Ok(Self::new(inner))
     }
@@ -0,0 +1,165 @@
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use http::{header::HeaderName, HeaderValue, Request, Response};
+use hyper::Body;
+use rand::Rng;
+use tower::{Layer, Service};
+use tracing::Instrument;
+
+/// Identifier attached to a request, stored in the extensions of its response
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RequestId(pub(crate) String);
+
+/// Layer that attaches a unique id header to every request
+///
+/// Requests that already have the header keep their id.
+#[derive(Clone)]
+pub struct RequestIdLayer {
+    header: HeaderName,
+}
+
+impl RequestIdLayer {
+    /// Create a layer setting `header` on every request
+    pub fn new(header: HeaderName) -> Self {
+        Self { header }
+    }
+}
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = RequestIdService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        RequestIdService {
+            header: self.header.clone(),
+            service,
+        }
+    }
+}
+
+/// Service that attaches a unique id header to every request
+///
+/// The request is executed in a span carrying the id, and the id is made
+/// available to [`Client`](crate::Client) for error reporting.
+#[derive(Clone)]
+pub struct RequestIdService<S> {
+    header: HeaderName,
+    service: S,
+}
+
+impl<S> Service<Request<Body>> for RequestIdService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Future: Send + 'static,
+{
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<Body>, S::Error>>;
+    type Response = Response<Body>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut req: Request<Body>) -> Self::Future {
+        let id = match req.headers().get(&self.header).and_then(|v| v.to_str().ok()) {
+            Some(id) => id.to_string(),
+            None => {
+                let id = generate_id();
+                req.headers_mut().insert(
+                    self.header.clone(),
+                    HeaderValue::from_str(&id).expect("valid header value"),
+                );
+                id
+            }
+        };
+        let span = tracing::debug_span!("request", request_id = %id);
+        let fut = self.service.call(req);
+        Box::pin(
+            async move {
+                let mut res = fut.await?;
+                res.extensions_mut().insert(RequestId(id));
+                Ok(res)
+            }
+            .instrument(span),
+        )
+    }
+}
+
+/// Random (v4) uuid
+fn generate_id() -> String {
+    let mut bytes: [u8; 16] = rand::thread_rng().gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use futures::pin_mut;
+    use http::{Request, Response};
+    use hyper::Body;
+    use tokio_test::assert_ready_ok;
+    use tower_test::mock;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn sets_request_id_header() {
+        let (mut service, handle) = mock::spawn_layer::<Request<Body>, Response<Body>, _>(
+            RequestIdLayer::new(HeaderName::from_static("audit-id")),
+        );
+        let spawned = tokio::spawn(async move {
+            // Receive the request and respond
+            pin_mut!(handle);
+            let (request, send) = handle.next_request().await.expect("service not called");
+            let id = request
+                .headers()
+                .get("audit-id")
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_string();
+            assert_eq!(id.len(), 36);
+            send.send_response(Response::builder().body(Body::empty()).unwrap());
+            id
+        });
+
+        assert_ready_ok!(service.poll_ready());
+        let res = service
+            .call(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let sent = spawned.await.unwrap();
+        assert_eq!(res.extensions().get::<RequestId>(), Some(&RequestId(sent)));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn keeps_existing_request_id() {
+        let (mut service, handle) = mock::spawn_layer::<Request<Body>, Response<Body>, _>(
+            RequestIdLayer::new(HeaderName::from_static("audit-id")),
+        );
+        let spawned = tokio::spawn(async move {
+            pin_mut!(handle);
+            let (request, send) = handle.next_request().await.expect("service not called");
+            assert_eq!(request.headers().get("audit-id").unwrap(), "mine");
+            send.send_response(Response::builder().body(Body::empty()).unwrap());
+        });
+
+        assert_ready_ok!(service.poll_ready());
+        let req = Request::builder()
+            .uri("/")
+            .header("audit-id", "mine")
+            .body(Body::empty())
+            .unwrap();
+        service.call(req).await.unwrap();
+        spawned.await.unwrap();
+    }
+}
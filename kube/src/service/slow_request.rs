@@ -0,0 +1,158 @@
+use std::{
+    fmt,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+
+use futures::future::BoxFuture;
+use http::{Method, Request, Response, StatusCode, Uri};
+use hyper::Body;
+use tower::{Layer, Service};
+
+/// An API call that took longer than the threshold of a [`SlowRequestLayer`]
+#[derive(Clone, Debug)]
+pub struct SlowRequest {
+    /// The verb of the call
+    pub method: Method,
+    /// The url of the call
+    pub uri: Uri,
+    /// The time until the response headers arrived, or until the call failed
+    pub duration: Duration,
+    /// The status of the response, `None` if the call failed
+    pub status: Option<StatusCode>,
+}
+
+impl fmt::Display for SlowRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} took {:?}", self.method, self.uri, self.duration)?;
+        match self.status {
+            Some(status) => write!(f, " ({})", status),
+            None => write!(f, " (failed)"),
+        }
+    }
+}
+
+type Handler = Arc<dyn Fn(&SlowRequest) + Send + Sync>;
+
+/// Layer that reports API calls taking longer than a threshold
+///
+/// Like the request latency warnings of client-go, this helps to spot an overloaded apiserver.
+/// Calls are measured until their response headers arrive, so the time spent streaming a body,
+/// like the events of a watch, does not count.
+/// By default, slow calls are logged at `warn` level.
+#[derive(Clone)]
+pub struct SlowRequestLayer {
+    threshold: Duration,
+    handler: Handler,
+}
+
+impl SlowRequestLayer {
+    /// Create a layer reporting calls that take longer than `threshold`
+    pub fn new(threshold: Duration) -> Self {
+        Self {
+            threshold,
+            handler: Arc::new(
+                |slow| tracing::warn!(method = %slow.method, uri = %slow.uri, duration = ?slow.duration, status = ?slow.status, "slow request: {}", slow),
+            ),
+        }
+    }
+
+    /// Report slow calls to `handler` instead of logging them
+    pub fn with_handler<F>(mut self, handler: F) -> Self
+    where
+        F: Fn(&SlowRequest) + Send + Sync + 'static,
+    {
+        self.handler = Arc::new(handler);
+        self
+    }
+}
+
+impl<S> Layer<S> for SlowRequestLayer {
+    type Service = SlowRequestService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        SlowRequestService {
+            threshold: self.threshold,
+            handler: self.handler.clone(),
+            service,
+        }
+    }
+}
+
+/// Service that reports API calls taking longer than a threshold, see [`SlowRequestLayer`]
+#[derive(Clone)]
+pub struct SlowRequestService<S> {
+    threshold: Duration,
+    handler: Handler,
+    service: S,
+}
+
+impl<S> Service<Request<Body>> for SlowRequestService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Future: Send + 'static,
+{
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response<Body>, S::Error>>;
+    type Response = Response<Body>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let (method, uri) = (req.method().clone(), req.uri().clone());
+        let (threshold, handler) = (self.threshold, self.handler.clone());
+        let start = Instant::now();
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = fut.await;
+            let duration = start.elapsed();
+            if duration > threshold {
+                handler(&SlowRequest {
+                    method,
+                    uri,
+                    duration,
+                    status: res.as_ref().ok().map(Response::status),
+                });
+            }
+            res
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlowRequestLayer;
+    use http::{Request, Response};
+    use hyper::Body;
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+    use tower::{Layer, ServiceExt};
+
+    #[tokio::test]
+    async fn reports_requests_over_the_threshold() {
+        let reported = Arc::new(Mutex::new(vec![]));
+        let svc = tower::service_fn(|_req: Request<Body>| async {
+            Ok::<_, tower::BoxError>(Response::new(Body::empty()))
+        });
+        let req = || Request::get("/api/v1/pods").body(Body::empty()).unwrap();
+
+        let sink = reported.clone();
+        let slow = SlowRequestLayer::new(Duration::from_secs(0))
+            .with_handler(move |slow| sink.lock().unwrap().push(slow.to_string()));
+        slow.layer(svc).oneshot(req()).await.unwrap();
+        let sink = reported.clone();
+        let fast = SlowRequestLayer::new(Duration::from_secs(60))
+            .with_handler(move |slow| sink.lock().unwrap().push(slow.to_string()));
+        fast.layer(svc).oneshot(req()).await.unwrap();
+
+        let reported = reported.lock().unwrap();
+        assert_eq!(reported.len(), 1);
+        assert!(reported[0].starts_with("GET /api/v1/pods took"));
+        assert!(reported[0].ends_with("(200 OK)"));
+    }
+}
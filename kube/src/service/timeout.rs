@@ -0,0 +1,273 @@
+use std::{
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use futures::{future::BoxFuture, StreamExt};
+use http::{header, Request, Response, StatusCode};
+use hyper::Body;
+use tokio::time::Instant;
+use tower::{BoxError, Layer, Service};
+
+use crate::{Config, Error};
+
+/// Timeouts applied to a single request
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct Timeouts {
+    /// Longest wait for the response headers, or the next chunk of the body
+    read: Option<Duration>,
+    /// Longest time for the whole request, including reading the body
+    total: Option<Duration>,
+}
+
+/// Layer applying the read and total timeouts of a [`Config`] to requests
+///
+/// Long running requests (watches, followed logs, and upgraded connections for exec, attach
+/// and port forwarding) only use [`Config::long_running_read_timeout`], and have no total timeout.
+#[derive(Clone, Debug)]
+pub(crate) struct TimeoutLayer {
+    regular: Timeouts,
+    long_running: Timeouts,
+}
+
+impl TimeoutLayer {
+    pub(crate) fn new(config: &Config) -> Self {
+        Self {
+            regular: Timeouts {
+                read: config.read_timeout,
+                total: config.total_timeout,
+            },
+            long_running: Timeouts {
+                read: config.long_running_read_timeout,
+                total: None,
+            },
+        }
+    }
+}
+
+impl<S> Layer<S> for TimeoutLayer {
+    type Service = TimeoutService<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        TimeoutService {
+            layer: self.clone(),
+            service,
+        }
+    }
+}
+
+/// Service applying the timeouts of a [`TimeoutLayer`]
+#[derive(Clone)]
+pub(crate) struct TimeoutService<S> {
+    layer: TimeoutLayer,
+    service: S,
+}
+
+impl<S> Service<Request<Body>> for TimeoutService<S>
+where
+    S: Service<Request<Body>, Response = Response<Body>>,
+    S::Error: Into<BoxError>,
+    S::Future: Send + 'static,
+{
+    type Error = BoxError;
+    type Future = BoxFuture<'static, Result<Response<Body>, BoxError>>;
+    type Response = Response<Body>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Request<Body>) -> Self::Future {
+        let timeouts = if is_long_running(&req) {
+            self.layer.long_running
+        } else {
+            self.layer.regular
+        };
+        let deadline = timeouts.total.map(|total| Instant::now() + total);
+        let fut = self.service.call(req);
+        Box::pin(async move {
+            let res = match wait_for(timeouts.read, deadline) {
+                Some(wait) => tokio::time::timeout(wait, fut)
+                    .await
+                    .map_err(|_| BoxError::from(Error::Timeout("waiting for the response headers")))?,
+                None => fut.await,
+            }
+            .map_err(Into::into)?;
+            // Upgraded connections are owned by the caller once switched
+            if res.status() == StatusCode::SWITCHING_PROTOCOLS || timeouts == Timeouts::default() {
+                return Ok(res);
+            }
+            Ok(res.map(|body| with_timeouts(body, timeouts.read, deadline)))
+        })
+    }
+}
+
+/// The [`Error::Timeout`] that `err` was caused by, if any
+///
+/// Timeouts while reading the body reach the client wrapped in a [`hyper::Error`].
+pub(crate) fn timeout_of(err: &(dyn std::error::Error + 'static)) -> Option<&'static str> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(Error::Timeout(what)) = err.downcast_ref::<Error>() {
+            return Some(what);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Whether a request is expected to stay open for a long time
+fn is_long_running<B>(req: &Request<B>) -> bool {
+    if req.headers().contains_key(header::UPGRADE) {
+        return true;
+    }
+    let uri = req.uri();
+    let path = uri.path();
+    if path.ends_with("/exec") || path.ends_with("/attach") || path.ends_with("/portforward") {
+        return true;
+    }
+    uri.query()
+        .unwrap_or_default()
+        .split('&')
+        .any(|pair| matches!(pair, "watch=true" | "watch=1" | "follow=true" | "follow=1"))
+}
+
+/// How long to wait for the next read, given the read timeout and the deadline of the request
+fn wait_for(read: Option<Duration>, deadline: Option<Instant>) -> Option<Duration> {
+    let remaining = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+    match (read, remaining) {
+        (Some(read), Some(remaining)) => Some(read.min(remaining)),
+        (read, remaining) => read.or(remaining),
+    }
+}
+
+/// Fail reading `body` when a chunk takes longer than `read`, or the `deadline` passes
+fn with_timeouts(body: Body, read: Option<Duration>, deadline: Option<Instant>) -> Body {
+    let chunks = futures::stream::unfold(Some(body), move |body| async move {
+        let mut body = body?;
+        let next = match wait_for(read, deadline) {
+            Some(wait) => match tokio::time::timeout(wait, body.next()).await {
+                Ok(next) => next,
+                Err(_) => return Some((Err(BoxError::from(Error::Timeout("reading the response body"))), None)),
+            },
+            None => body.next().await,
+        };
+        match next? {
+            Ok(chunk) => Some((Ok(chunk), Some(body))),
+            Err(err) => Some((Err(BoxError::from(err)), None)),
+        }
+    });
+    Body::wrap_stream(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::Config;
+    use tower::util::BoxService;
+
+    fn config() -> Config {
+        let mut config = Config::new("https://localhost:6443".parse().unwrap());
+        config.read_timeout = Some(Duration::from_secs(5));
+        config.total_timeout = Some(Duration::from_secs(60));
+        config.long_running_read_timeout = None;
+        config
+    }
+
+    #[test]
+    fn long_running_requests_time_out_by_default() {
+        let layer = TimeoutLayer::new(&Config::new("https://localhost:6443".parse().unwrap()));
+        assert_eq!(layer.long_running, Timeouts {
+            read: Some(Duration::from_secs(295)),
+            total: None,
+        });
+    }
+
+    #[test]
+    fn long_running_requests_are_detected() {
+        let get = |uri: &str| Request::get(uri).body(()).unwrap();
+        assert!(is_long_running(&get(
+            "/api/v1/pods?&watch=true&timeoutSeconds=290"
+        )));
+        assert!(is_long_running(&get(
+            "/api/v1/namespaces/ns/pods/p/log?&follow=true"
+        )));
+        assert!(is_long_running(&get(
+            "/api/v1/namespaces/ns/pods/p/exec?&command=ls"
+        )));
+        assert!(!is_long_running(&get(
+            "/api/v1/namespaces/ns/pods/p/log?&follow=false"
+        )));
+        assert!(!is_long_running(&get("/api/v1/pods?&labelSelector=watch%3Dtrue")));
+    }
+
+    /// A service responding after `headers`, then sending each chunk after its delay
+    fn service(
+        headers: Duration,
+        chunks: Vec<(Duration, &'static str)>,
+    ) -> BoxService<Request<Body>, Response<Body>, BoxError> {
+        BoxService::new(
+            TimeoutLayer::new(&config()).layer(tower::service_fn(move |_req: Request<Body>| {
+                let chunks = chunks.clone();
+                async move {
+                    tokio::time::sleep(headers).await;
+                    let (mut sender, body) = Body::channel();
+                    tokio::spawn(async move {
+                        for (delay, chunk) in chunks {
+                            tokio::time::sleep(delay).await;
+                            if sender.send_data(chunk.into()).await.is_err() {
+                                return;
+                            }
+                        }
+                    });
+                    Ok::<_, BoxError>(Response::new(body))
+                }
+            })),
+        )
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn read_timeout_applies_to_regular_requests() {
+        let mut svc = service(Duration::from_secs(10), vec![]);
+        let req = Request::get("/api/v1/pods").body(Body::empty()).unwrap();
+        let err = svc.call(req).await.unwrap_err().downcast::<Error>().unwrap();
+        assert!(matches!(*err, Error::Timeout(_)));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn read_timeout_applies_between_chunks() {
+        let chunks = vec![(Duration::from_secs(4), "{"), (Duration::from_secs(4), "}")];
+        let req = || Request::get("/api/v1/pods").body(Body::empty()).unwrap();
+        let res = service(Duration::from_secs(1), chunks).call(req()).await.unwrap();
+        assert_eq!(&hyper::body::to_bytes(res.into_body()).await.unwrap()[..], b"{}");
+
+        let chunks = vec![(Duration::from_secs(1), "{"), (Duration::from_secs(10), "}")];
+        let res = service(Duration::from_secs(1), chunks).call(req()).await.unwrap();
+        let err = hyper::body::to_bytes(res.into_body()).await.unwrap_err();
+        assert!(matches!(timeout_of(&err), Some("reading the response body")));
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn total_timeout_applies_to_slow_bodies() {
+        let chunks = vec![(Duration::from_secs(4), "."); 20];
+        let res = service(Duration::from_secs(1), chunks)
+            .call(Request::get("/api/v1/pods").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert!(hyper::body::to_bytes(res.into_body()).await.is_err());
+    }
+
+    #[tokio::test(flavor = "current_thread", start_paused = true)]
+    async fn long_running_requests_use_their_own_timeouts() {
+        let chunks = vec![(Duration::from_secs(20), "."); 5];
+        let req = Request::get("/api/v1/pods?&watch=true")
+            .body(Body::empty())
+            .unwrap();
+        let res = service(Duration::from_secs(120), chunks).call(req).await.unwrap();
+        assert_eq!(
+            &hyper::body::to_bytes(res.into_body()).await.unwrap()[..],
+            b"....."
+        );
+    }
+}